@@ -8,7 +8,7 @@
 use std::path::PathBuf;
 
 use anyhow::{Result, anyhow};
-use automerge::{Automerge, ObjId, ObjType, ROOT, ReadDoc, transaction::Transactable};
+use automerge::{Automerge, ChangeHash, ObjId, ObjType, ROOT, ReadDoc, transaction::Transactable};
 use uuid::Uuid;
 
 use crate::layers::{Layer, LayerId};
@@ -123,6 +123,10 @@ pub struct Document {
     storage_path: Option<PathBuf>,
     /// Whether there are unsaved changes
     dirty: bool,
+    /// Local edits are refused (we may only view a shared document)
+    read_only: bool,
+    /// A local edit was refused since the last [`Document::take_refused_edit`]
+    refused_edit: bool,
 }
 
 impl Document {
@@ -163,6 +167,8 @@ impl Document {
             doc,
             storage_path: None,
             dirty: false,
+            read_only: false,
+            refused_edit: false,
         }
     }
 
@@ -173,6 +179,8 @@ impl Document {
             doc,
             storage_path: None,
             dirty: false,
+            read_only: false,
+            refused_edit: false,
         }
     }
 
@@ -185,6 +193,8 @@ impl Document {
             doc,
             storage_path: Some(path.clone()),
             dirty: false,
+            read_only: false,
+            refused_edit: false,
         })
     }
 
@@ -239,6 +249,57 @@ impl Document {
         Ok(())
     }
 
+    /// Current heads of the document (for tracking what a peer has seen)
+    pub fn heads(&self) -> Vec<ChangeHash> {
+        self.doc.get_heads()
+    }
+
+    /// Encode the changes made since `heads` (for sync).
+    ///
+    /// Cost is proportional to the number of new changes, not the size of
+    /// the document. Empty heads yield every change.
+    pub fn changes_since(&self, heads: &[ChangeHash]) -> Vec<u8> {
+        self.doc.save_after(heads)
+    }
+
+    /// Apply encoded changes produced by [`Document::changes_since`].
+    ///
+    /// Changes already in the document are ignored, so applying the same
+    /// bytes twice is harmless.
+    pub fn apply_changes(&mut self, changes: &[u8]) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        self.doc.load_incremental(changes)?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Refuse local edits (e.g. to a document we may only view).
+    ///
+    /// While set, every editing method leaves the document untouched:
+    /// those returning `()` or `bool` succeed without doing anything, those
+    /// that would return a new id fail. Remote changes still merge.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Whether local edits are refused
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Whether a local edit was refused since the last call
+    pub fn take_refused_edit(&mut self) -> bool {
+        std::mem::take(&mut self.refused_edit)
+    }
+
+    /// Whether a local edit may go ahead, noting it if refused
+    fn may_edit(&mut self) -> bool {
+        self.refused_edit |= self.read_only;
+        !self.read_only
+    }
+
     /// Check if dirty
     pub fn is_dirty(&self) -> bool {
         self.dirty
@@ -270,6 +331,9 @@ impl Document {
 
     /// Add a new shape to the document
     pub fn add_shape(&mut self, kind: ShapeKind) -> Result<ShapeId> {
+        if !self.may_edit() {
+            return Err(anyhow!("Document is read-only"));
+        }
         // ShapeKind is always valid as an enum, no precondition check needed
         
         let id = ShapeId::new();
@@ -301,6 +365,9 @@ impl Document {
 
     /// Update an existing shape
     pub fn update_shape(&mut self, id: ShapeId, kind: ShapeKind) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         debug_assert!(!id.to_string().is_empty(), "Shape ID should not be empty");
         // ShapeKind is always valid as an enum, no precondition check needed
         
@@ -328,6 +395,9 @@ impl Document {
 
     /// Delete a shape
     pub fn delete_shape(&mut self, id: ShapeId) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         debug_assert!(!id.to_string().is_empty(), "Shape ID should not be empty");
         
         let shapes_obj = self.get_shapes_map()?;
@@ -519,6 +589,9 @@ impl Document {
 
     /// Set the complete shape order (internal helper)
     fn set_shape_order(&mut self, order: &[ShapeId]) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         let mut tx = self.doc.transaction();
 
         // Get or create order list
@@ -558,6 +631,9 @@ impl Document {
         members: &[ShapeId],
         parent: Option<GroupId>,
     ) -> Result<GroupId> {
+        if !self.may_edit() {
+            return Err(anyhow!("Document is read-only"));
+        }
         debug_assert!(!members.is_empty(), "create_group precondition: members must not be empty");
         debug_assert!(members.iter().all(|id| !id.to_string().is_empty()), "All member IDs should be valid");
         
@@ -597,6 +673,9 @@ impl Document {
 
     /// Delete a group (ungroups all shapes)
     pub fn delete_group(&mut self, id: GroupId) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         // Remove the group from the groups map
         let groups_obj = self.get_groups_map()?;
 
@@ -804,6 +883,9 @@ impl Document {
 
     /// Create a new layer
     pub fn create_layer(&mut self, name: &str) -> Result<LayerId> {
+        if !self.may_edit() {
+            return Err(anyhow!("Document is read-only"));
+        }
         debug_assert!(!name.is_empty(), "Layer name should not be empty");
         debug_assert!(name.len() <= 256, "Layer name should be reasonable length");
         
@@ -838,6 +920,9 @@ impl Document {
 
     /// Delete a layer (moves shapes to default layer)
     pub fn delete_layer(&mut self, id: LayerId) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         debug_assert!(!id.to_string().is_empty(), "Layer ID should not be empty");
         
         let layer_order = self.read_layer_order()?;
@@ -942,6 +1027,9 @@ impl Document {
 
     /// Rename a layer
     pub fn rename_layer(&mut self, id: LayerId, name: &str) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         debug_assert!(!id.to_string().is_empty(), "Layer ID should not be empty");
         debug_assert!(!name.is_empty(), "Layer name should not be empty");
         debug_assert!(name.len() <= 256, "Layer name should be reasonable length");
@@ -961,6 +1049,9 @@ impl Document {
 
     /// Set layer visibility
     pub fn set_layer_visible(&mut self, id: LayerId, visible: bool) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         let layers_obj = self.get_layers_map()?;
 
         let mut tx = self.doc.transaction();
@@ -976,6 +1067,9 @@ impl Document {
 
     /// Set layer locked state
     pub fn set_layer_locked(&mut self, id: LayerId, locked: bool) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         let layers_obj = self.get_layers_map()?;
 
         let mut tx = self.doc.transaction();
@@ -1015,6 +1109,9 @@ impl Document {
 
     /// Set the layer a shape belongs to
     pub fn set_shape_layer(&mut self, id: ShapeId, layer_id: LayerId) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         debug_assert!(!id.to_string().is_empty(), "Shape ID should not be empty");
         debug_assert!(!layer_id.to_string().is_empty(), "Layer ID should not be empty");
         
@@ -1034,6 +1131,9 @@ impl Document {
     /// Move layer in the order (toward top = higher index)
     #[allow(dead_code)]
     pub fn move_layer(&mut self, id: LayerId, new_index: u32) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         let mut order = self.read_layer_order()?;
         
         debug_assert!(!order.is_empty(), "Layer order should not be empty");
@@ -1146,6 +1246,28 @@ impl Document {
         Ok(updated)
     }
 
+    // --- Document Settings ---
+
+    /// Whether curves and freehand strokes render at braille sub-cell resolution
+    pub fn braille(&self) -> bool {
+        get_opt_bool(&self.doc, &ROOT, "braille")
+            .ok()
+            .flatten()
+            .unwrap_or(false)
+    }
+
+    /// Enable or disable braille rendering (synced to peers like any other edit)
+    pub fn set_braille(&mut self, enabled: bool) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
+        let mut tx = self.doc.transaction();
+        tx.put(ROOT, "braille", enabled)?;
+        tx.commit();
+        self.dirty = true;
+        Ok(())
+    }
+
     // --- Global Undo/Redo (synced via CRDT) ---
 
     /// Maximum undo history size
@@ -1176,6 +1298,9 @@ impl Document {
 
     /// Push current shapes state to undo stack (call before mutations)
     pub fn push_undo_checkpoint(&mut self) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         let snapshot = self.serialize_shapes()?;
 
         let mut tx = self.doc.transaction();
@@ -1214,6 +1339,9 @@ impl Document {
 
     /// Undo: pop from undo stack, push current to redo, restore shapes
     pub fn global_undo(&mut self) -> Result<bool> {
+        if !self.may_edit() {
+            return Ok(false);
+        }
         let undo_stack = self.get_undo_stack()?;
         let undo_len = self.doc.length(&undo_stack);
 
@@ -1280,6 +1408,9 @@ impl Document {
 
     /// Redo: pop from redo stack, push current to undo, restore shapes
     pub fn global_redo(&mut self) -> Result<bool> {
+        if !self.may_edit() {
+            return Ok(false);
+        }
         let redo_stack = self.get_redo_stack()?;
         let redo_len = self.doc.length(&redo_stack);
 
@@ -1346,6 +1477,9 @@ impl Document {
 
     /// Clear undo/redo stacks (e.g., on new document)
     pub fn clear_undo_history(&mut self) -> Result<()> {
+        if !self.may_edit() {
+            return Ok(());
+        }
         let mut tx = self.doc.transaction();
 
         // Clear undo stack
@@ -1514,6 +1648,23 @@ fn write_shape_kind<T: Transactable>(tx: &mut T, obj: &ObjId, kind: &ShapeKind)
             write_box_shape(tx, obj, "Cloud", start, end, label, *color)
         }
         
+        ShapeKind::Callout {
+            start,
+            end,
+            tail,
+            tail_connection,
+            label,
+            color,
+        } => {
+            write_box_shape(tx, obj, "Callout", start, end, label, *color)?;
+            tx.put(obj, "tail_x", tail.x as i64)?;
+            tx.put(obj, "tail_y", tail.y as i64)?;
+            if let Some(conn) = tail_connection {
+                tx.put(obj, "tail_conn", *conn as i64)?;
+            }
+            Ok(())
+        }
+
         ShapeKind::Diamond {
             center,
             half_width,
@@ -1555,13 +1706,7 @@ fn write_shape_kind<T: Transactable>(tx: &mut T, obj: &ObjId, kind: &ShapeKind)
             if let Some(l) = label {
                 tx.put(obj, "label", l.as_str())?;
             }
-            let points_obj = tx.put_object(obj, "points", ObjType::List)?;
-            for (i, point) in points.iter().enumerate() {
-                let point_obj = tx.insert_object(&points_obj, i, ObjType::Map)?;
-                tx.put(&point_obj, "x", point.x as i64)?;
-                tx.put(&point_obj, "y", point.y as i64)?;
-            }
-            Ok(())
+            write_points(tx, obj, points)
         }
         
         ShapeKind::Text {
@@ -1600,6 +1745,23 @@ fn write_shape_kind<T: Transactable>(tx: &mut T, obj: &ObjId, kind: &ShapeKind)
             Ok(())
         }
         
+        ShapeKind::Polygon {
+            points,
+            closed,
+            label,
+            color,
+        } => {
+            debug_assert!(!points.is_empty(), "Polygon must have at least one point");
+
+            tx.put(obj, "kind", "Polygon")?;
+            tx.put(obj, "closed", *closed)?;
+            tx.put(obj, "color", shape_color_to_str(*color))?;
+            if let Some(l) = label {
+                tx.put(obj, "label", l.as_str())?;
+            }
+            write_points(tx, obj, points)
+        }
+
         ShapeKind::Star {
             center,
             outer_radius,
@@ -1704,6 +1866,37 @@ fn read_centered_shape(
     })
 }
 
+/// Write a vertex list as a list of {x, y} maps
+fn write_points<T: Transactable>(tx: &mut T, obj: &ObjId, points: &[Position]) -> Result<()> {
+    let points_obj = tx.put_object(obj, "points", ObjType::List)?;
+    for (i, point) in points.iter().enumerate() {
+        let point_obj = tx.insert_object(&points_obj, i, ObjType::Map)?;
+        tx.put(&point_obj, "x", point.x as i64)?;
+        tx.put(&point_obj, "y", point.y as i64)?;
+    }
+    Ok(())
+}
+
+/// Read a vertex list written by write_points
+fn read_points(doc: &Automerge, obj: &ObjId) -> Result<Vec<Position>> {
+    let points = match doc.get(obj, "points")? {
+        Some((_, points_obj)) => {
+            let len = doc.length(&points_obj);
+            let mut pts = Vec::with_capacity(len);
+            for i in 0..len {
+                if let Some((_, point_obj)) = doc.get(&points_obj, i)? {
+                    let x = get_i32(doc, &point_obj, "x")?;
+                    let y = get_i32(doc, &point_obj, "y")?;
+                    pts.push(Position::new(x, y));
+                }
+            }
+            pts
+        }
+        None => Vec::new(),
+    };
+    Ok(points)
+}
+
 fn read_shape_kind(doc: &Automerge, obj: &ObjId) -> Result<Option<ShapeKind>> {
     let kind_value = doc.get(obj, "kind")?;
 
@@ -1729,6 +1922,15 @@ fn read_shape_kind(doc: &Automerge, obj: &ObjId) -> Result<Option<ShapeKind>> {
             read_centered_shape(doc, obj, &kind_str)?
         }
         
+        "Callout" => ShapeKind::Callout {
+            start: Position::new(get_i32(doc, obj, "start_x")?, get_i32(doc, obj, "start_y")?),
+            end: Position::new(get_i32(doc, obj, "end_x")?, get_i32(doc, obj, "end_y")?),
+            tail: Position::new(get_i32(doc, obj, "tail_x")?, get_i32(doc, obj, "tail_y")?),
+            tail_connection: get_opt_u64(doc, obj, "tail_conn")?,
+            label: get_opt_string(doc, obj, "label")?,
+            color: get_shape_color(doc, obj)?,
+        },
+
         "Diamond" => ShapeKind::Diamond {
             center: Position::new(
                 get_i32(doc, obj, "center_x")?,
@@ -1745,24 +1947,8 @@ fn read_shape_kind(doc: &Automerge, obj: &ObjId) -> Result<Option<ShapeKind>> {
             let ch = char_str.chars().next()
                 .expect("Freehand char should contain at least one character");
 
-            let points = match doc.get(obj, "points")? {
-                Some((_, points_obj)) => {
-                    let len = doc.length(&points_obj);
-                    let mut pts = Vec::with_capacity(len);
-                    for i in 0..len {
-                        if let Some((_, point_obj)) = doc.get(&points_obj, i)? {
-                            let x = get_i32(doc, &point_obj, "x")?;
-                            let y = get_i32(doc, &point_obj, "y")?;
-                            pts.push(Position::new(x, y));
-                        }
-                    }
-                    pts
-                }
-                None => Vec::new(),
-            };
-
             ShapeKind::Freehand {
-                points,
+                points: read_points(doc, obj)?,
                 char: ch,
                 label: get_opt_string(doc, obj, "label")?,
                 color: get_shape_color(doc, obj)?,
@@ -1783,6 +1969,13 @@ fn read_shape_kind(doc: &Automerge, obj: &ObjId) -> Result<Option<ShapeKind>> {
             color: get_shape_color(doc, obj)?,
         },
         
+        "Polygon" => ShapeKind::Polygon {
+            points: read_points(doc, obj)?,
+            closed: get_opt_bool(doc, obj, "closed")?.unwrap_or(false),
+            label: get_opt_string(doc, obj, "label")?,
+            color: get_shape_color(doc, obj)?,
+        },
+
         "Star" => ShapeKind::Star {
             center: Position::new(
                 get_i32(doc, obj, "center_x")?,
@@ -1848,11 +2041,10 @@ fn get_opt_u64(doc: &Automerge, obj: &ObjId, key: &str) -> Result<Option<u64>> {
     match doc.get(obj, key)? {
         Some((automerge::Value::Scalar(s), _)) => {
             if let Some(n) = s.to_i64() {
-                // Convert i64 to u64 - this is safe for positive values
-                // Negative values get wrapped but that's likely a data corruption issue
-                let value = n as u64;
-                debug_assert!(n >= 0, "Connection ID should be non-negative, got {}", n);
-                Ok(Some(value))
+                // Connection IDs are stored as the i64 bit pattern of the u64
+                // (see `shape_id_to_connection`), so the half of them with the
+                // high bit set read back negative; the cast restores them
+                Ok(Some(n as u64))
             } else {
                 Ok(None)
             }
@@ -1861,6 +2053,15 @@ fn get_opt_u64(doc: &Automerge, obj: &ObjId, key: &str) -> Result<Option<u64>> {
     }
 }
 
+fn get_opt_bool(doc: &Automerge, obj: &ObjId, key: &str) -> Result<Option<bool>> {
+    debug_assert!(!key.is_empty(), "Key should not be empty");
+
+    match doc.get(obj, key)? {
+        Some((automerge::Value::Scalar(s), _)) => Ok(s.to_bool()),
+        _ => Ok(None),
+    }
+}
+
 fn get_line_style(doc: &Automerge, obj: &ObjId) -> Result<LineStyle> {
     match doc.get(obj, "style")? {
         Some((automerge::Value::Scalar(s), _)) => {
@@ -2061,6 +2262,7 @@ fn update_line_connections_for_move(
                 color: *color,
             })
         }
+        ShapeKind::Callout { .. } => kind.translate_connected_endpoints(moved_conn_id, dx, dy),
         _ => None,
     }
 }
@@ -2163,6 +2365,7 @@ fn update_line_connections_for_resize(
                 color: *color,
             })
         }
+        ShapeKind::Callout { .. } => kind.update_for_resize(resized_conn_id, old_snaps, new_snaps),
         _ => None,
     }
 }
@@ -2302,6 +2505,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn document_callout_roundtrip() {
+        let mut doc = Document::new();
+        let conn = 42;
+        let callout = ShapeKind::Callout {
+            start: Position::new(0, 0),
+            end: Position::new(10, 4),
+            tail: Position::new(20, 2),
+            tail_connection: Some(conn),
+            label: Some("Note".to_string()),
+            color: ShapeColor::Yellow,
+        };
+        let id = doc.add_shape(callout).unwrap();
+
+        let kind = doc.read_shape(id).unwrap().expect("callout should exist");
+        if let ShapeKind::Callout {
+            tail,
+            tail_connection,
+            label,
+            color,
+            ..
+        } = kind
+        {
+            assert_eq!(tail, Position::new(20, 2));
+            assert_eq!(tail_connection, Some(conn));
+            assert_eq!(label.as_deref(), Some("Note"));
+            assert_eq!(color, ShapeColor::Yellow);
+        } else {
+            panic!("Expected Callout");
+        }
+    }
+
+    #[test]
+    fn document_polygon_roundtrip() {
+        let mut doc = Document::new();
+        let points = vec![
+            Position::new(0, 0),
+            Position::new(6, 0),
+            Position::new(3, 4),
+        ];
+        let polygon = ShapeKind::Polygon {
+            points: points.clone(),
+            closed: true,
+            label: None,
+            color: ShapeColor::Cyan,
+        };
+        let id = doc.add_shape(polygon).unwrap();
+
+        let kind = doc.read_shape(id).unwrap().expect("polygon should exist");
+        if let ShapeKind::Polygon {
+            points: read_points,
+            closed,
+            color,
+            ..
+        } = kind
+        {
+            assert_eq!(read_points, points);
+            assert!(closed);
+            assert_eq!(color, ShapeColor::Cyan);
+        } else {
+            panic!("Expected Polygon");
+        }
+    }
+
+    #[test]
+    fn connection_ids_with_the_high_bit_set_roundtrip() {
+        // Connection IDs are the low 64 bits of a random UUID, so about half
+        // have the high bit set and are stored as negative i64s
+        let mut doc = Document::new();
+        let high = 0x8000_0000_0000_0001u64;
+        let id = doc
+            .add_shape(ShapeKind::Line {
+                start: Position::new(0, 0),
+                end: Position::new(5, 0),
+                color: ShapeColor::default(),
+                style: LineStyle::Straight,
+                start_connection: Some(high),
+                end_connection: Some(u64::MAX),
+                label: None,
+            })
+            .unwrap();
+
+        let kind = doc.read_shape(id).unwrap().expect("line should exist");
+        if let ShapeKind::Line {
+            start_connection,
+            end_connection,
+            ..
+        } = kind
+        {
+            assert_eq!(start_connection, Some(high));
+            assert_eq!(end_connection, Some(u64::MAX));
+        } else {
+            panic!("Expected Line");
+        }
+    }
+
     #[test]
     fn document_delete_shape() {
         let mut doc = Document::new();
@@ -2583,6 +2882,26 @@ mod tests {
         assert_eq!(doc.get_shape_group(id2).unwrap(), None);
     }
 
+    // --- Settings tests ---
+
+    #[test]
+    fn document_braille_setting() {
+        let mut doc = Document::new();
+        assert!(!doc.braille());
+
+        doc.set_braille(true).unwrap();
+        assert!(doc.braille());
+        assert!(doc.is_dirty());
+
+        let mut view = crate::shapes::ShapeView::new();
+        view.rebuild(&doc).unwrap();
+        assert!(view.braille());
+
+        // The setting travels with the document
+        let copy = Document::from_automerge(doc.clone_automerge());
+        assert!(copy.braille());
+    }
+
     // --- Persistence tests ---
 
     #[test]
@@ -2658,6 +2977,78 @@ mod tests {
         assert!(doc.is_dirty());
     }
 
+    // --- Change delta tests ---
+
+    #[test]
+    fn changes_since_carries_only_new_changes() {
+        let mut local = Document::new();
+        local.add_shape(make_rect(0, 0, 10, 5)).unwrap();
+
+        let mut remote = Document::from_automerge(Automerge::new());
+        remote.apply_changes(&local.changes_since(&[])).unwrap();
+        assert_eq!(remote.read_all_shapes().unwrap().len(), 1);
+
+        let heads = local.heads();
+        let id = local.add_shape(make_line(0, 0, 5, 5)).unwrap();
+        let delta = local.changes_since(&heads);
+        assert!(delta.len() < local.changes_since(&[]).len());
+
+        remote.apply_changes(&delta).unwrap();
+        assert_eq!(remote.read_all_shapes().unwrap().len(), 2);
+        assert!(remote.read_shape(id).unwrap().is_some());
+        assert_eq!(remote.heads(), local.heads());
+    }
+
+    #[test]
+    fn changes_since_current_heads_is_empty() {
+        let mut doc = Document::new();
+        doc.add_shape(make_rect(0, 0, 10, 5)).unwrap();
+        assert!(doc.changes_since(&doc.heads()).is_empty());
+    }
+
+    #[test]
+    fn apply_changes_is_idempotent() {
+        let mut local = Document::new();
+        local.add_shape(make_rect(0, 0, 10, 5)).unwrap();
+        let changes = local.changes_since(&[]);
+
+        let mut remote = Document::from_automerge(Automerge::new());
+        remote.apply_changes(&changes).unwrap();
+        remote.apply_changes(&changes).unwrap();
+        assert_eq!(remote.read_all_shapes().unwrap().len(), 1);
+        assert!(remote.is_dirty());
+    }
+
+    #[test]
+    fn apply_empty_changes_keeps_clean() {
+        let mut doc = Document::new();
+        doc.apply_changes(&[]).unwrap();
+        assert!(!doc.is_dirty());
+    }
+
+    #[test]
+    fn read_only_refuses_local_edits_but_merges_remote_ones() {
+        let mut doc = Document::new();
+        let kept = doc.add_shape(make_rect(0, 0, 10, 5)).unwrap();
+        let mut editor = Document::from_automerge(doc.clone_automerge());
+        let heads = doc.heads();
+
+        doc.set_read_only(true);
+        assert!(doc.add_shape(make_line(0, 0, 5, 5)).is_err());
+        doc.translate_shape(kept, 3, 3).unwrap();
+        doc.delete_shape(kept).unwrap();
+        doc.bring_to_front(&[kept]).unwrap();
+        assert!(!doc.global_undo().unwrap());
+        assert_eq!(doc.heads(), heads);
+        assert!(doc.take_refused_edit());
+        assert!(!doc.take_refused_edit());
+
+        let added = editor.add_shape(make_line(0, 0, 5, 5)).unwrap();
+        doc.apply_changes(&editor.changes_since(&heads)).unwrap();
+        assert!(doc.read_shape(added).unwrap().is_some());
+        assert!(!doc.take_refused_edit());
+    }
+
     // --- Helper function tests ---
 
     #[test]
@@ -10,14 +10,17 @@
 
 mod document;
 mod layers;
+mod recognize;
 mod shapes;
 
 pub use document::{Document, DocumentId, Group, GroupId, ShapeId, default_storage_path};
 pub use layers::{Layer, LayerId};
+pub use recognize::recognize_freehand;
 pub use shapes::{
     CachedShape, ResizeHandle, ResizeHandleInfo, ShapeColor, ShapeKind, ShapeView, SnapPoint,
-    find_corresponding_snap, flip_horizontal, flip_vertical, resize_shape, rotate_90_ccw,
-    rotate_90_cw,
+    STROKE_SIMPLIFY_TOLERANCE, STROKE_SMOOTH_ITERATIONS, delete_vertex, find_corresponding_snap,
+    find_vertex_edge, flip_horizontal, flip_vertical, freehand_to_polyline, insert_vertex,
+    move_vertex, resize_shape, rotate_90_ccw, rotate_90_cw, simplify_freehand, vertex_handles,
 };
 
 // Re-export geometry types for convenience
@@ -0,0 +1,403 @@
+//! Shape recognition for freehand strokes.
+//!
+//! Classifies a `ShapeKind::Freehand` stroke as a rectangle, ellipse, diamond,
+//! triangle, straight line or arrow. Closed strokes are fitted against each
+//! candidate outline (rasterized with the same functions used for rendering)
+//! and the closest match wins; open strokes are tested for straightness.
+
+use irohscii_geometry::{
+    LineStyle, Position, diamond_points, ellipse_points, line_points, rect_points, triangle_points,
+};
+
+use crate::shapes::ShapeKind;
+
+/// Endpoints closer than this fraction of the stroke size make the stroke closed
+const CLOSE_RATIO: f64 = 0.25;
+/// Maximum deviation from the chord (as a fraction of its length) for a straight stroke
+const STRAIGHT_RATIO: f64 = 0.12;
+/// Maximum mean fit error for a closed shape (as a fraction of the stroke size)
+const FIT_RATIO: f64 = 0.12;
+/// An arrowhead scribble may reach back at most this fraction of the shaft length
+const ARROWHEAD_RATIO: f64 = 0.5;
+
+// Compile-time assertions for recognition thresholds
+const _: () = assert!(CLOSE_RATIO > 0.0 && CLOSE_RATIO < 1.0);
+const _: () = assert!(STRAIGHT_RATIO > 0.0 && STRAIGHT_RATIO < 1.0);
+const _: () = assert!(FIT_RATIO > 0.0 && FIT_RATIO < 1.0);
+const _: () = assert!(ARROWHEAD_RATIO > 0.0 && ARROWHEAD_RATIO < 1.0);
+
+/// Recognize a freehand stroke as a clean shape, keeping its label and color.
+///
+/// Returns `None` for non-freehand shapes and for strokes that match nothing.
+/// Recognized lines and arrows have no connections; callers attach them.
+pub fn recognize_freehand(kind: &ShapeKind) -> Option<ShapeKind> {
+    let ShapeKind::Freehand {
+        points,
+        label,
+        color,
+        ..
+    } = kind
+    else {
+        return None;
+    };
+
+    // Simplified strokes only store their vertices; fit against the drawn path
+    let points = &densify(points);
+    let (&first, &last) = (points.first()?, points.last()?);
+    let (min_x, min_y, max_x, max_y) = point_bounds(points);
+    let size = f64::from((max_x - min_x).max(max_y - min_y));
+    if size < 2.0 {
+        return None;
+    }
+
+    let closes = distance(first, last) <= (size * CLOSE_RATIO).max(1.5);
+    let has_area = max_x - min_x >= 2 && max_y - min_y >= 2;
+
+    let recognized = if closes && has_area && points.len() >= 4 {
+        recognize_closed(points, (min_x, min_y, max_x, max_y), size)?
+    } else {
+        recognize_open(points)?
+    };
+
+    Some(recognized.with_label(label.clone()).with_color(*color))
+}
+
+// ========== Open strokes ==========
+
+fn recognize_open(points: &[Position]) -> Option<ShapeKind> {
+    let first = points[0];
+
+    // The shaft runs from the start to the point farthest from it; anything after
+    // that is either nothing (a line) or a short scribble back (an arrowhead)
+    let tip_idx = points
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| distance(first, **a).total_cmp(&distance(first, **b)))
+        .map(|(i, _)| i)?;
+    let tip = points[tip_idx];
+    let shaft_len = distance(first, tip);
+    if shaft_len < 2.0 || !is_straight(&points[..=tip_idx]) {
+        return None;
+    }
+
+    let head = &points[tip_idx..];
+    let head_reach = head.iter().map(|p| distance(*p, tip)).fold(0.0, f64::max);
+    let is_arrow = head_reach >= 1.5 && head_reach <= shaft_len * ARROWHEAD_RATIO;
+    if !is_arrow && head_reach > 1.0 {
+        return None;
+    }
+
+    let (start, end) = (first, tip);
+    Some(if is_arrow {
+        ShapeKind::Arrow {
+            start,
+            end,
+            style: LineStyle::Straight,
+            start_connection: None,
+            end_connection: None,
+            label: None,
+            color: Default::default(),
+        }
+    } else {
+        ShapeKind::Line {
+            start,
+            end,
+            style: LineStyle::Straight,
+            start_connection: None,
+            end_connection: None,
+            label: None,
+            color: Default::default(),
+        }
+    })
+}
+
+/// Check that every point lies close to the chord from the first to the last point
+fn is_straight(points: &[Position]) -> bool {
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+        return false;
+    };
+    let len = distance(first, last);
+    if len == 0.0 {
+        return false;
+    }
+
+    let tolerance = (len * STRAIGHT_RATIO).max(1.0);
+    points
+        .iter()
+        .all(|p| distance_to_segment(*p, first, last) <= tolerance)
+}
+
+// ========== Closed strokes ==========
+
+fn recognize_closed(
+    points: &[Position],
+    bounds: (i32, i32, i32, i32),
+    size: f64,
+) -> Option<ShapeKind> {
+    let (min_x, min_y, max_x, max_y) = bounds;
+    let center = Position::new((min_x + max_x) / 2, (min_y + max_y) / 2);
+    let half_w = (max_x - min_x) / 2;
+    let half_h = (max_y - min_y) / 2;
+    let (p1, p2, p3) = triangle_corners(points);
+
+    let candidates = [
+        ShapeKind::Rectangle {
+            start: Position::new(min_x, min_y),
+            end: Position::new(max_x, max_y),
+            label: None,
+            color: Default::default(),
+        },
+        ShapeKind::Ellipse {
+            center,
+            radius_x: half_w,
+            radius_y: half_h,
+            label: None,
+            color: Default::default(),
+        },
+        ShapeKind::Diamond {
+            center,
+            half_width: half_w,
+            half_height: half_h,
+            label: None,
+            color: Default::default(),
+        },
+        ShapeKind::Triangle {
+            p1,
+            p2,
+            p3,
+            label: None,
+            color: Default::default(),
+        },
+    ];
+
+    let (best, error) = candidates
+        .into_iter()
+        .map(|candidate| {
+            let error = fit_error(points, &outline_of(&candidate));
+            (candidate, error)
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+    (error <= (size * FIT_RATIO).max(1.0)).then_some(best)
+}
+
+/// Rasterize a candidate's outline
+fn outline_of(kind: &ShapeKind) -> Vec<Position> {
+    let points = match kind {
+        ShapeKind::Rectangle { start, end, .. } => rect_points(*start, *end),
+        ShapeKind::Ellipse {
+            center,
+            radius_x,
+            radius_y,
+            ..
+        } => ellipse_points(*center, *radius_x, *radius_y),
+        ShapeKind::Diamond {
+            center,
+            half_width,
+            half_height,
+            ..
+        } => diamond_points(*center, *half_width, *half_height),
+        ShapeKind::Triangle { p1, p2, p3, .. } => triangle_points(*p1, *p2, *p3),
+        _ => unreachable!("outline_of called on non-candidate shape"),
+    };
+    points.into_iter().map(|(pos, _)| pos).collect()
+}
+
+/// Pick three triangle corners: the point farthest from the centroid, the point
+/// farthest from that, and the point farthest from the line between them
+fn triangle_corners(points: &[Position]) -> (Position, Position, Position) {
+    debug_assert!(!points.is_empty(), "triangle_corners needs points");
+
+    let n = points.len() as f64;
+    let cx = points.iter().map(|p| f64::from(p.x)).sum::<f64>() / n;
+    let cy = points.iter().map(|p| f64::from(p.y)).sum::<f64>() / n;
+    let farthest_by = |score: &dyn Fn(Position) -> f64| {
+        points
+            .iter()
+            .copied()
+            .max_by(|a, b| score(*a).total_cmp(&score(*b)))
+            .unwrap_or(points[0])
+    };
+
+    let a = farthest_by(&|p| (f64::from(p.x) - cx).hypot(f64::from(p.y) - cy));
+    let b = farthest_by(&|p| distance(a, p));
+    let c = farthest_by(&|p| distance_to_segment(p, a, b));
+    (a, b, c)
+}
+
+/// Symmetric mean distance between the stroke and a candidate outline
+fn fit_error(stroke: &[Position], outline: &[Position]) -> f64 {
+    if outline.is_empty() {
+        return f64::INFINITY;
+    }
+    (mean_nearest(stroke, outline) + mean_nearest(outline, stroke)) / 2.0
+}
+
+fn mean_nearest(from: &[Position], to: &[Position]) -> f64 {
+    let total: f64 = from
+        .iter()
+        .map(|p| {
+            to.iter()
+                .map(|q| distance(*p, *q))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .sum();
+    total / from.len() as f64
+}
+
+// ========== Helpers ==========
+
+/// Fill the gaps between consecutive stroke vertices
+fn densify(points: &[Position]) -> Vec<Position> {
+    let mut dense: Vec<Position> = points.first().copied().into_iter().collect();
+    for pair in points.windows(2) {
+        dense.extend(line_points(pair[0], pair[1]).into_iter().skip(1));
+    }
+    dense
+}
+
+fn point_bounds(points: &[Position]) -> (i32, i32, i32, i32) {
+    points.iter().fold(
+        (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+        |(min_x, min_y, max_x, max_y), p| {
+            (
+                min_x.min(p.x),
+                min_y.min(p.y),
+                max_x.max(p.x),
+                max_y.max(p.y),
+            )
+        },
+    )
+}
+
+fn distance(a: Position, b: Position) -> f64 {
+    f64::from(a.x - b.x).hypot(f64::from(a.y - b.y))
+}
+
+fn distance_to_segment(p: Position, a: Position, b: Position) -> f64 {
+    let (abx, aby) = (f64::from(b.x - a.x), f64::from(b.y - a.y));
+    let len_sq = abx * abx + aby * aby;
+    if len_sq == 0.0 {
+        return distance(p, a);
+    }
+    let (apx, apy) = (f64::from(p.x - a.x), f64::from(p.y - a.y));
+    let t = ((apx * abx + apy * aby) / len_sq).clamp(0.0, 1.0);
+    (apx - t * abx).hypot(apy - t * aby)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{ShapeColor, simplify_freehand};
+
+    /// Build a contiguous stroke through the given corners, like the freehand tool does
+    fn stroke(corners: &[Position]) -> ShapeKind {
+        let mut points = vec![corners[0]];
+        for pair in corners.windows(2) {
+            points.extend(line_points(pair[0], pair[1]).into_iter().skip(1));
+        }
+        ShapeKind::Freehand {
+            points,
+            char: '*',
+            label: None,
+            color: ShapeColor::Green,
+        }
+    }
+
+    fn p(x: i32, y: i32) -> Position {
+        Position::new(x, y)
+    }
+
+    #[test]
+    fn recognizes_rectangle() {
+        let kind = stroke(&[p(0, 0), p(20, 0), p(20, 8), p(0, 8), p(0, 1)]);
+        let recognized = recognize_freehand(&kind).expect("rectangle");
+        if let ShapeKind::Rectangle {
+            start, end, color, ..
+        } = recognized
+        {
+            assert_eq!((start, end), (p(0, 0), p(20, 8)));
+            assert_eq!(color, ShapeColor::Green);
+        } else {
+            panic!("Expected Rectangle, got {}", recognized.type_name());
+        }
+    }
+
+    #[test]
+    fn recognizes_ellipse() {
+        let corners: Vec<Position> = (0..=24)
+            .map(|i| {
+                let angle = f64::from(i) / 24.0 * std::f64::consts::TAU;
+                p(
+                    (20.0 + 16.0 * angle.cos()).round() as i32,
+                    (10.0 + 7.0 * angle.sin()).round() as i32,
+                )
+            })
+            .collect();
+        let recognized = recognize_freehand(&stroke(&corners)).expect("ellipse");
+        assert_eq!(recognized.type_name(), "Ellipse");
+    }
+
+    #[test]
+    fn recognizes_diamond_and_triangle() {
+        let diamond = stroke(&[p(10, 0), p(20, 5), p(10, 10), p(0, 5), p(9, 1)]);
+        assert_eq!(recognize_freehand(&diamond).unwrap().type_name(), "Diamond");
+
+        let triangle = stroke(&[p(0, 10), p(10, 0), p(20, 10), p(1, 10)]);
+        assert_eq!(
+            recognize_freehand(&triangle).unwrap().type_name(),
+            "Triangle"
+        );
+    }
+
+    #[test]
+    fn recognizes_line_and_arrow() {
+        let line = recognize_freehand(&stroke(&[p(0, 0), p(15, 3)])).expect("line");
+        if let ShapeKind::Line { start, end, .. } = line {
+            assert_eq!((start, end), (p(0, 0), p(15, 3)));
+        } else {
+            panic!("Expected Line, got {}", line.type_name());
+        }
+
+        // Shaft to the right, then a barb flicked back from the tip
+        let arrow = recognize_freehand(&stroke(&[p(0, 0), p(20, 0), p(17, -2)])).expect("arrow");
+        if let ShapeKind::Arrow { start, end, .. } = arrow {
+            assert_eq!((start, end), (p(0, 0), p(20, 0)));
+        } else {
+            panic!("Expected Arrow, got {}", arrow.type_name());
+        }
+    }
+
+    #[test]
+    fn rejects_scribbles_and_other_shapes() {
+        let zigzag = stroke(&[p(0, 0), p(5, 8), p(10, 0), p(15, 8), p(20, 0)]);
+        assert!(recognize_freehand(&zigzag).is_none());
+        assert!(recognize_freehand(&stroke(&[p(0, 0), p(1, 0)])).is_none());
+
+        let rect = ShapeKind::Rectangle {
+            start: p(0, 0),
+            end: p(4, 4),
+            label: None,
+            color: ShapeColor::White,
+        };
+        assert!(recognize_freehand(&rect).is_none());
+    }
+
+    #[test]
+    fn recognizes_simplified_stroke() {
+        let kind = stroke(&[p(0, 0), p(20, 0), p(20, 8), p(0, 8), p(0, 1)]);
+        let simplified = simplify_freehand(&kind, false).unwrap();
+        assert!(simplified.vertices().unwrap().len() < 8);
+        assert_eq!(
+            recognize_freehand(&simplified).unwrap().type_name(),
+            "Rectangle"
+        );
+    }
+
+    #[test]
+    fn keeps_label() {
+        let kind = stroke(&[p(0, 0), p(12, 0)]).with_label(Some("edge".to_string()));
+        let recognized = recognize_freehand(&kind).unwrap();
+        assert_eq!(recognized.label(), Some("edge"));
+    }
+}
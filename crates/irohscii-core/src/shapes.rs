@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::document::{Document, ShapeId};
 use crate::layers::LayerId;
-use irohscii_geometry::{LineStyle, Position};
+use irohscii_geometry::{LineStyle, Position, line_points, simplify_path, smooth_path};
 
 /// Color for shapes - 16-color terminal palette
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -211,6 +211,15 @@ pub enum ShapeKind {
         #[serde(default)]
         color: ShapeColor,
     },
+    /// A polygon through an arbitrary vertex list (open polyline or closed outline)
+    Polygon {
+        points: Vec<Position>,
+        #[serde(default)]
+        closed: bool,
+        label: Option<String>,
+        #[serde(default)]
+        color: ShapeColor,
+    },
     /// A parallelogram defined by start and end (slanted rectangle)
     Parallelogram {
         start: Position,
@@ -260,6 +269,17 @@ pub enum ShapeKind {
         #[serde(default)]
         color: ShapeColor,
     },
+    /// A callout (speech bubble) with a tail pointing at a position or a connected shape
+    Callout {
+        start: Position,
+        end: Position,
+        tail: Position,
+        #[serde(default)]
+        tail_connection: Option<u64>,
+        label: Option<String>,
+        #[serde(default)]
+        color: ShapeColor,
+    },
     /// A star shape
     Star {
         center: Position,
@@ -284,12 +304,14 @@ impl ShapeKind {
             ShapeKind::Freehand { .. } => "Freehand",
             ShapeKind::Text { .. } => "Text",
             ShapeKind::Triangle { .. } => "Triangle",
+            ShapeKind::Polygon { .. } => "Polygon",
             ShapeKind::Parallelogram { .. } => "Parallelogram",
             ShapeKind::Hexagon { .. } => "Hexagon",
             ShapeKind::Trapezoid { .. } => "Trapezoid",
             ShapeKind::RoundedRect { .. } => "RoundedRect",
             ShapeKind::Cylinder { .. } => "Cylinder",
             ShapeKind::Cloud { .. } => "Cloud",
+            ShapeKind::Callout { .. } => "Callout",
             ShapeKind::Star { .. } => "Star",
         }
     }
@@ -311,12 +333,14 @@ impl ShapeKind {
             ShapeKind::Freehand { .. } => translate_freehand(self, dx, dy),
             ShapeKind::Text { .. } => translate_text(self, dx, dy),
             ShapeKind::Triangle { .. } => translate_triangle(self, dx, dy),
+            ShapeKind::Polygon { .. } => translate_polygon(self, dx, dy),
             ShapeKind::Parallelogram { .. } => translate_parallelogram(self, dx, dy),
             ShapeKind::Hexagon { .. } => translate_hexagon(self, dx, dy),
             ShapeKind::Trapezoid { .. } => translate_trapezoid(self, dx, dy),
             ShapeKind::RoundedRect { .. } => translate_rounded_rect(self, dx, dy),
             ShapeKind::Cylinder { .. } => translate_cylinder(self, dx, dy),
             ShapeKind::Cloud { .. } => translate_cloud(self, dx, dy),
+            ShapeKind::Callout { .. } => translate_callout(self, dx, dy),
             ShapeKind::Star { .. } => translate_star(self, dx, dy),
         }
     }
@@ -332,12 +356,14 @@ impl ShapeKind {
             | ShapeKind::Ellipse { label, .. }
             | ShapeKind::Freehand { label, .. }
             | ShapeKind::Triangle { label, .. }
+            | ShapeKind::Polygon { label, .. }
             | ShapeKind::Parallelogram { label, .. }
             | ShapeKind::Hexagon { label, .. }
             | ShapeKind::Trapezoid { label, .. }
             | ShapeKind::RoundedRect { label, .. }
             | ShapeKind::Cylinder { label, .. }
             | ShapeKind::Cloud { label, .. }
+            | ShapeKind::Callout { label, .. }
             | ShapeKind::Star { label, .. } => label.as_deref(),
             ShapeKind::Text { .. } => None, // Text content is the label
         }
@@ -354,12 +380,14 @@ impl ShapeKind {
             ShapeKind::Ellipse { .. } => with_label_ellipse(self, new_label),
             ShapeKind::Freehand { .. } => with_label_freehand(self, new_label),
             ShapeKind::Triangle { .. } => with_label_triangle(self, new_label),
+            ShapeKind::Polygon { .. } => with_label_polygon(self, new_label),
             ShapeKind::Parallelogram { .. } => with_label_parallelogram(self, new_label),
             ShapeKind::Hexagon { .. } => with_label_hexagon(self, new_label),
             ShapeKind::Trapezoid { .. } => with_label_trapezoid(self, new_label),
             ShapeKind::RoundedRect { .. } => with_label_rounded_rect(self, new_label),
             ShapeKind::Cylinder { .. } => with_label_cylinder(self, new_label),
             ShapeKind::Cloud { .. } => with_label_cloud(self, new_label),
+            ShapeKind::Callout { .. } => with_label_callout(self, new_label),
             ShapeKind::Star { .. } => with_label_star(self, new_label),
             other => other, // Text doesn't have a separate label
         }
@@ -377,12 +405,14 @@ impl ShapeKind {
             | ShapeKind::Freehand { color, .. }
             | ShapeKind::Text { color, .. }
             | ShapeKind::Triangle { color, .. }
+            | ShapeKind::Polygon { color, .. }
             | ShapeKind::Parallelogram { color, .. }
             | ShapeKind::Hexagon { color, .. }
             | ShapeKind::Trapezoid { color, .. }
             | ShapeKind::RoundedRect { color, .. }
             | ShapeKind::Cylinder { color, .. }
             | ShapeKind::Cloud { color, .. }
+            | ShapeKind::Callout { color, .. }
             | ShapeKind::Star { color, .. } => *color,
         }
     }
@@ -399,12 +429,14 @@ impl ShapeKind {
             ShapeKind::Freehand { .. } => with_color_freehand(self, new_color),
             ShapeKind::Text { .. } => with_color_text(self, new_color),
             ShapeKind::Triangle { .. } => with_color_triangle(self, new_color),
+            ShapeKind::Polygon { .. } => with_color_polygon(self, new_color),
             ShapeKind::Parallelogram { .. } => with_color_parallelogram(self, new_color),
             ShapeKind::Hexagon { .. } => with_color_hexagon(self, new_color),
             ShapeKind::Trapezoid { .. } => with_color_trapezoid(self, new_color),
             ShapeKind::RoundedRect { .. } => with_color_rounded_rect(self, new_color),
             ShapeKind::Cylinder { .. } => with_color_cylinder(self, new_color),
             ShapeKind::Cloud { .. } => with_color_cloud(self, new_color),
+            ShapeKind::Callout { .. } => with_color_callout(self, new_color),
             ShapeKind::Star { .. } => with_color_star(self, new_color),
         }
     }
@@ -421,12 +453,14 @@ impl ShapeKind {
                 | ShapeKind::Ellipse { .. }
                 | ShapeKind::Freehand { .. }
                 | ShapeKind::Triangle { .. }
+                | ShapeKind::Polygon { .. }
                 | ShapeKind::Parallelogram { .. }
                 | ShapeKind::Hexagon { .. }
                 | ShapeKind::Trapezoid { .. }
                 | ShapeKind::RoundedRect { .. }
                 | ShapeKind::Cylinder { .. }
                 | ShapeKind::Cloud { .. }
+                | ShapeKind::Callout { .. }
                 | ShapeKind::Star { .. }
         )
     }
@@ -445,6 +479,9 @@ impl ShapeKind {
                 end_connection,
                 ..
             } => (*start_connection, *end_connection),
+            ShapeKind::Callout {
+                tail_connection, ..
+            } => (None, *tail_connection),
             _ => (None, None),
         }
     }
@@ -463,6 +500,9 @@ impl ShapeKind {
             ShapeKind::Arrow { .. } => {
                 translate_connected_endpoints_arrow(self, target_id, dx, dy)
             }
+            ShapeKind::Callout { .. } => {
+                translate_connected_endpoints_callout(self, target_id, dx, dy)
+            }
             _ => None,
         }
     }
@@ -482,10 +522,52 @@ impl ShapeKind {
             ShapeKind::Arrow { .. } => {
                 update_for_resize_arrow(self, resized_id, old_snaps, new_snaps)
             }
+            ShapeKind::Callout { .. } => {
+                update_for_resize_callout(self, resized_id, old_snaps, new_snaps)
+            }
             _ => None,
         }
     }
 
+    /// Drop the connections to `shape_conn_id` whose endpoint sits at `pos`
+    /// (used when the snap point they were attached to is removed)
+    pub fn detach_at(&self, shape_conn_id: u64, pos: Position) -> Option<ShapeKind> {
+        let mut kind = self.clone();
+        let mut changed = false;
+        let mut detach = |end: Position, connection: &mut Option<u64>| {
+            if end == pos && *connection == Some(shape_conn_id) {
+                *connection = None;
+                changed = true;
+            }
+        };
+        match &mut kind {
+            ShapeKind::Line {
+                start,
+                end,
+                start_connection,
+                end_connection,
+                ..
+            }
+            | ShapeKind::Arrow {
+                start,
+                end,
+                start_connection,
+                end_connection,
+                ..
+            } => {
+                detach(*start, start_connection);
+                detach(*end, end_connection);
+            }
+            ShapeKind::Callout {
+                tail,
+                tail_connection,
+                ..
+            } => detach(*tail, tail_connection),
+            _ => {}
+        }
+        changed.then_some(kind)
+    }
+
     /// Get snap points for this shape (used for connection updates during resize)
     pub fn snap_points(&self) -> Vec<Position> {
         match self {
@@ -500,15 +582,36 @@ impl ShapeKind {
             ShapeKind::Text { .. } => compute_text_snap_points(self),
             ShapeKind::Freehand { .. } => compute_freehand_snap_points(self),
             ShapeKind::Triangle { p1, p2, p3, .. } => vec![*p1, *p2, *p3],
+            ShapeKind::Polygon { points, .. } => points.clone(),
             ShapeKind::Parallelogram { .. }
             | ShapeKind::Trapezoid { .. }
             | ShapeKind::RoundedRect { .. }
             | ShapeKind::Cylinder { .. }
-            | ShapeKind::Cloud { .. } => compute_rect_snap_points(self),
+            | ShapeKind::Cloud { .. }
+            | ShapeKind::Callout { .. } => compute_rect_snap_points(self),
             ShapeKind::Hexagon { .. } => compute_hexagon_snap_points(self),
             ShapeKind::Star { .. } => compute_star_snap_points(self),
         }
     }
+
+    /// Get the editable vertices of this shape (Triangle, Polygon and Freehand only)
+    pub fn vertices(&self) -> Option<Vec<Position>> {
+        match self {
+            ShapeKind::Triangle { p1, p2, p3, .. } => Some(vec![*p1, *p2, *p3]),
+            ShapeKind::Polygon { points, .. } | ShapeKind::Freehand { points, .. } => {
+                Some(points.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if this shape can be edited vertex by vertex
+    pub fn supports_vertex_edit(&self) -> bool {
+        matches!(
+            self,
+            ShapeKind::Triangle { .. } | ShapeKind::Polygon { .. } | ShapeKind::Freehand { .. }
+        )
+    }
 }
 
 // ========== Private helper functions for translation ==========
@@ -669,6 +772,28 @@ fn translate_freehand(kind: &ShapeKind, dx: i32, dy: i32) -> ShapeKind {
     }
 }
 
+fn translate_polygon(kind: &ShapeKind, dx: i32, dy: i32) -> ShapeKind {
+    if let ShapeKind::Polygon {
+        points,
+        closed,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Polygon {
+            points: points
+                .iter()
+                .map(|p| translate_position(*p, dx, dy))
+                .collect(),
+            closed: *closed,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("translate_polygon called on non-Polygon shape")
+    }
+}
+
 fn translate_text(kind: &ShapeKind, dx: i32, dy: i32) -> ShapeKind {
     if let ShapeKind::Text {
         pos,
@@ -823,6 +948,35 @@ fn translate_cloud(kind: &ShapeKind, dx: i32, dy: i32) -> ShapeKind {
     }
 }
 
+fn translate_callout(kind: &ShapeKind, dx: i32, dy: i32) -> ShapeKind {
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        color,
+    } = kind
+    {
+        // A connected tail stays on its target; the tail re-aims from the moved body
+        let new_tail = if tail_connection.is_some() {
+            *tail
+        } else {
+            translate_position(*tail, dx, dy)
+        };
+        ShapeKind::Callout {
+            start: translate_position(*start, dx, dy),
+            end: translate_position(*end, dx, dy),
+            tail: new_tail,
+            tail_connection: *tail_connection,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("translate_callout called on non-Callout shape")
+    }
+}
+
 fn translate_star(kind: &ShapeKind, dx: i32, dy: i32) -> ShapeKind {
     if let ShapeKind::Star {
         center,
@@ -989,6 +1143,25 @@ fn with_label_freehand(kind: ShapeKind, new_label: Option<String>) -> ShapeKind
     }
 }
 
+fn with_label_polygon(kind: ShapeKind, new_label: Option<String>) -> ShapeKind {
+    if let ShapeKind::Polygon {
+        points,
+        closed,
+        color,
+        ..
+    } = kind
+    {
+        ShapeKind::Polygon {
+            points,
+            closed,
+            label: new_label,
+            color,
+        }
+    } else {
+        unreachable!("with_label_polygon called on non-Polygon shape")
+    }
+}
+
 fn with_label_triangle(kind: ShapeKind, new_label: Option<String>) -> ShapeKind {
     if let ShapeKind::Triangle {
         p1, p2, p3, color, ..
@@ -1107,6 +1280,29 @@ fn with_label_cloud(kind: ShapeKind, new_label: Option<String>) -> ShapeKind {
     }
 }
 
+fn with_label_callout(kind: ShapeKind, new_label: Option<String>) -> ShapeKind {
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        color,
+        ..
+    } = kind
+    {
+        ShapeKind::Callout {
+            start,
+            end,
+            tail,
+            tail_connection,
+            label: new_label,
+            color,
+        }
+    } else {
+        unreachable!("with_label_callout called on non-Callout shape")
+    }
+}
+
 fn with_label_star(kind: ShapeKind, new_label: Option<String>) -> ShapeKind {
     if let ShapeKind::Star {
         center,
@@ -1273,6 +1469,25 @@ fn with_color_freehand(kind: ShapeKind, new_color: ShapeColor) -> ShapeKind {
     }
 }
 
+fn with_color_polygon(kind: ShapeKind, new_color: ShapeColor) -> ShapeKind {
+    if let ShapeKind::Polygon {
+        points,
+        closed,
+        label,
+        ..
+    } = kind
+    {
+        ShapeKind::Polygon {
+            points,
+            closed,
+            label,
+            color: new_color,
+        }
+    } else {
+        unreachable!("with_color_polygon called on non-Polygon shape")
+    }
+}
+
 fn with_color_text(kind: ShapeKind, new_color: ShapeColor) -> ShapeKind {
     if let ShapeKind::Text { pos, content, .. } = kind {
         ShapeKind::Text {
@@ -1403,6 +1618,29 @@ fn with_color_cloud(kind: ShapeKind, new_color: ShapeColor) -> ShapeKind {
     }
 }
 
+fn with_color_callout(kind: ShapeKind, new_color: ShapeColor) -> ShapeKind {
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        ..
+    } = kind
+    {
+        ShapeKind::Callout {
+            start,
+            end,
+            tail,
+            tail_connection,
+            label,
+            color: new_color,
+        }
+    } else {
+        unreachable!("with_color_callout called on non-Callout shape")
+    }
+}
+
 fn with_color_star(kind: ShapeKind, new_color: ShapeColor) -> ShapeKind {
     if let ShapeKind::Star {
         center,
@@ -1524,6 +1762,38 @@ fn translate_connected_endpoints_arrow(
     }
 }
 
+fn translate_connected_endpoints_callout(
+    kind: &ShapeKind,
+    target_id: u64,
+    dx: i32,
+    dy: i32,
+) -> Option<ShapeKind> {
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        color,
+    } = kind
+    {
+        if *tail_connection != Some(target_id) {
+            return None;
+        }
+
+        Some(ShapeKind::Callout {
+            start: *start,
+            end: *end,
+            tail: Position::new(tail.x + dx, tail.y + dy),
+            tail_connection: *tail_connection,
+            label: label.clone(),
+            color: *color,
+        })
+    } else {
+        unreachable!("translate_connected_endpoints_callout called on non-Callout shape")
+    }
+}
+
 // ========== Private helper functions for update_for_resize ==========
 
 fn update_for_resize_line(
@@ -1636,6 +1906,43 @@ fn update_for_resize_arrow(
     }
 }
 
+fn update_for_resize_callout(
+    kind: &ShapeKind,
+    resized_id: u64,
+    old_snaps: &[Position],
+    new_snaps: &[Position],
+) -> Option<ShapeKind> {
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        color,
+    } = kind
+    {
+        if *tail_connection != Some(resized_id) {
+            return None;
+        }
+
+        let new_tail = find_corresponding_snap(tail, old_snaps, new_snaps).unwrap_or(*tail);
+        if new_tail == *tail {
+            return None;
+        }
+
+        Some(ShapeKind::Callout {
+            start: *start,
+            end: *end,
+            tail: new_tail,
+            tail_connection: *tail_connection,
+            label: label.clone(),
+            color: *color,
+        })
+    } else {
+        unreachable!("update_for_resize_callout called on non-Callout shape")
+    }
+}
+
 // ========== Private helper functions for snap points computation ==========
 
 fn compute_rect_snap_points(kind: &ShapeKind) -> Vec<Position> {
@@ -1646,7 +1953,8 @@ fn compute_rect_snap_points(kind: &ShapeKind) -> Vec<Position> {
         | ShapeKind::Trapezoid { start, end, .. }
         | ShapeKind::RoundedRect { start, end, .. }
         | ShapeKind::Cylinder { start, end, .. }
-        | ShapeKind::Cloud { start, end, .. } => (*start, *end),
+        | ShapeKind::Cloud { start, end, .. }
+        | ShapeKind::Callout { start, end, .. } => (*start, *end),
         _ => unreachable!("compute_rect_snap_points called on non-rect-like shape"),
     };
 
@@ -1803,6 +2111,8 @@ pub enum ResizeHandle {
     BottomRight,
     Start,
     End,
+    /// A single vertex of a Triangle, Polygon or Freehand (vertex-edit mode)
+    Vertex(usize),
 }
 
 /// A resize handle with its position
@@ -1913,11 +2223,15 @@ impl CachedShape {
             ShapeKind::Freehand { points, .. } => compute_freehand_bounds(points),
             ShapeKind::Text { pos, content, .. } => compute_text_bounds(pos, content),
             ShapeKind::Triangle { p1, p2, p3, .. } => compute_triangle_bounds(p1, p2, p3),
+            ShapeKind::Polygon { points, .. } => compute_freehand_bounds(points),
             ShapeKind::Parallelogram { start, end, .. }
             | ShapeKind::Trapezoid { start, end, .. }
             | ShapeKind::RoundedRect { start, end, .. }
             | ShapeKind::Cylinder { start, end, .. }
             | ShapeKind::Cloud { start, end, .. } => compute_rect_bounds(start, end),
+            ShapeKind::Callout {
+                start, end, tail, ..
+            } => compute_callout_bounds(start, end, tail),
             ShapeKind::Hexagon {
                 center,
                 radius_x,
@@ -1976,6 +2290,8 @@ impl CachedShape {
             | ShapeKind::RoundedRect { .. }
             | ShapeKind::Cylinder { .. }
             | ShapeKind::Cloud { .. } => compute_rect_resize_handles(kind),
+            ShapeKind::Callout { .. } => compute_callout_resize_handles(kind),
+            ShapeKind::Polygon { .. } => vertex_handles(kind),
             ShapeKind::Hexagon { .. } => compute_hexagon_resize_handles(kind),
             ShapeKind::Star { .. } => compute_star_resize_handles(kind),
             _ => vec![],
@@ -2087,6 +2403,21 @@ fn compute_star_bounds(center: &Position, outer_radius: i32) -> (i32, i32, i32,
     )
 }
 
+fn compute_callout_bounds(
+    start: &Position,
+    end: &Position,
+    tail: &Position,
+) -> (i32, i32, i32, i32) {
+    let (min_x, min_y, max_x, max_y) = compute_rect_bounds(start, end);
+
+    (
+        min_x.min(tail.x),
+        min_y.min(tail.y),
+        max_x.max(tail.x),
+        max_y.max(tail.y),
+    )
+}
+
 // ========== Private helper functions for resize handles ==========
 
 fn compute_rect_resize_handles(kind: &ShapeKind) -> Vec<ResizeHandleInfo> {
@@ -2097,7 +2428,8 @@ fn compute_rect_resize_handles(kind: &ShapeKind) -> Vec<ResizeHandleInfo> {
         | ShapeKind::Trapezoid { start, end, .. }
         | ShapeKind::RoundedRect { start, end, .. }
         | ShapeKind::Cylinder { start, end, .. }
-        | ShapeKind::Cloud { start, end, .. } => (*start, *end),
+        | ShapeKind::Cloud { start, end, .. }
+        | ShapeKind::Callout { start, end, .. } => (*start, *end),
         _ => unreachable!("compute_rect_resize_handles called on non-rect-like shape"),
     };
 
@@ -2126,6 +2458,20 @@ fn compute_rect_resize_handles(kind: &ShapeKind) -> Vec<ResizeHandleInfo> {
     ]
 }
 
+fn compute_callout_resize_handles(kind: &ShapeKind) -> Vec<ResizeHandleInfo> {
+    if let ShapeKind::Callout { tail, .. } = kind {
+        // Body corners resize the bubble, the End handle drags the tail tip
+        let mut handles = compute_rect_resize_handles(kind);
+        handles.push(ResizeHandleInfo {
+            handle: ResizeHandle::End,
+            pos: *tail,
+        });
+        handles
+    } else {
+        unreachable!("compute_callout_resize_handles called on non-Callout shape")
+    }
+}
+
 fn compute_diamond_resize_handles(kind: &ShapeKind) -> Vec<ResizeHandleInfo> {
     if let ShapeKind::Diamond {
         center,
@@ -2250,7 +2596,7 @@ fn compute_star_resize_handles(kind: &ShapeKind) -> Vec<ResizeHandleInfo> {
 }
 
 /// Read-only cache of shapes for rendering (rebuilds from document)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ShapeView {
     /// Cached shapes in render order (layer-first, then z-order within layer)
     shapes: Vec<CachedShape>,
@@ -2258,6 +2604,8 @@ pub struct ShapeView {
     by_id: HashMap<ShapeId, usize>,
     /// Hidden layer IDs (for visibility toggle)
     hidden_layers: std::collections::HashSet<LayerId>,
+    /// Document setting: render curves and freehand strokes with braille dots
+    braille: bool,
 }
 
 impl ShapeView {
@@ -2266,6 +2614,7 @@ impl ShapeView {
             shapes: Vec::new(),
             by_id: HashMap::new(),
             hidden_layers: std::collections::HashSet::new(),
+            braille: false,
         }
     }
 
@@ -2273,10 +2622,19 @@ impl ShapeView {
     pub fn rebuild(&mut self, doc: &Document) -> anyhow::Result<()> {
         self.shapes.clear();
         self.by_id.clear();
+        self.braille = doc.braille();
 
         // Get layer order (bottom to top)
         let layer_order = doc.read_layer_order()?;
 
+        // Hidden layers come from the document, so a loaded file keeps them hidden
+        self.hidden_layers = doc
+            .read_all_layers()?
+            .into_iter()
+            .filter(|layer| !layer.visible)
+            .map(|layer| layer.id)
+            .collect();
+
         // Get the ordered list of shape IDs
         let shape_order = doc.read_shape_order()?;
 
@@ -2343,6 +2701,11 @@ impl ShapeView {
         }
     }
 
+    /// Whether curves and freehand strokes render at braille sub-cell resolution
+    pub fn braille(&self) -> bool {
+        self.braille
+    }
+
     /// Check if a layer is visible
     #[allow(dead_code)]
     pub fn is_layer_visible(&self, layer_id: LayerId) -> bool {
@@ -2364,6 +2727,17 @@ impl ShapeView {
         })
     }
 
+    /// Keep only the shapes matching a predicate, preserving render order
+    pub fn retain(&mut self, mut keep: impl FnMut(&CachedShape) -> bool) {
+        self.shapes.retain(|shape| keep(shape));
+        self.by_id = self
+            .shapes
+            .iter()
+            .enumerate()
+            .map(|(idx, shape)| (shape.id, idx))
+            .collect();
+    }
+
     /// Get total number of shapes
     pub fn shape_count(&self) -> usize {
         self.shapes.len()
@@ -2439,6 +2813,25 @@ impl ShapeView {
         None
     }
 
+    /// Find a vertex handle on a shape near a position (vertex-edit mode)
+    pub fn find_vertex_handle(
+        &self,
+        shape_id: ShapeId,
+        pos: Position,
+        threshold: i32,
+    ) -> Option<ResizeHandle> {
+        let shape = self.get(shape_id)?;
+        vertex_handles(&shape.kind)
+            .into_iter()
+            .map(|info| {
+                let dist = (pos.x - info.pos.x).abs() + (pos.y - info.pos.y).abs();
+                (info.handle, dist)
+            })
+            .filter(|&(_, dist)| dist <= threshold)
+            .min_by_key(|&(_, dist)| dist)
+            .map(|(handle, _)| handle)
+    }
+
     /// Check if empty
     pub fn is_empty(&self) -> bool {
         self.shapes.is_empty()
@@ -2545,6 +2938,10 @@ impl Default for ShapeView {
 
 /// Apply resize to a shape kind
 pub fn resize_shape(kind: &ShapeKind, handle: ResizeHandle, new_pos: Position) -> ShapeKind {
+    if let ResizeHandle::Vertex(index) = handle {
+        return move_vertex(kind, index, new_pos);
+    }
+
     match kind {
         ShapeKind::Rectangle { .. } => resize_rectangle(kind, handle, new_pos),
         ShapeKind::DoubleBox { .. } => resize_double_box(kind, handle, new_pos),
@@ -2558,6 +2955,7 @@ pub fn resize_shape(kind: &ShapeKind, handle: ResizeHandle, new_pos: Position) -
         ShapeKind::RoundedRect { .. } => resize_rounded_rect(kind, handle, new_pos),
         ShapeKind::Cylinder { .. } => resize_cylinder(kind, handle, new_pos),
         ShapeKind::Cloud { .. } => resize_cloud(kind, handle, new_pos),
+        ShapeKind::Callout { .. } => resize_callout(kind, handle, new_pos),
         ShapeKind::Hexagon { .. } => resize_hexagon(kind, handle, new_pos),
         ShapeKind::Star { .. } => resize_star(kind, handle, new_pos),
         _ => kind.clone(),
@@ -2886,6 +3284,41 @@ fn resize_cloud(kind: &ShapeKind, handle: ResizeHandle, new_pos: Position) -> Sh
     }
 }
 
+fn resize_callout(kind: &ShapeKind, handle: ResizeHandle, new_pos: Position) -> ShapeKind {
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        color,
+    } = kind
+    {
+        match handle {
+            // Dragging the tip detaches it; the caller re-attaches if it lands on a snap point
+            ResizeHandle::End => ShapeKind::Callout {
+                start: *start,
+                end: *end,
+                tail: new_pos,
+                tail_connection: None,
+                label: label.clone(),
+                color: *color,
+            },
+            ResizeHandle::Start => kind.clone(),
+            _ => resize_rect_like(start, end, handle, new_pos, |s, e| ShapeKind::Callout {
+                start: s,
+                end: e,
+                tail: *tail,
+                tail_connection: *tail_connection,
+                label: label.clone(),
+                color: *color,
+            }),
+        }
+    } else {
+        unreachable!("resize_callout called on non-Callout shape")
+    }
+}
+
 fn resize_hexagon(kind: &ShapeKind, handle: ResizeHandle, new_pos: Position) -> ShapeKind {
     if let ShapeKind::Hexagon {
         center,
@@ -3126,35 +3559,230 @@ pub fn find_corresponding_snap(
     best_idx.and_then(|idx| new_snaps.get(idx).copied())
 }
 
-/// Flip a shape horizontally (mirror across vertical axis through center_x).
-pub fn flip_horizontal(kind: &ShapeKind, center_x: i32) -> ShapeKind {
-    let mirror_x = |x: i32| {
-        debug_assert!(x.abs() < 100000, "Coordinate too large for mirroring");
-        2 * center_x - x
+// ========== Vertex editing ==========
+
+/// Resize handles on every vertex (Polygon always, Triangle/Freehand in vertex-edit mode)
+pub fn vertex_handles(kind: &ShapeKind) -> Vec<ResizeHandleInfo> {
+    kind.vertices()
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(index, pos)| ResizeHandleInfo {
+            handle: ResizeHandle::Vertex(index),
+            pos,
+        })
+        .collect()
+}
+
+/// Move a single vertex to a new position
+pub fn move_vertex(kind: &ShapeKind, index: usize, new_pos: Position) -> ShapeKind {
+    let Some(mut vertices) = kind.vertices() else {
+        return kind.clone();
+    };
+    let Some(vertex) = vertices.get_mut(index) else {
+        return kind.clone();
     };
+    *vertex = new_pos;
+    with_vertices(kind, vertices)
+}
 
-    match kind {
-        ShapeKind::Line { .. } => flip_horizontal_line(kind, mirror_x),
-        ShapeKind::Arrow { .. } => flip_horizontal_arrow(kind, mirror_x),
-        ShapeKind::Rectangle { .. } => flip_horizontal_rectangle(kind, mirror_x),
-        ShapeKind::DoubleBox { .. } => flip_horizontal_double_box(kind, mirror_x),
-        ShapeKind::Diamond { .. } => flip_horizontal_diamond(kind, mirror_x),
-        ShapeKind::Ellipse { .. } => flip_horizontal_ellipse(kind, mirror_x),
-        ShapeKind::Freehand { .. } => flip_horizontal_freehand(kind, mirror_x),
-        ShapeKind::Text { .. } => flip_horizontal_text(kind, mirror_x),
-        ShapeKind::Triangle { .. } => flip_horizontal_triangle(kind, mirror_x),
-        ShapeKind::Parallelogram { .. } => flip_horizontal_parallelogram(kind, mirror_x),
-        ShapeKind::Hexagon { .. } => flip_horizontal_hexagon(kind, mirror_x),
-        ShapeKind::Trapezoid { .. } => flip_horizontal_trapezoid(kind, mirror_x),
-        ShapeKind::RoundedRect { .. } => flip_horizontal_rounded_rect(kind, mirror_x),
-        ShapeKind::Cylinder { .. } => flip_horizontal_cylinder(kind, mirror_x),
-        ShapeKind::Cloud { .. } => flip_horizontal_cloud(kind, mirror_x),
-        ShapeKind::Star { .. } => flip_horizontal_star(kind, mirror_x),
+/// Insert a vertex on an edge (between vertex `edge` and the next one).
+/// A Triangle gains a fourth vertex by becoming a closed Polygon.
+pub fn insert_vertex(kind: &ShapeKind, edge: usize, pos: Position) -> Option<ShapeKind> {
+    let mut vertices = kind.vertices()?;
+    if edge >= vertex_edge_count(kind, vertices.len()) {
+        return None;
     }
+    vertices.insert(edge + 1, pos);
+    Some(with_vertices(kind, vertices))
 }
 
-// ========== Private helper functions for horizontal flip ==========
-
+/// Delete a vertex, refusing to go below the shape's minimum vertex count
+pub fn delete_vertex(kind: &ShapeKind, index: usize) -> Option<ShapeKind> {
+    let mut vertices = kind.vertices()?;
+    if index >= vertices.len() || vertices.len() <= min_vertex_count(kind) {
+        return None;
+    }
+    vertices.remove(index);
+    Some(with_vertices(kind, vertices))
+}
+
+/// Find the edge closest to a position (within threshold) for vertex insertion
+pub fn find_vertex_edge(kind: &ShapeKind, pos: Position, threshold: i32) -> Option<usize> {
+    let vertices = kind.vertices()?;
+    let edge_count = vertex_edge_count(kind, vertices.len());
+
+    let mut best: Option<(usize, i32)> = None;
+    for edge in 0..edge_count {
+        let from = vertices[edge];
+        let to = vertices[(edge + 1) % vertices.len()];
+        let dist = line_points(from, to)
+            .into_iter()
+            .map(|p| (pos.x - p.x).abs() + (pos.y - p.y).abs())
+            .min()
+            .unwrap_or(i32::MAX);
+        let is_better = best.is_none_or(|(_, best_dist)| dist < best_dist);
+        if dist <= threshold && is_better {
+            best = Some((edge, dist));
+        }
+    }
+    best.map(|(edge, _)| edge)
+}
+
+fn is_closed_outline(kind: &ShapeKind) -> bool {
+    match kind {
+        ShapeKind::Triangle { .. } => true,
+        ShapeKind::Polygon { closed, .. } => *closed,
+        _ => false,
+    }
+}
+
+fn vertex_edge_count(kind: &ShapeKind, vertex_count: usize) -> usize {
+    if is_closed_outline(kind) && vertex_count > 2 {
+        vertex_count
+    } else {
+        vertex_count.saturating_sub(1)
+    }
+}
+
+fn min_vertex_count(kind: &ShapeKind) -> usize {
+    match kind {
+        ShapeKind::Triangle { .. } => 3,
+        ShapeKind::Polygon { closed: true, .. } => 3,
+        ShapeKind::Polygon { closed: false, .. } => 2,
+        _ => 1,
+    }
+}
+
+/// Rebuild a vertex-editable shape from a new vertex list
+fn with_vertices(kind: &ShapeKind, vertices: Vec<Position>) -> ShapeKind {
+    debug_assert!(!vertices.is_empty(), "Shape must keep at least one vertex");
+
+    match kind {
+        ShapeKind::Triangle { label, color, .. } => match vertices[..] {
+            [p1, p2, p3] => ShapeKind::Triangle {
+                p1,
+                p2,
+                p3,
+                label: label.clone(),
+                color: *color,
+            },
+            _ => ShapeKind::Polygon {
+                points: vertices,
+                closed: true,
+                label: label.clone(),
+                color: *color,
+            },
+        },
+        ShapeKind::Polygon {
+            closed,
+            label,
+            color,
+            ..
+        } => ShapeKind::Polygon {
+            points: vertices,
+            closed: *closed,
+            label: label.clone(),
+            color: *color,
+        },
+        ShapeKind::Freehand {
+            char, label, color, ..
+        } => ShapeKind::Freehand {
+            points: vertices,
+            char: *char,
+            label: label.clone(),
+            color: *color,
+        },
+        _ => unreachable!("with_vertices called on shape without vertices"),
+    }
+}
+
+// ========== Freehand simplification ==========
+
+/// Douglas–Peucker tolerance (in cells) used when committing freehand strokes
+pub const STROKE_SIMPLIFY_TOLERANCE: f64 = 1.0;
+
+/// Chaikin iterations applied when stroke smoothing is enabled
+pub const STROKE_SMOOTH_ITERATIONS: usize = 2;
+
+/// Simplify a freehand stroke to its significant vertices, optionally smoothing it
+pub fn simplify_freehand(kind: &ShapeKind, smooth: bool) -> Option<ShapeKind> {
+    let ShapeKind::Freehand { points, .. } = kind else {
+        return None;
+    };
+    if points.is_empty() {
+        return None;
+    }
+
+    let mut vertices = simplify_path(points, STROKE_SIMPLIFY_TOLERANCE);
+    if smooth {
+        vertices = smooth_path(&vertices, STROKE_SMOOTH_ITERATIONS);
+    }
+    Some(with_vertices(kind, vertices))
+}
+
+/// Convert a freehand stroke into a polyline of connected segments.
+///
+/// A stroke that ends where it started becomes a closed polygon.
+pub fn freehand_to_polyline(kind: &ShapeKind) -> Option<ShapeKind> {
+    let ShapeKind::Freehand {
+        points,
+        label,
+        color,
+        ..
+    } = kind
+    else {
+        return None;
+    };
+
+    let mut vertices = simplify_path(points, STROKE_SIMPLIFY_TOLERANCE);
+    if vertices.len() < 2 {
+        return None;
+    }
+    let closed = vertices.len() > 3 && vertices.first() == vertices.last();
+    if closed {
+        vertices.pop();
+    }
+
+    Some(ShapeKind::Polygon {
+        points: vertices,
+        closed,
+        label: label.clone(),
+        color: *color,
+    })
+}
+
+/// Flip a shape horizontally (mirror across vertical axis through center_x).
+pub fn flip_horizontal(kind: &ShapeKind, center_x: i32) -> ShapeKind {
+    let mirror_x = |x: i32| {
+        debug_assert!(x.abs() < 100000, "Coordinate too large for mirroring");
+        2 * center_x - x
+    };
+
+    match kind {
+        ShapeKind::Line { .. } => flip_horizontal_line(kind, mirror_x),
+        ShapeKind::Arrow { .. } => flip_horizontal_arrow(kind, mirror_x),
+        ShapeKind::Rectangle { .. } => flip_horizontal_rectangle(kind, mirror_x),
+        ShapeKind::DoubleBox { .. } => flip_horizontal_double_box(kind, mirror_x),
+        ShapeKind::Diamond { .. } => flip_horizontal_diamond(kind, mirror_x),
+        ShapeKind::Ellipse { .. } => flip_horizontal_ellipse(kind, mirror_x),
+        ShapeKind::Freehand { .. } => flip_horizontal_freehand(kind, mirror_x),
+        ShapeKind::Text { .. } => flip_horizontal_text(kind, mirror_x),
+        ShapeKind::Triangle { .. } => flip_horizontal_triangle(kind, mirror_x),
+        ShapeKind::Polygon { .. } => flip_horizontal_polygon(kind, mirror_x),
+        ShapeKind::Parallelogram { .. } => flip_horizontal_parallelogram(kind, mirror_x),
+        ShapeKind::Hexagon { .. } => flip_horizontal_hexagon(kind, mirror_x),
+        ShapeKind::Trapezoid { .. } => flip_horizontal_trapezoid(kind, mirror_x),
+        ShapeKind::RoundedRect { .. } => flip_horizontal_rounded_rect(kind, mirror_x),
+        ShapeKind::Cylinder { .. } => flip_horizontal_cylinder(kind, mirror_x),
+        ShapeKind::Cloud { .. } => flip_horizontal_cloud(kind, mirror_x),
+        ShapeKind::Callout { .. } => flip_horizontal_callout(kind, mirror_x),
+        ShapeKind::Star { .. } => flip_horizontal_star(kind, mirror_x),
+    }
+}
+
+// ========== Private helper functions for horizontal flip ==========
+
 fn flip_horizontal_line<F>(kind: &ShapeKind, mirror_x: F) -> ShapeKind
 where
     F: Fn(i32) -> i32,
@@ -3326,6 +3954,31 @@ where
     }
 }
 
+fn flip_horizontal_polygon<F>(kind: &ShapeKind, mirror_x: F) -> ShapeKind
+where
+    F: Fn(i32) -> i32,
+{
+    if let ShapeKind::Polygon {
+        points,
+        closed,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Polygon {
+            points: points
+                .iter()
+                .map(|p| Position::new(mirror_x(p.x), p.y))
+                .collect(),
+            closed: *closed,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("flip_horizontal_polygon called on non-Polygon shape")
+    }
+}
+
 fn flip_horizontal_text<F>(kind: &ShapeKind, mirror_x: F) -> ShapeKind
 where
     F: Fn(i32) -> i32,
@@ -3504,6 +4157,32 @@ where
     }
 }
 
+fn flip_horizontal_callout<F>(kind: &ShapeKind, mirror_x: F) -> ShapeKind
+where
+    F: Fn(i32) -> i32,
+{
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Callout {
+            start: Position::new(mirror_x(start.x), start.y),
+            end: Position::new(mirror_x(end.x), end.y),
+            tail: Position::new(mirror_x(tail.x), tail.y),
+            tail_connection: *tail_connection,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("flip_horizontal_callout called on non-Callout shape")
+    }
+}
+
 fn flip_horizontal_star<F>(kind: &ShapeKind, mirror_x: F) -> ShapeKind
 where
     F: Fn(i32) -> i32,
@@ -3545,12 +4224,14 @@ pub fn flip_vertical(kind: &ShapeKind, center_y: i32) -> ShapeKind {
         ShapeKind::Freehand { .. } => flip_vertical_freehand(kind, mirror_y),
         ShapeKind::Text { .. } => flip_vertical_text(kind, mirror_y),
         ShapeKind::Triangle { .. } => flip_vertical_triangle(kind, mirror_y),
+        ShapeKind::Polygon { .. } => flip_vertical_polygon(kind, mirror_y),
         ShapeKind::Parallelogram { .. } => flip_vertical_parallelogram(kind, mirror_y),
         ShapeKind::Hexagon { .. } => flip_vertical_hexagon(kind, mirror_y),
         ShapeKind::Trapezoid { .. } => flip_vertical_trapezoid(kind, mirror_y),
         ShapeKind::RoundedRect { .. } => flip_vertical_rounded_rect(kind, mirror_y),
         ShapeKind::Cylinder { .. } => flip_vertical_cylinder(kind, mirror_y),
         ShapeKind::Cloud { .. } => flip_vertical_cloud(kind, mirror_y),
+        ShapeKind::Callout { .. } => flip_vertical_callout(kind, mirror_y),
         ShapeKind::Star { .. } => flip_vertical_star(kind, mirror_y),
     }
 }
@@ -3728,6 +4409,31 @@ where
     }
 }
 
+fn flip_vertical_polygon<F>(kind: &ShapeKind, mirror_y: F) -> ShapeKind
+where
+    F: Fn(i32) -> i32,
+{
+    if let ShapeKind::Polygon {
+        points,
+        closed,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Polygon {
+            points: points
+                .iter()
+                .map(|p| Position::new(p.x, mirror_y(p.y)))
+                .collect(),
+            closed: *closed,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("flip_vertical_polygon called on non-Polygon shape")
+    }
+}
+
 fn flip_vertical_text<F>(kind: &ShapeKind, mirror_y: F) -> ShapeKind
 where
     F: Fn(i32) -> i32,
@@ -3906,6 +4612,32 @@ where
     }
 }
 
+fn flip_vertical_callout<F>(kind: &ShapeKind, mirror_y: F) -> ShapeKind
+where
+    F: Fn(i32) -> i32,
+{
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Callout {
+            start: Position::new(start.x, mirror_y(start.y)),
+            end: Position::new(end.x, mirror_y(end.y)),
+            tail: Position::new(tail.x, mirror_y(tail.y)),
+            tail_connection: *tail_connection,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("flip_vertical_callout called on non-Callout shape")
+    }
+}
+
 fn flip_vertical_star<F>(kind: &ShapeKind, mirror_y: F) -> ShapeKind
 where
     F: Fn(i32) -> i32,
@@ -3949,12 +4681,14 @@ pub fn rotate_90_cw(kind: &ShapeKind, center: Position) -> ShapeKind {
         ShapeKind::Freehand { .. } => rotate_90_cw_freehand(kind, rotate_point),
         ShapeKind::Text { .. } => rotate_90_cw_text(kind, rotate_point),
         ShapeKind::Triangle { .. } => rotate_90_cw_triangle(kind, rotate_point),
+        ShapeKind::Polygon { .. } => rotate_90_cw_polygon(kind, rotate_point),
         ShapeKind::Parallelogram { .. } => rotate_90_cw_parallelogram(kind, rotate_point),
         ShapeKind::Hexagon { .. } => rotate_90_cw_hexagon(kind, rotate_point),
         ShapeKind::Trapezoid { .. } => rotate_90_cw_trapezoid(kind, rotate_point),
         ShapeKind::RoundedRect { .. } => rotate_90_cw_rounded_rect(kind, rotate_point),
         ShapeKind::Cylinder { .. } => rotate_90_cw_cylinder(kind, rotate_point),
         ShapeKind::Cloud { .. } => rotate_90_cw_cloud(kind, rotate_point),
+        ShapeKind::Callout { .. } => rotate_90_cw_callout(kind, rotate_point),
         ShapeKind::Star { .. } => rotate_90_cw_star(kind, rotate_point),
     }
 }
@@ -4129,6 +4863,28 @@ where
     }
 }
 
+fn rotate_90_cw_polygon<F>(kind: &ShapeKind, rotate_point: F) -> ShapeKind
+where
+    F: Fn(Position) -> Position,
+{
+    if let ShapeKind::Polygon {
+        points,
+        closed,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Polygon {
+            points: points.iter().map(|p| rotate_point(*p)).collect(),
+            closed: *closed,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("rotate_90_cw_polygon called on non-Polygon shape")
+    }
+}
+
 fn rotate_90_cw_text<F>(kind: &ShapeKind, rotate_point: F) -> ShapeKind
 where
     F: Fn(Position) -> Position,
@@ -4307,6 +5063,32 @@ where
     }
 }
 
+fn rotate_90_cw_callout<F>(kind: &ShapeKind, rotate_point: F) -> ShapeKind
+where
+    F: Fn(Position) -> Position,
+{
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Callout {
+            start: rotate_point(*start),
+            end: rotate_point(*end),
+            tail: rotate_point(*tail),
+            tail_connection: *tail_connection,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("rotate_90_cw_callout called on non-Callout shape")
+    }
+}
+
 fn rotate_90_cw_star<F>(kind: &ShapeKind, rotate_point: F) -> ShapeKind
 where
     F: Fn(Position) -> Position,
@@ -4350,12 +5132,14 @@ pub fn rotate_90_ccw(kind: &ShapeKind, center: Position) -> ShapeKind {
         ShapeKind::Freehand { .. } => rotate_90_ccw_freehand(kind, rotate_point),
         ShapeKind::Text { .. } => rotate_90_ccw_text(kind, rotate_point),
         ShapeKind::Triangle { .. } => rotate_90_ccw_triangle(kind, rotate_point),
+        ShapeKind::Polygon { .. } => rotate_90_ccw_polygon(kind, rotate_point),
         ShapeKind::Parallelogram { .. } => rotate_90_ccw_parallelogram(kind, rotate_point),
         ShapeKind::Hexagon { .. } => rotate_90_ccw_hexagon(kind, rotate_point),
         ShapeKind::Trapezoid { .. } => rotate_90_ccw_trapezoid(kind, rotate_point),
         ShapeKind::RoundedRect { .. } => rotate_90_ccw_rounded_rect(kind, rotate_point),
         ShapeKind::Cylinder { .. } => rotate_90_ccw_cylinder(kind, rotate_point),
         ShapeKind::Cloud { .. } => rotate_90_ccw_cloud(kind, rotate_point),
+        ShapeKind::Callout { .. } => rotate_90_ccw_callout(kind, rotate_point),
         ShapeKind::Star { .. } => rotate_90_ccw_star(kind, rotate_point),
     }
 }
@@ -4530,6 +5314,28 @@ where
     }
 }
 
+fn rotate_90_ccw_polygon<F>(kind: &ShapeKind, rotate_point: F) -> ShapeKind
+where
+    F: Fn(Position) -> Position,
+{
+    if let ShapeKind::Polygon {
+        points,
+        closed,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Polygon {
+            points: points.iter().map(|p| rotate_point(*p)).collect(),
+            closed: *closed,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("rotate_90_ccw_polygon called on non-Polygon shape")
+    }
+}
+
 fn rotate_90_ccw_text<F>(kind: &ShapeKind, rotate_point: F) -> ShapeKind
 where
     F: Fn(Position) -> Position,
@@ -4708,6 +5514,32 @@ where
     }
 }
 
+fn rotate_90_ccw_callout<F>(kind: &ShapeKind, rotate_point: F) -> ShapeKind
+where
+    F: Fn(Position) -> Position,
+{
+    if let ShapeKind::Callout {
+        start,
+        end,
+        tail,
+        tail_connection,
+        label,
+        color,
+    } = kind
+    {
+        ShapeKind::Callout {
+            start: rotate_point(*start),
+            end: rotate_point(*end),
+            tail: rotate_point(*tail),
+            tail_connection: *tail_connection,
+            label: label.clone(),
+            color: *color,
+        }
+    } else {
+        unreachable!("rotate_90_ccw_callout called on non-Callout shape")
+    }
+}
+
 fn rotate_90_ccw_star<F>(kind: &ShapeKind, rotate_point: F) -> ShapeKind
 where
     F: Fn(Position) -> Position,
@@ -5166,6 +5998,307 @@ mod tests {
         }
     }
 
+    // ========== Callout tests ==========
+
+    fn make_callout(tail: Position, tail_connection: Option<u64>) -> ShapeKind {
+        ShapeKind::Callout {
+            start: Position::new(0, 0),
+            end: Position::new(10, 4),
+            tail,
+            tail_connection,
+            label: None,
+            color: ShapeColor::White,
+        }
+    }
+
+    #[test]
+    fn callout_bounds_include_tail() {
+        let callout = make_callout(Position::new(-3, 8), None);
+        assert_eq!(callout.bounds(), (-3, 0, 10, 8));
+    }
+
+    #[test]
+    fn callout_translated_keeps_connected_tail() {
+        let free = make_callout(Position::new(2, 8), None).translated(5, 1);
+        let pinned = make_callout(Position::new(2, 8), Some(42)).translated(5, 1);
+        if let (
+            ShapeKind::Callout {
+                tail: free_tail, ..
+            },
+            ShapeKind::Callout {
+                tail: pinned_tail,
+                start,
+                ..
+            },
+        ) = (free, pinned)
+        {
+            assert_eq!(free_tail, Position::new(7, 9));
+            assert_eq!(pinned_tail, Position::new(2, 8));
+            assert_eq!(start, Position::new(5, 1));
+        } else {
+            panic!("Expected Callout");
+        }
+    }
+
+    #[test]
+    fn callout_tail_follows_connected_target() {
+        let callout = make_callout(Position::new(2, 8), Some(42));
+        assert!(callout.translate_connected_endpoints(7, 3, 3).is_none());
+        let moved = callout
+            .translate_connected_endpoints(42, 3, 3)
+            .expect("tail is connected to target 42");
+        if let ShapeKind::Callout { tail, start, .. } = moved {
+            assert_eq!(tail, Position::new(5, 11));
+            assert_eq!(start, Position::new(0, 0));
+        } else {
+            panic!("Expected Callout");
+        }
+    }
+
+    #[test]
+    fn callout_flip_and_rotate_keep_tail_connection() {
+        let callout = make_callout(Position::new(2, 8), Some(42));
+        let center = Position::new(5, 4);
+        for transformed in [
+            flip_horizontal(&callout, center.x),
+            flip_vertical(&callout, center.y),
+            rotate_90_cw(&callout, center),
+            rotate_90_ccw(&callout, center),
+        ] {
+            assert_eq!(transformed.connections(), (None, Some(42)));
+        }
+    }
+
+    #[test]
+    fn callout_resize_end_moves_tail_and_detaches() {
+        let callout = make_callout(Position::new(2, 8), Some(42));
+        let handles = CachedShape::new(ShapeId::new(), callout.clone())
+            .resize_handles()
+            .to_vec();
+        assert_eq!(handles.len(), 5);
+        assert!(
+            handles
+                .iter()
+                .any(|h| h.handle == ResizeHandle::End && h.pos == Position::new(2, 8))
+        );
+
+        let resized = resize_shape(&callout, ResizeHandle::End, Position::new(20, 2));
+        if let ShapeKind::Callout {
+            tail,
+            tail_connection,
+            ..
+        } = resized
+        {
+            assert_eq!(tail, Position::new(20, 2));
+            assert_eq!(tail_connection, None);
+        } else {
+            panic!("Expected Callout");
+        }
+    }
+
+    #[test]
+    fn detach_at_clears_only_the_end_on_that_point() {
+        let line = ShapeKind::Line {
+            start: Position::new(0, 0),
+            end: Position::new(8, 6),
+            style: LineStyle::Straight,
+            start_connection: Some(7),
+            end_connection: Some(7),
+            label: None,
+            color: ShapeColor::White,
+        };
+        let detached = line
+            .detach_at(7, Position::new(8, 6))
+            .expect("end was attached there");
+        assert_eq!(detached.connections(), (Some(7), None));
+        assert_eq!(detached.snap_points(), line.snap_points());
+        assert!(line.detach_at(9, Position::new(8, 6)).is_none());
+        assert!(
+            make_line(0, 0, 8, 6)
+                .detach_at(7, Position::new(8, 6))
+                .is_none()
+        );
+    }
+
+    // ========== Polygon / vertex editing tests ==========
+
+    fn make_polygon(closed: bool) -> ShapeKind {
+        ShapeKind::Polygon {
+            points: vec![
+                Position::new(0, 0),
+                Position::new(8, 0),
+                Position::new(8, 6),
+            ],
+            closed,
+            label: None,
+            color: ShapeColor::White,
+        }
+    }
+
+    #[test]
+    fn polygon_handles_are_vertices() {
+        let cached = CachedShape::new(ShapeId::new(), make_polygon(true));
+        assert_eq!(cached.bounds(), (0, 0, 8, 6));
+        let handles = cached.resize_handles();
+        assert_eq!(handles.len(), 3);
+        assert_eq!(handles[2].handle, ResizeHandle::Vertex(2));
+        assert_eq!(handles[2].pos, Position::new(8, 6));
+    }
+
+    #[test]
+    fn move_vertex_on_triangle_and_freehand() {
+        let triangle = ShapeKind::Triangle {
+            p1: Position::new(0, 0),
+            p2: Position::new(4, 0),
+            p3: Position::new(2, 3),
+            label: None,
+            color: ShapeColor::White,
+        };
+        let moved = resize_shape(&triangle, ResizeHandle::Vertex(2), Position::new(2, 9));
+        assert_eq!(moved.vertices().unwrap()[2], Position::new(2, 9));
+
+        let freehand = ShapeKind::Freehand {
+            points: vec![Position::new(0, 0), Position::new(1, 1)],
+            char: '*',
+            label: None,
+            color: ShapeColor::White,
+        };
+        let moved = move_vertex(&freehand, 1, Position::new(5, 5));
+        assert_eq!(
+            moved.vertices().unwrap(),
+            vec![Position::new(0, 0), Position::new(5, 5)]
+        );
+        // Out-of-range index is a no-op
+        let unchanged = move_vertex(&freehand, 9, Position::new(5, 5));
+        assert_eq!(unchanged.vertices(), freehand.vertices());
+    }
+
+    #[test]
+    fn insert_vertex_on_edge() {
+        let polygon = make_polygon(true);
+        // Closing edge (2 -> 0) only exists on closed polygons
+        assert_eq!(find_vertex_edge(&polygon, Position::new(4, 3), 1), Some(2));
+        assert_eq!(
+            find_vertex_edge(&make_polygon(false), Position::new(4, 3), 1),
+            None
+        );
+
+        let inserted = insert_vertex(&polygon, 0, Position::new(4, -2)).unwrap();
+        assert_eq!(inserted.vertices().unwrap()[1], Position::new(4, -2));
+        assert!(insert_vertex(&make_polygon(false), 2, Position::new(0, 0)).is_none());
+    }
+
+    #[test]
+    fn insert_vertex_turns_triangle_into_polygon() {
+        let triangle = ShapeKind::Triangle {
+            p1: Position::new(0, 0),
+            p2: Position::new(4, 0),
+            p3: Position::new(2, 3),
+            label: Some("t".to_string()),
+            color: ShapeColor::Red,
+        };
+        let inserted = insert_vertex(&triangle, 1, Position::new(5, 2)).unwrap();
+        if let ShapeKind::Polygon {
+            points,
+            closed,
+            label,
+            color,
+        } = &inserted
+        {
+            assert_eq!(points.len(), 4);
+            assert!(*closed);
+            assert_eq!(label.as_deref(), Some("t"));
+            assert_eq!(*color, ShapeColor::Red);
+        } else {
+            panic!("Expected Polygon");
+        }
+        // Deleting back down to three vertices keeps it a polygon, but no further
+        let reduced = delete_vertex(&inserted, 2).unwrap();
+        assert_eq!(reduced.vertices().unwrap().len(), 3);
+        assert!(delete_vertex(&reduced, 0).is_none());
+        assert!(delete_vertex(&triangle, 0).is_none());
+    }
+
+    #[test]
+    fn delete_vertex_respects_open_minimum() {
+        let open = make_polygon(false);
+        let reduced = delete_vertex(&open, 1).unwrap();
+        assert_eq!(
+            reduced.vertices().unwrap(),
+            vec![Position::new(0, 0), Position::new(8, 6)]
+        );
+        assert!(delete_vertex(&reduced, 0).is_none());
+    }
+
+    // ========== Freehand simplification tests ==========
+
+    fn dense_freehand(corners: &[Position]) -> ShapeKind {
+        let mut points = vec![corners[0]];
+        for pair in corners.windows(2) {
+            points.extend(line_points(pair[0], pair[1]).into_iter().skip(1));
+        }
+        ShapeKind::Freehand {
+            points,
+            char: '*',
+            label: Some("path".to_string()),
+            color: ShapeColor::Cyan,
+        }
+    }
+
+    #[test]
+    fn simplify_freehand_drops_collinear_samples() {
+        let corners = [
+            Position::new(0, 0),
+            Position::new(12, 0),
+            Position::new(12, 6),
+        ];
+        let stroke = dense_freehand(&corners);
+        let simplified = simplify_freehand(&stroke, false).unwrap();
+        assert_eq!(simplified.vertices().unwrap(), corners.to_vec());
+        assert_eq!(simplified.label(), Some("path"));
+
+        let smoothed = simplify_freehand(&stroke, true)
+            .unwrap()
+            .vertices()
+            .unwrap();
+        assert_eq!(smoothed.first(), corners.first());
+        assert_eq!(smoothed.last(), corners.last());
+        assert!(!smoothed.contains(&Position::new(12, 0)));
+    }
+
+    #[test]
+    fn freehand_to_polyline_open_and_closed() {
+        let open = dense_freehand(&[
+            Position::new(0, 0),
+            Position::new(10, 0),
+            Position::new(10, 5),
+        ]);
+        if let Some(ShapeKind::Polygon {
+            points,
+            closed,
+            color,
+            ..
+        }) = freehand_to_polyline(&open)
+        {
+            assert_eq!(points.len(), 3);
+            assert!(!closed);
+            assert_eq!(color, ShapeColor::Cyan);
+        } else {
+            panic!("Expected Polygon");
+        }
+
+        let loop_stroke = dense_freehand(&[
+            Position::new(0, 0),
+            Position::new(10, 0),
+            Position::new(10, 5),
+            Position::new(0, 5),
+            Position::new(0, 0),
+        ]);
+        let polygon = freehand_to_polyline(&loop_stroke).unwrap();
+        assert!(matches!(polygon, ShapeKind::Polygon { closed: true, .. }));
+        assert_eq!(polygon.vertices().unwrap().len(), 4);
+    }
+
     // ========== ShapeView tests ==========
 
     #[test]
@@ -5180,4 +6313,47 @@ mod tests {
         let view = ShapeView::default();
         assert!(view.is_empty());
     }
+
+    #[test]
+    fn shape_view_rebuild_reads_hidden_layers() {
+        let mut doc = Document::new();
+        let layer = doc.create_layer("Notes").unwrap();
+        let id = doc
+            .add_shape(ShapeKind::Text {
+                pos: Position::new(0, 0),
+                content: "hi".to_string(),
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+        doc.set_shape_layer(id, layer).unwrap();
+        doc.set_layer_visible(layer, false).unwrap();
+
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+        assert!(!view.is_layer_visible(layer));
+        assert_eq!(view.len(), 1);
+        assert_eq!(view.iter_visible().count(), 0);
+    }
+
+    #[test]
+    fn shape_view_retain_keeps_lookup() {
+        let mut doc = Document::new();
+        let ids: Vec<_> = (0..3)
+            .map(|x| {
+                doc.add_shape(ShapeKind::Text {
+                    pos: Position::new(x, 0),
+                    content: "t".to_string(),
+                    color: ShapeColor::default(),
+                })
+                .unwrap()
+            })
+            .collect();
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+
+        view.retain(|shape| shape.id != ids[0]);
+        assert_eq!(view.len(), 2);
+        assert!(view.get(ids[0]).is_none());
+        assert_eq!(view.get(ids[2]).map(|shape| shape.id), Some(ids[2]));
+    }
 }
@@ -4,16 +4,62 @@ use std::path::Path;
 
 use anyhow::{Context, Result};
 
-use irohscii_core::{Position, ShapeColor, ShapeKind, ShapeView};
+use irohscii_core::{Document, Position, ShapeColor, ShapeId, ShapeKind, ShapeView};
 use irohscii_geometry::{
-    arrow_points_styled, cloud_points, cylinder_points, diamond_points, double_rect_points,
-    ellipse_points, hexagon_points, line_points_styled, parallelogram_points, rect_points,
-    rounded_rect_points, star_points, trapezoid_points, triangle_points,
+    arrow_points_styled, braille_union, callout_points, cloud_points, cloud_points_braille, cylinder_points,
+    diamond_points, double_rect_points, ellipse_points, ellipse_points_braille, hexagon_points,
+    line_points_styled, parallelogram_points, polygon_points, rect_points, rounded_rect_points, star_points,
+    stroke_points, stroke_points_braille, trapezoid_points, triangle_points,
 };
 
+use crate::ascii_import::{import_ascii, parse_ascii};
+
+/// A rendered character with the color and index of the shape that drew it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Cell {
+    pub(crate) ch: char,
+    pub(crate) color: ShapeColor,
+    pub(crate) shape: usize,
+}
+
+/// Character grid that shapes are drawn into, later shapes on top
+#[derive(Debug, Default)]
+pub(crate) struct Grid {
+    pub(crate) cells: HashMap<Position, Cell>,
+    /// Color and index of the shape being drawn
+    color: ShapeColor,
+    shape: usize,
+}
+
+impl Grid {
+    fn insert(&mut self, pos: Position, ch: char) {
+        let (color, shape) = (self.color, self.shape);
+        self.cells.insert(pos, Cell { ch, color, shape });
+    }
+
+    fn get(&self, pos: &Position) -> Option<&char> {
+        self.cells.get(pos).map(|cell| &cell.ch)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+/// Draw braille cells, merging dots with braille already in the grid
+fn insert_braille(grid: &mut Grid, points: Vec<(Position, char)>) {
+    for (pos, ch) in points {
+        let merged = grid
+            .get(&pos)
+            .and_then(|&existing| braille_union(existing, ch))
+            .unwrap_or(ch);
+        grid.insert(pos, merged);
+    }
+}
+
 /// Render a label centered inside a shape's bounds
 fn render_label_to_grid(
-    grid: &mut HashMap<Position, char>,
+    grid: &mut Grid,
     bounds: (i32, i32, i32, i32),
     text: &str,
 ) {
@@ -48,10 +94,13 @@ fn render_label_to_grid(
 }
 
 /// Build a character grid from shapes
-fn build_shape_grid(shapes: &ShapeView) -> HashMap<Position, char> {
-    let mut grid: HashMap<Position, char> = HashMap::new();
+pub(crate) fn build_shape_grid(shapes: &ShapeView) -> Grid {
+    let mut grid = Grid::default();
+    let braille = shapes.braille();
 
-    for shape in shapes.iter() {
+    for (index, shape) in shapes.iter().enumerate() {
+        grid.color = shape.kind.color();
+        grid.shape = index;
         match &shape.kind {
             ShapeKind::Line {
                 start, end, style, ..
@@ -108,16 +157,24 @@ fn build_shape_grid(shapes: &ShapeView) -> HashMap<Position, char> {
                 label,
                 ..
             } => {
-                for (pos, ch) in ellipse_points(*center, *radius_x, *radius_y) {
-                    grid.insert(pos, ch);
+                if braille {
+                    insert_braille(&mut grid, ellipse_points_braille(*center, *radius_x, *radius_y));
+                } else {
+                    for (pos, ch) in ellipse_points(*center, *radius_x, *radius_y) {
+                        grid.insert(pos, ch);
+                    }
                 }
                 if let Some(text) = label {
                     render_label_to_grid(&mut grid, shape.bounds(), text);
                 }
             }
             ShapeKind::Freehand { points, char, .. } => {
-                for &pos in points {
-                    grid.insert(pos, *char);
+                if braille {
+                    insert_braille(&mut grid, stroke_points_braille(points, *char));
+                } else {
+                    for (pos, ch) in stroke_points(points, *char) {
+                        grid.insert(pos, ch);
+                    }
                 }
             }
             ShapeKind::Text { pos, content, .. } => {
@@ -136,6 +193,19 @@ fn build_shape_grid(shapes: &ShapeView) -> HashMap<Position, char> {
                     render_label_to_grid(&mut grid, shape.bounds(), text);
                 }
             }
+            ShapeKind::Polygon {
+                points,
+                closed,
+                label,
+                ..
+            } => {
+                for (pos, ch) in polygon_points(points, *closed) {
+                    grid.insert(pos, ch);
+                }
+                if let Some(text) = label {
+                    render_label_to_grid(&mut grid, shape.bounds(), text);
+                }
+            }
             ShapeKind::Parallelogram {
                 start, end, label, ..
             } => {
@@ -193,13 +263,38 @@ fn build_shape_grid(shapes: &ShapeView) -> HashMap<Position, char> {
             ShapeKind::Cloud {
                 start, end, label, ..
             } => {
-                for (pos, ch) in cloud_points(*start, *end) {
-                    grid.insert(pos, ch);
+                if braille {
+                    insert_braille(&mut grid, cloud_points_braille(*start, *end));
+                } else {
+                    for (pos, ch) in cloud_points(*start, *end) {
+                        grid.insert(pos, ch);
+                    }
                 }
                 if let Some(text) = label {
                     render_label_to_grid(&mut grid, shape.bounds(), text);
                 }
             }
+            ShapeKind::Callout {
+                start,
+                end,
+                tail,
+                label,
+                ..
+            } => {
+                for (pos, ch) in callout_points(*start, *end, *tail) {
+                    grid.insert(pos, ch);
+                }
+                if let Some(text) = label {
+                    // Center on the body, not the bounds that include the tail
+                    let body = (
+                        start.x.min(end.x),
+                        start.y.min(end.y),
+                        start.x.max(end.x),
+                        start.y.max(end.y),
+                    );
+                    render_label_to_grid(&mut grid, body, text);
+                }
+            }
             ShapeKind::Star {
                 center,
                 outer_radius,
@@ -221,7 +316,7 @@ fn build_shape_grid(shapes: &ShapeView) -> HashMap<Position, char> {
 }
 
 /// Calculate the bounding box of a character grid
-fn calculate_grid_bounds(grid: &HashMap<Position, char>) -> Option<(i32, i32, i32, i32)> {
+pub(crate) fn calculate_grid_bounds(grid: &Grid) -> Option<(i32, i32, i32, i32)> {
     if grid.is_empty() {
         return None;
     }
@@ -231,7 +326,7 @@ fn calculate_grid_bounds(grid: &HashMap<Position, char>) -> Option<(i32, i32, i3
     let mut max_x = i32::MIN;
     let mut max_y = i32::MIN;
 
-    for pos in grid.keys() {
+    for pos in grid.cells.keys() {
         min_x = min_x.min(pos.x);
         min_y = min_y.min(pos.y);
         max_x = max_x.max(pos.x);
@@ -244,8 +339,9 @@ fn calculate_grid_bounds(grid: &HashMap<Position, char>) -> Option<(i32, i32, i3
     Some((min_x, min_y, max_x, max_y))
 }
 
-/// Render a character grid to a string
-fn render_grid_to_string(grid: &HashMap<Position, char>, bounds: (i32, i32, i32, i32)) -> String {
+/// Render a character grid to a string. With `trim`, trailing spaces and
+/// blank lines are dropped.
+pub(crate) fn render_grid_to_string(grid: &Grid, bounds: (i32, i32, i32, i32), trim: bool) -> String {
     let (min_x, min_y, max_x, max_y) = bounds;
     debug_assert!(max_x >= min_x, "render_grid: max_x must be >= min_x");
     debug_assert!(max_y >= min_y, "render_grid: max_y must be >= min_y");
@@ -257,11 +353,14 @@ fn render_grid_to_string(grid: &HashMap<Position, char>, bounds: (i32, i32, i32,
             let ch = grid.get(&Position::new(x, y)).copied().unwrap_or(' ');
             line.push(ch);
         }
-        lines.push(line.trim_end().to_string());
+        if trim {
+            line.truncate(line.trim_end().len());
+        }
+        lines.push(line);
     }
 
     // Remove trailing empty lines
-    while lines.last().is_some_and(|l| l.is_empty()) {
+    while trim && lines.last().is_some_and(|l| l.is_empty()) {
         lines.pop();
     }
 
@@ -269,7 +368,7 @@ fn render_grid_to_string(grid: &HashMap<Position, char>, bounds: (i32, i32, i32,
 }
 
 /// Render shapes to a text string
-fn render_shapes_to_text(shapes: &ShapeView) -> String {
+pub fn render_shapes_to_text(shapes: &ShapeView) -> String {
     let grid = build_shape_grid(shapes);
     
     if grid.is_empty() {
@@ -280,7 +379,7 @@ fn render_shapes_to_text(shapes: &ShapeView) -> String {
         return String::new();
     };
 
-    render_grid_to_string(&grid, bounds)
+    render_grid_to_string(&grid, bounds, true)
 }
 
 /// Save shapes to a file (renders as ASCII art)
@@ -295,36 +394,27 @@ pub fn save_ascii(shapes: &ShapeView, path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Load shapes from a file (imports as text lines - shapes become Text entries)
+/// Load shapes from a file, recognizing boxes, lines, arrows and text.
+/// Connections are not kept; use `load_ascii_into` to import them too.
 pub fn load_ascii(path: &Path) -> Result<Vec<ShapeKind>> {
     let content =
         fs::read_to_string(path).with_context(|| format!("Failed to read from {:?}", path))?;
 
     debug_assert!(path.exists(), "Path should exist before reading");
-    let mut shapes = Vec::new();
-
-    // Import each line as a separate text shape
-    for (y, line) in content.lines().enumerate() {
-        if !line.is_empty() {
-            let y_pos = i32::try_from(y)
-                .unwrap_or_else(|_| {
-                    // If line number exceeds i32::MAX, saturate
-                    i32::MAX
-                });
-            shapes.push(ShapeKind::Text {
-                pos: Position::new(0, y_pos),
-                content: line.to_string(),
-                color: ShapeColor::default(),
-            });
-        }
-    }
+    Ok(parse_ascii(&content).into_iter().map(|shape| shape.kind).collect())
+}
 
-    Ok(shapes)
+/// Import a file into a document, connecting lines to the boxes they touch
+pub fn load_ascii_into(doc: &mut Document, path: &Path) -> Result<Vec<ShapeId>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read from {:?}", path))?;
+    import_ascii(doc, &content)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use irohscii_core::ShapeColor;
     use tempfile::tempdir;
 
     fn make_rect(x: i32, y: i32, w: i32, h: i32) -> ShapeKind {
@@ -367,7 +457,7 @@ mod tests {
 
     #[test]
     fn render_label_to_grid_centered() {
-        let mut grid = HashMap::new();
+        let mut grid = Grid::default();
         let bounds = (0, 0, 10, 5);
         render_label_to_grid(&mut grid, bounds, "Hi");
 
@@ -406,6 +496,90 @@ mod tests {
         assert!(result.contains("Test"));
     }
 
+    #[test]
+    fn render_shapes_to_text_callout() {
+        let callout = ShapeKind::Callout {
+            start: Position::new(0, 0),
+            end: Position::new(10, 2),
+            tail: Position::new(3, 5),
+            tail_connection: None,
+            label: Some("Hey".to_string()),
+            color: ShapeColor::default(),
+        };
+        let view = build_shape_view(vec![callout]);
+        let result = render_shapes_to_text(&view);
+        let lines: Vec<&str> = result.lines().collect();
+
+        // Label sits on the body's middle row, tail junction on the bottom edge
+        // and the tail runs straight down to its tip
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1].contains("Hey"));
+        assert!(lines[2].contains('┬'));
+        assert_eq!(lines[5].chars().nth(3), Some('│'));
+    }
+
+    #[test]
+    fn render_shapes_to_text_open_polygon() {
+        let polygon = ShapeKind::Polygon {
+            points: vec![Position::new(0, 0), Position::new(4, 0), Position::new(4, 2)],
+            closed: false,
+            label: None,
+            color: ShapeColor::default(),
+        };
+        let view = build_shape_view(vec![polygon]);
+        let result = render_shapes_to_text(&view);
+        let lines: Vec<&str> = result.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("─"));
+        assert!(lines[2].ends_with('│'));
+        // Open polygons have no closing edge back to the first vertex
+        assert!(!lines[1].contains('\\'));
+    }
+
+    #[test]
+    fn render_shapes_to_text_freehand_joins_vertices() {
+        let stroke = ShapeKind::Freehand {
+            points: vec![Position::new(0, 0), Position::new(5, 0), Position::new(5, 2)],
+            char: '*',
+            label: None,
+            color: ShapeColor::default(),
+        };
+        let view = build_shape_view(vec![stroke]);
+        let result = render_shapes_to_text(&view);
+        let lines: Vec<&str> = result.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "─────│");
+        assert!(lines[2].ends_with('│'));
+        assert!(!result.contains('*'), "brush only marks single-point strokes");
+    }
+
+    #[test]
+    fn render_shapes_to_text_braille_curves() {
+        use irohscii_core::Document;
+
+        let ellipse = ShapeKind::Ellipse {
+            center: Position::new(8, 4),
+            radius_x: 8,
+            radius_y: 4,
+            label: None,
+            color: ShapeColor::default(),
+        };
+        let rect = make_rect(0, 10, 4, 12);
+        let mut doc = Document::new();
+        doc.add_shape(ellipse).unwrap();
+        doc.add_shape(rect).unwrap();
+        doc.set_braille(true).unwrap();
+        let mut view = ShapeView::default();
+        view.rebuild(&doc).unwrap();
+
+        let result = render_shapes_to_text(&view);
+        assert!(result.chars().any(|c| ('\u{2801}'..='\u{28FF}').contains(&c)));
+        assert!(!result.contains('('), "ellipse should not use cell characters");
+        assert!(result.contains('┌'), "rectangles keep box drawing");
+    }
+
     #[test]
     fn render_shapes_to_text_text_shape() {
         let view = build_shape_view(vec![make_text(0, 0, "Hello World")]);
@@ -427,10 +601,10 @@ mod tests {
         let loaded_shapes = load_ascii(&file_path).unwrap();
         assert!(!loaded_shapes.is_empty());
 
-        // All loaded shapes should be Text shapes
-        for shape in &loaded_shapes {
-            assert!(matches!(shape, ShapeKind::Text { .. }));
-        }
+        // The rectangle is recognized rather than imported as text
+        assert_eq!(loaded_shapes.len(), 1);
+        assert!(matches!(loaded_shapes[0], ShapeKind::Rectangle { .. }));
+        assert_eq!(loaded_shapes[0].bounds(), (0, 0, 5, 3));
     }
 
     #[test]
@@ -0,0 +1,760 @@
+//! ASCII-art import: recognizes boxes, lines, arrows and text in plain-text diagrams.
+//!
+//! Boxes are found from their corners (`+`, `┌`, `╔`, `╭`) and edges, and the text
+//! inside a box becomes its label. Remaining line characters are traced into
+//! straight or orthogonal lines (arrows when they end in an arrowhead); endpoints
+//! that touch a box border become connections. Everything else is kept as Text.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use irohscii_core::{Document, LineStyle, Position, ShapeColor, ShapeId, ShapeKind};
+use irohscii_geometry::line_points_styled;
+
+/// Maximum box edge length traced before giving up
+const MAX_EDGE_LENGTH: i32 = 10_000;
+
+/// A shape recognized in ASCII art
+#[derive(Debug, Clone)]
+pub struct ImportedShape {
+    pub kind: ShapeKind,
+    /// Index (into the imported shapes) of the box the line's start touches
+    pub start_target: Option<usize>,
+    /// Index (into the imported shapes) of the box the line's end touches
+    pub end_target: Option<usize>,
+}
+
+impl ImportedShape {
+    fn unconnected(kind: ShapeKind) -> Self {
+        Self {
+            kind,
+            start_target: None,
+            end_target: None,
+        }
+    }
+}
+
+/// Parse ASCII art into shapes. Boxes come first, then lines, then text, so
+/// connection targets always precede the lines that reference them.
+pub fn parse_ascii(text: &str) -> Vec<ImportedShape> {
+    let grid = Grid::parse(text);
+    let mut consumed: HashSet<Position> = HashSet::new();
+
+    let boxes = find_boxes(&grid);
+    let mut border: HashMap<Position, usize> = HashMap::new();
+    for (index, found) in boxes.iter().enumerate() {
+        for pos in found.border_cells() {
+            border.entry(pos).or_insert(index);
+            consumed.insert(pos);
+        }
+    }
+
+    // Smallest boxes claim their interior text first so nested boxes keep their labels
+    let mut labels = vec![None; boxes.len()];
+    let mut by_area: Vec<usize> = (0..boxes.len()).collect();
+    by_area.sort_by_key(|&i| boxes[i].area());
+    for index in by_area {
+        labels[index] = take_label(&grid, &boxes[index], &mut consumed);
+    }
+
+    let mut shapes: Vec<ImportedShape> = boxes
+        .iter()
+        .zip(labels)
+        .map(|(found, label)| ImportedShape::unconnected(found.to_shape(label)))
+        .collect();
+
+    for component in line_components(&grid, &consumed) {
+        if let Some(line) = trace_line(&grid, &component, &border) {
+            consumed.extend(component);
+            shapes.push(line);
+        }
+    }
+
+    shapes.extend(
+        leftover_text(&grid, &consumed)
+            .into_iter()
+            .map(ImportedShape::unconnected),
+    );
+    shapes
+}
+
+/// Import ASCII art into a document, resolving connections to the new shape IDs
+pub fn import_ascii(doc: &mut Document, text: &str) -> Result<Vec<ShapeId>> {
+    add_imported(doc, parse_ascii(text))
+}
+
+/// Add imported shapes to a document, turning target indices into connections
+pub(crate) fn add_imported(doc: &mut Document, shapes: Vec<ImportedShape>) -> Result<Vec<ShapeId>> {
+    let mut ids: Vec<ShapeId> = Vec::new();
+
+    for shape in shapes {
+        let connection = |target: Option<usize>| {
+            debug_assert!(
+                target.is_none_or(|t| t < ids.len()),
+                "targets precede lines"
+            );
+            target
+                .and_then(|t| ids.get(t))
+                .map(|id| id.0.as_u128() as u64)
+        };
+        let (start_connection, end_connection) =
+            (connection(shape.start_target), connection(shape.end_target));
+
+        let kind = match shape.kind {
+            ShapeKind::Line {
+                start,
+                end,
+                style,
+                label,
+                color,
+                ..
+            } => ShapeKind::Line {
+                start,
+                end,
+                style,
+                start_connection,
+                end_connection,
+                label,
+                color,
+            },
+            ShapeKind::Arrow {
+                start,
+                end,
+                style,
+                label,
+                color,
+                ..
+            } => ShapeKind::Arrow {
+                start,
+                end,
+                style,
+                start_connection,
+                end_connection,
+                label,
+                color,
+            },
+            other => other,
+        };
+        ids.push(doc.add_shape(kind)?);
+    }
+
+    Ok(ids)
+}
+
+// ========== Character grid ==========
+
+struct Grid {
+    cells: HashMap<Position, char>,
+}
+
+impl Grid {
+    fn parse(text: &str) -> Self {
+        let mut cells = HashMap::new();
+        for (y, line) in text.lines().enumerate() {
+            let y = i32::try_from(y).unwrap_or(i32::MAX);
+            for (x, ch) in line.chars().enumerate() {
+                if !ch.is_whitespace() {
+                    cells.insert(Position::new(i32::try_from(x).unwrap_or(i32::MAX), y), ch);
+                }
+            }
+        }
+        Self { cells }
+    }
+
+    fn get(&self, pos: Position) -> Option<char> {
+        self.cells.get(&pos).copied()
+    }
+
+    /// All cells in reading order (top to bottom, left to right)
+    fn sorted_cells(&self) -> Vec<(Position, char)> {
+        let mut cells: Vec<(Position, char)> = self.cells.iter().map(|(p, c)| (*p, *c)).collect();
+        cells.sort_by_key(|(p, _)| (p.y, p.x));
+        cells
+    }
+
+    /// Whether a cell holds a line character, using neighbors to tell ASCII
+    /// line characters (`-`, `|`, `>` ...) apart from punctuation in text
+    fn is_line_cell(&self, pos: Position) -> bool {
+        let Some(ch) = self.get(pos) else {
+            return false;
+        };
+        if is_box_line_char(ch) || is_unicode_arrowhead(ch) {
+            return true;
+        }
+        is_ascii_line_char(ch)
+            && neighbors(pos).any(|n| {
+                self.get(n)
+                    .is_some_and(|c| (is_line_char(c) && c != ch) || (c == ch && ch != 'v'))
+            })
+    }
+}
+
+fn neighbors(pos: Position) -> impl Iterator<Item = Position> {
+    (-1..=1)
+        .flat_map(move |dy| (-1..=1).map(move |dx| (dx, dy)))
+        .filter(|&(dx, dy)| dx != 0 || dy != 0)
+        .map(move |(dx, dy)| Position::new(pos.x + dx, pos.y + dy))
+}
+
+fn is_box_line_char(ch: char) -> bool {
+    matches!(ch, '─' | '│' | '┌' | '┐' | '└' | '┘' | '╱' | '╲')
+}
+
+fn is_unicode_arrowhead(ch: char) -> bool {
+    matches!(ch, '→' | '←' | '↑' | '↓' | '↗' | '↘' | '↙' | '↖')
+}
+
+fn is_ascii_line_char(ch: char) -> bool {
+    matches!(ch, '-' | '|' | '+' | '/' | '\\' | '>' | '<' | '^' | 'v')
+}
+
+fn is_line_char(ch: char) -> bool {
+    is_box_line_char(ch) || is_unicode_arrowhead(ch) || is_ascii_line_char(ch)
+}
+
+fn is_arrowhead(ch: char) -> bool {
+    is_unicode_arrowhead(ch) || matches!(ch, '>' | '<' | '^' | 'v')
+}
+
+// ========== Boxes ==========
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BoxStyle {
+    Single,
+    Ascii,
+    Double,
+    Rounded,
+}
+
+/// Corner and edge characters of a box style: [top-left, top-right, bottom-left, bottom-right]
+struct BoxChars {
+    corners: [char; 4],
+    horizontal: char,
+    vertical: char,
+}
+
+impl BoxStyle {
+    const ALL: [BoxStyle; 4] = [
+        BoxStyle::Single,
+        BoxStyle::Ascii,
+        BoxStyle::Double,
+        BoxStyle::Rounded,
+    ];
+
+    fn chars(self) -> BoxChars {
+        match self {
+            BoxStyle::Single => BoxChars {
+                corners: ['┌', '┐', '└', '┘'],
+                horizontal: '─',
+                vertical: '│',
+            },
+            BoxStyle::Ascii => BoxChars {
+                corners: ['+', '+', '+', '+'],
+                horizontal: '-',
+                vertical: '|',
+            },
+            BoxStyle::Double => BoxChars {
+                corners: ['╔', '╗', '╚', '╝'],
+                horizontal: '═',
+                vertical: '║',
+            },
+            BoxStyle::Rounded => BoxChars {
+                corners: ['╭', '╮', '╰', '╯'],
+                horizontal: '─',
+                vertical: '│',
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FoundBox {
+    style: BoxStyle,
+    min: Position,
+    max: Position,
+}
+
+impl FoundBox {
+    fn area(&self) -> i64 {
+        i64::from(self.max.x - self.min.x + 1) * i64::from(self.max.y - self.min.y + 1)
+    }
+
+    fn border_cells(&self) -> impl Iterator<Item = Position> + '_ {
+        let horizontal = (self.min.x..=self.max.x)
+            .flat_map(|x| [Position::new(x, self.min.y), Position::new(x, self.max.y)]);
+        let vertical = (self.min.y + 1..self.max.y)
+            .flat_map(|y| [Position::new(self.min.x, y), Position::new(self.max.x, y)]);
+        horizontal.chain(vertical)
+    }
+
+    fn to_shape(self, label: Option<String>) -> ShapeKind {
+        let (start, end, color) = (self.min, self.max, ShapeColor::default());
+        match self.style {
+            BoxStyle::Single | BoxStyle::Ascii => ShapeKind::Rectangle {
+                start,
+                end,
+                label,
+                color,
+            },
+            BoxStyle::Double => ShapeKind::DoubleBox {
+                start,
+                end,
+                label,
+                color,
+            },
+            BoxStyle::Rounded => ShapeKind::RoundedRect {
+                start,
+                end,
+                label,
+                color,
+            },
+        }
+    }
+}
+
+/// Find all boxes, largest first (so outer boxes sit below nested ones)
+fn find_boxes(grid: &Grid) -> Vec<FoundBox> {
+    let mut boxes: Vec<FoundBox> = grid
+        .sorted_cells()
+        .into_iter()
+        .filter_map(|(pos, ch)| {
+            BoxStyle::ALL
+                .into_iter()
+                .filter(|style| style.chars().corners[0] == ch)
+                .find_map(|style| trace_box(grid, pos, style))
+        })
+        .collect();
+    boxes.sort_by_key(|b| std::cmp::Reverse(b.area()));
+    boxes
+}
+
+/// Trace a box from its top-left corner; edges may carry line endpoints drawn over them
+fn trace_box(grid: &Grid, top_left: Position, style: BoxStyle) -> Option<FoundBox> {
+    let chars = style.chars();
+    let [_, top_right, bottom_left, bottom_right] = chars.corners;
+    let is_edge = |pos: Position, edge: char| {
+        grid.get(pos)
+            .is_some_and(|ch| ch == edge || is_joint(ch, style))
+    };
+
+    // Walk right along the top edge to the first top-right corner
+    let mut max_x = top_left.x + 1;
+    while grid.get(Position::new(max_x, top_left.y)) != Some(top_right) {
+        if !is_edge(Position::new(max_x, top_left.y), chars.horizontal)
+            || max_x - top_left.x > MAX_EDGE_LENGTH
+        {
+            return None;
+        }
+        max_x += 1;
+    }
+
+    // Walk down the left edge to the first bottom-left corner
+    let mut max_y = top_left.y + 1;
+    while grid.get(Position::new(top_left.x, max_y)) != Some(bottom_left) {
+        if !is_edge(Position::new(top_left.x, max_y), chars.vertical)
+            || max_y - top_left.y > MAX_EDGE_LENGTH
+        {
+            return None;
+        }
+        max_y += 1;
+    }
+
+    let right_ok =
+        (top_left.y + 1..max_y).all(|y| is_edge(Position::new(max_x, y), chars.vertical));
+    let bottom_ok =
+        (top_left.x + 1..max_x).all(|x| is_edge(Position::new(x, max_y), chars.horizontal));
+    let corner_ok = grid.get(Position::new(max_x, max_y)) == Some(bottom_right);
+
+    (right_ok && bottom_ok && corner_ok).then_some(FoundBox {
+        style,
+        min: top_left,
+        max: Position::new(max_x, max_y),
+    })
+}
+
+/// Characters a connected line can leave on a box edge
+fn is_joint(ch: char, style: BoxStyle) -> bool {
+    let is_corner = style.chars().corners.contains(&ch);
+    !is_corner && (is_line_char(ch) || ch == '○')
+}
+
+/// Collect the unclaimed text inside a box as its label
+fn take_label(grid: &Grid, found: &FoundBox, consumed: &mut HashSet<Position>) -> Option<String> {
+    let mut rows = Vec::new();
+    for y in found.min.y + 1..found.max.y {
+        let row: String = (found.min.x + 1..found.max.x)
+            .map(|x| {
+                let pos = Position::new(x, y);
+                match grid.get(pos) {
+                    Some(ch) if consumed.insert(pos) => ch,
+                    _ => ' ',
+                }
+            })
+            .collect();
+        let row = row.trim();
+        if !row.is_empty() {
+            rows.push(row.to_string());
+        }
+    }
+    (!rows.is_empty()).then(|| rows.join(" "))
+}
+
+// ========== Lines and arrows ==========
+
+/// Group unclaimed line cells into 8-connected components
+fn line_components(grid: &Grid, consumed: &HashSet<Position>) -> Vec<HashSet<Position>> {
+    let candidates: HashSet<Position> = grid
+        .cells
+        .keys()
+        .copied()
+        .filter(|pos| !consumed.contains(pos) && grid.is_line_cell(*pos))
+        .collect();
+
+    let mut ordered: Vec<Position> = candidates.iter().copied().collect();
+    ordered.sort_by_key(|p| (p.y, p.x));
+
+    let mut seen: HashSet<Position> = HashSet::new();
+    let mut components = Vec::new();
+    for start in ordered {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut component = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(pos) = queue.pop_front() {
+            for next in neighbors(pos) {
+                if candidates.contains(&next) && seen.insert(next) {
+                    component.insert(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Turn a component into a Line or Arrow if it is a straight or single-bend path
+fn trace_line(
+    grid: &Grid,
+    cells: &HashSet<Position>,
+    border: &HashMap<Position, usize>,
+) -> Option<ImportedShape> {
+    // Undirected lines run in reading order
+    let mut endpoints: Vec<Position> = cells
+        .iter()
+        .copied()
+        .filter(|&pos| neighbors(pos).filter(|n| cells.contains(n)).count() == 1)
+        .collect();
+    endpoints.sort_by_key(|p| (p.y, p.x));
+    let [a, b] = endpoints[..] else {
+        return None;
+    };
+
+    // Extend each end onto the box border it runs into
+    let extend = |end: Position| {
+        let inner = neighbors(end).find(|n| cells.contains(n))?;
+        let beyond = Position::new(2 * end.x - inner.x, 2 * end.y - inner.y);
+        border.get(&beyond).map(|&target| (beyond, target))
+    };
+    let (a_ext, b_ext) = (extend(a), extend(b));
+    // The arrowhead may sit on the border or just before it
+    let has_head = |end: Position, ext: Option<(Position, usize)>| {
+        let tip = ext.map(|(pos, _)| pos);
+        [Some(end), tip]
+            .into_iter()
+            .flatten()
+            .any(|pos| grid.get(pos).is_some_and(is_arrowhead))
+    };
+
+    // Arrows point at the end carrying the arrowhead
+    let ((start, start_ext), (end, end_ext)) = if has_head(a, a_ext) && !has_head(b, b_ext) {
+        ((b, b_ext), (a, a_ext))
+    } else {
+        ((a, a_ext), (b, b_ext))
+    };
+    let is_arrow = has_head(end, end_ext);
+    let style = matching_style(cells, start, end)?;
+
+    let start_target = start_ext.map(|(_, target)| target);
+    let end_target = end_ext.map(|(_, target)| target);
+    let start = start_ext.map_or(start, |(pos, _)| pos);
+    let end = end_ext.map_or(end, |(pos, _)| pos);
+
+    let kind = if is_arrow {
+        ShapeKind::Arrow {
+            start,
+            end,
+            style,
+            start_connection: None,
+            end_connection: None,
+            label: None,
+            color: ShapeColor::default(),
+        }
+    } else {
+        ShapeKind::Line {
+            start,
+            end,
+            style,
+            start_connection: None,
+            end_connection: None,
+            label: None,
+            color: ShapeColor::default(),
+        }
+    };
+    Some(ImportedShape {
+        kind,
+        start_target,
+        end_target,
+    })
+}
+
+/// Find the line style whose rasterized path covers exactly these cells
+fn matching_style(cells: &HashSet<Position>, start: Position, end: Position) -> Option<LineStyle> {
+    let path = |from: Position, to: Position, style: LineStyle| -> HashSet<Position> {
+        line_points_styled(from, to, style)
+            .into_iter()
+            .map(|(pos, _)| pos)
+            .collect()
+    };
+
+    if path(start, end, LineStyle::Straight) == *cells
+        || path(end, start, LineStyle::Straight) == *cells
+    {
+        return Some(LineStyle::Straight);
+    }
+    [LineStyle::OrthogonalHV, LineStyle::OrthogonalVH]
+        .into_iter()
+        .find(|&style| path(start, end, style) == *cells)
+}
+
+// ========== Text ==========
+
+/// Group unclaimed characters into Text shapes, one per run of words on a row
+fn leftover_text(grid: &Grid, consumed: &HashSet<Position>) -> Vec<ShapeKind> {
+    let mut texts = Vec::new();
+    let mut run: Option<(Position, String, i32)> = None;
+
+    for (pos, ch) in grid.sorted_cells() {
+        if consumed.contains(&pos) {
+            continue;
+        }
+        // Words separated by a single space stay in one text
+        match &mut run {
+            Some((start, content, last_x)) if start.y == pos.y && pos.x - *last_x <= 2 => {
+                if pos.x - *last_x == 2 {
+                    content.push(' ');
+                }
+                content.push(ch);
+                *last_x = pos.x;
+            }
+            _ => {
+                if let Some((start, content, _)) = run.take() {
+                    texts.push(text_shape(start, content));
+                }
+                run = Some((pos, ch.to_string(), pos.x));
+            }
+        }
+    }
+    if let Some((start, content, _)) = run {
+        texts.push(text_shape(start, content));
+    }
+    texts
+}
+
+fn text_shape(pos: Position, content: String) -> ShapeKind {
+    ShapeKind::Text {
+        pos,
+        content,
+        color: ShapeColor::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii::save_ascii;
+    use irohscii_core::ShapeView;
+
+    fn kinds(text: &str) -> Vec<ShapeKind> {
+        parse_ascii(text).into_iter().map(|s| s.kind).collect()
+    }
+
+    #[test]
+    fn parse_ascii_box_styles() {
+        let text = "+----+  ┌──┐  ╔══╗  ╭──╮\n| hi |  │  │  ║  ║  │  │\n+----+  └──┘  ╚══╝  ╰──╯";
+        let names: Vec<&str> = kinds(text).iter().map(|k| k.type_name()).collect();
+        assert_eq!(names.iter().filter(|n| **n == "Rectangle").count(), 2);
+        assert!(names.contains(&"DoubleBox"));
+        assert!(names.contains(&"RoundedRect"));
+        assert_eq!(names.len(), 4, "no leftover text: {:?}", names);
+
+        let ascii_box = kinds(text)
+            .into_iter()
+            .find(|k| k.label().is_some())
+            .unwrap();
+        assert_eq!(ascii_box.label(), Some("hi"));
+        assert_eq!(ascii_box.bounds(), (0, 0, 5, 2));
+    }
+
+    #[test]
+    fn parse_ascii_arrow_connects_boxes() {
+        let text = "+--+    +--+\n|  |--->|  |\n+--+    +--+";
+        let shapes = parse_ascii(text);
+        assert_eq!(shapes.len(), 3);
+
+        let arrow = &shapes[2];
+        if let ShapeKind::Arrow {
+            start, end, style, ..
+        } = &arrow.kind
+        {
+            assert_eq!((*start, *end), (Position::new(3, 1), Position::new(8, 1)));
+            assert_eq!(*style, LineStyle::Straight);
+        } else {
+            panic!("Expected Arrow, got {}", arrow.kind.type_name());
+        }
+        let left = shapes.iter().position(|s| s.kind.bounds().0 == 0).unwrap();
+        let right = shapes.iter().position(|s| s.kind.bounds().0 == 8).unwrap();
+        assert_eq!(
+            (arrow.start_target, arrow.end_target),
+            (Some(left), Some(right))
+        );
+    }
+
+    #[test]
+    fn parse_ascii_keeps_leftovers_as_text() {
+        let text = "well-known a/b\n\n  ───┐\n     │\n     ↓";
+        let shapes = kinds(text);
+        let texts: Vec<&str> = shapes
+            .iter()
+            .filter_map(|k| match k {
+                ShapeKind::Text { content, .. } => Some(content.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, vec!["well-known a/b"]);
+        assert!(shapes.iter().any(|k| matches!(
+            k,
+            ShapeKind::Arrow {
+                style: LineStyle::OrthogonalHV,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn save_then_import_round_trip() {
+        let mut original = Document::new();
+        let a = original
+            .add_shape(ShapeKind::Rectangle {
+                start: Position::new(0, 0),
+                end: Position::new(10, 4),
+                label: Some("Client".to_string()),
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+        let b = original
+            .add_shape(ShapeKind::DoubleBox {
+                start: Position::new(20, 0),
+                end: Position::new(30, 4),
+                label: Some("Server".to_string()),
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+        let c = original
+            .add_shape(ShapeKind::RoundedRect {
+                start: Position::new(0, 8),
+                end: Position::new(10, 12),
+                label: None,
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+        let conn = |id: ShapeId| Some(id.0.as_u128() as u64);
+        original
+            .add_shape(ShapeKind::Arrow {
+                start: Position::new(10, 2),
+                end: Position::new(20, 2),
+                style: LineStyle::Straight,
+                start_connection: conn(a),
+                end_connection: conn(b),
+                label: None,
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+        original
+            .add_shape(ShapeKind::Line {
+                start: Position::new(5, 4),
+                end: Position::new(5, 8),
+                style: LineStyle::Straight,
+                start_connection: conn(a),
+                end_connection: conn(c),
+                label: None,
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+        original
+            .add_shape(ShapeKind::Text {
+                pos: Position::new(14, 10),
+                content: "notes here".to_string(),
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+
+        let mut view = ShapeView::new();
+        view.rebuild(&original).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("diagram.txt");
+        save_ascii(&view, &path).unwrap();
+
+        let mut imported = Document::new();
+        let text = std::fs::read_to_string(&path).unwrap();
+        import_ascii(&mut imported, &text).unwrap();
+
+        let mut before = original.read_all_shapes().unwrap();
+        let mut after = imported.read_all_shapes().unwrap();
+        assert_eq!(before.len(), after.len());
+
+        // Compare kinds, bounds and labels, and resolve connections to the bounds they point at
+        let bounds_of = |shapes: &[(ShapeId, ShapeKind)], conn: Option<u64>| {
+            conn.and_then(|c| {
+                shapes
+                    .iter()
+                    .find(|(id, _)| id.0.as_u128() as u64 == c)
+                    .map(|(_, k)| k.bounds())
+            })
+        };
+        let describe = |shapes: &[(ShapeId, ShapeKind)]| {
+            let mut out: Vec<String> = shapes
+                .iter()
+                .map(|(_, kind)| {
+                    let (start_conn, end_conn) = match kind {
+                        ShapeKind::Line {
+                            start_connection,
+                            end_connection,
+                            ..
+                        }
+                        | ShapeKind::Arrow {
+                            start_connection,
+                            end_connection,
+                            ..
+                        } => (*start_connection, *end_connection),
+                        _ => (None, None),
+                    };
+                    format!(
+                        "{} {:?} {:?} {:?} {:?}",
+                        kind.type_name(),
+                        kind.bounds(),
+                        kind.label(),
+                        bounds_of(shapes, start_conn),
+                        bounds_of(shapes, end_conn)
+                    )
+                })
+                .collect();
+            out.sort();
+            out
+        };
+        before.sort_by_key(|(_, k)| k.type_name());
+        after.sort_by_key(|(_, k)| k.type_name());
+        assert_eq!(describe(&before), describe(&after));
+    }
+}
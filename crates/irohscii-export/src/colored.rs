@@ -0,0 +1,265 @@
+//! Colored text exports: ANSI escape sequences for terminals and HTML `<pre>`
+//! blocks for wikis.
+//!
+//! Both render the same character grid as the ASCII export, keeping each
+//! cell's shape color with the TUI's palette.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use irohscii_core::{Position, ShapeColor, ShapeView};
+
+use crate::ascii::{Cell, Grid, build_shape_grid, calculate_grid_bounds};
+use crate::options::Bounds;
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Render shapes as text with ANSI color escape sequences
+pub fn render_shapes_to_ansi(shapes: &ShapeView) -> String {
+    let grid = build_shape_grid(shapes);
+    render_grid_to_ansi(&grid, calculate_grid_bounds(&grid), true)
+}
+
+/// Render the cells of a grid within `bounds` as ANSI-colored text
+pub(crate) fn render_grid_to_ansi(grid: &Grid, bounds: Option<Bounds>, trim: bool) -> String {
+    let mut lines = Vec::new();
+    for row in grid_rows(grid, bounds, trim) {
+        let mut line = String::new();
+        let mut current: Option<ShapeColor> = None;
+        for cell in row {
+            match cell {
+                Some(cell) => {
+                    if current != Some(cell.color) {
+                        line.push_str(&format!("\x1b[{}m", ansi_code(cell.color)));
+                        current = Some(cell.color);
+                    }
+                    line.push(cell.ch);
+                }
+                // Gaps keep the current color; spaces show no foreground
+                None => line.push(' '),
+            }
+        }
+        if current.is_some() {
+            line.push_str(ANSI_RESET);
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Save shapes as ANSI-colored text, viewable with `cat`
+pub fn save_ansi(shapes: &ShapeView, path: &Path) -> Result<()> {
+    let mut content = render_shapes_to_ansi(shapes);
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    fs::write(path, content).with_context(|| format!("Failed to save to {:?}", path))
+}
+
+/// Whether a path names an ANSI text file
+pub fn is_ansi_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ans"))
+}
+
+/// Render shapes as an HTML `<pre>` block of colored spans. With `titles`,
+/// each span carries a hover title naming the shape that drew it.
+pub fn render_shapes_to_html(shapes: &ShapeView, titles: bool) -> String {
+    let grid = build_shape_grid(shapes);
+    render_grid_to_html(shapes, &grid, calculate_grid_bounds(&grid), true, titles)
+}
+
+/// Render the cells of a grid within `bounds` as an HTML `<pre>` block.
+/// `shapes` is the view the grid was built from, naming each cell's shape.
+pub(crate) fn render_grid_to_html(
+    shapes: &ShapeView,
+    grid: &Grid,
+    bounds: Option<Bounds>,
+    trim: bool,
+    titles: bool,
+) -> String {
+    let names: Vec<String> = shapes
+        .iter()
+        .map(|shape| match shape.kind.label() {
+            Some(label) => format!("{}: {}", shape.kind.type_name(), label),
+            None => shape.kind.type_name().to_string(),
+        })
+        .collect();
+
+    let mut out = String::from(
+        "<pre class=\"irohscii\" style=\"background:black;color:white;font-family:monospace\">",
+    );
+    let mut lines = Vec::new();
+    for row in grid_rows(grid, bounds, trim) {
+        let mut line = String::new();
+        let mut i = 0;
+        while i < row.len() {
+            let Some(first) = row[i] else {
+                line.push(' ');
+                i += 1;
+                continue;
+            };
+            // One span per run of cells from the same shape (or color)
+            let same = |cell: &Option<Cell>| {
+                cell.is_some_and(|c| c.color == first.color && (!titles || c.shape == first.shape))
+            };
+            let run = row[i..].iter().take_while(|c| same(c)).count();
+            let text: String = row[i..i + run].iter().flatten().map(|c| c.ch).collect();
+            line.push_str(&format!("<span style=\"color:{}\"", first.color.to_css()));
+            if titles {
+                line.push_str(&format!(" title=\"{}\"", escape_html(&names[first.shape])));
+            }
+            line.push('>');
+            line.push_str(&escape_html(&text));
+            line.push_str("</span>");
+            i += run;
+        }
+        lines.push(line);
+    }
+    out.push_str(&lines.join("\n"));
+    out.push_str("</pre>\n");
+    out
+}
+
+/// Save shapes as an HTML `<pre>` block with hover titles
+pub fn save_html(shapes: &ShapeView, path: &Path) -> Result<()> {
+    fs::write(path, render_shapes_to_html(shapes, true))
+        .with_context(|| format!("Failed to save to {:?}", path))
+}
+
+/// Whether a path names an HTML file
+pub fn is_html_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+}
+
+/// Grid rows within `bounds`; with `trim`, without trailing blank cells or rows
+fn grid_rows(grid: &Grid, bounds: Option<Bounds>, trim: bool) -> Vec<Vec<Option<Cell>>> {
+    let Some((min_x, min_y, max_x, max_y)) = bounds else {
+        return Vec::new();
+    };
+    let mut rows: Vec<Vec<Option<Cell>>> = (min_y..=max_y)
+        .map(|y| {
+            let mut row: Vec<Option<Cell>> = (min_x..=max_x)
+                .map(|x| {
+                    grid.cells
+                        .get(&Position::new(x, y))
+                        .copied()
+                        .filter(|c| c.ch != ' ')
+                })
+                .collect();
+            while trim && row.last().is_some_and(Option::is_none) {
+                row.pop();
+            }
+            row
+        })
+        .collect();
+    while trim && rows.last().is_some_and(Vec::is_empty) {
+        rows.pop();
+    }
+    rows
+}
+
+/// SGR foreground code for a color, matching the TUI's terminal colors
+fn ansi_code(color: ShapeColor) -> u8 {
+    match color {
+        ShapeColor::Black => 30,
+        ShapeColor::Red => 31,
+        ShapeColor::Green => 32,
+        ShapeColor::Yellow => 33,
+        ShapeColor::Blue => 34,
+        ShapeColor::Magenta => 35,
+        ShapeColor::Cyan => 36,
+        ShapeColor::Gray => 37,
+        ShapeColor::DarkGray => 90,
+        ShapeColor::LightRed => 91,
+        ShapeColor::LightGreen => 92,
+        ShapeColor::LightYellow => 93,
+        ShapeColor::LightBlue => 94,
+        ShapeColor::LightMagenta => 95,
+        ShapeColor::LightCyan => 96,
+        ShapeColor::White => 97,
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_shapes_to_text;
+    use irohscii_core::{Document, ShapeKind};
+
+    fn view(kinds: Vec<ShapeKind>) -> ShapeView {
+        let mut doc = Document::new();
+        for kind in kinds {
+            doc.add_shape(kind).unwrap();
+        }
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+        view
+    }
+
+    fn sample() -> ShapeView {
+        view(vec![
+            ShapeKind::Rectangle {
+                start: Position::new(0, 0),
+                end: Position::new(8, 2),
+                label: Some("A<B".to_string()),
+                color: ShapeColor::Red,
+            },
+            ShapeKind::Text {
+                pos: Position::new(12, 1),
+                content: "note".to_string(),
+                color: ShapeColor::default(),
+            },
+        ])
+    }
+
+    /// Drop escape sequences, leaving the visible text
+    fn strip_ansi(text: &str) -> String {
+        let mut out = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().take_while(|&c| c != 'm').for_each(drop);
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn ansi_colors_each_shape() {
+        let shapes = sample();
+        let ansi = render_shapes_to_ansi(&shapes);
+        assert!(ansi.starts_with("\x1b[31m┌"));
+        assert!(ansi.contains("\x1b[97mnote\x1b[0m"));
+        // Same characters as the plain export
+        assert_eq!(strip_ansi(&ansi), render_shapes_to_text(&shapes));
+        assert_eq!(render_shapes_to_ansi(&view(vec![])), "");
+    }
+
+    #[test]
+    fn html_spans_with_titles() {
+        let shapes = sample();
+        let html = render_shapes_to_html(&shapes, true);
+        assert!(html.starts_with("<pre class=\"irohscii\""));
+        assert!(html.ends_with("</pre>\n"));
+        assert!(html.contains("<span style=\"color:#cd0000\" title=\"Rectangle: A&lt;B\">"));
+        assert!(html.contains("title=\"Text\">note</span>"));
+
+        let plain = render_shapes_to_html(&shapes, false);
+        assert!(!plain.contains("title="));
+        assert!(plain.contains("A&lt;B"));
+    }
+}
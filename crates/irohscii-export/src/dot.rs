@@ -0,0 +1,835 @@
+//! Graphviz DOT import and export.
+//!
+//! Imports `graph`/`digraph` definitions: nodes become shapes matching their
+//! `shape` attribute, edges become connected arrows (or lines) with labels, and
+//! the result is laid out in ranks along `rankdir`. Subgraphs are flattened.
+//! Exports the connection graph of a document as a digraph.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use irohscii_core::{Document, ShapeId, ShapeKind, ShapeView};
+
+use crate::ascii_import::{ImportedShape, add_imported};
+use crate::graph::{Direction, Edge, Graph, NodeShape, connection_graph};
+
+type Attrs = HashMap<String, String>;
+
+/// Deepest nesting of subgraphs accepted, so hostile input can't overflow the stack
+const MAX_SUBGRAPH_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier, number, quoted string or HTML string
+    Id(String),
+    /// Punctuation or an edge operator
+    Symbol(&'static str),
+}
+
+/// Parse a DOT graph into laid-out shapes. Nodes come first, then edges,
+/// which reference their nodes by index.
+pub fn parse_dot(text: &str) -> Result<Vec<ImportedShape>> {
+    let mut parser = Parser::new(tokenize(text)?);
+    parser.graph()?;
+    Ok(parser.into_graph().shapes())
+}
+
+/// Import a DOT graph into a document with connected edges
+pub fn import_dot(doc: &mut Document, text: &str) -> Result<Vec<ShapeId>> {
+    add_imported(doc, parse_dot(text)?)
+}
+
+/// Import a DOT file into a document
+pub fn load_dot(doc: &mut Document, path: &Path) -> Result<Vec<ShapeId>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read from {:?}", path))?;
+    import_dot(doc, &content)
+}
+
+/// Whether a path names a Graphviz DOT file
+pub fn is_dot_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("dot") || ext.eq_ignore_ascii_case("gv"))
+}
+
+// ========== Tokenizer ==========
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\n' => {
+                line_start = true;
+                i += 1;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            // `#` lines are preprocessor output and ignored
+            '#' if line_start => skip_line(&chars, &mut i),
+            '/' if next == Some('/') => skip_line(&chars, &mut i),
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '-' if next == Some('>') => {
+                tokens.push(Token::Symbol("->"));
+                i += 2;
+            }
+            '-' if next == Some('-') => {
+                tokens.push(Token::Symbol("--"));
+                i += 2;
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' => {
+                let symbol = match c {
+                    '{' => "{",
+                    '}' => "}",
+                    '[' => "[",
+                    ']' => "]",
+                    ';' => ";",
+                    ',' => ",",
+                    '=' => "=",
+                    _ => ":",
+                };
+                tokens.push(Token::Symbol(symbol));
+                i += 1;
+            }
+            '"' => {
+                let mut value = quoted(&chars, &mut i)?;
+                // `"a" + "b"` concatenates
+                loop {
+                    let mut j = i;
+                    while j < chars.len() && chars[j].is_whitespace() {
+                        j += 1;
+                    }
+                    if chars.get(j) != Some(&'+') {
+                        break;
+                    }
+                    j += 1;
+                    while j < chars.len() && chars[j].is_whitespace() {
+                        j += 1;
+                    }
+                    if chars.get(j) != Some(&'"') {
+                        break;
+                    }
+                    i = j;
+                    value.push_str(&quoted(&chars, &mut i)?);
+                }
+                tokens.push(Token::Id(value));
+            }
+            '<' => {
+                let start = i + 1;
+                let mut depth = 0;
+                while i < chars.len() {
+                    match chars[i] {
+                        '<' => depth += 1,
+                        '>' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    bail!("Unterminated HTML string in DOT graph");
+                }
+                tokens.push(Token::Id(strip_tags(
+                    &chars[start..i].iter().collect::<String>(),
+                )));
+                i += 1;
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || !c.is_ascii() => {
+                let start = i;
+                while i < chars.len() {
+                    let c = chars[i];
+                    let is_edge = c == '-' && matches!(chars.get(i + 1), Some('-' | '>'));
+                    if is_edge
+                        || !(c.is_alphanumeric()
+                            || c == '_'
+                            || c == '.'
+                            || c == '-'
+                            || !c.is_ascii())
+                    {
+                        break;
+                    }
+                    i += 1;
+                }
+                tokens.push(Token::Id(chars[start..i].iter().collect()));
+            }
+            c => bail!("Unexpected character {:?} in DOT graph", c),
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+fn skip_line(chars: &[char], i: &mut usize) {
+    while *i < chars.len() && chars[*i] != '\n' {
+        *i += 1;
+    }
+}
+
+/// Read a quoted string starting at `chars[*i]`. Only `\"` is unescaped here;
+/// other escapes are label escapes and are handled by [`clean_label`].
+fn quoted(chars: &[char], i: &mut usize) -> Result<String> {
+    let mut value = String::new();
+    *i += 1;
+    while *i < chars.len() {
+        match chars[*i] {
+            '"' => {
+                *i += 1;
+                return Ok(value);
+            }
+            '\\' if chars.get(*i + 1) == Some(&'"') => {
+                value.push('"');
+                *i += 1;
+            }
+            '\\' if chars.get(*i + 1) == Some(&'\n') => *i += 1,
+            c => value.push(c),
+        }
+        *i += 1;
+    }
+    bail!("Unterminated string in DOT graph")
+}
+
+/// Reduce an HTML label to its text
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                // Tags such as <br/> and </td> separate words
+                if !text.ends_with(' ') && !text.is_empty() {
+                    text.push(' ');
+                }
+            }
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .trim()
+        .to_string()
+}
+
+// ========== Parsing ==========
+
+/// Recursive descent parser that collects nodes and edges with their attributes
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Number of subgraphs enclosing the current position
+    depth: usize,
+    directed: bool,
+    direction: Direction,
+    /// Node and edge attribute defaults of the current (sub)graph
+    node_defaults: Attrs,
+    edge_defaults: Attrs,
+    /// Node ids in order of first appearance, with their attributes
+    node_ids: Vec<String>,
+    nodes: HashMap<String, Attrs>,
+    edges: Vec<(String, String, Attrs)>,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            depth: 0,
+            directed: false,
+            direction: Direction::TopDown,
+            node_defaults: Attrs::new(),
+            edge_defaults: Attrs::new(),
+            node_ids: Vec::new(),
+            nodes: HashMap::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.peek_symbol(symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if !self.eat_symbol(symbol) {
+            bail!(
+                "Expected '{}' in DOT graph, found {:?}",
+                symbol,
+                self.peek()
+            );
+        }
+        Ok(())
+    }
+
+    fn id(&mut self) -> Option<String> {
+        if let Some(Token::Id(id)) = self.peek() {
+            let id = id.clone();
+            self.pos += 1;
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    /// `strict? (graph | digraph) ID? { statements }`
+    fn graph(&mut self) -> Result<()> {
+        if self.peek_keyword("strict") {
+            self.pos += 1;
+        }
+        if self.peek_keyword("digraph") {
+            self.directed = true;
+        } else if !self.peek_keyword("graph") {
+            bail!(
+                "Not a DOT graph: expected 'graph' or 'digraph', found {:?}",
+                self.peek()
+            );
+        }
+        self.pos += 1;
+        if !self.peek_symbol("{") {
+            self.id();
+        }
+        self.expect_symbol("{")?;
+        self.statements()?;
+        self.expect_symbol("}")
+    }
+
+    /// Parse statements up to the closing brace, returning the nodes they mention
+    fn statements(&mut self) -> Result<Vec<String>> {
+        let mut mentioned = Vec::new();
+        while self.peek().is_some() && !self.peek_symbol("}") {
+            mentioned.extend(self.statement()?);
+            while self.eat_symbol(";") || self.eat_symbol(",") {}
+        }
+        Ok(mentioned)
+    }
+
+    fn statement(&mut self) -> Result<Vec<String>> {
+        let is_attr_target = ["graph", "node", "edge"]
+            .iter()
+            .any(|k| self.peek_keyword(k))
+            && matches!(self.tokens.get(self.pos + 1), Some(Token::Symbol("[")));
+        if is_attr_target {
+            let target = self.id().unwrap_or_default().to_ascii_lowercase();
+            let attrs = self.attr_lists()?;
+            match target.as_str() {
+                "node" => self.node_defaults.extend(attrs),
+                "edge" => self.edge_defaults.extend(attrs),
+                _ => self.graph_attrs(attrs),
+            }
+            return Ok(Vec::new());
+        }
+
+        if matches!(self.peek(), Some(Token::Id(_)))
+            && matches!(self.tokens.get(self.pos + 1), Some(Token::Symbol("=")))
+        {
+            let key = self.id().unwrap_or_default();
+            self.pos += 1;
+            let value = self
+                .id()
+                .context("Expected a value after '=' in DOT graph")?;
+            self.graph_attrs(Attrs::from([(key, value)]));
+            return Ok(Vec::new());
+        }
+
+        let mut operands = vec![self.operand()?];
+        while self.peek_symbol("->") || self.peek_symbol("--") {
+            self.pos += 1;
+            operands.push(self.operand()?);
+        }
+        let attrs = self.attr_lists()?;
+
+        if operands.len() == 1 {
+            let nodes = operands.remove(0);
+            // A bare node statement carries node attributes; a subgraph does not
+            if let [node] = nodes.as_slice() {
+                self.nodes.entry(node.clone()).or_default().extend(attrs);
+            }
+            return Ok(nodes);
+        }
+
+        let mut edge_attrs = self.edge_defaults.clone();
+        edge_attrs.extend(attrs);
+        for pair in operands.windows(2) {
+            for from in &pair[0] {
+                for to in &pair[1] {
+                    self.edges
+                        .push((from.clone(), to.clone(), edge_attrs.clone()));
+                }
+            }
+        }
+        Ok(operands.concat())
+    }
+
+    /// An edge operand: a node id (with optional port) or a subgraph
+    fn operand(&mut self) -> Result<Vec<String>> {
+        if self.peek_keyword("subgraph") || self.peek_symbol("{") {
+            return self.subgraph();
+        }
+        let id = self
+            .id()
+            .with_context(|| format!("Expected a node in DOT graph, found {:?}", self.peek()))?;
+        // Ports (`node:port:compass`) do not affect layout
+        while self.eat_symbol(":") {
+            self.id();
+        }
+        self.touch(&id);
+        Ok(vec![id])
+    }
+
+    /// `subgraph ID? { statements }`, flattened into the graph. Defaults set
+    /// inside a subgraph only apply within it.
+    fn subgraph(&mut self) -> Result<Vec<String>> {
+        if self.peek_keyword("subgraph") {
+            self.pos += 1;
+            if !self.peek_symbol("{") {
+                self.id();
+            }
+        }
+        self.expect_symbol("{")?;
+        if self.depth >= MAX_SUBGRAPH_DEPTH {
+            bail!(
+                "DOT subgraphs nested deeper than {} levels",
+                MAX_SUBGRAPH_DEPTH
+            );
+        }
+        let saved = (self.node_defaults.clone(), self.edge_defaults.clone());
+        let direction = self.direction;
+        self.depth += 1;
+        let nodes = self.statements()?;
+        self.depth -= 1;
+        (self.node_defaults, self.edge_defaults) = saved;
+        // Only the root graph's rankdir counts
+        self.direction = direction;
+        self.expect_symbol("}")?;
+        Ok(nodes)
+    }
+
+    /// One or more `[key=value, ...]` lists
+    fn attr_lists(&mut self) -> Result<Attrs> {
+        let mut attrs = Attrs::new();
+        while self.eat_symbol("[") {
+            while !self.eat_symbol("]") {
+                let key = self
+                    .id()
+                    .with_context(|| format!("Expected an attribute, found {:?}", self.peek()))?;
+                let value = if self.eat_symbol("=") {
+                    self.id()
+                        .context("Expected a value after '=' in DOT graph")?
+                } else {
+                    "true".to_string()
+                };
+                attrs.insert(key, value);
+                while self.eat_symbol(",") || self.eat_symbol(";") {}
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn graph_attrs(&mut self, attrs: Attrs) {
+        if let Some(direction) = attrs.get("rankdir").and_then(|d| Direction::parse(d)) {
+            self.direction = direction;
+        }
+    }
+
+    /// Register a node on first reference with the current node defaults
+    fn touch(&mut self, id: &str) {
+        if !self.nodes.contains_key(id) {
+            self.node_ids.push(id.to_string());
+            self.nodes
+                .insert(id.to_string(), self.node_defaults.clone());
+        }
+    }
+
+    fn into_graph(self) -> Graph {
+        let mut graph = Graph::new(self.direction);
+        for id in &self.node_ids {
+            let attrs = &self.nodes[id];
+            let label = match attrs.get("label") {
+                Some(label) => clean_label(label, id),
+                None => Some(id.clone()),
+            };
+            graph.node(id, Some((node_shape(attrs), label)));
+        }
+
+        for (from, to, attrs) in &self.edges {
+            let dir = attrs
+                .get("dir")
+                .map(|d| d.to_ascii_lowercase())
+                .unwrap_or_else(|| if self.directed { "forward" } else { "none" }.into());
+            let (from, to) = if dir == "back" {
+                (to, from)
+            } else {
+                (from, to)
+            };
+            let (from, to) = (graph.node(from, None), graph.node(to, None));
+            graph.edges.push(Edge {
+                from,
+                to,
+                arrow: dir != "none",
+                label: attrs.get("label").and_then(|l| clean_label(l, "")),
+            });
+        }
+        graph
+    }
+}
+
+/// Map a node's `shape` and `style` attributes to a node shape
+fn node_shape(attrs: &Attrs) -> NodeShape {
+    let style = attrs.get("style").map_or("", String::as_str);
+    let rounded = style.split(',').any(|s| s.trim() == "rounded");
+    let double = attrs
+        .get("peripheries")
+        .and_then(|p| p.parse::<u32>().ok())
+        .is_some_and(|p| p >= 2);
+    let shape = attrs.get("shape").map_or("ellipse", String::as_str);
+
+    match shape.to_ascii_lowercase().as_str() {
+        "box" | "rect" | "rectangle" | "square" | "plaintext" | "plain" | "none" | "underline"
+        | "note" | "tab" | "folder" | "record" | "msquare" => {
+            if rounded {
+                NodeShape::Rounded
+            } else if double {
+                NodeShape::Subroutine
+            } else {
+                NodeShape::Rect
+            }
+        }
+        "mrecord" => NodeShape::Rounded,
+        "box3d" | "component" => NodeShape::Subroutine,
+        "diamond" | "mdiamond" => NodeShape::Diamond,
+        "hexagon" | "octagon" | "doubleoctagon" | "tripleoctagon" => NodeShape::Hexagon,
+        "cylinder" => NodeShape::Cylinder,
+        "parallelogram" => NodeShape::Parallelogram,
+        "trapezium" | "invtrapezium" | "house" | "invhouse" => NodeShape::Trapezoid,
+        "cds" | "rarrow" | "larrow" | "rpromoter" | "lpromoter" => NodeShape::Asymmetric,
+        "ellipse" | "oval" | "circle" | "doublecircle" | "point" | "egg" => NodeShape::Circle,
+        _ => NodeShape::Rect,
+    }
+}
+
+/// Expand label escapes (`\N` is the node id) and flatten line breaks
+fn clean_label(label: &str, id: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'l' | 'r') => text.push(' '),
+            Some('N') => text.push_str(id),
+            Some('G' | 'E' | 'T' | 'H') => {}
+            Some(c) => text.push(c),
+            None => text.push('\\'),
+        }
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+// ========== Export ==========
+
+/// Export the connection graph of a document as a DOT digraph. Labeled or
+/// connected shapes become nodes; lines and arrows connected at both ends
+/// become edges, lines with `dir=none`.
+pub fn export_dot(shapes: &ShapeView) -> String {
+    let graph = connection_graph(shapes);
+    let mut out = String::from("digraph {\n");
+    let rankdir = if graph.horizontal { "LR" } else { "TB" };
+    out.push_str(&format!("    rankdir={};\n", rankdir));
+
+    for (i, kind) in graph.nodes.iter().enumerate() {
+        let label = escape_label(kind.label().unwrap_or(""));
+        out.push_str(&format!(
+            "    n{} [label=\"{}\", {}];\n",
+            i + 1,
+            label,
+            node_attrs(kind)
+        ));
+    }
+    for edge in &graph.edges {
+        let mut attrs = Vec::new();
+        if let Some(label) = &edge.label {
+            attrs.push(format!("label=\"{}\"", escape_label(label)));
+        }
+        if !edge.arrow {
+            attrs.push("dir=none".to_string());
+        }
+        let attrs = if attrs.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", attrs.join(", "))
+        };
+        out.push_str(&format!(
+            "    n{} -> n{}{};\n",
+            edge.from + 1,
+            edge.to + 1,
+            attrs
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Save the connection graph of a document as a DOT file
+pub fn save_dot(shapes: &ShapeView, path: &Path) -> Result<()> {
+    fs::write(path, export_dot(shapes)).with_context(|| format!("Failed to save to {:?}", path))
+}
+
+fn node_attrs(kind: &ShapeKind) -> &'static str {
+    match kind {
+        ShapeKind::RoundedRect { .. } => "shape=box, style=rounded",
+        ShapeKind::DoubleBox { .. } => "shape=box, peripheries=2",
+        ShapeKind::Ellipse { .. } => "shape=ellipse",
+        ShapeKind::Diamond { .. } => "shape=diamond",
+        ShapeKind::Hexagon { .. } => "shape=hexagon",
+        ShapeKind::Cylinder { .. } => "shape=cylinder",
+        ShapeKind::Parallelogram { .. } => "shape=parallelogram",
+        ShapeKind::Trapezoid { .. } => "shape=trapezium",
+        ShapeKind::Triangle { .. } => "shape=triangle",
+        ShapeKind::Star { .. } => "shape=star",
+        _ => "shape=box",
+    }
+}
+
+fn escape_label(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<ShapeKind> {
+        parse_dot(text)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.kind)
+            .collect()
+    }
+
+    /// Edges as (from label, to label, arrow, edge label)
+    fn topology(shapes: &[ImportedShape]) -> Vec<(String, String, bool, Option<String>)> {
+        let label = |i: Option<usize>| shapes[i.unwrap()].kind.label().unwrap_or("").to_string();
+        shapes
+            .iter()
+            .filter(|s| s.start_target.is_some())
+            .map(|s| {
+                (
+                    label(s.start_target),
+                    label(s.end_target),
+                    matches!(s.kind, ShapeKind::Arrow { .. }),
+                    s.kind.label().map(str::to_string),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_dot_node_shapes() {
+        let text = r#"digraph G {
+            node [shape=box];
+            a [label="Rect"]; b [style="rounded,filled"]; c [shape=diamond]
+            d [shape=cylinder]; e [shape=ellipse]; f [shape=hexagon]
+            g [shape=box, peripheries=2]; h [shape=parallelogram]; i [shape=trapezium]
+            subgraph cluster_x { node [shape=circle]; j }
+            k
+        }"#;
+        let names: Vec<&str> = kinds(text).iter().map(|k| k.type_name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Rectangle",
+                "RoundedRect",
+                "Diamond",
+                "Cylinder",
+                "Ellipse",
+                "Hexagon",
+                "DoubleBox",
+                "Parallelogram",
+                "Trapezoid",
+                "Ellipse",
+                // Subgraph defaults do not leak out
+                "Rectangle",
+            ]
+        );
+        assert_eq!(kinds(text)[0].label(), Some("Rect"));
+        assert_eq!(kinds(text)[1].label(), Some("b"));
+        // Nodes default to ellipses
+        assert_eq!(kinds("graph { a }")[0].type_name(), "Ellipse");
+    }
+
+    #[test]
+    fn parse_dot_edges_and_labels() {
+        let text = r#"
+            // A comment
+            strict digraph "deps" {
+                /* block
+                   comment */
+                a -> b [label="uses"];
+                b -> c -> d
+                a -> {c d} [label=<<b>both</b>>]
+                d -> a [dir=none]
+                e -> a [dir=back, label="line\nbreak"]
+                "x y":p:n -> a
+            }"#;
+        let shapes = parse_dot(text).unwrap();
+        let edges = topology(&shapes);
+        let pair = |a: &str, b: &str, arrow, label: Option<&str>| {
+            (
+                a.to_string(),
+                b.to_string(),
+                arrow,
+                label.map(str::to_string),
+            )
+        };
+        assert_eq!(
+            edges,
+            vec![
+                pair("a", "b", true, Some("uses")),
+                pair("b", "c", true, None),
+                pair("c", "d", true, None),
+                pair("a", "c", true, Some("both")),
+                pair("a", "d", true, Some("both")),
+                pair("d", "a", false, None),
+                pair("a", "e", true, Some("line break")),
+                pair("x y", "a", true, None),
+            ]
+        );
+
+        // Undirected graphs draw lines
+        let shapes = parse_dot("graph { a -- b; b -- c [dir=forward] }").unwrap();
+        let arrows: Vec<bool> = topology(&shapes).iter().map(|e| e.2).collect();
+        assert_eq!(arrows, vec![false, true]);
+    }
+
+    #[test]
+    fn parse_dot_rejects_other_input() {
+        assert!(parse_dot("flowchart TD\n  A --> B").is_err());
+        assert!(parse_dot("digraph { a -> }").is_err());
+        assert!(parse_dot("digraph { a [label=\"open }").is_err());
+        assert!(parse_dot("").is_err());
+    }
+
+    #[test]
+    fn parse_dot_limits_subgraph_nesting() {
+        let nested = |depth: usize| {
+            format!(
+                "digraph {{ {} a {} }}",
+                "{ ".repeat(depth),
+                "} ".repeat(depth)
+            )
+        };
+        assert!(parse_dot(&nested(MAX_SUBGRAPH_DEPTH)).is_ok());
+        assert!(parse_dot(&nested(MAX_SUBGRAPH_DEPTH + 1)).is_err());
+        assert!(parse_dot(&nested(100_000)).is_err());
+    }
+
+    #[test]
+    fn layout_follows_rankdir() {
+        let td = kinds("digraph { a -> b }");
+        assert!(td[0].bounds().3 < td[1].bounds().1);
+
+        let lr = kinds("digraph { rankdir=LR; a -> b }");
+        assert!(lr[0].bounds().2 < lr[1].bounds().0);
+
+        let bt = kinds("digraph { graph [rankdir=BT]; a -> b }");
+        assert!(bt[1].bounds().3 < bt[0].bounds().1);
+    }
+
+    #[test]
+    fn import_dot_connects_edges() {
+        let mut doc = Document::new();
+        let ids = import_dot(&mut doc, "digraph { start [shape=box]; start -> done }").unwrap();
+        assert_eq!(ids.len(), 3);
+
+        let arrow = doc.read_shape(ids[2]).unwrap().unwrap();
+        if let ShapeKind::Arrow {
+            start_connection,
+            end_connection,
+            ..
+        } = arrow
+        {
+            assert_eq!(start_connection, Some(ids[0].0.as_u128() as u64));
+            assert_eq!(end_connection, Some(ids[1].0.as_u128() as u64));
+        } else {
+            panic!("Expected Arrow");
+        }
+    }
+
+    #[test]
+    fn export_then_import_preserves_topology() {
+        let source = r#"digraph {
+            client [shape=box, label="Web \"client\" app"];
+            api [shape=box, style=rounded];
+            db [shape=cylinder]; cache [shape=diamond]
+            client -> api [label="HTTP"]
+            api -> db; api -> cache
+            cache -> db [dir=none]
+            db -> client
+        }"#;
+        let mut doc = Document::new();
+        import_dot(&mut doc, source).unwrap();
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+
+        let exported = export_dot(&view);
+        assert!(exported.starts_with("digraph {\n    rankdir=TB;\n"));
+        assert!(exported.contains("n1 [label=\"Web \\\"client\\\" app\", shape=box];"));
+        assert!(exported.contains("n2 [label=\"api\", shape=box, style=rounded];"));
+        assert!(exported.contains("n1 -> n2 [label=\"HTTP\"];"));
+        assert!(exported.contains("n4 -> n3 [dir=none];"));
+
+        let original = parse_dot(source).unwrap();
+        let reimported = parse_dot(&exported).unwrap();
+        assert_eq!(topology(&reimported), topology(&original));
+        let names = |shapes: &[ImportedShape]| -> Vec<&'static str> {
+            shapes.iter().map(|s| s.kind.type_name()).collect()
+        };
+        assert_eq!(names(&reimported), names(&original));
+    }
+}
@@ -0,0 +1,464 @@
+//! draw.io (mxGraph XML) export.
+//!
+//! Each shape becomes an mxCell whose style matches its kind, connected lines
+//! and arrows become edges with a source and target, and document layers
+//! become draw.io layers.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use irohscii_core::{Layer, LineStyle, Position, ShapeColor, ShapeKind, ShapeView};
+
+/// Width of a character cell in draw.io units (one grid step)
+const CELL_WIDTH: i32 = 10;
+/// Height of a character cell in draw.io units
+const CELL_HEIGHT: i32 = 20;
+/// Layer used when the document has none
+const DEFAULT_LAYER: &str = "1";
+
+/// Export shapes as an uncompressed draw.io file. `layers` are the document's
+/// layers from bottom to top; shapes without a known layer go on the first.
+pub fn export_drawio(shapes: &ShapeView, layers: &[Layer]) -> String {
+    let mut out = String::new();
+    out.push_str("<mxfile host=\"irohscii\">\n");
+    out.push_str("  <diagram id=\"irohscii\" name=\"Page-1\">\n");
+    out.push_str(
+        "    <mxGraphModel grid=\"1\" gridSize=\"10\" guides=\"1\" connect=\"1\" arrows=\"1\" page=\"0\">\n",
+    );
+    out.push_str("      <root>\n");
+    out.push_str("        <mxCell id=\"0\"/>\n");
+
+    let layer_ids: Vec<String> = layers.iter().map(|l| format!("layer-{}", l.id)).collect();
+    for (layer, id) in layers.iter().zip(&layer_ids) {
+        let mut attrs = format!("id=\"{}\" value=\"{}\"", id, escape(&layer.name));
+        if layer.locked {
+            attrs.push_str(" style=\"locked=1;\"");
+        }
+        if !layer.visible {
+            attrs.push_str(" visible=\"0\"");
+        }
+        writeln!(out, "        <mxCell {} parent=\"0\"/>", attrs)
+            .expect("write to String is infallible");
+    }
+    if layers.is_empty() {
+        writeln!(
+            out,
+            "        <mxCell id=\"{}\" value=\"Default\" parent=\"0\"/>",
+            DEFAULT_LAYER
+        )
+        .expect("write to String is infallible");
+    }
+    let default_layer = layer_ids.first().map_or(DEFAULT_LAYER, String::as_str);
+
+    let cell_ids: HashMap<u64, String> = shapes
+        .iter()
+        .map(|s| (s.id.0.as_u128() as u64, s.id.0.to_string()))
+        .collect();
+
+    for shape in shapes.iter() {
+        let parent = shape
+            .layer_id
+            .and_then(|id| layers.iter().position(|l| l.id == id))
+            .map_or(default_layer, |i| layer_ids[i].as_str());
+        let id = shape.id.0.to_string();
+        let cell = match &shape.kind {
+            ShapeKind::Line { .. } | ShapeKind::Arrow { .. } => {
+                edge_cell(&id, parent, &shape.kind, &cell_ids)
+            }
+            ShapeKind::Freehand { points, .. } => {
+                path_cell(&id, parent, points, false, &shape.kind)
+            }
+            ShapeKind::Polygon { points, closed, .. } => {
+                path_cell(&id, parent, points, *closed, &shape.kind)
+            }
+            kind => vertex_cell(&id, parent, kind),
+        };
+        out.push_str(&cell);
+    }
+
+    out.push_str("      </root>\n");
+    out.push_str("    </mxGraphModel>\n");
+    out.push_str("  </diagram>\n");
+    out.push_str("</mxfile>\n");
+    out
+}
+
+/// Save shapes as a draw.io file
+pub fn save_drawio(shapes: &ShapeView, layers: &[Layer], path: &Path) -> Result<()> {
+    fs::write(path, export_drawio(shapes, layers))
+        .with_context(|| format!("Failed to save to {:?}", path))
+}
+
+/// Whether a path names a draw.io file
+pub fn is_drawio_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("drawio"))
+}
+
+fn units(pos: Position) -> (i32, i32) {
+    (pos.x * CELL_WIDTH, pos.y * CELL_HEIGHT)
+}
+
+/// draw.io style for a shape's outline
+fn vertex_style(kind: &ShapeKind) -> &'static str {
+    match kind {
+        ShapeKind::Rectangle { .. } => "rounded=0;whiteSpace=wrap;",
+        ShapeKind::RoundedRect { .. } => "rounded=1;whiteSpace=wrap;",
+        ShapeKind::DoubleBox { .. } => "shape=ext;double=1;rounded=0;whiteSpace=wrap;",
+        ShapeKind::Diamond { .. } => "rhombus;whiteSpace=wrap;",
+        ShapeKind::Ellipse { .. } => "ellipse;whiteSpace=wrap;",
+        ShapeKind::Cylinder { .. } => {
+            "shape=cylinder3;boundedLbl=1;backgroundOutline=1;size=10;whiteSpace=wrap;"
+        }
+        ShapeKind::Cloud { .. } => "ellipse;shape=cloud;whiteSpace=wrap;",
+        ShapeKind::Hexagon { .. } => {
+            "shape=hexagon;perimeter=hexagonPerimeter2;fixedSize=1;whiteSpace=wrap;"
+        }
+        ShapeKind::Parallelogram { .. } => {
+            "shape=parallelogram;perimeter=parallelogramPerimeter;fixedSize=1;whiteSpace=wrap;"
+        }
+        ShapeKind::Trapezoid { .. } => {
+            "shape=trapezoid;perimeter=trapezoidPerimeter;fixedSize=1;whiteSpace=wrap;"
+        }
+        ShapeKind::Triangle { .. } => "triangle;direction=north;whiteSpace=wrap;",
+        ShapeKind::Star { .. } => "shape=mxgraph.basic.star;whiteSpace=wrap;",
+        ShapeKind::Callout { .. } => "shape=callout;perimeter=calloutPerimeter;whiteSpace=wrap;",
+        ShapeKind::Text { .. } => "text;align=left;verticalAlign=top;whiteSpace=nowrap;",
+        _ => "whiteSpace=wrap;",
+    }
+}
+
+/// Stroke and font color, omitted for the default foreground
+fn color_style(color: ShapeColor) -> String {
+    match color {
+        ShapeColor::White | ShapeColor::Black => String::new(),
+        color => format!("strokeColor={0};fontColor={0};", color.to_css()),
+    }
+}
+
+fn vertex_cell(id: &str, parent: &str, kind: &ShapeKind) -> String {
+    let (min_x, min_y, max_x, max_y) = kind.bounds();
+    let (x, y) = units(Position::new(min_x, min_y));
+    let width = ((max_x - min_x) * CELL_WIDTH).max(CELL_WIDTH);
+    let height = ((max_y - min_y) * CELL_HEIGHT).max(CELL_HEIGHT);
+    let value = match kind {
+        ShapeKind::Text { content, .. } => content.as_str(),
+        kind => kind.label().unwrap_or(""),
+    };
+    format!(
+        "        <mxCell id=\"{}\" value=\"{}\" style=\"{}{}\" vertex=\"1\" parent=\"{}\">\n          <mxGeometry x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" as=\"geometry\"/>\n        </mxCell>\n",
+        id,
+        escape(value),
+        vertex_style(kind),
+        color_style(kind.color()),
+        parent,
+        x,
+        y,
+        width,
+        height
+    )
+}
+
+fn edge_cell(id: &str, parent: &str, kind: &ShapeKind, cell_ids: &HashMap<u64, String>) -> String {
+    let (ShapeKind::Line {
+        start,
+        end,
+        style,
+        start_connection,
+        end_connection,
+        label,
+        ..
+    }
+    | ShapeKind::Arrow {
+        start,
+        end,
+        style,
+        start_connection,
+        end_connection,
+        label,
+        ..
+    }) = kind
+    else {
+        unreachable!("edge_cell is only called for lines and arrows");
+    };
+
+    let edge_style = match style {
+        LineStyle::Straight => "edgeStyle=none;",
+        // draw.io routes orthogonal edges itself, including around obstacles
+        LineStyle::OrthogonalHV | LineStyle::OrthogonalVH | LineStyle::OrthogonalAuto => {
+            "edgeStyle=orthogonalEdgeStyle;"
+        }
+    };
+    let arrow = if matches!(kind, ShapeKind::Arrow { .. }) {
+        "endArrow=classic;"
+    } else {
+        "endArrow=none;"
+    };
+    let mut ends = String::new();
+    for (attr, connection) in [("source", start_connection), ("target", end_connection)] {
+        if let Some(cell) = connection.and_then(|c| cell_ids.get(&c)) {
+            write!(ends, " {}=\"{}\"", attr, cell).expect("write to String is infallible");
+        }
+    }
+
+    let (sx, sy) = units(*start);
+    let (ex, ey) = units(*end);
+    format!(
+        "        <mxCell id=\"{}\" value=\"{}\" style=\"{}{}rounded=0;{}\" edge=\"1\" parent=\"{}\"{}>\n          <mxGeometry relative=\"1\" as=\"geometry\">\n            <mxPoint x=\"{}\" y=\"{}\" as=\"sourcePoint\"/>\n            <mxPoint x=\"{}\" y=\"{}\" as=\"targetPoint\"/>\n          </mxGeometry>\n        </mxCell>\n",
+        id,
+        escape(label.as_deref().unwrap_or("")),
+        edge_style,
+        arrow,
+        color_style(kind.color()),
+        parent,
+        ends,
+        sx,
+        sy,
+        ex,
+        ey
+    )
+}
+
+/// Freehand strokes and polygons become unconnected edges through waypoints
+fn path_cell(
+    id: &str,
+    parent: &str,
+    points: &[Position],
+    closed: bool,
+    kind: &ShapeKind,
+) -> String {
+    let mut path: Vec<Position> = points.to_vec();
+    if closed && let Some(&first) = points.first() {
+        path.push(first);
+    }
+    let (Some(&first), Some(&last)) = (path.first(), path.last()) else {
+        return String::new();
+    };
+
+    let mut waypoints = String::new();
+    for &point in &path[1..path.len().saturating_sub(1)] {
+        let (x, y) = units(point);
+        writeln!(
+            waypoints,
+            "              <mxPoint x=\"{}\" y=\"{}\"/>",
+            x, y
+        )
+        .expect("write to String is infallible");
+    }
+    let (sx, sy) = units(first);
+    let (ex, ey) = units(last);
+    format!(
+        "        <mxCell id=\"{}\" value=\"{}\" style=\"edgeStyle=none;endArrow=none;rounded=0;{}\" edge=\"1\" parent=\"{}\">\n          <mxGeometry relative=\"1\" as=\"geometry\">\n            <mxPoint x=\"{}\" y=\"{}\" as=\"sourcePoint\"/>\n            <mxPoint x=\"{}\" y=\"{}\" as=\"targetPoint\"/>\n            <Array as=\"points\">\n{}            </Array>\n          </mxGeometry>\n        </mxCell>\n",
+        id,
+        escape(kind.label().unwrap_or("")),
+        color_style(kind.color()),
+        parent,
+        sx,
+        sy,
+        ex,
+        ey,
+        waypoints
+    )
+}
+
+/// Escape text for an XML attribute; line breaks are kept as character references
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#xa;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use irohscii_core::Document;
+
+    fn rect(x: i32, y: i32, label: &str) -> ShapeKind {
+        ShapeKind::Rectangle {
+            start: Position::new(x, y),
+            end: Position::new(x + 10, y + 4),
+            label: Some(label.to_string()),
+            color: ShapeColor::default(),
+        }
+    }
+
+    /// The opening tag of the mxCell with the given id
+    fn cell<'a>(xml: &'a str, id: &str) -> &'a str {
+        let start = xml
+            .find(&format!("<mxCell id=\"{}\"", id))
+            .unwrap_or_else(|| panic!("no cell {}", id));
+        let end = start + xml[start..].find('>').unwrap();
+        &xml[start..=end]
+    }
+
+    fn export(doc: &Document) -> String {
+        let mut view = ShapeView::new();
+        view.rebuild(doc).unwrap();
+        export_drawio(&view, &doc.read_all_layers().unwrap())
+    }
+
+    #[test]
+    fn shapes_map_to_styles() {
+        let mut doc = Document::new();
+        let kinds = [
+            (rect(0, 0, "A & B"), "rounded=0;"),
+            (
+                ShapeKind::Diamond {
+                    center: Position::new(20, 10),
+                    half_width: 4,
+                    half_height: 2,
+                    label: None,
+                    color: ShapeColor::Red,
+                },
+                "rhombus;",
+            ),
+            (
+                ShapeKind::Cylinder {
+                    start: Position::new(30, 0),
+                    end: Position::new(38, 5),
+                    label: None,
+                    color: ShapeColor::default(),
+                },
+                "shape=cylinder3;",
+            ),
+            (
+                ShapeKind::Cloud {
+                    start: Position::new(40, 0),
+                    end: Position::new(50, 5),
+                    label: None,
+                    color: ShapeColor::default(),
+                },
+                "shape=cloud;",
+            ),
+            (
+                ShapeKind::Hexagon {
+                    center: Position::new(60, 10),
+                    radius_x: 5,
+                    radius_y: 2,
+                    label: None,
+                    color: ShapeColor::default(),
+                },
+                "shape=hexagon;",
+            ),
+            (
+                ShapeKind::Text {
+                    pos: Position::new(0, 20),
+                    content: "two\nlines".to_string(),
+                    color: ShapeColor::default(),
+                },
+                "text;",
+            ),
+        ];
+        let mut ids = Vec::new();
+        for (kind, _) in &kinds {
+            ids.push(doc.add_shape(kind.clone()).unwrap());
+        }
+
+        let xml = export(&doc);
+        for (id, (_, style)) in ids.iter().zip(&kinds) {
+            let tag = cell(&xml, &id.0.to_string());
+            assert!(tag.contains(style), "{} lacks {}", tag, style);
+            assert!(tag.contains("vertex=\"1\""));
+        }
+        assert!(cell(&xml, &ids[0].0.to_string()).contains("value=\"A &amp; B\""));
+        assert!(cell(&xml, &ids[1].0.to_string()).contains("strokeColor=#cd0000;"));
+        assert!(cell(&xml, &ids[5].0.to_string()).contains("value=\"two&#xa;lines\""));
+        assert!(xml.contains("<mxGeometry x=\"0\" y=\"0\" width=\"100\" height=\"80\""));
+    }
+
+    #[test]
+    fn connected_lines_become_edges() {
+        let mut doc = Document::new();
+        let a = doc.add_shape(rect(0, 0, "a")).unwrap();
+        let b = doc.add_shape(rect(20, 0, "b")).unwrap();
+        let arrow = doc
+            .add_shape(ShapeKind::Arrow {
+                start: Position::new(10, 2),
+                end: Position::new(20, 2),
+                style: LineStyle::OrthogonalHV,
+                start_connection: Some(a.0.as_u128() as u64),
+                end_connection: Some(b.0.as_u128() as u64),
+                label: Some("calls".to_string()),
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+        let line = doc
+            .add_shape(ShapeKind::Line {
+                start: Position::new(0, 10),
+                end: Position::new(5, 10),
+                style: LineStyle::Straight,
+                start_connection: None,
+                end_connection: None,
+                label: None,
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+
+        let xml = export(&doc);
+        let tag = cell(&xml, &arrow.0.to_string());
+        assert!(tag.contains("edge=\"1\""));
+        assert!(tag.contains(&format!("source=\"{}\"", a.0)));
+        assert!(tag.contains(&format!("target=\"{}\"", b.0)));
+        assert!(tag.contains("edgeStyle=orthogonalEdgeStyle;endArrow=classic;"));
+        assert!(tag.contains("value=\"calls\""));
+
+        let tag = cell(&xml, &line.0.to_string());
+        assert!(tag.contains("endArrow=none;"));
+        assert!(!tag.contains("source="));
+        assert!(xml.contains("<mxPoint x=\"0\" y=\"200\" as=\"sourcePoint\"/>"));
+    }
+
+    #[test]
+    fn layers_become_drawio_layers() {
+        let mut doc = Document::new();
+        let base = doc.add_shape(rect(0, 0, "base")).unwrap();
+        let notes = doc.create_layer("Notes & todo").unwrap();
+        doc.set_layer_locked(notes, true).unwrap();
+        let top = doc.add_shape(rect(20, 0, "top")).unwrap();
+        doc.set_shape_layer(top, notes).unwrap();
+
+        let xml = export(&doc);
+        let layers = doc.read_all_layers().unwrap();
+        assert_eq!(layers.len(), 2);
+        let first = cell(&xml, &format!("layer-{}", layers[0].id));
+        assert!(first.contains("parent=\"0\""));
+        let second = cell(&xml, &format!("layer-{}", notes));
+        assert!(second.contains("value=\"Notes &amp; todo\""));
+        assert!(second.contains("locked=1;"));
+        // Layers come bottom to top, before their shapes
+        assert!(xml.find(first).unwrap() < xml.find(second).unwrap());
+
+        assert!(
+            cell(&xml, &base.0.to_string()).contains(&format!("parent=\"layer-{}\"", layers[0].id))
+        );
+        assert!(cell(&xml, &top.0.to_string()).contains(&format!("parent=\"layer-{}\"", notes)));
+    }
+
+    #[test]
+    fn freehand_becomes_waypoint_path() {
+        let mut doc = Document::new();
+        doc.add_shape(ShapeKind::Polygon {
+            points: vec![
+                Position::new(0, 0),
+                Position::new(4, 0),
+                Position::new(2, 3),
+            ],
+            closed: true,
+            label: None,
+            color: ShapeColor::default(),
+        })
+        .unwrap();
+
+        let xml = export(&doc);
+        assert!(xml.contains("<mxPoint x=\"40\" y=\"0\"/>"));
+        assert!(xml.contains("<mxPoint x=\"20\" y=\"60\"/>"));
+        // Closed outlines end where they start
+        assert!(xml.contains("<mxPoint x=\"0\" y=\"0\" as=\"targetPoint\"/>"));
+    }
+}
@@ -0,0 +1,966 @@
+//! Excalidraw scene import and export.
+//!
+//! Imports rectangles, ellipses, diamonds, arrows, lines, text and freehand
+//! strokes from Excalidraw scene JSON. Pixel coordinates are quantized to the
+//! character grid, arrow bindings become connections and text bound to a
+//! container becomes its label. Exports shapes back to an Excalidraw scene.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use irohscii_core::{Document, LineStyle, Position, ShapeColor, ShapeId, ShapeKind, ShapeView};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::ascii_import::{ImportedShape, add_imported};
+
+/// Width of a character cell in Excalidraw pixels
+const CELL_WIDTH: f64 = 10.0;
+/// Height of a character cell: one line of FONT_SIZE text at LINE_HEIGHT
+const CELL_HEIGHT: f64 = 20.0;
+const FONT_SIZE: f64 = 16.0;
+const LINE_HEIGHT: f64 = 1.25;
+/// Excalidraw's monospace font family
+const FONT_FAMILY_MONOSPACE: u32 = 3;
+/// Excalidraw's default stroke color, used for the terminal foreground
+const DEFAULT_STROKE: &str = "#1e1e1e";
+/// Brush character for imported freehand strokes
+const FREEHAND_CHAR: char = '*';
+
+const _: () = assert!(CELL_HEIGHT == FONT_SIZE * LINE_HEIGHT);
+
+#[derive(Debug, Deserialize)]
+struct Scene {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    elements: Vec<Element>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Element {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    x: f64,
+    #[serde(default)]
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    is_deleted: bool,
+    #[serde(default)]
+    stroke_color: Option<String>,
+    #[serde(default)]
+    roundness: Option<Value>,
+    /// Points of linear and freedraw elements, relative to (x, y)
+    #[serde(default)]
+    points: Vec<(f64, f64)>,
+    #[serde(default)]
+    start_binding: Option<Binding>,
+    #[serde(default)]
+    end_binding: Option<Binding>,
+    #[serde(default)]
+    start_arrowhead: Option<String>,
+    /// Arrows have an end arrowhead unless it is explicitly null
+    #[serde(default = "default_arrowhead")]
+    end_arrowhead: Option<String>,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    container_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Binding {
+    element_id: String,
+}
+
+fn default_arrowhead() -> Option<String> {
+    Some("arrow".to_string())
+}
+
+/// Parse an Excalidraw scene into shapes, moved so the drawing starts at the
+/// origin. Bindable shapes come first, then arrows and lines, which reference
+/// the shapes they are bound to by index.
+pub fn parse_excalidraw(json: &str) -> Result<Vec<ImportedShape>> {
+    let scene: Scene = serde_json::from_str(json).context("Invalid Excalidraw JSON")?;
+    if !scene.kind.starts_with("excalidraw") {
+        bail!(
+            "Not an Excalidraw scene: expected type 'excalidraw', found {:?}",
+            scene.kind
+        );
+    }
+    let elements: Vec<&Element> = scene.elements.iter().filter(|e| !e.is_deleted).collect();
+
+    // Text bound to a container becomes the container's label
+    let containers: HashMap<&str, &Element> =
+        elements.iter().map(|e| (e.id.as_str(), *e)).collect();
+    let mut labels: HashMap<&str, String> = HashMap::new();
+    for element in &elements {
+        if element.kind == "text"
+            && let Some(container) = element.container_id.as_deref()
+            && containers.contains_key(container)
+        {
+            labels.insert(container, flatten(&element.text));
+        }
+    }
+    let is_label = |e: &Element| {
+        e.kind == "text"
+            && e.container_id
+                .as_deref()
+                .is_some_and(|c| labels.contains_key(c))
+    };
+
+    let mut shapes = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for element in elements.iter().filter(|e| !is_linear(e) && !is_label(e)) {
+        let label = labels.get(element.id.as_str()).cloned();
+        if let Some(kind) = node_kind(element, label) {
+            index.insert(element.id.as_str(), shapes.len());
+            shapes.push(ImportedShape {
+                kind,
+                start_target: None,
+                end_target: None,
+            });
+        }
+    }
+    for element in elements.iter().filter(|e| is_linear(e)) {
+        let label = labels.get(element.id.as_str()).cloned();
+        if let Some(shape) = linear_shape(element, label, &index) {
+            shapes.push(shape);
+        }
+    }
+
+    let min_x = shapes.iter().map(|s| s.kind.bounds().0).min().unwrap_or(0);
+    let min_y = shapes.iter().map(|s| s.kind.bounds().1).min().unwrap_or(0);
+    for shape in &mut shapes {
+        shape.kind = shape.kind.translated(-min_x, -min_y);
+    }
+    Ok(shapes)
+}
+
+/// Import an Excalidraw scene into a document with connected arrows
+pub fn import_excalidraw(doc: &mut Document, json: &str) -> Result<Vec<ShapeId>> {
+    add_imported(doc, parse_excalidraw(json)?)
+}
+
+/// Import an Excalidraw file into a document
+pub fn load_excalidraw(doc: &mut Document, path: &Path) -> Result<Vec<ShapeId>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read from {:?}", path))?;
+    import_excalidraw(doc, &content)
+}
+
+/// Whether a path names an Excalidraw file
+pub fn is_excalidraw_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("excalidraw"))
+}
+
+// ========== Import ==========
+
+fn is_linear(element: &Element) -> bool {
+    matches!(element.kind.as_str(), "arrow" | "line")
+}
+
+/// Quantize a pixel position to the nearest character cell
+fn cell(x: f64, y: f64) -> Position {
+    Position::new(
+        (x / CELL_WIDTH).round() as i32,
+        (y / CELL_HEIGHT).round() as i32,
+    )
+}
+
+/// Absolute cells of an element's points, without consecutive duplicates
+fn point_cells(element: &Element) -> Vec<Position> {
+    let mut cells: Vec<Position> = element
+        .points
+        .iter()
+        .map(|&(px, py)| cell(element.x + px, element.y + py))
+        .collect();
+    cells.dedup();
+    cells
+}
+
+fn flatten(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Map a stroke color to the nearest terminal color. Dark strokes are the
+/// default ink and map to the default foreground.
+fn color(stroke: Option<&str>) -> ShapeColor {
+    let Some(rgb) = stroke.and_then(parse_hex) else {
+        return ShapeColor::default();
+    };
+    if rgb.0.max(rgb.1).max(rgb.2) < 64 {
+        return ShapeColor::default();
+    }
+    PALETTE
+        .iter()
+        .filter_map(|&c| parse_hex(c.to_css()).map(|p| (c, p)))
+        .min_by_key(|(_, p)| {
+            let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+            d(p.0, rgb.0) + d(p.1, rgb.1) + d(p.2, rgb.2)
+        })
+        .map_or_else(ShapeColor::default, |(c, _)| c)
+}
+
+/// Colors a stroke can map to; white and black are the default foreground
+const PALETTE: [ShapeColor; 14] = [
+    ShapeColor::Red,
+    ShapeColor::Green,
+    ShapeColor::Yellow,
+    ShapeColor::Blue,
+    ShapeColor::Magenta,
+    ShapeColor::Cyan,
+    ShapeColor::Gray,
+    ShapeColor::DarkGray,
+    ShapeColor::LightRed,
+    ShapeColor::LightGreen,
+    ShapeColor::LightYellow,
+    ShapeColor::LightBlue,
+    ShapeColor::LightMagenta,
+    ShapeColor::LightCyan,
+];
+
+pub(crate) fn parse_hex(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    let channel = |i: usize, len: usize| {
+        let value = u8::from_str_radix(hex.get(i * len..(i + 1) * len)?, 16).ok()?;
+        Some(if len == 1 { value * 17 } else { value })
+    };
+    let len = match hex.len() {
+        3 | 4 => 1,
+        6 | 8 => 2,
+        _ => return None,
+    };
+    Some((channel(0, len)?, channel(1, len)?, channel(2, len)?))
+}
+
+/// Build the shape for a non-linear element
+fn node_kind(element: &Element, label: Option<String>) -> Option<ShapeKind> {
+    let color = color(element.stroke_color.as_deref());
+    let (x, y, w, h) = (element.x, element.y, element.width, element.height);
+    let start = cell(x, y);
+    let end = cell(x + w, y + h);
+    // Keep at least the border and one cell inside
+    let end = Position::new(end.x.max(start.x + 2), end.y.max(start.y + 2));
+    let center = cell(x + w / 2.0, y + h / 2.0);
+    let radius_x = ((w / 2.0 / CELL_WIDTH).round() as i32).max(1);
+    let radius_y = ((h / 2.0 / CELL_HEIGHT).round() as i32).max(1);
+
+    let kind = match element.kind.as_str() {
+        "rectangle" if element.roundness.as_ref().is_some_and(|r| !r.is_null()) => {
+            ShapeKind::RoundedRect {
+                start,
+                end,
+                label,
+                color,
+            }
+        }
+        "rectangle" => ShapeKind::Rectangle {
+            start,
+            end,
+            label,
+            color,
+        },
+        "ellipse" => ShapeKind::Ellipse {
+            center,
+            radius_x,
+            radius_y,
+            label,
+            color,
+        },
+        "diamond" => ShapeKind::Diamond {
+            center,
+            half_width: radius_x,
+            half_height: radius_y,
+            label,
+            color,
+        },
+        "text" if !element.text.trim().is_empty() => ShapeKind::Text {
+            pos: start,
+            content: element.text.clone(),
+            color,
+        },
+        "freedraw" => {
+            let points = point_cells(element);
+            if points.is_empty() {
+                return None;
+            }
+            ShapeKind::Freehand {
+                points,
+                char: FREEHAND_CHAR,
+                label,
+                color,
+            }
+        }
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Build the shape for an arrow or line. Multi-point lines become polygons;
+/// arrows keep their end points, with an orthogonal style when elbowed.
+fn linear_shape(
+    element: &Element,
+    label: Option<String>,
+    index: &HashMap<&str, usize>,
+) -> Option<ImportedShape> {
+    let color = color(element.stroke_color.as_deref());
+    let mut cells = point_cells(element);
+    if element.kind == "line" && cells.len() > 2 {
+        let closed = cells.first() == cells.last();
+        if closed {
+            cells.pop();
+        }
+        return Some(ImportedShape {
+            kind: ShapeKind::Polygon {
+                points: cells,
+                closed,
+                label,
+                color,
+            },
+            start_target: None,
+            end_target: None,
+        });
+    }
+    if cells.len() < 2 {
+        return None;
+    }
+
+    let target = |binding: &Option<Binding>| {
+        binding
+            .as_ref()
+            .and_then(|b| index.get(b.element_id.as_str()).copied())
+    };
+    let (mut start_target, mut end_target) =
+        (target(&element.start_binding), target(&element.end_binding));
+    let (start_head, end_head) = if element.kind == "arrow" {
+        (
+            element.start_arrowhead.is_some(),
+            element.end_arrowhead.is_some(),
+        )
+    } else {
+        (false, false)
+    };
+    // An arrow pointing back at its start is drawn the other way round
+    if start_head && !end_head {
+        cells.reverse();
+        std::mem::swap(&mut start_target, &mut end_target);
+    }
+
+    let axis_aligned = cells
+        .windows(2)
+        .all(|w| w[0].x == w[1].x || w[0].y == w[1].y);
+    let style = if cells.len() > 2 && axis_aligned {
+        if cells[0].y == cells[1].y {
+            LineStyle::OrthogonalHV
+        } else {
+            LineStyle::OrthogonalVH
+        }
+    } else {
+        LineStyle::Straight
+    };
+    let (start, end) = (cells[0], cells[cells.len() - 1]);
+    let (start_connection, end_connection) = (None, None);
+    let kind = if start_head || end_head {
+        ShapeKind::Arrow {
+            start,
+            end,
+            style,
+            start_connection,
+            end_connection,
+            label,
+            color,
+        }
+    } else {
+        ShapeKind::Line {
+            start,
+            end,
+            style,
+            start_connection,
+            end_connection,
+            label,
+            color,
+        }
+    };
+    Some(ImportedShape {
+        kind,
+        start_target,
+        end_target,
+    })
+}
+
+// ========== Export ==========
+
+/// Export shapes as an Excalidraw scene. Lines and arrows are bound to the
+/// shapes they connect, and labels become text bound to their shape.
+pub fn export_excalidraw(shapes: &ShapeView) -> String {
+    let ids: HashMap<u64, String> = shapes
+        .iter()
+        .map(|s| (s.id.0.as_u128() as u64, s.id.0.to_string()))
+        .collect();
+
+    // Arrows bound to each shape, listed in the shape's boundElements
+    let mut bound: HashMap<u64, Vec<Value>> = HashMap::new();
+    for shape in shapes.iter() {
+        if let ShapeKind::Line { .. } | ShapeKind::Arrow { .. } = shape.kind {
+            let (start, end) = shape.kind.connections();
+            for target in [start, end].into_iter().flatten() {
+                if ids.contains_key(&target) {
+                    bound
+                        .entry(target)
+                        .or_default()
+                        .push(json!({ "id": shape.id.0.to_string(), "type": "arrow" }));
+                }
+            }
+        }
+    }
+
+    let mut elements = Vec::new();
+    for shape in shapes.iter() {
+        let id = shape.id.0.to_string();
+        let mut bound_elements = bound
+            .remove(&(shape.id.0.as_u128() as u64))
+            .unwrap_or_default();
+        let seed = elements.len() + 1;
+        let (mut element, label_center) = shape_element(&id, &shape.kind, &ids, seed);
+
+        // Lines and strokes cannot contain text, so their label floats free
+        let mut label_element = None;
+        let label = shape.kind.label().filter(|l| !l.is_empty());
+        if let (Some(label), Some(center)) = (label, label_center) {
+            let label_id = format!("{}-label", id);
+            let container = element["type"] != "line" && element["type"] != "freedraw";
+            if container {
+                bound_elements.push(json!({ "id": label_id, "type": "text" }));
+            }
+            label_element = Some(text_element(
+                &label_id,
+                label,
+                center,
+                true,
+                container.then_some(id.as_str()),
+                shape.kind.color(),
+                seed + 1,
+            ));
+        }
+        if !bound_elements.is_empty() {
+            element["boundElements"] = Value::Array(bound_elements);
+        }
+        elements.push(element);
+        elements.extend(label_element);
+    }
+
+    let scene = json!({
+        "type": "excalidraw",
+        "version": 2,
+        "source": "irohscii",
+        "elements": elements,
+        "appState": {
+            "viewBackgroundColor": "#ffffff",
+            "gridSize": null,
+        },
+        "files": {},
+    });
+    serde_json::to_string_pretty(&scene).expect("serializing a JSON value is infallible")
+}
+
+/// Save shapes as an Excalidraw scene file
+pub fn save_excalidraw(shapes: &ShapeView, path: &Path) -> Result<()> {
+    fs::write(path, export_excalidraw(shapes))
+        .with_context(|| format!("Failed to save to {:?}", path))
+}
+
+fn px(pos: Position) -> (f64, f64) {
+    (
+        f64::from(pos.x) * CELL_WIDTH,
+        f64::from(pos.y) * CELL_HEIGHT,
+    )
+}
+
+fn stroke_color(color: ShapeColor) -> &'static str {
+    match color {
+        ShapeColor::White | ShapeColor::Black => DEFAULT_STROKE,
+        color => color.to_css(),
+    }
+}
+
+/// Fields shared by every element
+fn base_element(
+    id: &str,
+    kind: &str,
+    seed: usize,
+    (x, y, width, height): (f64, f64, f64, f64),
+    color: ShapeColor,
+) -> Value {
+    json!({
+        "id": id,
+        "type": kind,
+        "x": x,
+        "y": y,
+        "width": width,
+        "height": height,
+        "angle": 0,
+        "strokeColor": stroke_color(color),
+        "backgroundColor": "transparent",
+        "fillStyle": "solid",
+        "strokeWidth": 2,
+        "strokeStyle": "solid",
+        "roughness": 0,
+        "opacity": 100,
+        "groupIds": [],
+        "frameId": null,
+        "roundness": null,
+        "seed": seed,
+        "version": 1,
+        "versionNonce": seed,
+        "isDeleted": false,
+        "boundElements": null,
+        "updated": 1,
+        "link": null,
+        "locked": false,
+    })
+}
+
+/// Build a shape's element, and the pixel center for its label if it has room for one
+fn shape_element(
+    id: &str,
+    kind: &ShapeKind,
+    ids: &HashMap<u64, String>,
+    seed: usize,
+) -> (Value, Option<(f64, f64)>) {
+    let color = kind.color();
+    let (min_x, min_y, max_x, max_y) = kind.bounds();
+    let (x0, y0) = px(Position::new(min_x, min_y));
+    let (x1, y1) = px(Position::new(max_x, max_y));
+    let rect = (x0, y0, x1 - x0, y1 - y0);
+    let center = Some(((x0 + x1) / 2.0, (y0 + y1) / 2.0));
+
+    match kind {
+        ShapeKind::Rectangle { .. } | ShapeKind::DoubleBox { .. } | ShapeKind::Cylinder { .. } => {
+            (base_element(id, "rectangle", seed, rect, color), center)
+        }
+        ShapeKind::RoundedRect { .. } | ShapeKind::Cloud { .. } | ShapeKind::Callout { .. } => {
+            let mut element = base_element(id, "rectangle", seed, rect, color);
+            element["roundness"] = json!({ "type": 3 });
+            (element, center)
+        }
+        ShapeKind::Ellipse { .. } => (base_element(id, "ellipse", seed, rect, color), center),
+        ShapeKind::Diamond { .. } => (base_element(id, "diamond", seed, rect, color), center),
+        ShapeKind::Line {
+            start,
+            end,
+            style,
+            start_connection,
+            end_connection,
+            ..
+        }
+        | ShapeKind::Arrow {
+            start,
+            end,
+            style,
+            start_connection,
+            end_connection,
+            ..
+        } => {
+            let (sx, sy) = px(*start);
+            let (ex, ey) = px(*end);
+            let (dx, dy) = (ex - sx, ey - sy);
+            let points = match style {
+                LineStyle::Straight => vec![(0.0, 0.0), (dx, dy)],
+                LineStyle::OrthogonalVH => vec![(0.0, 0.0), (0.0, dy), (dx, dy)],
+                LineStyle::OrthogonalHV | LineStyle::OrthogonalAuto => {
+                    vec![(0.0, 0.0), (dx, 0.0), (dx, dy)]
+                }
+            };
+            let mut element = linear_element(id, "arrow", seed, (sx, sy), &points, color);
+            let binding = |connection: &Option<u64>| {
+                connection.and_then(|c| ids.get(&c)).map_or(
+                    Value::Null,
+                    |target| json!({ "elementId": target, "focus": 0, "gap": 1 }),
+                )
+            };
+            element["startBinding"] = binding(start_connection);
+            element["endBinding"] = binding(end_connection);
+            element["startArrowhead"] = Value::Null;
+            element["endArrowhead"] = if matches!(kind, ShapeKind::Arrow { .. }) {
+                json!("arrow")
+            } else {
+                Value::Null
+            };
+            let mid = points[points.len() / 2];
+            let label_at = if points.len() == 3 {
+                (sx + mid.0, sy + mid.1)
+            } else {
+                ((sx + ex) / 2.0, (sy + ey) / 2.0)
+            };
+            (element, Some(label_at))
+        }
+        ShapeKind::Text { pos, content, .. } => (
+            text_element(id, content, px(*pos), false, None, color, seed),
+            None,
+        ),
+        ShapeKind::Freehand { points, .. } => {
+            let origin = px(points[0]);
+            let relative: Vec<(f64, f64)> = points
+                .iter()
+                .map(|&p| {
+                    let (x, y) = px(p);
+                    (x - origin.0, y - origin.1)
+                })
+                .collect();
+            let mut element = linear_element(id, "freedraw", seed, origin, &relative, color);
+            element["pressures"] = json!([]);
+            element["simulatePressure"] = json!(true);
+            (element, center)
+        }
+        ShapeKind::Triangle { .. }
+        | ShapeKind::Polygon { .. }
+        | ShapeKind::Parallelogram { .. }
+        | ShapeKind::Trapezoid { .. }
+        | ShapeKind::Hexagon { .. }
+        | ShapeKind::Star { .. } => {
+            let (mut outline, closed) = outline(kind);
+            if closed && let Some(&first) = outline.first() {
+                outline.push(first);
+            }
+            let origin = outline.first().copied().unwrap_or((x0, y0));
+            let relative: Vec<(f64, f64)> = outline
+                .iter()
+                .map(|&(x, y)| (x - origin.0, y - origin.1))
+                .collect();
+            (
+                linear_element(id, "line", seed, origin, &relative, color),
+                center,
+            )
+        }
+    }
+}
+
+/// Vertices in pixels of shapes exported as Excalidraw lines, and whether the
+/// outline is closed
+fn outline(kind: &ShapeKind) -> (Vec<(f64, f64)>, bool) {
+    let (min_x, min_y, max_x, max_y) = kind.bounds();
+    let (x0, y0) = px(Position::new(min_x, min_y));
+    let (x1, y1) = px(Position::new(max_x, max_y));
+    let (cx, cy) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+    let slant = ((x1 - x0) / 4.0).min(y1 - y0);
+
+    match kind {
+        ShapeKind::Polygon { points, closed, .. } => {
+            (points.iter().map(|&p| px(p)).collect(), *closed)
+        }
+        ShapeKind::Triangle { p1, p2, p3, .. } => (vec![px(*p1), px(*p2), px(*p3)], true),
+        ShapeKind::Parallelogram { .. } => (
+            vec![(x0 + slant, y0), (x1, y0), (x1 - slant, y1), (x0, y1)],
+            true,
+        ),
+        ShapeKind::Trapezoid { .. } => (
+            vec![(x0 + slant, y0), (x1 - slant, y0), (x1, y1), (x0, y1)],
+            true,
+        ),
+        ShapeKind::Hexagon { .. } => {
+            let quarter = (x1 - x0) / 4.0;
+            (
+                vec![
+                    (x0, cy),
+                    (x0 + quarter, y0),
+                    (x1 - quarter, y0),
+                    (x1, cy),
+                    (x1 - quarter, y1),
+                    (x0 + quarter, y1),
+                ],
+                true,
+            )
+        }
+        ShapeKind::Star {
+            outer_radius,
+            inner_radius,
+            ..
+        } => {
+            let points = (0..10)
+                .map(|i| {
+                    let radius = if i % 2 == 0 {
+                        *outer_radius
+                    } else {
+                        *inner_radius
+                    };
+                    let angle =
+                        -std::f64::consts::FRAC_PI_2 + f64::from(i) * std::f64::consts::PI / 5.0;
+                    (
+                        cx + f64::from(radius) * CELL_WIDTH * angle.cos(),
+                        cy + f64::from(radius) * CELL_HEIGHT * angle.sin(),
+                    )
+                })
+                .collect();
+            (points, true)
+        }
+        _ => (Vec::new(), false),
+    }
+}
+
+/// A linear or freedraw element through points relative to `origin`
+fn linear_element(
+    id: &str,
+    kind: &str,
+    seed: usize,
+    origin: (f64, f64),
+    points: &[(f64, f64)],
+    color: ShapeColor,
+) -> Value {
+    let xs = points.iter().map(|p| p.0);
+    let ys = points.iter().map(|p| p.1);
+    let width = xs.clone().fold(f64::MIN, f64::max) - xs.fold(f64::MAX, f64::min);
+    let height = ys.clone().fold(f64::MIN, f64::max) - ys.fold(f64::MAX, f64::min);
+    let mut element = base_element(id, kind, seed, (origin.0, origin.1, width, height), color);
+    element["points"] = json!(points.iter().map(|&(x, y)| [x, y]).collect::<Vec<_>>());
+    element["lastCommittedPoint"] = Value::Null;
+    element
+}
+
+/// A text element at `pos`, centered on it when `centered`
+fn text_element(
+    id: &str,
+    text: &str,
+    pos: (f64, f64),
+    centered: bool,
+    container: Option<&str>,
+    color: ShapeColor,
+    seed: usize,
+) -> Value {
+    let lines: Vec<&str> = text.lines().collect();
+    let columns = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = columns as f64 * CELL_WIDTH;
+    let height = lines.len().max(1) as f64 * CELL_HEIGHT;
+    let (x, y) = if centered {
+        (pos.0 - width / 2.0, pos.1 - height / 2.0)
+    } else {
+        pos
+    };
+    let mut element = base_element(id, "text", seed, (x, y, width, height), color);
+    element["text"] = json!(text);
+    element["originalText"] = json!(text);
+    element["fontSize"] = json!(FONT_SIZE);
+    element["fontFamily"] = json!(FONT_FAMILY_MONOSPACE);
+    element["lineHeight"] = json!(LINE_HEIGHT);
+    element["textAlign"] = json!(if centered { "center" } else { "left" });
+    element["verticalAlign"] = json!(if centered { "middle" } else { "top" });
+    element["containerId"] = json!(container);
+    element["autoResize"] = json!(true);
+    element
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r##"{
+        "type": "excalidraw",
+        "version": 2,
+        "elements": [
+            {"id": "box", "type": "rectangle", "x": 100, "y": 40, "width": 120, "height": 60,
+             "roundness": null, "strokeColor": "#1e1e1e",
+             "boundElements": [{"id": "label", "type": "text"}, {"id": "link", "type": "arrow"}]},
+            {"id": "label", "type": "text", "x": 130, "y": 60, "width": 60, "height": 20,
+             "text": "Client", "containerId": "box"},
+            {"id": "db", "type": "ellipse", "x": 300, "y": 40, "width": 80, "height": 60,
+             "strokeColor": "#e03131"},
+            {"id": "link", "type": "arrow", "x": 220, "y": 70,
+             "points": [[0, 0], [80, 0]],
+             "startBinding": {"elementId": "box", "focus": 0, "gap": 1},
+             "endBinding": {"elementId": "db", "focus": 0, "gap": 1},
+             "startArrowhead": null, "endArrowhead": "arrow"},
+            {"id": "gone", "type": "diamond", "x": 0, "y": 0, "width": 40, "height": 40,
+             "isDeleted": true},
+            {"id": "note", "type": "text", "x": 100, "y": 140, "text": "free text"},
+            {"id": "pen", "type": "freedraw", "x": 100, "y": 180,
+             "points": [[0, 0], [4, 2], [20, 0], [40, 20]]}
+        ]
+    }"##;
+
+    #[test]
+    fn parse_excalidraw_quantizes_elements() {
+        let shapes = parse_excalidraw(SCENE).unwrap();
+        let names: Vec<&str> = shapes.iter().map(|s| s.kind.type_name()).collect();
+        assert_eq!(
+            names,
+            vec!["Rectangle", "Ellipse", "Text", "Freehand", "Arrow"]
+        );
+
+        // Moved so the drawing starts at the origin, 10x20 pixels per cell
+        match &shapes[0].kind {
+            ShapeKind::Rectangle {
+                start, end, label, ..
+            } => {
+                assert_eq!((start.x, start.y, end.x, end.y), (0, 0, 12, 3));
+                assert_eq!(label.as_deref(), Some("Client"));
+            }
+            other => panic!("Expected Rectangle, got {:?}", other),
+        }
+        match &shapes[1].kind {
+            ShapeKind::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                color,
+                ..
+            } => {
+                assert_eq!((center.x, center.y, *radius_x, *radius_y), (24, 2, 4, 2));
+                assert_eq!(*color, ShapeColor::Red);
+            }
+            other => panic!("Expected Ellipse, got {:?}", other),
+        }
+        match &shapes[3].kind {
+            ShapeKind::Freehand { points, .. } => assert_eq!(points.len(), 3),
+            other => panic!("Expected Freehand, got {:?}", other),
+        }
+        assert_eq!(
+            (shapes[4].start_target, shapes[4].end_target),
+            (Some(0), Some(1))
+        );
+    }
+
+    #[test]
+    fn parse_excalidraw_arrowheads_and_elbows() {
+        let scene = r##"{"type": "excalidraw", "elements": [
+            {"id": "a", "type": "arrow", "x": 0, "y": 0, "points": [[0, 0], [100, 0]],
+             "startArrowhead": null, "endArrowhead": null},
+            {"id": "b", "type": "arrow", "x": 0, "y": 40, "points": [[0, 0], [100, 0]],
+             "startArrowhead": "arrow", "endArrowhead": null},
+            {"id": "c", "type": "arrow", "x": 0, "y": 80, "points": [[0, 0], [0, 40], [100, 40]]},
+            {"id": "d", "type": "line", "x": 0, "y": 200,
+             "points": [[0, 0], [100, 0], [50, 60], [0, 0]]}
+        ]}"##;
+        let shapes = parse_excalidraw(scene).unwrap();
+        assert!(matches!(shapes[0].kind, ShapeKind::Line { .. }));
+        // A start-only arrowhead flips the arrow
+        match &shapes[1].kind {
+            ShapeKind::Arrow { start, end, .. } => assert!(start.x > end.x),
+            other => panic!("Expected Arrow, got {:?}", other),
+        }
+        assert!(matches!(
+            shapes[2].kind,
+            ShapeKind::Arrow {
+                style: LineStyle::OrthogonalVH,
+                ..
+            }
+        ));
+        match &shapes[3].kind {
+            ShapeKind::Polygon { points, closed, .. } => {
+                assert_eq!(points.len(), 3);
+                assert!(*closed);
+            }
+            other => panic!("Expected Polygon, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_excalidraw_rejects_other_json() {
+        assert!(parse_excalidraw("not json").is_err());
+        assert!(parse_excalidraw(r#"{"type": "tldraw", "elements": []}"#).is_err());
+        assert!(
+            parse_excalidraw(r#"{"type": "excalidraw", "elements": []}"#)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn import_excalidraw_connects_bindings() {
+        let mut doc = Document::new();
+        let ids = import_excalidraw(&mut doc, SCENE).unwrap();
+        assert_eq!(ids.len(), 5);
+
+        let arrow = doc.read_shape(ids[4]).unwrap().unwrap();
+        assert_eq!(
+            arrow.connections(),
+            (
+                Some(ids[0].0.as_u128() as u64),
+                Some(ids[1].0.as_u128() as u64)
+            )
+        );
+    }
+
+    #[test]
+    fn export_writes_valid_scene() {
+        let mut doc = Document::new();
+        import_excalidraw(&mut doc, SCENE).unwrap();
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+
+        let scene: Value = serde_json::from_str(&export_excalidraw(&view)).unwrap();
+        assert_eq!(scene["type"], "excalidraw");
+        assert_eq!(scene["version"], 2);
+        let elements = scene["elements"].as_array().unwrap();
+        let by_id: HashMap<&str, &Value> = elements
+            .iter()
+            .map(|e| (e["id"].as_str().unwrap(), e))
+            .collect();
+        assert_eq!(by_id.len(), elements.len(), "element ids are unique");
+
+        for element in elements {
+            for key in [
+                "x",
+                "y",
+                "width",
+                "height",
+                "seed",
+                "strokeColor",
+                "isDeleted",
+            ] {
+                assert!(
+                    !element[key].is_null(),
+                    "{} missing {}",
+                    element["type"],
+                    key
+                );
+            }
+            // Bindings and containers point at elements that list them back
+            for binding in ["startBinding", "endBinding"] {
+                if let Some(target) = element[binding]["elementId"].as_str() {
+                    let listed = by_id[target]["boundElements"].as_array().unwrap();
+                    assert!(listed.iter().any(|b| b["id"] == element["id"]));
+                }
+            }
+            if let Some(container) = element["containerId"].as_str() {
+                let listed = by_id[container]["boundElements"].as_array().unwrap();
+                assert!(listed.iter().any(|b| b["id"] == element["id"]));
+            }
+        }
+        let label = elements.iter().find(|e| e["text"] == "Client").unwrap();
+        assert_eq!(label["fontFamily"], FONT_FAMILY_MONOSPACE);
+    }
+
+    #[test]
+    fn export_then_import_round_trip() {
+        let mut doc = Document::new();
+        import_excalidraw(&mut doc, SCENE).unwrap();
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+
+        let original = parse_excalidraw(SCENE).unwrap();
+        let reimported = parse_excalidraw(&export_excalidraw(&view)).unwrap();
+        assert_eq!(reimported.len(), original.len());
+        for (a, b) in original.iter().zip(&reimported) {
+            assert_eq!(a.kind.type_name(), b.kind.type_name());
+            assert_eq!(a.kind.bounds(), b.kind.bounds());
+            assert_eq!(a.kind.label(), b.kind.label());
+            assert_eq!(a.kind.color(), b.kind.color());
+            assert_eq!(
+                (a.start_target, a.end_target),
+                (b.start_target, b.end_target)
+            );
+        }
+    }
+}
@@ -0,0 +1,465 @@
+//! Bundled bitmap font for raster exports.
+//!
+//! Printable ASCII comes from a classic 5x7 font. Box drawing, arrows,
+//! braille and the other symbols shapes draw are generated procedurally so
+//! lines join seamlessly across neighbouring cells. No system fonts needed.
+
+/// Width of one character cell in pixels (before scaling)
+pub(crate) const CELL_WIDTH: u32 = 6;
+/// Height of one character cell in pixels (before scaling)
+pub(crate) const CELL_HEIGHT: u32 = 12;
+
+/// Center column and row that box-drawing lines run through, aligned with
+/// the ASCII `|` and `-` glyphs
+const CX: i32 = 2;
+const CY: i32 = 5;
+/// First row of the 5x7 ASCII glyphs within a cell
+const GLYPH_TOP: i32 = 2;
+
+/// One cell bitmap: a row per entry, bit `x` set for each lit pixel
+pub(crate) type Glyph = [u8; CELL_HEIGHT as usize];
+
+/// Classic 5x7 font for `' '..='~'`, stored column by column with bit 0 as
+/// the top row
+const ASCII: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x10, 0x08, 0x08, 0x10, 0x08], // ~
+];
+
+/// Stroke weight of one box-drawing arm
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+/// Arms of a box-drawing character: left, right, up, down
+type Arms = [Weight; 4];
+
+/// Bitmap for a character. Characters the font doesn't know draw as an
+/// outlined box so they still show up in the image.
+pub(crate) fn glyph(ch: char) -> Glyph {
+    let mut canvas = Canvas::default();
+    match ch {
+        ' '..='~' => canvas.ascii(ch),
+        '\u{2800}'..='\u{28FF}' => canvas.braille(ch as u32 - 0x2800),
+        _ => {
+            if let Some(arms) = box_arms(ch) {
+                canvas.arms(arms);
+            } else if !canvas.symbol(ch) {
+                canvas.rect(0, GLYPH_TOP, 4, GLYPH_TOP + 6);
+            }
+        }
+    }
+    canvas.rows
+}
+
+fn box_arms(ch: char) -> Option<Arms> {
+    use Weight::{Double as D, Heavy as H, Light as L, None as N};
+    let arms = match ch {
+        '─' | '┄' | '┈' | '╌' => [L, L, N, N],
+        '│' | '┆' | '┊' | '╎' => [N, N, L, L],
+        '┌' | '╭' => [N, L, N, L],
+        '┐' | '╮' => [L, N, N, L],
+        '└' | '╰' => [N, L, L, N],
+        '┘' | '╯' => [L, N, L, N],
+        '├' => [N, L, L, L],
+        '┤' => [L, N, L, L],
+        '┬' => [L, L, N, L],
+        '┴' => [L, L, L, N],
+        '┼' => [L, L, L, L],
+        '╴' => [L, N, N, N],
+        '╶' => [N, L, N, N],
+        '╵' => [N, N, L, N],
+        '╷' => [N, N, N, L],
+        '━' | '┅' | '┉' | '╍' => [H, H, N, N],
+        '┃' | '┇' | '┋' | '╏' => [N, N, H, H],
+        '┏' => [N, H, N, H],
+        '┓' => [H, N, N, H],
+        '┗' => [N, H, H, N],
+        '┛' => [H, N, H, N],
+        '┣' => [N, H, H, H],
+        '┫' => [H, N, H, H],
+        '┳' => [H, H, N, H],
+        '┻' => [H, H, H, N],
+        '╋' => [H, H, H, H],
+        '═' => [D, D, N, N],
+        '║' => [N, N, D, D],
+        '╔' => [N, D, N, D],
+        '╗' => [D, N, N, D],
+        '╚' => [N, D, D, N],
+        '╝' => [D, N, D, N],
+        '╠' => [N, D, D, D],
+        '╣' => [D, N, D, D],
+        '╦' => [D, D, N, D],
+        '╩' => [D, D, D, N],
+        '╬' => [D, D, D, D],
+        _ => return None,
+    };
+    Some(arms)
+}
+
+#[derive(Default)]
+struct Canvas {
+    rows: Glyph,
+}
+
+impl Canvas {
+    fn set(&mut self, x: i32, y: i32) {
+        if (0..CELL_WIDTH as i32).contains(&x) && (0..CELL_HEIGHT as i32).contains(&y) {
+            self.rows[y as usize] |= 1 << x;
+        }
+    }
+
+    fn hline(&mut self, x0: i32, x1: i32, y: i32) {
+        for x in x0.min(x1)..=x0.max(x1) {
+            self.set(x, y);
+        }
+    }
+
+    fn vline(&mut self, x: i32, y0: i32, y1: i32) {
+        for y in y0.min(y1)..=y0.max(y1) {
+            self.set(x, y);
+        }
+    }
+
+    fn rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        self.hline(x0, x1, y0);
+        self.hline(x0, x1, y1);
+        self.vline(x0, y0, y1);
+        self.vline(x1, y0, y1);
+    }
+
+    fn fill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        for y in y0..=y1 {
+            self.hline(x0, x1, y);
+        }
+    }
+
+    /// Bresenham line between two pixels
+    fn line(&mut self, (mut x0, mut y0): (i32, i32), (x1, y1): (i32, i32)) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        loop {
+            self.set(x0, y0);
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+        }
+    }
+
+    /// Draw rows of `#`/`.` art with its top-left corner at `(0, GLYPH_TOP)`
+    fn pattern(&mut self, art: &[&str]) {
+        for (y, row) in art.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    self.set(x as i32, GLYPH_TOP + y as i32);
+                }
+            }
+        }
+    }
+
+    fn ascii(&mut self, ch: char) {
+        let columns = ASCII[ch as usize - ' ' as usize];
+        for (x, bits) in columns.iter().enumerate() {
+            for y in 0..8 {
+                if bits & (1 << y) != 0 {
+                    self.set(x as i32, GLYPH_TOP + y);
+                }
+            }
+        }
+    }
+
+    /// Braille dots 1-8 map to a 2x4 grid: bits 0-2 and 6 down the left
+    /// column, bits 3-5 and 7 down the right
+    fn braille(&mut self, bits: u32) {
+        const DOTS: [(i32, i32); 8] = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (0, 3),
+            (1, 3),
+        ];
+        for (bit, (col, row)) in DOTS.iter().enumerate() {
+            if bits & (1 << bit) != 0 {
+                let (x, y) = (1 + col * 3, 1 + row * 3);
+                self.fill(x, y, x + 1, y + 1);
+            }
+        }
+    }
+
+    /// Lines from the cell center out to each edge with an arm. Double arms
+    /// stop short where they meet so corners and junctions stay open.
+    fn arms(&mut self, [left, right, up, down]: Arms) {
+        let (w, h) = (CELL_WIDTH as i32 - 1, CELL_HEIGHT as i32 - 1);
+        let has = |weight: Weight| weight != Weight::None;
+        let inset = |present: bool| if present { 1 } else { -1 };
+
+        for (weight, horizontal, toward_end) in [
+            (left, true, false),
+            (right, true, true),
+            (up, false, false),
+            (down, false, true),
+        ] {
+            let (center, edge) = match (horizontal, toward_end) {
+                (true, false) => (CX, 0),
+                (true, true) => (CX, w),
+                (false, false) => (CY, 0),
+                (false, true) => (CY, h),
+            };
+            let dir = if toward_end { 1 } else { -1 };
+            let line = |canvas: &mut Canvas, offset: i32, start: i32| {
+                if horizontal {
+                    canvas.hline(start, edge, CY + offset);
+                } else {
+                    canvas.vline(CX + offset, start, edge);
+                }
+            };
+            match weight {
+                Weight::None => {}
+                Weight::Light => line(self, 0, center),
+                Weight::Heavy => {
+                    line(self, 0, center);
+                    line(self, 1, center);
+                }
+                Weight::Double => {
+                    // Each rail reaches the far rail of a crossing arm on its
+                    // side, or the near one when that side is open
+                    let (before, after) = if horizontal {
+                        (up, down)
+                    } else {
+                        (left, right)
+                    };
+                    line(self, -1, center + dir * inset(has(before)));
+                    line(self, 1, center + dir * inset(has(after)));
+                }
+            }
+        }
+    }
+
+    /// Arrows, geometry symbols and block elements. Returns false for
+    /// characters the font doesn't cover.
+    fn symbol(&mut self, ch: char) -> bool {
+        let (w, h) = (CELL_WIDTH as i32 - 1, CELL_HEIGHT as i32 - 1);
+        match ch {
+            '╱' => self.line((0, h), (w, 0)),
+            '╲' => self.line((0, 0), (w, h)),
+            '╳' => {
+                self.line((0, h), (w, 0));
+                self.line((0, 0), (w, h));
+            }
+            '→' => {
+                self.hline(0, 4, CY);
+                self.line((2, CY - 2), (4, CY));
+                self.line((2, CY + 2), (4, CY));
+            }
+            '←' => {
+                self.hline(0, 4, CY);
+                self.line((2, CY - 2), (0, CY));
+                self.line((2, CY + 2), (0, CY));
+            }
+            '↑' => {
+                self.vline(CX, GLYPH_TOP, GLYPH_TOP + 6);
+                self.line((0, GLYPH_TOP + 2), (CX, GLYPH_TOP));
+                self.line((4, GLYPH_TOP + 2), (CX, GLYPH_TOP));
+            }
+            '↓' => {
+                self.vline(CX, GLYPH_TOP, GLYPH_TOP + 6);
+                self.line((0, GLYPH_TOP + 4), (CX, GLYPH_TOP + 6));
+                self.line((4, GLYPH_TOP + 4), (CX, GLYPH_TOP + 6));
+            }
+            '↗' | '↖' | '↘' | '↙' => {
+                let right = matches!(ch, '↗' | '↘');
+                let down = matches!(ch, '↘' | '↙');
+                let (tip_x, tail_x) = if right { (4, 0) } else { (0, 4) };
+                let (tip_y, tail_y) = if down {
+                    (GLYPH_TOP + 6, GLYPH_TOP + 2)
+                } else {
+                    (GLYPH_TOP, GLYPH_TOP + 4)
+                };
+                self.line((tail_x, tail_y), (tip_x, tip_y));
+                self.hline(tip_x, tip_x - (tip_x - tail_x).signum() * 2, tip_y);
+                self.vline(tip_x, tip_y, tip_y - (tip_y - tail_y).signum() * 2);
+            }
+            '▶' | '►' => self.pattern(&["#", "##", "###", "####", "###", "##", "#"]),
+            '◀' | '◄' => {
+                self.pattern(&["...#", "..##", ".###", "####", ".###", "..##", "...#"])
+            }
+            '▲' => self.pattern(&["", "", "..#", ".###", "#####"]),
+            '▼' => self.pattern(&["", "", "#####", ".###", "..#"]),
+            '○' | '◯' => self.pattern(&["", ".###", "#...#", "#...#", "#...#", ".###"]),
+            '●' | '•' => self.pattern(&["", "", ".##", "####", "####", ".##"]),
+            '◇' => self.pattern(&["", "..#", ".#.#", "#...#", ".#.#", "..#"]),
+            '◆' => self.pattern(&["", "..#", ".###", "#####", ".###", "..#"]),
+            '°' => self.pattern(&[".##", "#..#", ".##"]),
+            '·' => self.fill(CX, CY, CX + 1, CY + 1),
+            '–' => self.hline(0, 4, CY),
+            '—' => self.hline(0, w, CY),
+            '█' => self.fill(0, 0, w, h),
+            '▀' => self.fill(0, 0, w, h / 2),
+            '▄' => self.fill(0, h / 2 + 1, w, h),
+            '░' | '▒' | '▓' => {
+                for y in 0..=h {
+                    for x in 0..=w {
+                        let on = match ch {
+                            '░' => x % 2 == 0 && y % 2 == 0,
+                            '▒' => (x + y) % 2 == 0,
+                            _ => x % 2 == 0 || y % 2 == 0,
+                        };
+                        if on {
+                            self.set(x, y);
+                        }
+                    }
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(glyph: &Glyph, x: i32, y: i32) -> bool {
+        glyph[y as usize] & (1 << x) != 0
+    }
+
+    #[test]
+    fn box_lines_reach_cell_edges() {
+        let horizontal = glyph('─');
+        assert!(lit(&horizontal, 0, CY) && lit(&horizontal, CELL_WIDTH as i32 - 1, CY));
+        let vertical = glyph('│');
+        assert!(lit(&vertical, CX, 0) && lit(&vertical, CX, CELL_HEIGHT as i32 - 1));
+        // The ASCII fallbacks line up with box drawing
+        assert!(lit(&glyph('-'), CX, CY) && lit(&glyph('|'), CX, CY));
+
+        // Double corner: outer rail turns at the corner, inner rail stays open
+        let corner = glyph('╔');
+        assert!(lit(&corner, CX - 1, CY - 1));
+        assert!(lit(&corner, CX + 1, CY + 1));
+        assert!(!lit(&corner, CX, CY));
+    }
+
+    #[test]
+    fn braille_and_unknown_glyphs() {
+        assert_eq!(glyph('\u{2800}'), Glyph::default());
+        let full = glyph('\u{28FF}');
+        assert_eq!(full.iter().map(|row| row.count_ones()).sum::<u32>(), 8 * 4);
+        assert_ne!(glyph('A'), glyph('B'));
+        // Unknown characters still draw something
+        assert_ne!(glyph('漢'), Glyph::default());
+        assert_eq!(glyph(' '), Glyph::default());
+    }
+}
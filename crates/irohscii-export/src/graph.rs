@@ -0,0 +1,519 @@
+//! Graph model and layout shared by the Mermaid and DOT importers.
+//!
+//! Importers parse their syntax into a [`Graph`] of nodes and edges; this module
+//! ranks and places the nodes, routes the edges and builds the shapes. Exporters
+//! use [`connection_graph`] to read nodes and edges back out of a document.
+
+use std::collections::HashMap;
+
+use irohscii_core::{LineStyle, Position, ShapeColor, ShapeKind, ShapeView};
+
+use crate::ascii_import::ImportedShape;
+
+/// Gap between ranks when they are stacked vertically
+const RANK_GAP_VERTICAL: i32 = 4;
+/// Gap between ranks side by side (wider, so edge labels fit)
+const RANK_GAP_HORIZONTAL: i32 = 8;
+/// Gap between nodes within a rank
+const NODE_GAP: i32 = 4;
+
+/// Direction ranks are laid out in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    TopDown,
+    BottomUp,
+    LeftRight,
+    RightLeft,
+}
+
+impl Direction {
+    pub(crate) fn parse(token: &str) -> Option<Self> {
+        match token.to_ascii_uppercase().as_str() {
+            "TD" | "TB" => Some(Direction::TopDown),
+            "BT" => Some(Direction::BottomUp),
+            "LR" => Some(Direction::LeftRight),
+            "RL" => Some(Direction::RightLeft),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_vertical(self) -> bool {
+        matches!(self, Direction::TopDown | Direction::BottomUp)
+    }
+}
+
+/// Node shapes shared by the graph formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NodeShape {
+    Rect,
+    Rounded,
+    Stadium,
+    Subroutine,
+    Cylinder,
+    Circle,
+    Diamond,
+    Hexagon,
+    Parallelogram,
+    Trapezoid,
+    Asymmetric,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Node {
+    shape: NodeShape,
+    label: Option<String>,
+    defined: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Edge {
+    pub(crate) from: usize,
+    pub(crate) to: usize,
+    pub(crate) arrow: bool,
+    pub(crate) label: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Graph {
+    pub(crate) direction: Direction,
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) edges: Vec<Edge>,
+    ids: HashMap<String, usize>,
+}
+
+impl Graph {
+    pub(crate) fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Look up a node, creating it on first reference. The first shaped
+    /// definition wins; bare references show the id as their text.
+    pub(crate) fn node(
+        &mut self,
+        id: &str,
+        definition: Option<(NodeShape, Option<String>)>,
+    ) -> usize {
+        let index = *self.ids.entry(id.to_string()).or_insert_with(|| {
+            self.nodes.push(Node {
+                shape: NodeShape::Rect,
+                label: Some(id.to_string()),
+                defined: false,
+            });
+            self.nodes.len() - 1
+        });
+        if let Some((shape, label)) = definition
+            && !self.nodes[index].defined
+        {
+            self.nodes[index] = Node {
+                shape,
+                label,
+                defined: true,
+            };
+        }
+        index
+    }
+
+    /// Lay out the graph as shapes. Nodes come first, then edges, which
+    /// reference their nodes by index.
+    pub(crate) fn shapes(&self) -> Vec<ImportedShape> {
+        let boxes = layout(self);
+
+        let mut shapes: Vec<ImportedShape> = self
+            .nodes
+            .iter()
+            .zip(&boxes)
+            .map(|(node, &(min, size))| ImportedShape {
+                kind: node_shape(node, min, size),
+                start_target: None,
+                end_target: None,
+            })
+            .collect();
+
+        for edge in &self.edges {
+            let (start, end, style) = route_edge(self.direction, boxes[edge.from], boxes[edge.to]);
+            let (start_connection, end_connection, color) = (None, None, ShapeColor::default());
+            let label = edge.label.clone();
+            let kind = if edge.arrow {
+                ShapeKind::Arrow {
+                    start,
+                    end,
+                    style,
+                    start_connection,
+                    end_connection,
+                    label,
+                    color,
+                }
+            } else {
+                ShapeKind::Line {
+                    start,
+                    end,
+                    style,
+                    start_connection,
+                    end_connection,
+                    label,
+                    color,
+                }
+            };
+            shapes.push(ImportedShape {
+                kind,
+                start_target: Some(edge.from),
+                end_target: Some(edge.to),
+            });
+        }
+        shapes
+    }
+}
+
+// ========== Layout ==========
+
+/// Node box: top-left corner and (width, height)
+type NodeBox = (Position, (i32, i32));
+
+fn node_size(node: &Node) -> (i32, i32) {
+    let len = node.label.as_deref().map_or(0, |l| {
+        i32::try_from(l.chars().count()).unwrap_or(i32::MAX / 4)
+    });
+    match node.shape {
+        NodeShape::Rect | NodeShape::Rounded | NodeShape::Stadium | NodeShape::Subroutine => {
+            ((len + 4).max(7), 3)
+        }
+        NodeShape::Cylinder => ((len + 4).max(7), 5),
+        NodeShape::Parallelogram | NodeShape::Trapezoid | NodeShape::Asymmetric => {
+            ((len + 6).max(9), 3)
+        }
+        // Centered shapes have odd sizes around their center cell
+        NodeShape::Circle | NodeShape::Diamond | NodeShape::Hexagon => ((len / 2 + 3) * 2 + 1, 5),
+    }
+}
+
+/// Assign each node a rank: longest path from a source, ignoring back edges
+fn ranks(graph: &Graph) -> Vec<usize> {
+    let count = graph.nodes.len();
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for edge in &graph.edges {
+        outgoing[edge.from].push(edge.to);
+    }
+
+    // Depth-first order; edges into a node still on the stack close a cycle
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        New,
+        Active,
+        Done,
+    }
+    let mut state = vec![Visit::New; count];
+    let mut order = Vec::with_capacity(count);
+    let mut forward: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for root in 0..count {
+        if state[root] != Visit::New {
+            continue;
+        }
+        let mut stack = vec![(root, 0)];
+        state[root] = Visit::Active;
+        while let Some((node, next)) = stack.pop() {
+            if let Some(&child) = outgoing[node].get(next) {
+                stack.push((node, next + 1));
+                match state[child] {
+                    Visit::New => {
+                        forward[node].push(child);
+                        state[child] = Visit::Active;
+                        stack.push((child, 0));
+                    }
+                    Visit::Done => forward[node].push(child),
+                    Visit::Active => {}
+                }
+            } else {
+                state[node] = Visit::Done;
+                order.push(node);
+            }
+        }
+    }
+
+    // Reverse post-order is a topological order of the forward edges
+    let mut rank = vec![0; count];
+    for &node in order.iter().rev() {
+        for &child in &forward[node] {
+            rank[child] = rank[child].max(rank[node] + 1);
+        }
+    }
+    rank
+}
+
+/// Place nodes rank by rank along the graph direction
+fn layout(graph: &Graph) -> Vec<NodeBox> {
+    let rank = ranks(graph);
+    let rank_count = rank.iter().max().map_or(0, |r| r + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); rank_count];
+    for (node, &r) in rank.iter().enumerate() {
+        layers[r].push(node);
+    }
+
+    // One barycenter sweep keeps children near their parents
+    let mut slot = vec![0.0f64; graph.nodes.len()];
+    for layer in &mut layers {
+        let key = |node: usize| {
+            let parents: Vec<f64> = graph
+                .edges
+                .iter()
+                .filter(|e| e.to == node && rank[e.from] < rank[node])
+                .map(|e| slot[e.from])
+                .collect();
+            if parents.is_empty() {
+                f64::MAX
+            } else {
+                parents.iter().sum::<f64>() / parents.len() as f64
+            }
+        };
+        let mut keyed: Vec<(f64, usize)> = layer.iter().map(|&n| (key(n), n)).collect();
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        *layer = keyed.into_iter().map(|(_, n)| n).collect();
+        for (i, &node) in layer.iter().enumerate() {
+            slot[node] = i as f64;
+        }
+    }
+
+    if matches!(graph.direction, Direction::BottomUp | Direction::RightLeft) {
+        layers.reverse();
+    }
+
+    let sizes: Vec<(i32, i32)> = graph.nodes.iter().map(node_size).collect();
+    let vertical = graph.direction.is_vertical();
+    // Map (along rank, across rank) to (x, y)
+    let across = |(w, h): (i32, i32)| if vertical { w } else { h };
+    let along = |(w, h): (i32, i32)| if vertical { h } else { w };
+
+    let spans: Vec<i32> = layers
+        .iter()
+        .map(|layer| {
+            let total: i32 = layer.iter().map(|&n| across(sizes[n])).sum();
+            total + NODE_GAP * (i32::try_from(layer.len()).unwrap_or(1) - 1).max(0)
+        })
+        .collect();
+    let widest = spans.iter().copied().max().unwrap_or(0);
+    let rank_gap = if vertical {
+        RANK_GAP_VERTICAL
+    } else {
+        RANK_GAP_HORIZONTAL
+    };
+
+    let mut boxes = vec![(Position::new(0, 0), (0, 0)); graph.nodes.len()];
+    let mut rank_pos = 0;
+    for (layer, span) in layers.iter().zip(&spans) {
+        let depth = layer.iter().map(|&n| along(sizes[n])).max().unwrap_or(0);
+        let mut cross_pos = (widest - span) / 2;
+        for &node in layer {
+            let size = sizes[node];
+            let offset = rank_pos + (depth - along(size)) / 2;
+            let min = if vertical {
+                Position::new(cross_pos, offset)
+            } else {
+                Position::new(offset, cross_pos)
+            };
+            boxes[node] = (min, size);
+            cross_pos += across(size) + NODE_GAP;
+        }
+        rank_pos += depth + rank_gap;
+    }
+    boxes
+}
+
+/// Build the irohscii shape for a node placed in its box
+fn node_shape(node: &Node, min: Position, (w, h): (i32, i32)) -> ShapeKind {
+    let end = Position::new(min.x + w - 1, min.y + h - 1);
+    let center = Position::new(min.x + w / 2, min.y + h / 2);
+    let (label, color) = (node.label.clone(), ShapeColor::default());
+    let start = min;
+    match node.shape {
+        NodeShape::Rect => ShapeKind::Rectangle {
+            start,
+            end,
+            label,
+            color,
+        },
+        NodeShape::Rounded | NodeShape::Stadium => ShapeKind::RoundedRect {
+            start,
+            end,
+            label,
+            color,
+        },
+        NodeShape::Subroutine => ShapeKind::DoubleBox {
+            start,
+            end,
+            label,
+            color,
+        },
+        NodeShape::Cylinder => ShapeKind::Cylinder {
+            start,
+            end,
+            label,
+            color,
+        },
+        NodeShape::Parallelogram | NodeShape::Asymmetric => ShapeKind::Parallelogram {
+            start,
+            end,
+            label,
+            color,
+        },
+        NodeShape::Trapezoid => ShapeKind::Trapezoid {
+            start,
+            end,
+            label,
+            color,
+        },
+        NodeShape::Circle => ShapeKind::Ellipse {
+            center,
+            radius_x: w / 2,
+            radius_y: h / 2,
+            label,
+            color,
+        },
+        NodeShape::Diamond => ShapeKind::Diamond {
+            center,
+            half_width: w / 2,
+            half_height: h / 2,
+            label,
+            color,
+        },
+        NodeShape::Hexagon => ShapeKind::Hexagon {
+            center,
+            radius_x: w / 2,
+            radius_y: h / 2,
+            label,
+            color,
+        },
+    }
+}
+
+/// Route an edge between node boxes. Edges to the next ranks leave the
+/// source's side and turn into the target's near face; back edges leave
+/// upstream and turn into the target's side; edges within a rank run straight.
+fn route_edge(direction: Direction, from: NodeBox, to: NodeBox) -> (Position, Position, LineStyle) {
+    // Work in rank space: `along` grows downstream, `across` runs along a rank
+    let vertical = direction.is_vertical();
+    let sign = if matches!(direction, Direction::BottomUp | Direction::RightLeft) {
+        -1
+    } else {
+        1
+    };
+    let to_rank_space = |(min, (w, h)): NodeBox| {
+        let (max_x, max_y) = (min.x + w - 1, min.y + h - 1);
+        let (a0, a1, c0, c1) = if vertical {
+            (min.y, max_y, min.x, max_x)
+        } else {
+            (min.x, max_x, min.y, max_y)
+        };
+        let (a0, a1) = if sign < 0 { (-a1, -a0) } else { (a0, a1) };
+        (a0, a1, c0, c1, (a0 + a1) / 2, (c0 + c1) / 2)
+    };
+    let to_canvas = |across: i32, along: i32| {
+        if vertical {
+            Position::new(across, along * sign)
+        } else {
+            Position::new(along * sign, across)
+        }
+    };
+    // Styles named in rank space
+    let (across_first, along_first) = if vertical {
+        (LineStyle::OrthogonalHV, LineStyle::OrthogonalVH)
+    } else {
+        (LineStyle::OrthogonalVH, LineStyle::OrthogonalHV)
+    };
+
+    let (sa0, sa1, sc0, sc1, sa, sc) = to_rank_space(from);
+    let (ta0, ta1, tc0, tc1, ta, tc) = to_rank_space(to);
+    let (start, end, style) = if ta0 > sa1 {
+        if sc == tc {
+            ((sc, sa1), (tc, ta0), LineStyle::Straight)
+        } else {
+            let side = if tc > sc { sc1 } else { sc0 };
+            ((side, sa), (tc, ta0), across_first)
+        }
+    } else if ta1 < sa0 {
+        if sc == tc {
+            ((sc, sa0), (tc, ta1), LineStyle::Straight)
+        } else {
+            let side = if sc > tc { tc1 } else { tc0 };
+            ((sc, sa0), (side, ta), along_first)
+        }
+    } else {
+        let (start, end) = if tc >= sc { (sc1, tc0) } else { (sc0, tc1) };
+        ((start, sa), (end, ta), LineStyle::Straight)
+    };
+    (to_canvas(start.0, start.1), to_canvas(end.0, end.1), style)
+}
+
+// ========== Export ==========
+
+/// Nodes and edges read back from a document
+pub(crate) struct ConnectionGraph<'a> {
+    pub(crate) nodes: Vec<&'a ShapeKind>,
+    /// Edges between indices into `nodes`
+    pub(crate) edges: Vec<Edge>,
+    /// Whether edges mostly run horizontally
+    pub(crate) horizontal: bool,
+}
+
+/// Collect the connection graph of a document. Shapes other than lines, arrows,
+/// text and freehand strokes become nodes; lines and arrows connected to nodes
+/// at both ends become edges.
+pub(crate) fn connection_graph(shapes: &ShapeView) -> ConnectionGraph<'_> {
+    let mut nodes = Vec::new();
+    let mut index: HashMap<u64, usize> = HashMap::new();
+    let mut links = Vec::new();
+    let (mut across, mut along) = (0i64, 0i64);
+
+    for shape in shapes.iter() {
+        match &shape.kind {
+            ShapeKind::Line {
+                start,
+                end,
+                start_connection,
+                end_connection,
+                label,
+                ..
+            }
+            | ShapeKind::Arrow {
+                start,
+                end,
+                start_connection,
+                end_connection,
+                label,
+                ..
+            } => {
+                if let (Some(from), Some(to)) = (start_connection, end_connection) {
+                    let arrow = matches!(shape.kind, ShapeKind::Arrow { .. });
+                    links.push((*from, *to, arrow, label.clone()));
+                    across += i64::from((end.x - start.x).abs());
+                    along += i64::from((end.y - start.y).abs());
+                }
+            }
+            ShapeKind::Text { .. } | ShapeKind::Freehand { .. } => {}
+            kind => {
+                index.insert(shape.id.0.as_u128() as u64, nodes.len());
+                nodes.push(kind);
+            }
+        }
+    }
+
+    let edges = links
+        .into_iter()
+        .filter_map(|(from, to, arrow, label)| {
+            Some(Edge {
+                from: *index.get(&from)?,
+                to: *index.get(&to)?,
+                arrow,
+                label,
+            })
+        })
+        .collect();
+    ConnectionGraph {
+        nodes,
+        edges,
+        horizontal: across > along,
+    }
+}
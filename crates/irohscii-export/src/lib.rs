@@ -1,14 +1,57 @@
 //! ASCII and SVG export for irohscii.
 //!
 //! This crate provides file I/O functionality:
+//! - Export options: selection, layers, region, padding and hidden layers
 //! - ASCII art rendering to text files
+//! - ASCII art import that recognizes boxes, lines, arrows and text
+//! - Colored ANSI and HTML text exports
+//! - Diagram blocks embedded in Markdown files
+//! - Mermaid flowchart import and export
+//! - Graphviz DOT import and export
+//! - Excalidraw scene import and export
+//! - draw.io (mxGraph XML) export with layers
 //! - SVG export with proper shape rendering
+//! - PNG export rasterized with a bundled bitmap font
 
 mod ascii;
+mod ascii_import;
+mod colored;
+mod dot;
+mod drawio;
+mod excalidraw;
+mod font;
+mod graph;
+mod markdown;
+mod mermaid;
+mod options;
+mod png;
 mod svg;
 
-pub use ascii::{load_ascii, save_ascii};
-pub use svg::{export_svg, save_svg};
+pub use ascii::{load_ascii, load_ascii_into, render_shapes_to_text, save_ascii};
+pub use ascii_import::{ImportedShape, import_ascii, parse_ascii};
+pub use colored::{
+    is_ansi_path, is_html_path, render_shapes_to_ansi, render_shapes_to_html, save_ansi, save_html,
+};
+pub use dot::{export_dot, import_dot, is_dot_path, load_dot, parse_dot, save_dot};
+pub use drawio::{export_drawio, is_drawio_path, save_drawio};
+pub use excalidraw::{
+    export_excalidraw, import_excalidraw, is_excalidraw_path, load_excalidraw, parse_excalidraw,
+    save_excalidraw,
+};
+pub use markdown::{
+    BlockRef, DIAGRAM_LANGUAGE, DiagramBlock, find_diagram_blocks, is_markdown_path,
+    load_markdown_block, regenerate_blocks, replace_block, save_markdown_block,
+    split_block_selector,
+};
+pub use mermaid::{
+    export_mermaid, import_mermaid, is_mermaid_path, load_mermaid, parse_mermaid, save_mermaid,
+};
+pub use options::{
+    ExportFormat, ExportOptions, ExportScope, Region, export_with_options,
+    render_text_with_options, save_with_options,
+};
+pub use png::{PngOptions, is_png_path, parse_png_background, render_png, save_png};
+pub use svg::{export_svg, is_svg_path, save_svg};
 
 // Re-export core types for convenience
 pub use irohscii_core::{Position, ShapeColor, ShapeKind, ShapeView};
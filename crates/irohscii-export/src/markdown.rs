@@ -0,0 +1,400 @@
+//! Markdown diagram blocks: edit diagrams embedded in fenced code blocks.
+//!
+//! Diagram blocks are fenced code blocks whose info string starts with
+//! `irohscii` (optionally with an `id=` attribute) or with a plain-text
+//! language. Edited diagrams are spliced back into their block, leaving the
+//! rest of the file byte-for-byte unchanged.
+
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use irohscii_core::{Document, ShapeView};
+
+use crate::ascii::render_shapes_to_text;
+use crate::ascii_import::import_ascii;
+
+/// Info-string language that marks a block as an irohscii diagram
+pub const DIAGRAM_LANGUAGE: &str = "irohscii";
+
+/// Plain-text languages whose blocks are also offered as diagrams
+const PLAIN_LANGUAGES: [&str; 4] = ["text", "txt", "ascii", "plain"];
+
+/// A fenced code block holding a diagram
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagramBlock {
+    /// Position among the file's diagram blocks (0-based)
+    pub index: usize,
+    /// Line number of the opening fence (1-based)
+    pub line: usize,
+    /// Info string after the opening fence
+    pub info: String,
+    /// Value of the `id=` attribute, if any
+    pub id: Option<String>,
+    /// Text between the fences
+    pub content: String,
+    /// Byte range of the content within the file
+    pub body: Range<usize>,
+}
+
+impl DiagramBlock {
+    /// Whether the block is explicitly marked as an irohscii diagram
+    pub fn is_marked(&self) -> bool {
+        self.info.split_whitespace().next() == Some(DIAGRAM_LANGUAGE)
+    }
+
+    /// Reference that finds this block again after the file changes
+    pub fn block_ref(&self) -> BlockRef {
+        match &self.id {
+            Some(id) => BlockRef::Id(id.clone()),
+            None => BlockRef::Index(self.index),
+        }
+    }
+
+    /// Short description for block lists
+    pub fn title(&self) -> String {
+        match &self.id {
+            Some(id) => format!("{} (line {})", id, self.line),
+            None => format!("#{} (line {})", self.index + 1, self.line),
+        }
+    }
+}
+
+/// Identifies a diagram block: by id when it has one, else by position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockRef {
+    Id(String),
+    /// Position among the diagram blocks only; code in other languages
+    /// doesn't count
+    Index(usize),
+}
+
+impl BlockRef {
+    /// Parse a selector: a 1-based diagram block number or a block id
+    pub fn parse(selector: &str) -> Self {
+        match selector.parse::<usize>() {
+            Ok(n) if n > 0 => BlockRef::Index(n - 1),
+            _ => BlockRef::Id(selector.to_string()),
+        }
+    }
+
+    pub fn find<'a>(&self, blocks: &'a [DiagramBlock]) -> Option<&'a DiagramBlock> {
+        match self {
+            BlockRef::Id(id) => blocks.iter().find(|b| b.id.as_deref() == Some(id.as_str())),
+            BlockRef::Index(index) => blocks.iter().find(|b| b.index == *index),
+        }
+    }
+}
+
+impl fmt::Display for BlockRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockRef::Id(id) => write!(f, "{}", id),
+            BlockRef::Index(index) => write!(f, "{}", index + 1),
+        }
+    }
+}
+
+/// Whether a path names a Markdown file
+pub fn is_markdown_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+/// Split `notes.md#arch` into the file path and block selector.
+/// Only Markdown paths take a selector; other paths are returned as-is.
+pub fn split_block_selector(path: &str) -> (PathBuf, Option<BlockRef>) {
+    if let Some((file, selector)) = path.rsplit_once('#')
+        && !selector.is_empty()
+        && is_markdown_path(Path::new(file))
+    {
+        return (PathBuf::from(file), Some(BlockRef::parse(selector)));
+    }
+    (PathBuf::from(path), None)
+}
+
+/// Find the diagram blocks in a Markdown document
+pub fn find_diagram_blocks(markdown: &str) -> Vec<DiagramBlock> {
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    let mut open: Option<(Fence, usize, String, usize)> = None;
+
+    for (line_index, line) in markdown.split_inclusive('\n').enumerate() {
+        let line_start = offset;
+        offset += line.len();
+
+        match &open {
+            None => {
+                if let Some((fence, info)) = opening_fence(line) {
+                    open = Some((fence, line_index + 1, info, offset));
+                }
+            }
+            Some((fence, _, _, _)) => {
+                if fence.closes(line)
+                    && let Some((_, line_number, info, body_start)) = open.take()
+                {
+                    push_block(
+                        &mut blocks,
+                        markdown,
+                        line_number,
+                        info,
+                        body_start..line_start,
+                    );
+                }
+            }
+        }
+    }
+
+    // An unclosed fence runs to the end of the document
+    if let Some((_, line_number, info, body_start)) = open {
+        push_block(
+            &mut blocks,
+            markdown,
+            line_number,
+            info,
+            body_start..markdown.len(),
+        );
+    }
+    blocks
+}
+
+/// Replace a block's content, leaving the rest of the document unchanged
+pub fn replace_block(markdown: &str, block: &DiagramBlock, content: &str) -> String {
+    debug_assert!(
+        block.body.end <= markdown.len(),
+        "Block must come from this document"
+    );
+
+    let mut out = String::with_capacity(markdown.len() + content.len());
+    out.push_str(&markdown[..block.body.start]);
+    out.push_str(content);
+    if !content.is_empty() && !content.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&markdown[block.body.end..]);
+    out
+}
+
+/// Regenerate every marked block that has an id. `render` returns the new
+/// content for a block, or None to leave it unchanged. Returns the updated
+/// document and the number of blocks rewritten.
+pub fn regenerate_blocks(
+    markdown: &str,
+    mut render: impl FnMut(&DiagramBlock) -> Result<Option<String>>,
+) -> Result<(String, usize)> {
+    let mut updated = markdown.to_string();
+    let mut count = 0;
+
+    // Replace from the end so earlier byte ranges stay valid
+    let blocks = find_diagram_blocks(markdown);
+    for block in blocks
+        .iter()
+        .rev()
+        .filter(|b| b.is_marked() && b.id.is_some())
+    {
+        if let Some(content) = render(block)? {
+            updated = replace_block(&updated, block, &content);
+            count += 1;
+        }
+    }
+    Ok((updated, count))
+}
+
+/// Import a diagram block from a Markdown file into a document
+pub fn load_markdown_block(
+    doc: &mut Document,
+    path: &Path,
+    block_ref: &BlockRef,
+) -> Result<DiagramBlock> {
+    let markdown =
+        fs::read_to_string(path).with_context(|| format!("Failed to read from {:?}", path))?;
+    let blocks = find_diagram_blocks(&markdown);
+    let block = block_ref
+        .find(&blocks)
+        .ok_or_else(|| anyhow!("No diagram block {} in {:?}", block_ref, path))?;
+
+    import_ascii(doc, &block.content)?;
+    Ok(block.clone())
+}
+
+/// Write shapes back into a diagram block of a Markdown file
+pub fn save_markdown_block(shapes: &ShapeView, path: &Path, block_ref: &BlockRef) -> Result<()> {
+    let markdown =
+        fs::read_to_string(path).with_context(|| format!("Failed to read from {:?}", path))?;
+    let blocks = find_diagram_blocks(&markdown);
+    let block = block_ref
+        .find(&blocks)
+        .ok_or_else(|| anyhow!("No diagram block {} in {:?}", block_ref, path))?;
+
+    let updated = replace_block(&markdown, block, &render_shapes_to_text(shapes));
+    fs::write(path, updated).with_context(|| format!("Failed to save to {:?}", path))?;
+    Ok(())
+}
+
+// ========== Fence parsing ==========
+
+#[derive(Debug, Clone, Copy)]
+struct Fence {
+    marker: char,
+    len: usize,
+}
+
+impl Fence {
+    /// A closing fence uses the same marker, at least as long, with nothing after it
+    fn closes(&self, line: &str) -> bool {
+        let Some(rest) = strip_fence_indent(line) else {
+            return false;
+        };
+        let len = rest.chars().take_while(|&c| c == self.marker).count();
+        len >= self.len && rest[len * self.marker.len_utf8()..].trim().is_empty()
+    }
+}
+
+/// Up to three spaces of indentation are allowed before a fence
+fn strip_fence_indent(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    (indent <= 3).then(|| &line[indent..])
+}
+
+fn opening_fence(line: &str) -> Option<(Fence, String)> {
+    let rest = strip_fence_indent(line)?;
+    let marker = rest.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = rest.chars().take_while(|&c| c == marker).count();
+    if len < 3 {
+        return None;
+    }
+
+    let info = rest[len..].trim();
+    // Backtick fences cannot have backticks in their info string
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    Some((Fence { marker, len }, info.to_string()))
+}
+
+fn push_block(
+    blocks: &mut Vec<DiagramBlock>,
+    markdown: &str,
+    line: usize,
+    info: String,
+    body: Range<usize>,
+) {
+    let language = info.split_whitespace().next().unwrap_or("");
+    let is_diagram = language == DIAGRAM_LANGUAGE
+        || language.is_empty()
+        || PLAIN_LANGUAGES.contains(&language.to_ascii_lowercase().as_str());
+    if !is_diagram {
+        return;
+    }
+
+    let id = info
+        .split_whitespace()
+        .skip(1)
+        .find_map(|attr| attr.strip_prefix("id="))
+        .map(|id| id.trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|id| !id.is_empty());
+
+    blocks.push(DiagramBlock {
+        index: blocks.len(),
+        line,
+        info,
+        id,
+        content: markdown[body.clone()].to_string(),
+        body,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use irohscii_core::ShapeKind;
+    use tempfile::tempdir;
+
+    const DOC: &str = "# Design\n\n```irohscii id=arch\n┌──┐\n└──┘\n```\n\n```rust\nfn main() {}\n```\n\n~~~\nplain\n~~~\n\n```irohscii\n```\n";
+
+    #[test]
+    fn find_diagram_blocks_skips_code() {
+        let blocks = find_diagram_blocks(DOC);
+        assert_eq!(blocks.len(), 3);
+
+        assert_eq!(blocks[0].id.as_deref(), Some("arch"));
+        assert!(blocks[0].is_marked());
+        assert_eq!(blocks[0].line, 3);
+        assert_eq!(blocks[0].content, "┌──┐\n└──┘\n");
+
+        assert!(!blocks[1].is_marked());
+        assert_eq!(blocks[1].content, "plain\n");
+
+        assert!(blocks[2].is_marked());
+        assert_eq!(blocks[2].id, None);
+        assert!(blocks[2].content.is_empty());
+        assert_eq!(blocks[2].index, 2);
+    }
+
+    #[test]
+    fn replace_block_keeps_rest_of_file() {
+        let blocks = find_diagram_blocks(DOC);
+        let updated = replace_block(DOC, &blocks[0], "+--+\n+--+");
+
+        assert!(updated.starts_with("# Design\n\n```irohscii id=arch\n+--+\n+--+\n```\n"));
+        assert!(updated.ends_with(&DOC[blocks[0].body.end..]));
+        assert_eq!(find_diagram_blocks(&updated).len(), 3);
+    }
+
+    #[test]
+    fn block_ref_selectors() {
+        let blocks = find_diagram_blocks(DOC);
+        assert_eq!(BlockRef::parse("arch").find(&blocks), Some(&blocks[0]));
+        assert_eq!(BlockRef::parse("2").find(&blocks), Some(&blocks[1]));
+        assert_eq!(BlockRef::parse("missing").find(&blocks), None);
+
+        // Numbers skip code blocks, and still name the same block in a
+        // filtered list
+        let with_code = format!("```rust
+fn main() {{}}
+```
+
+{}", DOC);
+        let blocks = find_diagram_blocks(&with_code);
+        assert_eq!(BlockRef::parse("1").find(&blocks).unwrap().id.as_deref(), Some("arch"));
+        let unmarked: Vec<DiagramBlock> = blocks.iter().filter(|b| !b.is_marked()).cloned().collect();
+        assert_eq!(BlockRef::parse("2").find(&unmarked).unwrap().content, "plain\n");
+        assert_eq!(BlockRef::parse("1").find(&unmarked), None);
+
+        let (path, selector) = split_block_selector("docs/notes.md#arch");
+        assert_eq!(path, PathBuf::from("docs/notes.md"));
+        assert_eq!(selector, Some(BlockRef::Id("arch".to_string())));
+        assert_eq!(split_block_selector("drawing#1.txt").1, None);
+    }
+
+    #[test]
+    fn regenerate_blocks_only_touches_marked_ids() {
+        let (updated, count) =
+            regenerate_blocks(DOC, |block| Ok(Some(format!("[{}]", block.title())))).unwrap();
+        assert_eq!(count, 1);
+        assert!(updated.contains("```irohscii id=arch\n[arch (line 3)]\n```"));
+        assert!(updated.contains("~~~\nplain\n~~~"));
+    }
+
+    #[test]
+    fn markdown_block_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        fs::write(&path, DOC).unwrap();
+
+        let mut doc = Document::new();
+        let block = load_markdown_block(&mut doc, &path, &BlockRef::parse("arch")).unwrap();
+        assert_eq!(block.block_ref(), BlockRef::Id("arch".to_string()));
+        let shapes = doc.read_all_shapes().unwrap();
+        assert_eq!(shapes.len(), 1);
+        assert!(matches!(shapes[0].1, ShapeKind::Rectangle { .. }));
+
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+        save_markdown_block(&view, &path, &block.block_ref()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), DOC);
+    }
+}
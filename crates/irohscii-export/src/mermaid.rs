@@ -0,0 +1,512 @@
+//! Mermaid flowchart import and export.
+//!
+//! Imports `flowchart`/`graph` definitions: nodes become shapes of the matching
+//! type, edges become connected arrows (or lines) with labels, and the result is
+//! laid out in ranks along the flowchart direction. Exports connected shapes
+//! back to a flowchart.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use irohscii_core::{Document, ShapeId, ShapeKind, ShapeView};
+
+use crate::ascii_import::{ImportedShape, add_imported};
+use crate::graph::{Direction, Edge, Graph, NodeShape, connection_graph};
+
+/// Node shape delimiters, longest openers first; an opener may have several closers
+const NODE_DELIMITERS: [(&str, &str, NodeShape); 14] = [
+    ("(((", ")))", NodeShape::Circle),
+    ("([", "])", NodeShape::Stadium),
+    ("[[", "]]", NodeShape::Subroutine),
+    ("[(", ")]", NodeShape::Cylinder),
+    ("((", "))", NodeShape::Circle),
+    ("{{", "}}", NodeShape::Hexagon),
+    ("[/", "/]", NodeShape::Parallelogram),
+    ("[/", "\\]", NodeShape::Trapezoid),
+    ("[\\", "\\]", NodeShape::Parallelogram),
+    ("[\\", "/]", NodeShape::Trapezoid),
+    ("[", "]", NodeShape::Rect),
+    ("(", ")", NodeShape::Rounded),
+    ("{", "}", NodeShape::Diamond),
+    (">", "]", NodeShape::Asymmetric),
+];
+
+/// Statements that carry styling or grouping only
+const IGNORED_STATEMENTS: [&str; 10] = [
+    "subgraph",
+    "end",
+    "classDef",
+    "class",
+    "style",
+    "linkStyle",
+    "click",
+    "direction",
+    "accTitle",
+    "accDescr",
+];
+
+/// Parse a Mermaid flowchart into laid-out shapes. Nodes come first, then
+/// edges, which reference their nodes by index.
+pub fn parse_mermaid(text: &str) -> Result<Vec<ImportedShape>> {
+    Ok(parse_flowchart(text)?.shapes())
+}
+
+/// Import a Mermaid flowchart into a document with connected edges
+pub fn import_mermaid(doc: &mut Document, text: &str) -> Result<Vec<ShapeId>> {
+    add_imported(doc, parse_mermaid(text)?)
+}
+
+/// Import a Mermaid file into a document
+pub fn load_mermaid(doc: &mut Document, path: &Path) -> Result<Vec<ShapeId>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read from {:?}", path))?;
+    import_mermaid(doc, &content)
+}
+
+/// Whether a path names a Mermaid file
+pub fn is_mermaid_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mmd") || ext.eq_ignore_ascii_case("mermaid"))
+}
+
+// ========== Parsing ==========
+
+fn parse_flowchart(text: &str) -> Result<Graph> {
+    let mut statements = text
+        .lines()
+        .flat_map(split_statements)
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && !s.starts_with("%%"));
+
+    let header = statements.next().unwrap_or("");
+    let mut words = header.split_whitespace();
+    if !matches!(words.next(), Some("flowchart" | "graph")) {
+        bail!(
+            "Not a Mermaid flowchart: expected 'flowchart' or 'graph', found {:?}",
+            header
+        );
+    }
+    let direction = words
+        .next()
+        .and_then(Direction::parse)
+        .unwrap_or(Direction::TopDown);
+
+    let mut chart = Graph::new(direction);
+    for statement in statements {
+        let keyword = statement.split_whitespace().next().unwrap_or("");
+        // Accessibility statements are written `accTitle: ...`
+        if IGNORED_STATEMENTS
+            .iter()
+            .any(|k| keyword.trim_end_matches(':') == *k)
+        {
+            continue;
+        }
+        parse_statement(&mut chart, statement);
+    }
+    Ok(chart)
+}
+
+/// Split a line on `;` outside quotes and node text
+fn split_statements(line: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quoted, mut start) = (0i32, false, 0);
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            '[' | '(' | '{' if !quoted => depth += 1,
+            ']' | ')' | '}' if !quoted => depth -= 1,
+            ';' if !quoted && depth <= 0 => {
+                parts.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&line[start..]);
+    parts
+}
+
+/// Parse `A[x] & B --> C -->|label| D` into nodes and edges
+fn parse_statement(chart: &mut Graph, statement: &str) {
+    let mut cursor = Cursor::new(statement);
+    let Some(mut previous) = parse_node_group(chart, &mut cursor) else {
+        return;
+    };
+
+    while let Some((arrow, label)) = parse_link(&mut cursor) {
+        let Some(group) = parse_node_group(chart, &mut cursor) else {
+            return;
+        };
+        for &from in &previous {
+            for &to in &group {
+                chart.edges.push(Edge {
+                    from,
+                    to,
+                    arrow,
+                    label: label.clone(),
+                });
+            }
+        }
+        previous = group;
+    }
+}
+
+fn parse_node_group(chart: &mut Graph, cursor: &mut Cursor<'_>) -> Option<Vec<usize>> {
+    let mut group = vec![parse_node(chart, cursor)?];
+    loop {
+        let mark = cursor.pos;
+        cursor.skip_whitespace();
+        if !cursor.eat("&") {
+            cursor.pos = mark;
+            return Some(group);
+        }
+        cursor.skip_whitespace();
+        group.push(parse_node(chart, cursor)?);
+    }
+}
+
+fn parse_node(chart: &mut Graph, cursor: &mut Cursor<'_>) -> Option<usize> {
+    cursor.skip_whitespace();
+    let id = cursor.take_while(|c| c.is_alphanumeric() || c == '_');
+    if id.is_empty() {
+        return None;
+    }
+    let definition = parse_node_text(cursor);
+
+    // Drop `:::className` shorthand
+    if cursor.eat(":::") {
+        cursor.take_while(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    }
+    Some(chart.node(id, definition))
+}
+
+/// Parse the delimited node text following an id, e.g. `{{text}}`
+fn parse_node_text(cursor: &mut Cursor<'_>) -> Option<(NodeShape, Option<String>)> {
+    let rest = cursor.rest();
+    let opener = NODE_DELIMITERS
+        .iter()
+        .map(|(open, _, _)| *open)
+        .find(|open| rest.starts_with(open))?;
+    let body = &rest[opener.len()..];
+
+    // Quoted text may contain closing delimiters
+    let text_end = if body.trim_start().starts_with('"') {
+        let quote = body.find('"')?;
+        quote + 1 + body[quote + 1..].find('"')? + 1
+    } else {
+        0
+    };
+
+    let (close_at, closer, shape) = NODE_DELIMITERS
+        .iter()
+        .filter(|(open, _, _)| *open == opener)
+        .filter_map(|(_, close, shape)| {
+            body[text_end..]
+                .find(close)
+                .map(|at| (text_end + at, *close, *shape))
+        })
+        .min_by_key(|(at, _, _)| *at)?;
+
+    cursor.pos += opener.len() + close_at + closer.len();
+    Some((shape, clean_label(&body[..close_at])))
+}
+
+/// Parse a link such as `-->`, `---`, `-.->`, `==>`, `-->|text|` or `-- text -->`
+fn parse_link(cursor: &mut Cursor<'_>) -> Option<(bool, Option<String>)> {
+    let mark = cursor.pos;
+    cursor.skip_whitespace();
+    cursor.eat("<");
+
+    let run = cursor.take_while(|c| matches!(c, '-' | '=' | '.' | '~'));
+    if run.len() < 2 {
+        cursor.pos = mark;
+        return None;
+    }
+    let mut arrow = link_head(cursor);
+    let mut label = None;
+
+    // `-- text -->`: a two-character run opens an inline label
+    if !arrow && run.len() == 2 {
+        let rest = cursor.rest();
+        let close = ["--", "==", "-."]
+            .iter()
+            .filter_map(|op| rest.find(op))
+            .min()?;
+        label = clean_label(&rest[..close]);
+        cursor.pos += close;
+        cursor.take_while(|c| matches!(c, '-' | '=' | '.'));
+        arrow = link_head(cursor);
+    }
+
+    cursor.skip_whitespace();
+    if cursor.eat("|") {
+        let text = cursor.take_while(|c| c != '|');
+        cursor.eat("|");
+        label = clean_label(text);
+    }
+    Some((arrow, label))
+}
+
+/// Consume an arrowhead (`>`, or circle/cross ends `o`/`x`)
+fn link_head(cursor: &mut Cursor<'_>) -> bool {
+    if cursor.eat(">") {
+        return true;
+    }
+    let rest = cursor.rest();
+    let is_end = |c: char| c == 'o' || c == 'x';
+    let mut chars = rest.chars();
+    if chars.next().is_some_and(is_end) && chars.next().is_none_or(char::is_whitespace) {
+        cursor.pos += 1;
+        return true;
+    }
+    false
+}
+
+/// Strip quotes and markdown backticks, and flatten line breaks
+fn clean_label(text: &str) -> Option<String> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .unwrap_or(text);
+    let text = text
+        .strip_prefix('`')
+        .and_then(|t| t.strip_suffix('`'))
+        .unwrap_or(text);
+    let text = text
+        .replace("<br/>", " ")
+        .replace("<br>", " ")
+        .replace("#quot;", "\"");
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let matched = self.rest().starts_with(token);
+        if matched {
+            self.pos += token.len();
+        }
+        matched
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c: char| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+}
+
+// ========== Export ==========
+
+/// Export shapes as a Mermaid flowchart. Every shape other than a line,
+/// arrow, text or freehand stroke becomes a node, labeled or not; lines and
+/// arrows connected to nodes at both ends become edges.
+pub fn export_mermaid(shapes: &ShapeView) -> String {
+    let graph = connection_graph(shapes);
+    // Mostly horizontal edges read better left to right
+    let direction = if graph.horizontal { "LR" } else { "TD" };
+    let mut out = format!("flowchart {}\n", direction);
+
+    for (i, kind) in graph.nodes.iter().enumerate() {
+        let (open, close) = node_delimiters(kind);
+        let text = kind.label().map_or_else(|| " ".to_string(), escape_label);
+        out.push_str(&format!("    n{}{}\"{}\"{}\n", i + 1, open, text, close));
+    }
+    for edge in &graph.edges {
+        let link = if edge.arrow { "-->" } else { "---" };
+        let (from, to) = (edge.from + 1, edge.to + 1);
+        match &edge.label {
+            Some(label) => out.push_str(&format!(
+                "    n{} {}|\"{}\"| n{}\n",
+                from,
+                link,
+                escape_label(label),
+                to
+            )),
+            None => out.push_str(&format!("    n{} {} n{}\n", from, link, to)),
+        }
+    }
+    out
+}
+
+/// Save shapes as a Mermaid flowchart file
+pub fn save_mermaid(shapes: &ShapeView, path: &Path) -> Result<()> {
+    fs::write(path, export_mermaid(shapes)).with_context(|| format!("Failed to save to {:?}", path))
+}
+
+fn node_delimiters(kind: &ShapeKind) -> (&'static str, &'static str) {
+    match kind {
+        ShapeKind::RoundedRect { .. } => ("(", ")"),
+        ShapeKind::DoubleBox { .. } => ("[[", "]]"),
+        ShapeKind::Cylinder { .. } => ("[(", ")]"),
+        ShapeKind::Ellipse { .. } => ("((", "))"),
+        ShapeKind::Diamond { .. } => ("{", "}"),
+        ShapeKind::Hexagon { .. } => ("{{", "}}"),
+        ShapeKind::Parallelogram { .. } => ("[/", "/]"),
+        ShapeKind::Trapezoid { .. } => ("[/", "\\]"),
+        _ => ("[", "]"),
+    }
+}
+
+fn escape_label(label: &str) -> String {
+    label.replace('"', "#quot;").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<ShapeKind> {
+        parse_mermaid(text)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.kind)
+            .collect()
+    }
+
+    #[test]
+    fn parse_mermaid_node_shapes() {
+        let text = "flowchart TD\n  a[Rect] --> b(Round) --> c{Choice}\n  d[(DB)]; e((Hub)); f{{Hex}}\n  g[[Sub]] & h([Pill]) & i[/In/] & j[/Top\\]";
+        let names: Vec<&str> = kinds(text).iter().map(|k| k.type_name()).collect();
+        assert_eq!(
+            &names[..10],
+            &[
+                "Rectangle",
+                "RoundedRect",
+                "Diamond",
+                "Cylinder",
+                "Ellipse",
+                "Hexagon",
+                "DoubleBox",
+                "RoundedRect",
+                "Parallelogram",
+                "Trapezoid"
+            ]
+        );
+        assert_eq!(kinds(text)[2].label(), Some("Choice"));
+    }
+
+    #[test]
+    fn parse_mermaid_edges_and_labels() {
+        let text = "graph LR\n  A -->|yes| B\n  A -- \"no\" --> C\n  B --- D\n  C -.-> D & E\n  %% comment\n  style A fill:#f9f";
+        let shapes = parse_mermaid(text).unwrap();
+        let edges: Vec<&ImportedShape> =
+            shapes.iter().filter(|s| s.start_target.is_some()).collect();
+        assert_eq!(edges.len(), 5);
+
+        assert!(matches!(&edges[0].kind, ShapeKind::Arrow { label: Some(l), .. } if l == "yes"));
+        assert!(matches!(&edges[1].kind, ShapeKind::Arrow { label: Some(l), .. } if l == "no"));
+        assert!(matches!(
+            &edges[2].kind,
+            ShapeKind::Line { label: None, .. }
+        ));
+        // Bare references show their id
+        assert_eq!(shapes[3].kind.label(), Some("D"));
+        assert_eq!(
+            (edges[4].start_target, edges[4].end_target),
+            (Some(2), Some(4))
+        );
+    }
+
+    #[test]
+    fn parse_mermaid_rejects_other_diagrams() {
+        assert!(parse_mermaid("sequenceDiagram\n  A->>B: hi").is_err());
+        assert!(parse_mermaid("").is_err());
+        // Keywords only match whole words
+        let shapes = kinds("graph TD\n  accTitle: Orders\n  account --> endpoint");
+        assert_eq!(shapes.len(), 3);
+    }
+
+    #[test]
+    fn layout_follows_direction_without_overlap() {
+        let text = "flowchart TD\n  A --> B & C\n  B --> D\n  C --> D\n  D --> A";
+        let shapes = kinds(text);
+        let bounds: Vec<(i32, i32, i32, i32)> = shapes[..4].iter().map(|k| k.bounds()).collect();
+        // Ranks go downward even with the cycle back to A
+        assert!(bounds[0].3 < bounds[1].1);
+        assert_eq!(bounds[1].1, bounds[2].1);
+        assert!(bounds[1].3 < bounds[3].1);
+        for (i, a) in bounds.iter().enumerate() {
+            for b in &bounds[i + 1..] {
+                let overlap = a.0 <= b.2 && b.0 <= a.2 && a.1 <= b.3 && b.1 <= a.3;
+                assert!(!overlap, "{:?} overlaps {:?}", a, b);
+            }
+        }
+
+        let lr = kinds("flowchart LR\n  A --> B");
+        assert!(lr[0].bounds().2 < lr[1].bounds().0);
+    }
+
+    #[test]
+    fn import_mermaid_connects_edges() {
+        let mut doc = Document::new();
+        let ids = import_mermaid(&mut doc, "flowchart TD\n  A[Start] --> B{Ok?}").unwrap();
+        assert_eq!(ids.len(), 3);
+
+        let arrow = doc.read_shape(ids[2]).unwrap().unwrap();
+        if let ShapeKind::Arrow {
+            start_connection,
+            end_connection,
+            ..
+        } = arrow
+        {
+            assert_eq!(start_connection, Some(ids[0].0.as_u128() as u64));
+            assert_eq!(end_connection, Some(ids[1].0.as_u128() as u64));
+        } else {
+            panic!("Expected Arrow");
+        }
+    }
+
+    #[test]
+    fn export_then_import_round_trip() {
+        let mut doc = Document::new();
+        import_mermaid(
+            &mut doc,
+            "flowchart TD\n  a[\"Client\"] -->|HTTP| b([API])\n  b --- c[(Store)]",
+        )
+        .unwrap();
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+
+        let exported = export_mermaid(&view);
+        assert!(exported.starts_with("flowchart TD\n"));
+        assert!(exported.contains("n1[\"Client\"]"));
+        assert!(exported.contains("n1 -->|\"HTTP\"| n2"));
+        assert!(exported.contains("n2 --- n3"));
+
+        let reimported = kinds(&exported);
+        let labels: Vec<Option<&str>> = reimported.iter().map(|k| k.label()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                Some("Client"),
+                Some("API"),
+                Some("Store"),
+                Some("HTTP"),
+                None
+            ]
+        );
+        assert_eq!(reimported[2].type_name(), "Cylinder");
+    }
+}
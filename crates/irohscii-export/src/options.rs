@@ -0,0 +1,517 @@
+//! Export options shared by every format: which shapes to include and which
+//! part of the canvas to frame.
+
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow};
+use irohscii_core::{Layer, LayerId, Position, ShapeId, ShapeView};
+
+use crate::ascii::{Grid, build_shape_grid, calculate_grid_bounds, render_grid_to_string};
+use crate::colored::{is_ansi_path, is_html_path, render_grid_to_ansi, render_grid_to_html};
+use crate::dot::{export_dot, is_dot_path};
+use crate::drawio::{export_drawio, is_drawio_path};
+use crate::excalidraw::{export_excalidraw, is_excalidraw_path};
+use crate::mermaid::{export_mermaid, is_mermaid_path};
+use crate::png::{PngOptions, is_png_path, render_grid_to_png};
+use crate::svg::{calculate_bounds, export_svg_framed, is_svg_path};
+
+/// Inclusive cell bounds: min x, min y, max x, max y
+pub(crate) type Bounds = (i32, i32, i32, i32);
+
+/// Which shapes an export includes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ExportScope {
+    /// Every shape in the drawing
+    #[default]
+    All,
+    /// Only these shapes, typically the current selection
+    Shapes(Vec<ShapeId>),
+    /// Only shapes on these layers
+    Layers(Vec<LayerId>),
+}
+
+impl ExportScope {
+    /// Scope of the layers with the given names, matched case-insensitively
+    pub fn layers_named(layers: &[Layer], names: &[String]) -> Result<Self> {
+        let ids = names
+            .iter()
+            .map(|name| {
+                layers
+                    .iter()
+                    .find(|layer| layer.name.eq_ignore_ascii_case(name))
+                    .map(|layer| layer.id)
+                    .ok_or_else(|| anyhow!("No layer named {:?}", name))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self::Layers(ids))
+    }
+}
+
+/// A rectangle of canvas cells, both corners inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub min: Position,
+    pub max: Position,
+}
+
+impl Region {
+    /// Region spanning two opposite corners, in any order
+    pub fn new(a: Position, b: Position) -> Self {
+        Self {
+            min: Position::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Position::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        (self.min.x..=self.max.x).contains(&pos.x) && (self.min.y..=self.max.y).contains(&pos.y)
+    }
+
+    fn bounds(&self) -> Bounds {
+        (self.min.x, self.min.y, self.max.x, self.max.y)
+    }
+
+    fn intersect(&self, (min_x, min_y, max_x, max_y): Bounds) -> Option<Bounds> {
+        let clipped = (
+            min_x.max(self.min.x),
+            min_y.max(self.min.y),
+            max_x.min(self.max.x),
+            max_y.min(self.max.y),
+        );
+        (clipped.0 <= clipped.2 && clipped.1 <= clipped.3).then_some(clipped)
+    }
+}
+
+impl FromStr for Region {
+    type Err = anyhow::Error;
+
+    /// Parse `X1,Y1,X2,Y2`
+    fn from_str(s: &str) -> Result<Self> {
+        let values = s
+            .split(',')
+            .map(|part| part.trim().parse::<i32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow!("Region must be X1,Y1,X2,Y2, got {:?}", s))?;
+        let [x1, y1, x2, y2] = values[..] else {
+            return Err(anyhow!("Region must be X1,Y1,X2,Y2, got {:?}", s));
+        };
+        Ok(Self::new(Position::new(x1, y1), Position::new(x2, y2)))
+    }
+}
+
+/// What to export and how to frame it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub scope: ExportScope,
+    /// Canvas area to export, such as the current viewport. Shapes outside it
+    /// are left out; text, images and SVG are cropped to it.
+    pub region: Option<Region>,
+    /// Include shapes on hidden layers
+    pub include_hidden: bool,
+    /// Blank cells around the exported area on each side
+    pub padding: u32,
+    /// Crop to the drawn content and drop trailing whitespace from text.
+    /// Without it, the frame keeps the whole region, or reaches back to the
+    /// canvas origin so positions are preserved.
+    pub trim: bool,
+    /// PNG pixels per font pixel
+    pub png_scale: u32,
+    /// PNG background color as RGB, or `None` for a transparent background
+    pub png_background: Option<[u8; 3]>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            scope: ExportScope::All,
+            region: None,
+            include_hidden: false,
+            padding: 0,
+            trim: true,
+            png_scale: PngOptions::default().scale,
+            png_background: PngOptions::default().background,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// The shapes these options export, in render order
+    pub fn select(&self, shapes: &ShapeView) -> ShapeView {
+        let mut selected = shapes.clone();
+        selected.retain(|shape| {
+            let visible = self.include_hidden
+                || shape
+                    .layer_id
+                    .is_none_or(|layer| shapes.is_layer_visible(layer));
+            let in_scope = match &self.scope {
+                ExportScope::All => true,
+                ExportScope::Shapes(ids) => ids.contains(&shape.id),
+                ExportScope::Layers(layers) => shape.layer_id.is_some_and(|l| layers.contains(&l)),
+            };
+            let in_region = self
+                .region
+                .is_none_or(|r| r.intersect(shape.bounds()).is_some());
+            visible && in_scope && in_region
+        });
+        selected
+    }
+
+    /// Cells to frame around content with the given bounds, if any
+    pub(crate) fn frame(&self, content: Option<Bounds>) -> Option<Bounds> {
+        let (min_x, min_y, max_x, max_y) = match (self.region, self.trim) {
+            (Some(region), false) => region.bounds(),
+            (Some(region), true) => region.intersect(content?)?,
+            (None, true) => content?,
+            (None, false) => {
+                let (min_x, min_y, max_x, max_y) = content?;
+                (min_x.min(0), min_y.min(0), max_x, max_y)
+            }
+        };
+        let pad = self.padding as i32;
+        Some((min_x - pad, min_y - pad, max_x + pad, max_y + pad))
+    }
+}
+
+/// Render shapes to plain text with export options applied
+pub fn render_text_with_options(shapes: &ShapeView, options: &ExportOptions) -> String {
+    let (grid, frame) = framed_grid(&options.select(shapes), options);
+    frame.map_or_else(String::new, |frame| {
+        render_grid_to_string(&grid, frame, options.trim)
+    })
+}
+
+/// Character grid of the selected shapes cropped to the region, with the
+/// frame to render
+fn framed_grid(selected: &ShapeView, options: &ExportOptions) -> (Grid, Option<Bounds>) {
+    let mut grid = build_shape_grid(selected);
+    if let Some(region) = options.region {
+        grid.cells.retain(|pos, _| region.contains(*pos));
+    }
+    let frame = options.frame(calculate_grid_bounds(&grid));
+    (grid, frame)
+}
+
+/// File formats the exporters write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Text,
+    Ansi,
+    Html,
+    Png,
+    Svg,
+    Mermaid,
+    Dot,
+    Excalidraw,
+    DrawIo,
+}
+
+impl ExportFormat {
+    /// Format for a path's extension, plain text for anything unrecognized
+    pub fn from_path(path: &Path) -> Self {
+        if is_mermaid_path(path) {
+            Self::Mermaid
+        } else if is_dot_path(path) {
+            Self::Dot
+        } else if is_excalidraw_path(path) {
+            Self::Excalidraw
+        } else if is_drawio_path(path) {
+            Self::DrawIo
+        } else if is_svg_path(path) {
+            Self::Svg
+        } else if is_png_path(path) {
+            Self::Png
+        } else if is_ansi_path(path) {
+            Self::Ansi
+        } else if is_html_path(path) {
+            Self::Html
+        } else {
+            Self::Text
+        }
+    }
+
+    /// Whether the output is binary rather than text
+    pub fn is_binary(self) -> bool {
+        self == Self::Png
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    /// Parse a format name or its usual file extension
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "ascii" | "text" | "txt" => Ok(Self::Text),
+            "ansi" | "ans" => Ok(Self::Ansi),
+            "html" | "htm" => Ok(Self::Html),
+            "png" => Ok(Self::Png),
+            "svg" => Ok(Self::Svg),
+            "mermaid" | "mmd" => Ok(Self::Mermaid),
+            "dot" | "gv" | "graphviz" => Ok(Self::Dot),
+            "excalidraw" => Ok(Self::Excalidraw),
+            "drawio" => Ok(Self::DrawIo),
+            _ => Err(anyhow!("Unknown export format {:?}", s)),
+        }
+    }
+}
+
+/// Export shapes in a format with export options applied, returning the
+/// file contents. `layers` names the layers for formats that keep them.
+pub fn export_with_options(
+    shapes: &ShapeView,
+    layers: &[Layer],
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> Result<Vec<u8>> {
+    let selected = options.select(shapes);
+
+    // Graph and editor formats have no canvas frame, only the selected shapes
+    let content = match format {
+        ExportFormat::Mermaid => export_mermaid(&selected),
+        ExportFormat::Dot => export_dot(&selected),
+        ExportFormat::Excalidraw => export_excalidraw(&selected),
+        ExportFormat::DrawIo => export_drawio(&selected, layers),
+        ExportFormat::Svg => {
+            let content = (!selected.is_empty()).then(|| calculate_bounds(&selected));
+            let frame = options.frame(content).unwrap_or((0, 0, 10, 10));
+            export_svg_framed(&selected, frame)
+        }
+        ExportFormat::Png | ExportFormat::Ansi | ExportFormat::Html | ExportFormat::Text => {
+            return export_grid(&selected, format, options);
+        }
+    };
+    Ok(content.into_bytes())
+}
+
+/// Export the character-grid formats: PNG, ANSI, HTML and plain text
+fn export_grid(
+    selected: &ShapeView,
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> Result<Vec<u8>> {
+    let (grid, frame) = framed_grid(selected, options);
+    let content = match format {
+        ExportFormat::Png => {
+            // The frame already holds the padding
+            let png = PngOptions {
+                scale: options.png_scale,
+                background: options.png_background,
+                padding: 0,
+            };
+            let image = render_grid_to_png(&grid, frame.unwrap_or((0, 0, -1, -1)), &png)?;
+            let mut bytes = Vec::new();
+            image
+                .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+                .context("Failed to encode PNG")?;
+            return Ok(bytes);
+        }
+        ExportFormat::Ansi => {
+            let mut ansi = render_grid_to_ansi(&grid, frame, options.trim);
+            if !ansi.is_empty() {
+                ansi.push('\n');
+            }
+            ansi
+        }
+        ExportFormat::Html => render_grid_to_html(selected, &grid, frame, options.trim, true),
+        _ => frame.map_or_else(String::new, |frame| {
+            render_grid_to_string(&grid, frame, options.trim)
+        }),
+    };
+    Ok(content.into_bytes())
+}
+
+/// Export shapes to a file with export options applied, picking the format
+/// from the extension (plain text for anything unrecognized)
+pub fn save_with_options(
+    shapes: &ShapeView,
+    layers: &[Layer],
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<()> {
+    let content = export_with_options(shapes, layers, ExportFormat::from_path(path), options)?;
+    fs::write(path, content).with_context(|| format!("Failed to save to {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use irohscii_core::{Document, ShapeColor, ShapeKind};
+    use tempfile::tempdir;
+
+    fn text(x: i32, y: i32, content: &str) -> ShapeKind {
+        ShapeKind::Text {
+            pos: Position::new(x, y),
+            content: content.to_string(),
+            color: ShapeColor::default(),
+        }
+    }
+
+    /// Two texts on the default layer and one on a hidden "Notes" layer
+    fn sample() -> (Document, Vec<ShapeId>, LayerId) {
+        let mut doc = Document::new();
+        let ids = vec![
+            doc.add_shape(text(2, 1, "one")).unwrap(),
+            doc.add_shape(text(10, 3, "two")).unwrap(),
+            doc.add_shape(text(2, 5, "secret")).unwrap(),
+        ];
+        let notes = doc.create_layer("Notes").unwrap();
+        doc.set_shape_layer(ids[2], notes).unwrap();
+        doc.set_layer_visible(notes, false).unwrap();
+        (doc, ids, notes)
+    }
+
+    fn view(doc: &Document) -> ShapeView {
+        let mut view = ShapeView::new();
+        view.rebuild(doc).unwrap();
+        view
+    }
+
+    #[test]
+    fn region_parses_corners_in_any_order() {
+        let region: Region = "10, 5,0,-2".parse().unwrap();
+        assert_eq!(
+            region,
+            Region::new(Position::new(0, -2), Position::new(10, 5))
+        );
+        assert!(region.contains(Position::new(0, 5)));
+        assert!(!region.contains(Position::new(11, 0)));
+        assert!("1,2,3".parse::<Region>().is_err());
+        assert!("a,b,c,d".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn select_skips_hidden_layers_unless_asked() {
+        let (doc, ids, notes) = sample();
+        let shapes = view(&doc);
+        let default = ExportOptions::default();
+        assert_eq!(
+            render_text_with_options(&shapes, &default),
+            "one\n\n        two"
+        );
+
+        let hidden = ExportOptions {
+            include_hidden: true,
+            ..ExportOptions::default()
+        };
+        assert_eq!(hidden.select(&shapes).len(), 3);
+
+        let layers = doc.read_all_layers().unwrap();
+        let scope = ExportScope::layers_named(&layers, &["notes".to_string()]).unwrap();
+        assert_eq!(scope, ExportScope::Layers(vec![notes]));
+        assert!(ExportScope::layers_named(&layers, &["Nope".to_string()]).is_err());
+        let layer = ExportOptions {
+            scope,
+            include_hidden: true,
+            ..ExportOptions::default()
+        };
+        assert_eq!(render_text_with_options(&shapes, &layer), "secret");
+
+        let selection = ExportOptions {
+            scope: ExportScope::Shapes(vec![ids[1]]),
+            ..ExportOptions::default()
+        };
+        assert_eq!(render_text_with_options(&shapes, &selection), "two");
+    }
+
+    #[test]
+    fn region_crops_and_padding_frames() {
+        let (doc, _, _) = sample();
+        let shapes = view(&doc);
+
+        // Cells outside the region are cut, even from shapes crossing it
+        let region = ExportOptions {
+            region: Some("0,0,11,3".parse().unwrap()),
+            ..ExportOptions::default()
+        };
+        assert_eq!(
+            render_text_with_options(&shapes, &region),
+            "one\n\n        tw"
+        );
+
+        let padded = ExportOptions {
+            scope: ExportScope::Shapes(vec![doc.read_shape_order().unwrap()[0]]),
+            padding: 1,
+            trim: false,
+            region: Some("2,1,6,1".parse().unwrap()),
+            ..ExportOptions::default()
+        };
+        assert_eq!(
+            render_text_with_options(&shapes, &padded),
+            "       \n one   \n       "
+        );
+
+        // Without trimming the frame reaches back to the origin
+        let untrimmed = ExportOptions {
+            trim: false,
+            ..ExportOptions::default()
+        };
+        let lines: Vec<String> = render_text_with_options(&shapes, &untrimmed)
+            .lines()
+            .map(String::from)
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "  one        ");
+    }
+
+    #[test]
+    fn save_with_options_picks_format() {
+        let (doc, ids, _) = sample();
+        let shapes = view(&doc);
+        let layers = doc.read_all_layers().unwrap();
+        let dir = tempdir().unwrap();
+        let options = ExportOptions {
+            scope: ExportScope::Shapes(vec![ids[0]]),
+            padding: 1,
+            ..ExportOptions::default()
+        };
+
+        let txt = dir.path().join("out.txt");
+        save_with_options(&shapes, &layers, &txt, &options).unwrap();
+        assert_eq!(fs::read_to_string(&txt).unwrap(), "\n one");
+
+        let svg = dir.path().join("out.svg");
+        save_with_options(&shapes, &layers, &svg, &options).unwrap();
+        let svg = fs::read_to_string(&svg).unwrap();
+        assert!(svg.contains("viewBox=\"0 0 50 48\""));
+        assert!(svg.contains(">one<") && !svg.contains("two"));
+
+        let png = dir.path().join("out.png");
+        save_with_options(&shapes, &layers, &png, &options).unwrap();
+        let image = image::open(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (5 * 6 * 2, 3 * 12 * 2));
+
+        let plain = ExportOptions {
+            png_scale: 1,
+            png_background: None,
+            ..options.clone()
+        };
+        save_with_options(&shapes, &layers, &png, &plain).unwrap();
+        let image = image::open(&png).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (5 * 6, 3 * 12));
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+
+        let bytes =
+            export_with_options(&shapes, &layers, "ascii".parse().unwrap(), &options).unwrap();
+        assert_eq!(bytes, b"\n one");
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.GV")),
+            ExportFormat::Dot
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.md")),
+            ExportFormat::Text
+        );
+        assert!("jpeg".parse::<ExportFormat>().is_err());
+
+        let mermaid = dir.path().join("out.mmd");
+        save_with_options(&shapes, &layers, &mermaid, &options).unwrap();
+        assert!(
+            fs::read_to_string(&mermaid)
+                .unwrap()
+                .starts_with("flowchart")
+        );
+    }
+}
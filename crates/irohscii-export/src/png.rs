@@ -0,0 +1,277 @@
+//! PNG export: rasterizes the ASCII character grid with the bundled bitmap
+//! font, keeping each shape's color.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use image::{ImageFormat, Rgba, RgbaImage};
+use irohscii_core::{ShapeColor, ShapeView};
+
+use crate::ascii::{Grid, build_shape_grid, calculate_grid_bounds};
+use crate::excalidraw::parse_hex;
+use crate::font::{CELL_HEIGHT, CELL_WIDTH, glyph};
+use crate::options::Bounds;
+
+/// Largest accepted scale factor, keeping huge diagrams within memory
+const MAX_SCALE: u32 = 16;
+
+/// Largest image rendered, in pixels (256 MiB of RGBA)
+const MAX_PIXELS: u64 = 1 << 26;
+
+/// Options for rendering a PNG
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PngOptions {
+    /// Pixels per font pixel (1 gives 6x12 pixel character cells)
+    pub scale: u32,
+    /// Background color as RGB, or `None` for a transparent background
+    pub background: Option<[u8; 3]>,
+    /// Blank cells around the drawing on each side
+    pub padding: u32,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            scale: 2,
+            background: Some([0, 0, 0]),
+            padding: 1,
+        }
+    }
+}
+
+/// Parse a PNG background: `none` or `transparent`, or an RGB hex color such
+/// as `#1e1e1e` (the `#` is optional)
+pub fn parse_png_background(s: &str) -> Result<Option<[u8; 3]>> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("none") || s.eq_ignore_ascii_case("transparent") {
+        return Ok(None);
+    }
+    match parse_hex(&format!("#{}", s.trim_start_matches('#'))) {
+        Some((r, g, b)) => Ok(Some([r, g, b])),
+        None => bail!("Background must be a hex color or none, got {:?}", s),
+    }
+}
+
+/// Render shapes to an RGBA image. An empty drawing gives an image of just
+/// the padding. Fails if the image would exceed [`MAX_PIXELS`].
+pub fn render_png(shapes: &ShapeView, options: &PngOptions) -> Result<RgbaImage> {
+    let grid = build_shape_grid(shapes);
+    let pad = i32::try_from(options.padding).unwrap_or(i32::MAX);
+    let (min_x, min_y, max_x, max_y) = calculate_grid_bounds(&grid).unwrap_or((0, 0, -1, -1));
+    render_grid_to_png(
+        &grid,
+        (
+            min_x.saturating_sub(pad),
+            min_y.saturating_sub(pad),
+            max_x.saturating_add(pad),
+            max_y.saturating_add(pad),
+        ),
+        options,
+    )
+}
+
+/// Rasterize the cells of a grid within `bounds`. The options' padding is
+/// not applied again; `bounds` already frames the image.
+pub(crate) fn render_grid_to_png(grid: &Grid, bounds: Bounds, options: &PngOptions) -> Result<RgbaImage> {
+    let scale = options.scale.clamp(1, MAX_SCALE);
+    let (min_x, min_y, max_x, max_y) = bounds;
+    let pixels = |min: i32, max: i32, cell: u32| {
+        let cells = (i64::from(max) - i64::from(min) + 1).max(0);
+        u32::try_from(cells)
+            .ok()
+            .and_then(|cells| cells.checked_mul(cell)?.checked_mul(scale))
+    };
+    let (Some(width), Some(height)) = (
+        pixels(min_x, max_x, CELL_WIDTH),
+        pixels(min_y, max_y, CELL_HEIGHT),
+    ) else {
+        bail!("PNG image too large");
+    };
+    if u64::from(width) * u64::from(height) > MAX_PIXELS {
+        bail!(
+            "PNG image too large: {}x{} pixels (at most {} allowed)",
+            width,
+            height,
+            MAX_PIXELS
+        );
+    }
+    let background = match options.background {
+        Some([r, g, b]) => Rgba([r, g, b, 255]),
+        None => Rgba([0, 0, 0, 0]),
+    };
+    let mut image = RgbaImage::from_pixel(width, height, background);
+
+    for (pos, cell) in &grid.cells {
+        if pos.x < min_x || pos.x > max_x || pos.y < min_y || pos.y > max_y {
+            continue;
+        }
+        let left = (pos.x - min_x) as u32 * CELL_WIDTH * scale;
+        let top = (pos.y - min_y) as u32 * CELL_HEIGHT * scale;
+        let [r, g, b] = rgb(cell.color);
+        let color = Rgba([r, g, b, 255]);
+        for (y, bits) in glyph(cell.ch).iter().enumerate() {
+            for x in 0..CELL_WIDTH {
+                if bits & (1 << x) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        image.put_pixel(left + x * scale + dx, top + y as u32 * scale + dy, color);
+                    }
+                }
+            }
+        }
+    }
+    Ok(image)
+}
+
+/// Save shapes as a PNG image
+pub fn save_png(shapes: &ShapeView, path: &Path, options: &PngOptions) -> Result<()> {
+    render_png(shapes, options)?
+        .save_with_format(path, ImageFormat::Png)
+        .with_context(|| format!("Failed to save to {:?}", path))
+}
+
+/// Whether a path names a PNG file
+pub fn is_png_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+}
+
+/// RGB value of a color, taken from the CSS palette the SVG and HTML
+/// exports use
+fn rgb(color: ShapeColor) -> [u8; 3] {
+    match color.to_css() {
+        "white" => [0xff, 0xff, 0xff],
+        "black" => [0x00, 0x00, 0x00],
+        css => parse_hex(css).map_or([0xff, 0xff, 0xff], |(r, g, b)| [r, g, b]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use irohscii_core::{Document, Position, ShapeKind};
+    use tempfile::tempdir;
+
+    fn view(kinds: Vec<ShapeKind>) -> ShapeView {
+        let mut doc = Document::new();
+        for kind in kinds {
+            doc.add_shape(kind).unwrap();
+        }
+        let mut view = ShapeView::new();
+        view.rebuild(&doc).unwrap();
+        view
+    }
+
+    fn red_box() -> ShapeView {
+        view(vec![ShapeKind::Rectangle {
+            start: Position::new(0, 0),
+            end: Position::new(4, 2),
+            label: Some("A".to_string()),
+            color: ShapeColor::Red,
+        }])
+    }
+
+    #[test]
+    fn render_png_sizes_and_colors_cells() {
+        let options = PngOptions {
+            scale: 1,
+            background: Some([0, 0, 0]),
+            padding: 0,
+        };
+        let image = render_png(&red_box(), &options).unwrap();
+        assert_eq!(image.dimensions(), (5 * CELL_WIDTH, 3 * CELL_HEIGHT));
+        // The top border runs through the first cell row in the shape's color
+        let red = Rgba([0xcd, 0, 0, 255]);
+        let border_row = (0..image.width())
+            .filter(|&x| *image.get_pixel(x, 5) == red)
+            .count();
+        // Corners reach from the cell center, the three `─` cells span fully
+        assert_eq!(border_row as u32, 4 + 3 * CELL_WIDTH + 3);
+        assert!(
+            image
+                .pixels()
+                .all(|p| *p == red || *p == Rgba([0, 0, 0, 255]))
+        );
+
+        // Scaling multiplies every dimension, padding adds whole cells
+        let scaled = render_png(
+            &red_box(),
+            &PngOptions {
+                scale: 3,
+                padding: 1,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            scaled.dimensions(),
+            (7 * CELL_WIDTH * 3, 5 * CELL_HEIGHT * 3)
+        );
+    }
+
+    #[test]
+    fn render_png_transparent_background() {
+        let options = PngOptions {
+            background: None,
+            ..PngOptions::default()
+        };
+        let image = render_png(&red_box(), &options).unwrap();
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+        assert!(image.pixels().any(|p| p[3] == 255));
+
+        let empty = render_png(&view(vec![]), &options).unwrap();
+        assert_eq!(
+            empty.dimensions(),
+            (2 * CELL_WIDTH * 2, 2 * CELL_HEIGHT * 2)
+        );
+    }
+
+    #[test]
+    fn render_png_rejects_oversized_images() {
+        let grid = Grid::default();
+        let options = PngOptions::default();
+        // Far past the pixel cap, and past u32 once scaled
+        assert!(render_grid_to_png(&grid, (0, 0, 100_000, 100_000), &options).is_err());
+        assert!(render_grid_to_png(&grid, (i32::MIN, 0, i32::MAX, 0), &options).is_err());
+        let huge_padding = PngOptions {
+            padding: u32::MAX,
+            ..options
+        };
+        assert!(render_png(&red_box(), &huge_padding).is_err());
+        assert!(render_grid_to_png(&grid, (0, 0, 99, 49), &options).is_ok());
+    }
+
+    #[test]
+    fn parse_png_background_reads_hex_and_none() {
+        assert_eq!(parse_png_background("#1e1e1e").unwrap(), Some([0x1e, 0x1e, 0x1e]));
+        assert_eq!(parse_png_background("fff").unwrap(), Some([0xff, 0xff, 0xff]));
+        assert_eq!(parse_png_background("None").unwrap(), None);
+        assert_eq!(parse_png_background("transparent").unwrap(), None);
+        assert!(parse_png_background("blue").is_err());
+        assert!(parse_png_background("#12345").is_err());
+    }
+
+    #[test]
+    fn rgb_follows_the_css_palette() {
+        assert_eq!(rgb(ShapeColor::Red), [0xcd, 0x00, 0x00]);
+        assert_eq!(rgb(ShapeColor::White), [0xff, 0xff, 0xff]);
+        assert_eq!(rgb(ShapeColor::Black), [0x00, 0x00, 0x00]);
+        assert_eq!(rgb(ShapeColor::DarkGray), [0x55, 0x55, 0x55]);
+    }
+
+    #[test]
+    fn save_png_round_trips_through_decoder() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("diagram.png");
+        assert!(is_png_path(&path));
+        assert!(!is_png_path(Path::new("diagram.txt")));
+
+        let options = PngOptions::default();
+        save_png(&red_box(), &path, &options).unwrap();
+        let decoded = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(decoded, render_png(&red_box(), &options).unwrap());
+    }
+}
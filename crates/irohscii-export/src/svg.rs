@@ -11,6 +11,7 @@ use std::path::Path;
 use anyhow::Result;
 
 use irohscii_core::{CachedShape, LineStyle, Position, ShapeKind, ShapeView};
+use irohscii_geometry::{callout_tail_anchor, CalloutSide};
 
 /// Character dimensions in SVG units
 const CHAR_WIDTH: i32 = 10;
@@ -44,21 +45,28 @@ fn to_svg_coords(pos: Position) -> (i32, i32) {
 
 /// Export shapes to SVG string
 pub fn export_svg(shapes: &ShapeView) -> String {
-    let mut output = String::new();
-
-    // Calculate bounding box
+    // Calculate bounding box, with a one-cell margin above and to the left
     let (min_x, min_y, max_x, max_y) = calculate_bounds(shapes);
     debug_assert!(max_x >= min_x, "max_x must be >= min_x");
     debug_assert!(max_y >= min_y, "max_y must be >= min_y");
-    
-    let width = (max_x - min_x + 2) * CHAR_WIDTH;
-    let height = (max_y - min_y + 2) * CHAR_HEIGHT;
+
+    export_svg_framed(shapes, (min_x - 1, min_y - 1, max_x, max_y))
+}
+
+/// Export shapes to an SVG string whose canvas covers exactly the cells in
+/// `frame`; anything outside is clipped by the viewBox
+pub(crate) fn export_svg_framed(shapes: &ShapeView, frame: (i32, i32, i32, i32)) -> String {
+    let mut output = String::new();
+    let (min_x, min_y, max_x, max_y) = frame;
+
+    let width = (max_x - min_x + 1) * CHAR_WIDTH;
+    let height = (max_y - min_y + 1) * CHAR_HEIGHT;
     debug_assert!(width > 0, "SVG width must be positive");
     debug_assert!(height > 0, "SVG height must be positive");
 
-    // Offset to translate shapes to start at (CHAR_WIDTH, CHAR_HEIGHT)
-    let offset_x = -min_x + 1;
-    let offset_y = -min_y + 1;
+    // Offset to translate the frame's corner to the SVG origin
+    let offset_x = -min_x;
+    let offset_y = -min_y;
 
     // SVG header
     writeln!(
@@ -102,8 +110,15 @@ pub fn save_svg(shapes: &ShapeView, path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Whether a path names an SVG file
+pub fn is_svg_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+}
+
 /// Calculate the bounding box of all shapes
-fn calculate_bounds(shapes: &ShapeView) -> (i32, i32, i32, i32) {
+pub(crate) fn calculate_bounds(shapes: &ShapeView) -> (i32, i32, i32, i32) {
     if shapes.is_empty() {
         return (0, 0, 10, 10);
     }
@@ -191,6 +206,14 @@ fn dispatch_shape_renderer(ctx: &mut RenderContext<'_>, kind: &ShapeKind) {
         } => {
             render_triangle(ctx, *p1, *p2, *p3, label.as_deref());
         }
+        ShapeKind::Polygon {
+            points,
+            closed,
+            label,
+            ..
+        } => {
+            render_polygon(ctx, points, *closed, label.as_deref());
+        }
         ShapeKind::Parallelogram {
             start, end, label, ..
         } => {
@@ -225,6 +248,15 @@ fn dispatch_shape_renderer(ctx: &mut RenderContext<'_>, kind: &ShapeKind) {
         } => {
             render_cloud(ctx, *start, *end, label.as_deref());
         }
+        ShapeKind::Callout {
+            start,
+            end,
+            tail,
+            label,
+            ..
+        } => {
+            render_callout(ctx, *start, *end, *tail, label.as_deref());
+        }
         ShapeKind::Star {
             center,
             outer_radius,
@@ -389,19 +421,36 @@ fn render_ellipse(ctx: &mut RenderContext<'_>, center: Position, radius_x: i32,
 fn render_freehand(ctx: &mut RenderContext<'_>, points: &[Position]) {
     debug_assert!(points.len() <= 100000, "Freehand should have reasonable number of points");
     
-    if points.is_empty() {
+    let [first, rest @ ..] = points else {
         return;
-    }
+    };
 
-    for point in points {
-        let (x, y) = ctx.to_svg(*point);
+    // A single sample is a dot
+    if rest.is_empty() {
+        let (x, y) = ctx.to_svg(*first);
         writeln!(
             ctx.output,
             r#"  <circle cx="{}" cy="{}" r="3" fill="{}"/>"#,
             x, y, ctx.color
         )
         .expect("write to String is infallible");
+        return;
     }
+
+    let point_list = points
+        .iter()
+        .map(|p| {
+            let (x, y) = ctx.to_svg(*p);
+            format!("{},{}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    writeln!(
+        ctx.output,
+        r#"  <polyline points="{}" stroke="{}" stroke-width="1" fill="none" stroke-linejoin="round"/>"#,
+        point_list, ctx.color
+    )
+    .expect("write to String is infallible");
 }
 
 /// Render text
@@ -456,6 +505,50 @@ fn render_triangle(ctx: &mut RenderContext<'_>, p1: Position, p2: Position, p3:
     }
 }
 
+/// Render a polygon (closed) or polyline (open) through its vertices
+fn render_polygon(ctx: &mut RenderContext<'_>, points: &[Position], closed: bool, label: Option<&str>) {
+    debug_assert!(points.len() <= 100000, "Polygon should have reasonable number of points");
+    
+    if points.is_empty() {
+        return;
+    }
+
+    let coords: Vec<(i32, i32)> = points.iter().map(|p| ctx.to_svg(*p)).collect();
+    let point_list = coords
+        .iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if closed {
+        writeln!(
+            ctx.output,
+            r#"  <polygon points="{}" stroke="{}" stroke-width="1" fill="white"/>"#,
+            point_list, ctx.color
+        )
+        .expect("write to String is infallible");
+    } else {
+        writeln!(
+            ctx.output,
+            r#"  <polyline points="{}" stroke="{}" stroke-width="1" fill="none"/>"#,
+            point_list, ctx.color
+        )
+        .expect("write to String is infallible");
+    }
+
+    if let Some(text) = label {
+        let count = i32::try_from(coords.len()).unwrap_or(i32::MAX);
+        let cx = coords.iter().map(|(x, _)| x).sum::<i32>() / count;
+        let cy = coords.iter().map(|(_, y)| y).sum::<i32>() / count;
+        writeln!(
+            ctx.output,
+            r#"  <text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-family="monospace" font-size="12" fill="{}">{}</text>"#,
+            cx, cy, ctx.color, escape_xml(text)
+        )
+        .expect("write to String is infallible");
+    }
+}
+
 /// Render a parallelogram
 fn render_parallelogram(ctx: &mut RenderContext<'_>, start: Position, end: Position, label: Option<&str>) {
     let start = Position::new(start.x + ctx.offset_x, start.y + ctx.offset_y);
@@ -714,6 +807,59 @@ fn render_cloud(ctx: &mut RenderContext<'_>, start: Position, end: Position, lab
     }
 }
 
+/// Render a callout as a single path: the body outline with the tail spliced into one edge
+fn render_callout(ctx: &mut RenderContext<'_>, start: Position, end: Position, tail: Position, label: Option<&str>) {
+    let min = Position::new(start.x.min(end.x), start.y.min(end.y));
+    let max = Position::new(start.x.max(end.x), start.y.max(end.y));
+    let anchor = callout_tail_anchor(min, max, tail);
+
+    let (x1, y1) = ctx.to_svg(min);
+    let (x2, y2) = ctx.to_svg(max);
+    let (tx, ty) = ctx.to_svg(tail);
+    debug_assert!(x2 >= x1, "Callout width must be non-negative");
+    debug_assert!(y2 >= y1, "Callout height must be non-negative");
+
+    // Tail base is one character wide on horizontal edges, one line tall on vertical edges
+    let half_w = CHAR_WIDTH / 2;
+    let half_h = CHAR_HEIGHT / 2;
+    let tail_on = |side: CalloutSide| anchor.filter(|(_, s)| *s == side).map(|(pos, _)| ctx.to_svg(pos));
+
+    // Trace clockwise from the top-left corner
+    let mut path = format!("M {} {}", x1, y1);
+    if let Some((ax, _)) = tail_on(CalloutSide::Top) {
+        path.push_str(&format!(" L {} {} L {} {} L {} {}", ax - half_w, y1, tx, ty, ax + half_w, y1));
+    }
+    path.push_str(&format!(" L {} {}", x2, y1));
+    if let Some((_, ay)) = tail_on(CalloutSide::Right) {
+        path.push_str(&format!(" L {} {} L {} {} L {} {}", x2, ay - half_h, tx, ty, x2, ay + half_h));
+    }
+    path.push_str(&format!(" L {} {}", x2, y2));
+    if let Some((ax, _)) = tail_on(CalloutSide::Bottom) {
+        path.push_str(&format!(" L {} {} L {} {} L {} {}", ax + half_w, y2, tx, ty, ax - half_w, y2));
+    }
+    path.push_str(&format!(" L {} {}", x1, y2));
+    if let Some((_, ay)) = tail_on(CalloutSide::Left) {
+        path.push_str(&format!(" L {} {} L {} {} L {} {}", x1, ay + half_h, tx, ty, x1, ay - half_h));
+    }
+    path.push_str(" Z");
+
+    writeln!(
+        ctx.output,
+        r#"  <path d="{}" stroke="{}" stroke-width="1" fill="white"/>"#,
+        path, ctx.color
+    )
+    .expect("write to String is infallible");
+
+    if let Some(text) = label {
+        writeln!(
+            ctx.output,
+            r#"  <text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-family="monospace" font-size="12" fill="{}">{}</text>"#,
+            (x1 + x2) / 2, (y1 + y2) / 2, ctx.color, escape_xml(text)
+        )
+        .expect("write to String is infallible");
+    }
+}
+
 /// Render a star shape
 fn render_star(ctx: &mut RenderContext<'_>, center: Position, outer_radius: i32, _inner_radius: i32, label: Option<&str>) {
     debug_assert!(outer_radius > 0, "Star outer_radius must be positive");
@@ -1029,6 +1175,59 @@ mod tests {
         assert!(content.contains("</svg>"));
     }
 
+    #[test]
+    fn export_svg_callout_is_path_through_tail() {
+        let callout = ShapeKind::Callout {
+            start: Position::new(0, 0),
+            end: Position::new(10, 4),
+            tail: Position::new(4, 8),
+            tail_connection: None,
+            label: Some("Hi".to_string()),
+            color: ShapeColor::default(),
+        };
+        let view = build_shape_view(vec![callout]);
+        let svg = export_svg(&view);
+
+        // Offset is (1, 1), so the tip lands at (5, 9) in character cells
+        let tip = format!("L {} {}", 5 * CHAR_WIDTH, 9 * CHAR_HEIGHT);
+        assert!(svg.contains("<path"));
+        assert!(svg.contains(&tip));
+        assert!(svg.contains(">Hi</text>"));
+    }
+
+    #[test]
+    fn export_svg_polygon_open_and_closed() {
+        let make = |closed| ShapeKind::Polygon {
+            points: vec![Position::new(0, 0), Position::new(6, 0), Position::new(3, 4)],
+            closed,
+            label: None,
+            color: ShapeColor::default(),
+        };
+        let closed_svg = export_svg(&build_shape_view(vec![make(true)]));
+        let open_svg = export_svg(&build_shape_view(vec![make(false)]));
+
+        assert!(closed_svg.contains("<polygon points="));
+        assert!(open_svg.contains("<polyline points="));
+        assert!(open_svg.contains(r#"fill="none""#));
+    }
+
+    #[test]
+    fn export_svg_freehand_polyline_or_dot() {
+        let make = |points| ShapeKind::Freehand {
+            points,
+            char: '*',
+            label: None,
+            color: ShapeColor::default(),
+        };
+        let stroke = vec![Position::new(0, 0), Position::new(6, 0), Position::new(3, 4)];
+        let stroke_svg = export_svg(&build_shape_view(vec![make(stroke)]));
+        let dot_svg = export_svg(&build_shape_view(vec![make(vec![Position::new(2, 2)])]));
+
+        assert!(stroke_svg.contains("<polyline points="));
+        assert!(!stroke_svg.contains("<circle"));
+        assert!(dot_svg.contains("<circle"));
+    }
+
     // --- Color export tests ---
 
     #[test]
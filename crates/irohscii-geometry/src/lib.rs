@@ -8,6 +8,8 @@
 //!
 //! All functions produce `Vec<(Position, char)>` suitable for rendering to a terminal.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 // Re-export core types from rat-canvas
@@ -859,6 +861,171 @@ pub fn triangle_points(p1: Position, p2: Position, p3: Position) -> Vec<(Positio
     points
 }
 
+/// Generate polygon outline points through a vertex list (closed joins last to first)
+pub fn polygon_points(vertices: &[Position], closed: bool) -> Vec<(Position, char)> {
+    let mut points = Vec::new();
+
+    if let [only] = vertices {
+        points.push((*only, '*'));
+        return points;
+    }
+
+    for pair in vertices.windows(2) {
+        draw_line_edge(&mut points, pair[0], pair[1]);
+    }
+    if closed && vertices.len() > 2 {
+        draw_line_edge(&mut points, vertices[vertices.len() - 1], vertices[0]);
+    }
+
+    debug_assert!(vertices.is_empty() || !points.is_empty(), "polygon_points should return points");
+
+    points
+}
+
+/// Generate freehand stroke points, joining consecutive samples with direction-aware
+/// characters (─ │ ╱ ╲). A stroke that never moves is drawn with the brush character.
+pub fn stroke_points(samples: &[Position], brush: char) -> Vec<(Position, char)> {
+    let mut points = Vec::new();
+
+    for pair in samples.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if from == to {
+            continue;
+        }
+        let ch = stroke_char(to.x - from.x, to.y - from.y);
+        for pos in line_points(from, to) {
+            points.push((pos, ch));
+        }
+    }
+
+    if points.is_empty()
+        && let Some(&first) = samples.first()
+    {
+        points.push((first, brush));
+    }
+
+    debug_assert!(samples.is_empty() || !points.is_empty(), "stroke_points should return points");
+
+    points
+}
+
+/// Pick the stroke character closest to a segment's direction
+fn stroke_char(dx: i32, dy: i32) -> char {
+    debug_assert!(dx != 0 || dy != 0, "stroke_char needs a direction");
+
+    let (ax, ay) = (dx.abs(), dy.abs());
+    if ay * 2 <= ax {
+        '\u{2500}' // ─
+    } else if ax * 2 <= ay {
+        '\u{2502}' // │
+    } else if (dx > 0) == (dy > 0) {
+        '\u{2572}' // ╲
+    } else {
+        '\u{2571}' // ╱
+    }
+}
+
+/// Simplify a sample path with the Douglas–Peucker algorithm.
+///
+/// Keeps the endpoints and every sample farther than `tolerance` cells from the
+/// simplified path. Consecutive duplicate samples are dropped.
+pub fn simplify_path(samples: &[Position], tolerance: f64) -> Vec<Position> {
+    debug_assert!(tolerance >= 0.0, "tolerance must be non-negative");
+
+    let mut path: Vec<Position> = Vec::with_capacity(samples.len());
+    for &pos in samples {
+        if path.last() != Some(&pos) {
+            path.push(pos);
+        }
+    }
+    if path.len() <= 2 {
+        return path;
+    }
+
+    let last = path.len() - 1;
+    let mut keep = vec![false; path.len()];
+    keep[0] = true;
+    keep[last] = true;
+
+    // Explicit stack instead of recursion: long strokes can have thousands of samples
+    let mut spans = vec![(0, last)];
+    while let Some((start, end)) = spans.pop() {
+        let farthest = (start + 1..end)
+            .map(|i| (i, segment_distance(path[i], path[start], path[end])))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((index, dist)) = farthest
+            && dist > tolerance
+        {
+            keep[index] = true;
+            spans.push((start, index));
+            spans.push((index, end));
+        }
+    }
+
+    let simplified: Vec<Position> = path
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(pos, kept)| kept.then_some(pos))
+        .collect();
+
+    debug_assert!(simplified.len() >= 2, "simplify_path keeps both endpoints");
+
+    simplified
+}
+
+/// Smooth a path with Chaikin corner cutting, keeping its endpoints.
+///
+/// Each iteration replaces every corner with two points a quarter of the way
+/// along its edges; results are snapped back to the grid.
+pub fn smooth_path(vertices: &[Position], iterations: usize) -> Vec<Position> {
+    let mut path: Vec<(f64, f64)> = vertices
+        .iter()
+        .map(|p| (f64::from(p.x), f64::from(p.y)))
+        .collect();
+
+    for _ in 0..iterations {
+        if path.len() < 3 {
+            break;
+        }
+        let mut next = Vec::with_capacity(path.len() * 2);
+        next.push(path[0]);
+        for pair in path.windows(2) {
+            let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+            next.push((0.75 * ax + 0.25 * bx, 0.75 * ay + 0.25 * by));
+            next.push((0.25 * ax + 0.75 * bx, 0.25 * ay + 0.75 * by));
+        }
+        next.push(path[path.len() - 1]);
+        path = next;
+    }
+
+    let mut smoothed: Vec<Position> = Vec::with_capacity(path.len());
+    for (x, y) in path {
+        let pos = Position::new(x.round() as i32, y.round() as i32);
+        if smoothed.last() != Some(&pos) {
+            smoothed.push(pos);
+        }
+    }
+
+    debug_assert!(
+        vertices.is_empty() || smoothed.first() == vertices.first(),
+        "smooth_path keeps the start point"
+    );
+
+    smoothed
+}
+
+/// Distance from a point to a line segment
+fn segment_distance(p: Position, a: Position, b: Position) -> f64 {
+    let (abx, aby) = (f64::from(b.x - a.x), f64::from(b.y - a.y));
+    let (apx, apy) = (f64::from(p.x - a.x), f64::from(p.y - a.y));
+    let len_sq = abx * abx + aby * aby;
+    if len_sq == 0.0 {
+        return apx.hypot(apy);
+    }
+    let t = ((apx * abx + apy * aby) / len_sq).clamp(0.0, 1.0);
+    (apx - t * abx).hypot(apy - t * aby)
+}
+
 /// Generate parallelogram outline points (slanted rectangle)
 pub fn parallelogram_points(from: Position, to: Position) -> Vec<(Position, char)> {
     let mut points = Vec::new();
@@ -1381,6 +1548,121 @@ pub fn star_points(
     points
 }
 
+/// Edge of a callout body that the tail leaves from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalloutSide {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl CalloutSide {
+    /// Junction glyph drawn on the border where the tail attaches
+    pub fn junction_char(self) -> char {
+        match self {
+            CalloutSide::Top => '\u{2534}',    // ┴
+            CalloutSide::Bottom => '\u{252C}', // ┬
+            CalloutSide::Left => '\u{2524}',   // ┤
+            CalloutSide::Right => '\u{251C}',  // ├
+        }
+    }
+
+    /// Unit step pointing away from the body
+    pub fn outward(self) -> (i32, i32) {
+        match self {
+            CalloutSide::Top => (0, -1),
+            CalloutSide::Bottom => (0, 1),
+            CalloutSide::Left => (-1, 0),
+            CalloutSide::Right => (1, 0),
+        }
+    }
+}
+
+/// Find where a callout tail leaves the body on its way to `tip`.
+///
+/// The anchor is the border cell closest to the tip, kept off the corners so the
+/// junction glyph always sits on a straight edge. Returns `None` when the tip is
+/// inside the body, in which case no tail is drawn.
+pub fn callout_tail_anchor(
+    from: Position,
+    to: Position,
+    tip: Position,
+) -> Option<(Position, CalloutSide)> {
+    let min_x = from.x.min(to.x);
+    let max_x = from.x.max(to.x);
+    let min_y = from.y.min(to.y);
+    let max_y = from.y.max(to.y);
+
+    debug_assert!(max_x >= min_x, "max_x should be >= min_x");
+    debug_assert!(max_y >= min_y, "max_y should be >= min_y");
+
+    let over_x = if tip.x < min_x {
+        min_x - tip.x
+    } else {
+        (tip.x - max_x).max(0)
+    };
+    let over_y = if tip.y < min_y {
+        min_y - tip.y
+    } else {
+        (tip.y - max_y).max(0)
+    };
+
+    if over_x == 0 && over_y == 0 {
+        return None;
+    }
+
+    let edge_x = if max_x - min_x >= 2 {
+        tip.x.clamp(min_x + 1, max_x - 1)
+    } else {
+        (min_x + max_x) / 2
+    };
+    let edge_y = if max_y - min_y >= 2 {
+        tip.y.clamp(min_y + 1, max_y - 1)
+    } else {
+        (min_y + max_y) / 2
+    };
+
+    let anchor = if over_y >= over_x {
+        if tip.y > max_y {
+            (Position::new(edge_x, max_y), CalloutSide::Bottom)
+        } else {
+            (Position::new(edge_x, min_y), CalloutSide::Top)
+        }
+    } else if tip.x > max_x {
+        (Position::new(max_x, edge_y), CalloutSide::Right)
+    } else {
+        (Position::new(min_x, edge_y), CalloutSide::Left)
+    };
+
+    Some(anchor)
+}
+
+/// Generate callout (speech bubble) points - a box with a tail aimed at `tip`
+pub fn callout_points(from: Position, to: Position, tip: Position) -> Vec<(Position, char)> {
+    let mut points = rect_points(from, to);
+
+    let Some((anchor, side)) = callout_tail_anchor(from, to, tip) else {
+        return points;
+    };
+
+    // The tail starts one cell outside the border so it never overwrites the body
+    let (step_x, step_y) = side.outward();
+    let tail_start = Position::new(anchor.x + step_x, anchor.y + step_y);
+
+    if tail_start == tip {
+        let ch = if step_x == 0 { '\u{2502}' } else { '\u{2500}' }; // │ or ─
+        points.push((tip, ch));
+    } else {
+        points.extend(straight_line_points(tail_start, tip));
+    }
+    points.push((anchor, side.junction_char()));
+
+    debug_assert!(points.len() >= 2, "callout with a tail has body and tail points");
+
+    points
+}
+
 fn draw_star_segment(points: &mut Vec<(Position, char)>, from: Position, to: Position) {
     debug_assert!(!points.is_empty() || points.is_empty(), "points vector is valid");
     
@@ -1421,6 +1703,186 @@ fn draw_star_segment(points: &mut Vec<(Position, char)>, from: Position, to: Pos
     }
 }
 
+// ========== Braille sub-cell rendering ==========
+
+/// Braille dot columns per character cell
+pub const BRAILLE_DOTS_X: i32 = 2;
+/// Braille dot rows per character cell
+pub const BRAILLE_DOTS_Y: i32 = 4;
+
+/// Blank braille pattern (U+2800); dot patterns are offsets from it
+const BRAILLE_BASE: u32 = 0x2800;
+
+/// Bit for each dot, indexed by [column][row] within a cell
+const BRAILLE_BITS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// Map a cell position to the braille dot nearest its center
+pub fn to_dot_space(pos: Position) -> Position {
+    Position::new(pos.x * BRAILLE_DOTS_X, pos.y * BRAILLE_DOTS_Y + 1)
+}
+
+/// Pack dot-space points into braille characters, one per touched cell
+pub fn braille_points(dots: impl IntoIterator<Item = Position>) -> Vec<(Position, char)> {
+    let mut cells: Vec<(Position, u8)> = Vec::new();
+    let mut index: HashMap<Position, usize> = HashMap::new();
+
+    for dot in dots {
+        let cell = Position::new(dot.x.div_euclid(BRAILLE_DOTS_X), dot.y.div_euclid(BRAILLE_DOTS_Y));
+        let column = dot.x.rem_euclid(BRAILLE_DOTS_X) as usize;
+        let row = dot.y.rem_euclid(BRAILLE_DOTS_Y) as usize;
+        let bit = BRAILLE_BITS[column][row];
+
+        match index.get(&cell) {
+            Some(&i) => cells[i].1 |= bit,
+            None => {
+                index.insert(cell, cells.len());
+                cells.push((cell, bit));
+            }
+        }
+    }
+
+    cells
+        .into_iter()
+        .map(|(cell, bits)| (cell, braille_char(bits)))
+        .collect()
+}
+
+/// Combine two braille characters drawn into the same cell.
+/// Returns None if either character is not a braille pattern.
+pub fn braille_union(a: char, b: char) -> Option<char> {
+    let bits = |ch: char| u8::try_from(u32::from(ch).checked_sub(BRAILLE_BASE)?).ok();
+    Some(braille_char(bits(a)? | bits(b)?))
+}
+
+fn braille_char(bits: u8) -> char {
+    char::from_u32(BRAILLE_BASE + u32::from(bits)).expect("braille block covers every 8-bit pattern")
+}
+
+/// Generate an ellipse outline at braille sub-cell resolution
+pub fn ellipse_points_braille(center: Position, radius_x: i32, radius_y: i32) -> Vec<(Position, char)> {
+    debug_assert!(radius_x >= 0, "radius_x should be non-negative");
+    debug_assert!(radius_y >= 0, "radius_y should be non-negative");
+
+    let rx = radius_x.max(1) * BRAILLE_DOTS_X;
+    let ry = radius_y.max(1) * BRAILLE_DOTS_Y;
+    let dots = ellipse_dots(to_dot_space(center), rx, ry, |_, _| true);
+
+    braille_points(dots)
+}
+
+/// Generate a cloud outline at braille sub-cell resolution: scalloped arcs along each edge
+pub fn cloud_points_braille(from: Position, to: Position) -> Vec<(Position, char)> {
+    let min = to_dot_space(Position::new(from.x.min(to.x), from.y.min(to.y)));
+    let max = to_dot_space(Position::new(from.x.max(to.x), from.y.max(to.y)));
+    let (width, height) = (f64::from(max.x - min.x), f64::from(max.y - min.y));
+
+    // Bulge depth of the bumps, capped so tiny clouds still close
+    let bulge_x = (width / 4.0).min(f64::from(BRAILLE_DOTS_X));
+    let bulge_y = (height / 4.0).min(f64::from(BRAILLE_DOTS_Y) * 0.75);
+    let (left, right) = (f64::from(min.x) + bulge_x, f64::from(max.x) - bulge_x);
+    let (top, bottom) = (f64::from(min.y) + bulge_y, f64::from(max.y) - bulge_y);
+
+    let mut dots = Vec::new();
+    let mut scallop = |start: f64, end: f64, bump_len: f64, arc: &dyn Fn(f64, f64) -> Vec<Position>| {
+        let count = ((end - start) / bump_len).round().max(1.0);
+        let step = (end - start) / count;
+        for i in 0..count as i32 {
+            let mid = start + step * (f64::from(i) + 0.5);
+            dots.extend(arc(mid, step / 2.0));
+        }
+    };
+
+    let bump_x = f64::from(4 * BRAILLE_DOTS_X);
+    let bump_y = f64::from(2 * BRAILLE_DOTS_Y);
+    let at = |x: f64, y: f64| Position::new(x.round() as i32, y.round() as i32);
+    let (bulge_x, bulge_y) = (bulge_x.round() as i32, bulge_y.round() as i32);
+    // Each bump is the outward half of a small ellipse
+    scallop(left, right, bump_x, &|mid, half| {
+        ellipse_dots(at(mid, top), half.round() as i32, bulge_y, |_, dy| dy <= 0)
+    });
+    scallop(left, right, bump_x, &|mid, half| {
+        ellipse_dots(at(mid, bottom), half.round() as i32, bulge_y, |_, dy| dy >= 0)
+    });
+    scallop(top, bottom, bump_y, &|mid, half| {
+        ellipse_dots(at(left, mid), bulge_x, half.round() as i32, |dx, _| dx <= 0)
+    });
+    scallop(top, bottom, bump_y, &|mid, half| {
+        ellipse_dots(at(right, mid), bulge_x, half.round() as i32, |dx, _| dx >= 0)
+    });
+
+    braille_points(dots)
+}
+
+/// Generate freehand stroke points at braille sub-cell resolution.
+/// A stroke that never moves is drawn with the brush character.
+pub fn stroke_points_braille(samples: &[Position], brush: char) -> Vec<(Position, char)> {
+    let moves = samples.windows(2).any(|pair| pair[0] != pair[1]);
+    if !moves {
+        return samples.first().map(|&first| (first, brush)).into_iter().collect();
+    }
+
+    let dots = samples
+        .windows(2)
+        .flat_map(|pair| line_points(to_dot_space(pair[0]), to_dot_space(pair[1])));
+    braille_points(dots)
+}
+
+/// Dot-space points on an axis-aligned ellipse, traced with the midpoint
+/// ellipse algorithm so the outline is 8-connected without sampling.
+/// `keep` gets each point's offset from the center and picks the part of
+/// the outline to draw (the whole ellipse, or a half for cloud bumps).
+fn ellipse_dots(center: Position, rx: i32, ry: i32, keep: impl Fn(i32, i32) -> bool) -> Vec<Position> {
+    debug_assert!(rx >= 0 && ry >= 0, "radii should be non-negative");
+
+    let mut dots = Vec::new();
+    let mut plot = |x: i32, y: i32| {
+        for (dx, dy) in [(x, y), (-x, y), (x, -y), (-x, -y)] {
+            if keep(dx, dy) {
+                dots.push(Position::new(center.x + dx, center.y + dy));
+            }
+        }
+    };
+
+    // A flat ellipse is a straight segment
+    if rx == 0 || ry == 0 {
+        let (from, to) = (Position::new(-rx, -ry), Position::new(rx, ry));
+        for p in line_points(from, to) {
+            plot(p.x, p.y);
+        }
+        return dots;
+    }
+
+    let (rx2, ry2) = (f64::from(rx) * f64::from(rx), f64::from(ry) * f64::from(ry));
+    let (mut x, mut y) = (0, ry);
+
+    // Region 1: slope shallower than -1, step along x
+    let mut d = ry2 - rx2 * f64::from(ry) + rx2 / 4.0;
+    while ry2 * f64::from(x) < rx2 * f64::from(y) {
+        plot(x, y);
+        x += 1;
+        if d < 0.0 {
+            d += ry2 * f64::from(2 * x + 1);
+        } else {
+            y -= 1;
+            d += ry2 * f64::from(2 * x + 1) - 2.0 * rx2 * f64::from(y);
+        }
+    }
+
+    // Region 2: slope steeper than -1, step along y
+    let mut d = ry2 * (f64::from(x) + 0.5).powi(2) + rx2 * f64::from(y - 1).powi(2) - rx2 * ry2;
+    while y >= 0 {
+        plot(x, y);
+        y -= 1;
+        if d > 0.0 {
+            d += rx2 * f64::from(1 - 2 * y);
+        } else {
+            x += 1;
+            d += ry2 * f64::from(2 * x) + rx2 * f64::from(1 - 2 * y);
+        }
+    }
+    dots
+}
+
 fn draw_line_edge(points: &mut Vec<(Position, char)>, from: Position, to: Position) {
     debug_assert!(!points.is_empty() || points.is_empty(), "points vector is valid");
     
@@ -1537,4 +1999,148 @@ mod tests {
         let points = ellipse_points(Position::new(10, 10), 5, 3);
         assert!(!points.is_empty());
     }
+
+    #[test]
+    fn callout_tail_anchor_inside_is_none() {
+        let anchor = callout_tail_anchor(Position::new(0, 0), Position::new(10, 4), Position::new(5, 2));
+        assert!(anchor.is_none());
+    }
+
+    #[test]
+    fn callout_tail_anchor_picks_nearest_side() {
+        let from = Position::new(0, 0);
+        let to = Position::new(10, 4);
+        assert_eq!(
+            callout_tail_anchor(from, to, Position::new(3, 9)),
+            Some((Position::new(3, 4), CalloutSide::Bottom))
+        );
+        assert_eq!(
+            callout_tail_anchor(from, to, Position::new(20, 2)),
+            Some((Position::new(10, 2), CalloutSide::Right))
+        );
+        // Anchor stays off the corners even when the tip is diagonal
+        assert_eq!(
+            callout_tail_anchor(from, to, Position::new(-2, -6)),
+            Some((Position::new(1, 0), CalloutSide::Top))
+        );
+    }
+
+    #[test]
+    fn callout_points_tail_reaches_tip() {
+        let tip = Position::new(4, 8);
+        let points = callout_points(Position::new(0, 0), Position::new(10, 4), tip);
+        assert!(points.iter().any(|(p, _)| *p == tip));
+        assert!(points.iter().any(|&(p, ch)| p == Position::new(4, 4) && ch == '\u{252C}'));
+    }
+
+    #[test]
+    fn polygon_points_closed_joins_last_to_first() {
+        let vertices = [Position::new(0, 0), Position::new(6, 0), Position::new(6, 4)];
+        let open = polygon_points(&vertices, false);
+        let closed = polygon_points(&vertices, true);
+        assert!(!open.iter().any(|(p, _)| *p == Position::new(3, 2)));
+        assert!(closed.iter().any(|(p, _)| *p == Position::new(3, 2)));
+    }
+
+    #[test]
+    fn stroke_points_use_direction_chars() {
+        let stroke = [
+            Position::new(0, 0),
+            Position::new(4, 0),
+            Position::new(4, 3),
+            Position::new(7, 6),
+            Position::new(10, 3),
+        ];
+        let points = stroke_points(&stroke, '*');
+        let char_at = |pos: Position| points.iter().rev().find(|(p, _)| *p == pos).map(|(_, c)| *c);
+        assert_eq!(char_at(Position::new(2, 0)), Some('\u{2500}'));
+        assert_eq!(char_at(Position::new(4, 2)), Some('\u{2502}'));
+        assert_eq!(char_at(Position::new(5, 4)), Some('\u{2572}'));
+        assert_eq!(char_at(Position::new(9, 4)), Some('\u{2571}'));
+
+        // A stroke that never moves falls back to the brush
+        assert_eq!(stroke_points(&[Position::new(3, 3)], '#'), vec![(Position::new(3, 3), '#')]);
+    }
+
+    #[test]
+    fn simplify_path_keeps_corners_only() {
+        let mut samples = line_points(Position::new(0, 0), Position::new(20, 0));
+        samples.extend(line_points(Position::new(20, 0), Position::new(20, 10)).into_iter().skip(1));
+        samples.push(Position::new(20, 10));
+
+        let simplified = simplify_path(&samples, 1.0);
+        assert_eq!(
+            simplified,
+            vec![Position::new(0, 0), Position::new(20, 0), Position::new(20, 10)]
+        );
+        assert_eq!(simplify_path(&[Position::new(1, 1), Position::new(1, 1)], 1.0).len(), 1);
+    }
+
+    #[test]
+    fn braille_points_pack_dots_per_cell() {
+        // Left column of cell (0, 0) plus one dot in cell (1, -1)
+        let dots = (0..4).map(|y| Position::new(0, y)).chain([Position::new(3, -1)]);
+        let cells = braille_points(dots);
+        assert_eq!(cells, vec![(Position::new(0, 0), '\u{2847}'), (Position::new(1, -1), '\u{2880}')]);
+
+        assert_eq!(braille_union('\u{2801}', '\u{2808}'), Some('\u{2809}'));
+        assert_eq!(braille_union('\u{2801}', '*'), None);
+    }
+
+    #[test]
+    fn ellipse_points_braille_matches_cell_bounds() {
+        let cells = ellipse_points_braille(Position::new(10, 5), 6, 3);
+        let xs = cells.iter().map(|(p, _)| p.x);
+        let ys = cells.iter().map(|(p, _)| p.y);
+        assert_eq!((xs.clone().min(), xs.max()), (Some(4), Some(16)));
+        assert_eq!((ys.clone().min(), ys.max()), (Some(2), Some(8)));
+        assert!(cells.iter().all(|(_, ch)| ('\u{2801}'..='\u{28FF}').contains(ch)));
+    }
+
+    #[test]
+    fn ellipse_dots_trace_a_connected_outline() {
+        let center = Position::new(0, 0);
+        for (rx, ry) in [(12, 8), (2, 12), (20, 4), (1, 1), (5, 0)] {
+            let dots = ellipse_dots(center, rx, ry, |_, _| true);
+            // Extremes land on the radii, and every dot touches another
+            assert!(dots.contains(&Position::new(rx, 0)) && dots.contains(&Position::new(-rx, 0)));
+            assert!(dots.contains(&Position::new(0, ry)) && dots.contains(&Position::new(0, -ry)));
+            assert!(dots.iter().all(|p| p.x.abs() <= rx && p.y.abs() <= ry));
+            assert!(dots.iter().all(|p| {
+                dots.iter().any(|q| q != p && (q.x - p.x).abs() <= 1 && (q.y - p.y).abs() <= 1)
+            }));
+        }
+
+        let top = ellipse_dots(center, 6, 3, |_, dy| dy <= 0);
+        assert!(!top.is_empty() && top.iter().all(|p| p.y <= 0));
+    }
+
+    #[test]
+    fn cloud_points_braille_stays_in_bounds() {
+        for (from, to) in [((0, 0), (20, 8)), ((3, 3), (5, 4)), ((2, 2), (2, 2))] {
+            let (from, to) = (Position::new(from.0, from.1), Position::new(to.0, to.1));
+            let cells = cloud_points_braille(from, to);
+            assert!(!cells.is_empty());
+            assert!(cells.iter().all(|(p, _)| (from.x..=to.x).contains(&p.x)));
+            assert!(cells.iter().all(|(p, _)| (from.y..=to.y).contains(&p.y)));
+        }
+    }
+
+    #[test]
+    fn stroke_points_braille_joins_samples() {
+        let cells = stroke_points_braille(&[Position::new(0, 0), Position::new(4, 2)], '*');
+        assert!(cells.iter().any(|(p, _)| *p == Position::new(0, 0)));
+        assert!(cells.iter().any(|(p, _)| *p == Position::new(4, 2)));
+        assert_eq!(stroke_points_braille(&[Position::new(1, 1)], '#'), vec![(Position::new(1, 1), '#')]);
+    }
+
+    #[test]
+    fn smooth_path_cuts_corners_and_keeps_endpoints() {
+        let path = [Position::new(0, 0), Position::new(8, 0), Position::new(8, 8)];
+        let smoothed = smooth_path(&path, 2);
+        assert_eq!(smoothed.first(), Some(&Position::new(0, 0)));
+        assert_eq!(smoothed.last(), Some(&Position::new(8, 8)));
+        assert!(smoothed.len() > path.len());
+        assert!(!smoothed.contains(&Position::new(8, 0)), "corner was cut");
+    }
 }
@@ -0,0 +1,37 @@
+//! Chat history: messages exchanged in a session, kept alongside its document.
+
+use irohscii_core::Position;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Most messages loaded back from a session's history (the newest ones)
+pub const MAX_CHAT_HISTORY: usize = 500;
+
+/// A chat message as stored in a session's history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatEntry {
+    pub id: Uuid,
+    /// Sender's peer ID (hex)
+    pub from: String,
+    /// Sender's display name when they sent it
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Sender's cursor color (index into the presence palette)
+    #[serde(default)]
+    pub color: u8,
+    pub text: String,
+    /// Unix timestamp in milliseconds
+    pub timestamp_ms: u64,
+    /// Canvas position the message points at, if pinned
+    #[serde(default)]
+    pub pin: Option<Position>,
+}
+
+impl ChatEntry {
+    /// The sender's display name, or a short form of their peer ID
+    pub fn sender_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("Peer-{}", self.from.get(..4).unwrap_or(&self.from)))
+    }
+}
@@ -4,12 +4,18 @@
 //! - Named sessions with metadata and persistent storage
 //! - Disk-backed undo/redo with unlimited history
 //! - Session registry for quick access to recent sessions
+//! - The local user's profile and identity key location
+//! - Per-session chat history
 
+mod chat;
+mod profile;
 mod session;
 mod undo;
 
+pub use chat::{ChatEntry, MAX_CHAT_HISTORY};
+pub use profile::Profile;
 pub use session::{
-    Collaborator, SessionId, SessionManager, SessionMeta, SessionRegistry, TicketInfo,
+    Collaborator, PeerId, SessionId, SessionManager, SessionMeta, SessionRegistry, TicketInfo,
 };
 pub use undo::UndoManager;
 
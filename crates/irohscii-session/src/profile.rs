@@ -0,0 +1,14 @@
+//! The local user's profile: how we appear to collaborators.
+
+use serde::{Deserialize, Serialize};
+
+/// Display name and preferred cursor color, broadcast with presence
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Name shown to others instead of our peer ID
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Preferred cursor color (index into the presence palette)
+    #[serde(default)]
+    pub color: Option<u8>,
+}
@@ -4,7 +4,8 @@
 //! enabling ticket-based workflow switching between multiple drawings.
 
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use anyhow::{Context, Result, anyhow};
@@ -12,6 +13,9 @@ use serde::{Deserialize, Serialize};
 
 use irohscii_core::Document;
 
+use crate::chat::{ChatEntry, MAX_CHAT_HISTORY};
+use crate::profile::Profile;
+
 // We define a simplified PeerId here instead of depending on irohscii-sync
 // to avoid circular dependencies
 /// Peer ID bytes (same format as sync crate)
@@ -60,6 +64,12 @@ impl SessionId {
         Self(slug)
     }
 
+    /// Whether the ID is safe to use as a directory name: non-empty, only
+    /// alphanumeric characters and hyphens
+    pub fn is_valid(&self) -> bool {
+        !self.0.is_empty() && self.0.chars().all(|c| c.is_alphanumeric() || c == '-')
+    }
+
     /// Get the raw slug string
     #[allow(dead_code)]
     pub fn as_str(&self) -> &str {
@@ -76,11 +86,20 @@ impl std::fmt::Display for SessionId {
 /// Information about a known collaborator
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Collaborator {
-    pub peer_id: String,
+    pub peer_id: String, // Hex
     pub display_name: Option<String>,
     pub last_seen: u64, // Unix timestamp
 }
 
+impl Collaborator {
+    /// The collaborator's display name, or a short form of their peer ID
+    pub fn name(&self) -> String {
+        self.display_name
+            .clone()
+            .unwrap_or_else(|| format!("Peer-{}", self.peer_id.get(..4).unwrap_or(&self.peer_id)))
+    }
+}
+
 /// Ticket information for reconnecting to a session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TicketInfo {
@@ -161,7 +180,6 @@ impl SessionMeta {
     }
 
     /// Add or update a collaborator
-    #[allow(dead_code)]
     pub fn add_collaborator(&mut self, peer_id: PeerId, display_name: Option<String>) {
         let prev_count = self.collaborators.len();
         let now = SystemTime::now()
@@ -169,8 +187,11 @@ impl SessionMeta {
             .unwrap_or_default()
             .as_secs();
 
-        let peer_id_str = format!("{:?}", peer_id);
-        debug_assert!(!peer_id_str.is_empty(), "Peer ID string should not be empty");
+        let peer_id_str = hex_id(&peer_id.0);
+        debug_assert!(
+            !peer_id_str.is_empty(),
+            "Peer ID string should not be empty"
+        );
 
         // Update existing or add new
         if let Some(collab) = self
@@ -198,6 +219,53 @@ impl SessionMeta {
             );
         }
     }
+
+    /// Rewrite collaborator ids saved in the older `PeerId([..])` form as hex,
+    /// merging entries that turn out to be the same peer
+    fn migrate_collaborators(&mut self) {
+        let mut merged: Vec<Collaborator> = Vec::with_capacity(self.collaborators.len());
+        for mut collab in std::mem::take(&mut self.collaborators) {
+            if let Some(hex) = legacy_peer_id_hex(&collab.peer_id) {
+                collab.peer_id = hex;
+            }
+            match merged.iter_mut().find(|c| c.peer_id == collab.peer_id) {
+                Some(existing) => {
+                    // Keep the name from whichever entry was seen last
+                    if existing.display_name.is_none()
+                        || (collab.display_name.is_some() && collab.last_seen >= existing.last_seen)
+                    {
+                        existing.display_name = collab.display_name;
+                    }
+                    existing.last_seen = existing.last_seen.max(collab.last_seen);
+                }
+                None => merged.push(collab),
+            }
+        }
+        self.collaborators = merged;
+    }
+
+    /// Known collaborators, most recently seen first
+    pub fn recent_collaborators(&self) -> Vec<&Collaborator> {
+        let mut collaborators: Vec<_> = self.collaborators.iter().collect();
+        collaborators.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+        collaborators
+    }
+}
+
+/// Lowercase hex form of a peer ID, as stored in [`Collaborator::peer_id`]
+fn hex_id(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hex form of a peer ID stored in the `PeerId([1, 2, ..])` debug format
+/// older versions wrote
+fn legacy_peer_id_hex(id: &str) -> Option<String> {
+    let list = id.strip_prefix("PeerId([")?.strip_suffix("])")?;
+    let bytes = list
+        .split(',')
+        .map(|b| b.trim().parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    (bytes.len() == 32).then(|| hex_id(&bytes))
 }
 
 /// Registry of sessions for quick access
@@ -267,7 +335,11 @@ impl SessionManager {
                     .unwrap_or_else(|| PathBuf::from("."))
                     .join(".local/share")
             });
+        Self::with_data_dir(&data_dir)
+    }
 
+    /// Create a SessionManager storing everything under `data_dir/irohscii`
+    pub fn with_data_dir(data_dir: &Path) -> Result<Self> {
         let sessions_dir = data_dir.join("irohscii").join("sessions");
         let registry_path = data_dir.join("irohscii").join("registry.json");
 
@@ -304,6 +376,34 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Path of the file holding our sync identity (secret key), shared by
+    /// every session so our peer ID stays the same across restarts
+    pub fn identity_path(&self) -> PathBuf {
+        self.registry_path.with_file_name("identity.key")
+    }
+
+    /// Load the user's profile (default if missing or unreadable)
+    pub fn load_profile(&self) -> Profile {
+        fs::read_to_string(self.profile_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the user's profile (atomic write via temp file)
+    pub fn save_profile(&self, profile: &Profile) -> Result<()> {
+        let path = self.profile_path();
+        let content = serde_json::to_string_pretty(profile)?;
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, &content).context("Failed to write temp profile")?;
+        fs::rename(&temp_path, &path).context("Failed to rename profile")?;
+        Ok(())
+    }
+
+    fn profile_path(&self) -> PathBuf {
+        self.registry_path.with_file_name("profile.json")
+    }
+
     /// Get the path for a session's directory
     fn session_dir(&self, id: &SessionId) -> PathBuf {
         self.sessions_dir.join(&id.0)
@@ -319,6 +419,49 @@ impl SessionManager {
         self.session_dir(id).join("document.automerge")
     }
 
+    /// Get the path for a session's chat history (one JSON message per line)
+    fn chat_path(&self, id: &SessionId) -> PathBuf {
+        self.session_dir(id).join("chat.jsonl")
+    }
+
+    /// Load a session's chat history, oldest first (at most the newest
+    /// [`MAX_CHAT_HISTORY`] messages; unreadable lines are skipped)
+    pub fn load_chat(&self, id: &SessionId) -> Vec<ChatEntry> {
+        if !id.is_valid() {
+            return Vec::new();
+        }
+        let Ok(content) = fs::read_to_string(self.chat_path(id)) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<ChatEntry> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let excess = entries.len().saturating_sub(MAX_CHAT_HISTORY);
+        entries.drain(..excess);
+        entries
+    }
+
+    /// Append a message to a session's chat history
+    pub fn append_chat(&self, id: &SessionId, entry: &ChatEntry) -> Result<()> {
+        if !id.is_valid() {
+            return Err(anyhow!("Invalid session ID: {}", id));
+        }
+        if !self.session_dir(id).exists() {
+            return Err(anyhow!("Session not found: {}", id));
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.chat_path(id))
+            .context("Failed to open chat history")?;
+        file.write_all(line.as_bytes())
+            .context("Failed to write chat history")?;
+        Ok(())
+    }
+
     /// List all sessions with their metadata
     pub fn list_sessions(&self) -> Result<Vec<SessionMeta>> {
         let mut sessions = Vec::new();
@@ -351,7 +494,10 @@ impl SessionManager {
     pub fn load_meta(&self, id: &SessionId) -> Result<SessionMeta> {
         let path = self.meta_path(id);
         let content = fs::read_to_string(&path).context("Failed to read session metadata")?;
-        serde_json::from_str(&content).context("Failed to parse session metadata")
+        let mut meta: SessionMeta =
+            serde_json::from_str(&content).context("Failed to parse session metadata")?;
+        meta.migrate_collaborators();
+        Ok(meta)
     }
 
     /// Save session metadata (atomic write via temp file)
@@ -401,6 +547,26 @@ impl SessionManager {
             return Err(anyhow!("Could not create unique session ID"));
         }
 
+        self.init_session(meta)
+    }
+
+    /// Create a session with a given ID, e.g. to hold a document joined
+    /// from another peer's ticket
+    pub fn create_session_with_id(&mut self, id: &SessionId, name: &str) -> Result<SessionMeta> {
+        if !id.is_valid() {
+            return Err(anyhow!("Invalid session ID: {}", id));
+        }
+        if self.session_dir(id).exists() {
+            return Err(anyhow!("Session already exists: {}", id));
+        }
+
+        let mut meta = SessionMeta::new(name);
+        meta.id = id.clone();
+        self.init_session(meta)
+    }
+
+    /// Write a new session's directory, empty document and metadata
+    fn init_session(&mut self, meta: SessionMeta) -> Result<SessionMeta> {
         // Create session directory
         let dir = self.session_dir(&meta.id);
         fs::create_dir_all(&dir)?;
@@ -485,6 +651,15 @@ impl SessionManager {
         Ok((doc, meta))
     }
 
+    /// Load a session's document without marking the session as accessed
+    pub fn load_document(&self, id: &SessionId) -> Result<Document> {
+        let doc_path = self.doc_path(id);
+        if !doc_path.exists() {
+            return Err(anyhow!("Session document not found: {}", id));
+        }
+        Document::load(&doc_path)
+    }
+
     /// Save a session's document and metadata
     pub fn save_session(
         &self,
@@ -723,6 +898,23 @@ mod tests {
         assert_eq!(sessions[1].id, meta1.id);
     }
 
+    #[test]
+    fn test_create_session_with_id() {
+        let (mut manager, _temp) = test_manager();
+
+        let id = SessionId("team-board-4821".to_string());
+        let meta = manager.create_session_with_id(&id, "Team board").unwrap();
+        assert_eq!(meta.id, id);
+        assert_eq!(meta.name, "Team board");
+        assert!(manager.session_exists(&id));
+        assert!(manager.load_document(&id).is_ok());
+
+        // Existing IDs and unsafe path components are refused
+        assert!(manager.create_session_with_id(&id, "Again").is_err());
+        let escape = SessionId("../outside".to_string());
+        assert!(manager.create_session_with_id(&escape, "Escape").is_err());
+    }
+
     #[test]
     fn test_open_and_save_session() {
         let (mut manager, _temp) = test_manager();
@@ -748,6 +940,24 @@ mod tests {
         assert_eq!(shapes.len(), 1);
     }
 
+    #[test]
+    fn test_load_document_keeps_last_active() {
+        let (mut manager, _temp) = test_manager();
+
+        let first = manager.create_session("First").unwrap();
+        let second = manager.create_session("Second").unwrap();
+        manager.open_session(&second.id).unwrap();
+
+        let doc = manager.load_document(&first.id).unwrap();
+        assert!(doc.read_all_shapes().unwrap().is_empty());
+        assert_eq!(manager.last_active(), Some(&second.id));
+        assert!(
+            manager
+                .load_document(&SessionId("missing".to_string()))
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_delete_session() {
         let (mut manager, _temp) = test_manager();
@@ -793,4 +1003,95 @@ mod tests {
         assert!(!sessions[1].pinned);
         assert_eq!(sessions[1].id, meta1.id);
     }
+
+    #[test]
+    fn test_profile_round_trip() {
+        let (manager, temp) = test_manager();
+        assert_eq!(manager.load_profile(), Profile::default());
+
+        let profile = Profile {
+            display_name: Some("Ada".to_string()),
+            color: Some(3),
+        };
+        manager.save_profile(&profile).unwrap();
+        assert_eq!(manager.load_profile(), profile);
+        assert_eq!(manager.identity_path(), temp.path().join("identity.key"));
+    }
+
+    #[test]
+    fn test_collaborators_keyed_by_peer_id() {
+        let mut meta = SessionMeta::new("Shared");
+        meta.add_collaborator(PeerId([0xab; 32]), None);
+        assert_eq!(meta.collaborators[0].peer_id, "ab".repeat(32));
+        assert_eq!(meta.collaborators[0].name(), "Peer-abab");
+
+        // The same peer under a name it picked later
+        meta.add_collaborator(PeerId([0xab; 32]), Some("Ada".to_string()));
+        meta.add_collaborator(PeerId([0x01; 32]), Some("Bob".to_string()));
+        meta.collaborators[1].last_seen += 10;
+        let names: Vec<_> = meta
+            .recent_collaborators()
+            .iter()
+            .map(|c| c.name())
+            .collect();
+        assert_eq!(names, ["Bob", "Ada"]);
+    }
+
+    #[test]
+    fn test_legacy_collaborator_ids_migrated_on_load() {
+        let (mut manager, _temp) = test_manager();
+        let mut meta = manager.create_session("Old").unwrap();
+        meta.collaborators.push(Collaborator {
+            peer_id: format!("{:?}", PeerId([0xab; 32])),
+            display_name: Some("Ada".to_string()),
+            last_seen: 5,
+        });
+        meta.add_collaborator(PeerId([0xab; 32]), None);
+        manager.save_meta(&meta).unwrap();
+
+        let loaded = manager.load_meta(&meta.id).unwrap();
+        assert_eq!(loaded.collaborators.len(), 1);
+        assert_eq!(loaded.collaborators[0].peer_id, "ab".repeat(32));
+        assert_eq!(loaded.collaborators[0].name(), "Ada");
+        assert!(loaded.collaborators[0].last_seen > 5);
+    }
+
+    #[test]
+    fn test_chat_history_round_trip() {
+        use irohscii_core::Position;
+
+        let (mut manager, _temp) = test_manager();
+        let meta = manager.create_session("Chatty").unwrap();
+        assert!(manager.load_chat(&meta.id).is_empty());
+
+        let entry = |text: &str, pin: Option<Position>| ChatEntry {
+            id: uuid::Uuid::new_v4(),
+            from: "ab".repeat(32),
+            name: None,
+            color: 0,
+            text: text.to_string(),
+            timestamp_ms: 1,
+            pin,
+        };
+        let first = entry("look at the box", Some(Position::new(3, 4)));
+        manager.append_chat(&meta.id, &first).unwrap();
+        manager
+            .append_chat(&meta.id, &entry("the left one", None))
+            .unwrap();
+
+        let history = manager.load_chat(&meta.id);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0], first);
+        assert_eq!(history[0].sender_name(), "Peer-abab");
+        assert_eq!(history[1].text, "the left one");
+
+        // Only sessions that exist keep history
+        let missing = SessionId("missing".to_string());
+        assert!(manager.append_chat(&missing, &first).is_err());
+
+        // Nor can an ID reach outside the sessions directory
+        let escape = SessionId(format!("../{}", meta.id));
+        assert!(manager.append_chat(&escape, &first).is_err());
+        assert!(manager.load_chat(&escape).is_empty());
+    }
 }
@@ -0,0 +1,154 @@
+//! Per-edit cost of handing document changes to the sync thread.
+//!
+//! Compares shipping the whole document on every flush (clone + save, as
+//! `SyncDoc` used to) against shipping only the changes since the last flush
+//! (`Document::changes_since` + `Document::apply_changes`). The delta cost
+//! should stay flat as the document grows; the full cost grows with it.
+//!
+//! The store columns time the sync thread's side of the same delta:
+//! `SyncStore::apply_from_app` taking it in and `SyncStore::changes_since`
+//! reporting it back, once with the in-memory store and once on disk.
+//!
+//! Run with `cargo bench -p irohscii-sync --bench sync_delta`. Without
+//! `--bench` (as under `cargo test --all-targets`) only the smallest size
+//! runs, as a smoke test.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use aspen_automerge::DocumentId;
+use automerge::Automerge;
+use irohscii_core::{Document, Position, ShapeColor, ShapeKind};
+use irohscii_sync::SyncStore;
+use tokio::sync::mpsc;
+
+/// Document sizes (in shapes) to measure at
+const SIZES: [usize; 5] = [100, 500, 1_000, 2_000, 4_000];
+
+/// Edits timed at each size
+const EDITS: u32 = 50;
+
+fn rect(i: usize) -> ShapeKind {
+    let x = (i % 100) as i32 * 12;
+    let y = (i / 100) as i32 * 6;
+    ShapeKind::Rectangle {
+        start: Position::new(x, y),
+        end: Position::new(x + 10, y + 4),
+        color: ShapeColor::default(),
+        label: None,
+    }
+}
+
+/// Build a document with `shapes` rectangles and a replica in sync with it
+fn setup(shapes: usize) -> (Document, Document) {
+    let mut doc = Document::new();
+    for i in 0..shapes {
+        doc.add_shape(rect(i)).expect("add shape");
+    }
+    let mut replica = Document::from_automerge(Automerge::new());
+    replica
+        .apply_changes(&doc.changes_since(&[]))
+        .expect("seed replica");
+    (doc, replica)
+}
+
+/// Average time per edit to ship the whole document
+fn full_per_edit(shapes: usize) -> Duration {
+    let (mut doc, _) = setup(shapes);
+    let mut total = Duration::ZERO;
+    for i in 0..EDITS {
+        doc.add_shape(rect(i as usize)).expect("add shape");
+        let t0 = Instant::now();
+        let copy = doc.clone_automerge();
+        black_box(copy.save());
+        total += t0.elapsed();
+    }
+    total / EDITS
+}
+
+/// Average time per edit to ship and apply only the new changes, plus the
+/// average delta size in bytes
+fn delta_per_edit(shapes: usize) -> (Duration, usize) {
+    let (mut doc, mut replica) = setup(shapes);
+    let mut heads = doc.heads();
+    let mut total = Duration::ZERO;
+    let mut bytes = 0;
+    for i in 0..EDITS {
+        doc.add_shape(rect(i as usize)).expect("add shape");
+        let t0 = Instant::now();
+        let changes = doc.changes_since(&heads);
+        heads = doc.heads();
+        replica.apply_changes(&changes).expect("apply changes");
+        total += t0.elapsed();
+        bytes += changes.len();
+    }
+    (total / EDITS, bytes / EDITS as usize)
+}
+
+/// Average time per edit for a store to apply the app's changes and report
+/// them back (on disk under `dir`, else in memory)
+fn store_per_edit(shapes: usize, dir: Option<std::path::PathBuf>) -> Duration {
+    let (change_tx, _change_rx) = mpsc::channel(16);
+    let store = SyncStore::open(dir, change_tx).expect("open store");
+    let id = DocumentId::from_string("bench").expect("doc id");
+    let mut doc = Document::new();
+    for i in 0..shapes {
+        doc.add_shape(rect(i)).expect("add shape");
+    }
+    tokio_test::block_on(async {
+        store
+            .apply_from_app(&id, &doc.changes_since(&[]))
+            .await
+            .expect("seed store");
+        let mut heads = doc.heads();
+        let (_, mut reported) = store.changes_since(&id, &[]).await.expect("stored doc");
+        let mut total = Duration::ZERO;
+        for i in 0..EDITS {
+            doc.add_shape(rect(i as usize)).expect("add shape");
+            let changes = doc.changes_since(&heads);
+            heads = doc.heads();
+            let t0 = Instant::now();
+            store
+                .apply_from_app(&id, &changes)
+                .await
+                .expect("apply changes");
+            let (delta, new_heads) = store
+                .changes_since(&id, &reported)
+                .await
+                .expect("stored doc");
+            reported = new_heads;
+            black_box(delta);
+            total += t0.elapsed();
+        }
+        total / EDITS
+    })
+}
+
+fn main() {
+    // Like libtest benches, only measure properly when run by `cargo bench`
+    let sizes = if std::env::args().any(|arg| arg == "--bench") {
+        &SIZES[..]
+    } else {
+        &SIZES[..1]
+    };
+    println!(
+        "{:>8}  {:>14}  {:>14}  {:>12}  {:>14}  {:>14}",
+        "shapes", "full/edit", "delta/edit", "delta bytes", "memory/edit", "disk/edit"
+    );
+    for &shapes in sizes {
+        let full = full_per_edit(shapes);
+        let (delta, bytes) = delta_per_edit(shapes);
+        let memory = store_per_edit(shapes, None);
+        let dir = tempfile::tempdir().expect("temp dir");
+        let disk = store_per_edit(shapes, Some(dir.path().to_path_buf()));
+        println!(
+            "{:>8}  {:>12.1}us  {:>12.1}us  {:>12}  {:>12.1}us  {:>12.1}us",
+            shapes,
+            full.as_secs_f64() * 1e6,
+            delta.as_secs_f64() * 1e6,
+            bytes,
+            memory.as_secs_f64() * 1e6,
+            disk.as_secs_f64() * 1e6
+        );
+    }
+}
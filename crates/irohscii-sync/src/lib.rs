@@ -4,6 +4,7 @@
 //! - Real-time collaborative editing via iroh P2P networking
 //! - Automerge CRDT-based conflict-free data synchronization
 //! - Presence tracking for cursor/activity visibility
+//! - In-session text chat between participants
 
 pub mod peer;
 pub mod presence;
@@ -11,12 +12,18 @@ mod sync;
 
 pub use peer::PeerId;
 pub use presence::{
-    CursorActivity, PEER_COLORS, PeerPresence, PresenceManager, PresenceMessage, ToolKind,
-    peer_color,
+    CursorActivity, MAX_DISPLAY_NAME_LEN, PEER_COLOR_NAMES, PEER_COLORS, PeerPresence,
+    PeerViewport, PresenceManager, PresenceMessage, ToolKind, peer_color, peer_color_index,
 };
+pub use sync::access::{Role, TicketAccess};
+pub use sync::chat_protocol::{ChatMessage, MAX_CHAT_TEXT_LEN};
+pub use sync::invite::{Invite, InviteId, InviteInfo, PresentedInvite, Revocation, SignedInvite};
+pub use sync::store::SyncStore;
 pub use sync::{
-    CapabilityToken, SyncCommand, SyncConfig, SyncEvent, SyncHandle, SyncMode,
-    decode_cluster_ticket, decode_ticket, encode_ticket, start_sync_thread,
+    CapabilityToken, ConnectionType, DEFAULT_DOC_ID, SyncCommand, SyncConfig, SyncEvent,
+    SyncHandle, SyncMode, decode_cluster_ticket, decode_doc_ticket, decode_ticket,
+    decode_ticket_access, encode_access_ticket, encode_doc_ticket, encode_ticket,
+    start_sync_thread,
 };
 
 // Re-export core types for convenience
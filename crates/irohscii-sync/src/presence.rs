@@ -11,7 +11,8 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 
-use irohscii_core::{LayerId, Position, ShapeId};
+use irohscii_core::{LayerId, Position, ShapeId, Viewport};
+use irohscii_geometry::{MAX_ZOOM, MIN_ZOOM};
 
 // Re-export PeerId from peer module for compatibility
 pub use crate::peer::PeerId;
@@ -31,8 +32,24 @@ pub const PEER_COLORS: &[Color] = &[
     Color::LightMagenta,
 ];
 
+/// Names for [`PEER_COLORS`], in palette order (for picking a preferred color)
+pub const PEER_COLOR_NAMES: &[&str] = &[
+    "red",
+    "green",
+    "blue",
+    "magenta",
+    "light-red",
+    "light-green",
+    "light-blue",
+    "light-magenta",
+];
+
+/// Longest display name broadcast to peers, in characters
+pub const MAX_DISPLAY_NAME_LEN: usize = 24;
+
 // Compile-time assertion: color palette must fit in u8
 const _: () = assert!(PEER_COLORS.len() <= 256, "PEER_COLORS palette too large for u8");
+const _: () = assert!(PEER_COLOR_NAMES.len() == PEER_COLORS.len(), "every peer color needs a name");
 
 /// Tool kind for presence (simplified from Tool enum)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -54,6 +71,8 @@ pub enum ToolKind {
     Cylinder,
     Cloud,
     Star,
+    Callout,
+    Polygon,
 }
 
 /// Cursor activity state
@@ -101,6 +120,68 @@ impl CursorActivity {
     }
 }
 
+/// The part of the canvas a peer is looking at (their `Viewport`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PeerViewport {
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub width: u16,
+    pub height: u16,
+    pub zoom: f32,
+}
+
+impl PeerViewport {
+    /// The peer's zoom, clamped to what a local viewport allows (it arrives
+    /// off the wire, so a non-finite value falls back to 1.0)
+    pub fn zoom(&self) -> f32 {
+        if self.zoom.is_finite() {
+            self.zoom.clamp(MIN_ZOOM, MAX_ZOOM)
+        } else {
+            1.0
+        }
+    }
+
+    /// Move `viewport` to the same offset and zoom, keeping its own size
+    pub fn apply_to(&self, viewport: &mut Viewport) {
+        viewport.offset_x = self.offset_x;
+        viewport.offset_y = self.offset_y;
+        viewport.zoom = self.zoom();
+    }
+
+    /// Whether `viewport` shows the same offset and zoom
+    pub fn matches(&self, viewport: &Viewport) -> bool {
+        self.offset_x == viewport.offset_x
+            && self.offset_y == viewport.offset_y
+            && (self.zoom() - viewport.zoom).abs() < f32::EPSILON
+    }
+
+    /// Canvas-space corners (min, max) of the visible area
+    pub fn bounds(&self) -> (Position, Position) {
+        let zoom = self.zoom();
+        let w = ((f32::from(self.width) / zoom) as i32).max(1);
+        let h = ((f32::from(self.height) / zoom) as i32).max(1);
+        (
+            Position::new(self.offset_x, self.offset_y),
+            Position::new(
+                self.offset_x.saturating_add(w - 1),
+                self.offset_y.saturating_add(h - 1),
+            ),
+        )
+    }
+}
+
+impl From<&Viewport> for PeerViewport {
+    fn from(viewport: &Viewport) -> Self {
+        Self {
+            offset_x: viewport.offset_x,
+            offset_y: viewport.offset_y,
+            width: viewport.width,
+            height: viewport.height,
+            zoom: viewport.zoom,
+        }
+    }
+}
+
 /// A peer's presence state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeerPresence {
@@ -115,6 +196,12 @@ pub struct PeerPresence {
     /// Earlier timestamp = higher priority for the shape
     #[serde(default)]
     pub drag_start_ms: Option<u64>,
+    /// Name the peer chose to show to others
+    #[serde(default)]
+    pub name: Option<String>,
+    /// What the peer is looking at (for follow mode and viewport outlines)
+    #[serde(default)]
+    pub viewport: Option<PeerViewport>,
 }
 
 impl PeerPresence {
@@ -143,12 +230,37 @@ impl PeerPresence {
             timestamp_ms,
             active_layer_id,
             drag_start_ms,
+            name: None,
+            viewport: None,
         }
     }
 
-    /// Get a short display name from the peer ID (first 4 hex chars)
+    /// Attach the sender's viewport
+    pub fn with_viewport(mut self, viewport: &Viewport) -> Self {
+        self.viewport = Some(PeerViewport::from(viewport));
+        self
+    }
+
+    /// Apply the user's chosen display name and preferred color (index into
+    /// [`PEER_COLORS`]). Names are trimmed and capped at
+    /// [`MAX_DISPLAY_NAME_LEN`] characters; an empty name is dropped.
+    pub fn with_profile(mut self, name: Option<&str>, color_index: Option<u8>) -> Self {
+        self.name = name
+            .map(|n| n.trim().chars().take(MAX_DISPLAY_NAME_LEN).collect::<String>())
+            .filter(|n| !n.is_empty());
+        if let Some(index) = color_index {
+            self.color_index = index % PEER_COLORS.len() as u8;
+        }
+        self
+    }
+
+    /// The peer's chosen name (control characters stripped, since it comes
+    /// off the wire), or a short one from the peer ID (first 4 hex chars)
     pub fn display_name(&self) -> String {
-        format!("Peer-{}", self.peer_id.short_name())
+        match &self.name {
+            Some(name) => name.chars().filter(|c| !c.is_control()).collect(),
+            None => format!("Peer-{}", self.peer_id.short_name()),
+        }
     }
 }
 
@@ -165,6 +277,17 @@ pub enum PresenceMessage {
     RequestAll,
 }
 
+/// Palette index for a color given by name (see [`PEER_COLOR_NAMES`]) or
+/// by index
+pub fn peer_color_index(color: &str) -> Option<u8> {
+    let color = color.trim().to_ascii_lowercase().replace('_', "-");
+    let index = PEER_COLOR_NAMES
+        .iter()
+        .position(|name| *name == color)
+        .or_else(|| color.parse().ok().filter(|i| *i < PEER_COLORS.len()))?;
+    u8::try_from(index).ok()
+}
+
 /// Get color for a peer based on their color index
 pub fn peer_color(presence: &PeerPresence) -> Color {
     let idx = usize::from(presence.color_index) % PEER_COLORS.len();
@@ -224,6 +347,19 @@ impl PresenceManager {
         self.peers.values().map(|(p, _)| p)
     }
 
+    /// Active peers in a stable order (by peer ID), for lists the user
+    /// clicks through
+    pub fn sorted_peers(&self) -> Vec<&PeerPresence> {
+        let mut peers: Vec<_> = self.active_peers().collect();
+        peers.sort_by_key(|p| p.peer_id.0);
+        peers
+    }
+
+    /// Latest presence from a peer, if still active
+    pub fn get_peer(&self, peer_id: &PeerId) -> Option<&PeerPresence> {
+        self.peers.get(peer_id).map(|(p, _)| p)
+    }
+
     /// Get peer count
     pub fn peer_count(&self) -> usize {
         self.peers.len()
@@ -345,6 +481,142 @@ mod tests {
         assert!(!presence.display_name().is_empty());
     }
 
+    #[test]
+    fn peer_presence_profile_name_and_color() {
+        let presence = PeerPresence::new(
+            PeerId([0xAB; 32]),
+            Position::new(0, 0),
+            CursorActivity::Idle,
+            None,
+            None,
+        );
+
+        let named = presence.clone().with_profile(Some("  Ada  "), Some(2));
+        assert_eq!(named.display_name(), "Ada");
+        assert_eq!(peer_color(&named), Color::Blue);
+
+        // Blank names fall back to the peer ID; long ones are capped
+        let blank = presence.clone().with_profile(Some("   "), None);
+        assert_eq!(blank.display_name(), "Peer-abab");
+        assert_eq!(blank.color_index, presence.color_index);
+        let long = presence.with_profile(Some(&"x".repeat(100)), None);
+        assert_eq!(long.display_name().chars().count(), MAX_DISPLAY_NAME_LEN);
+
+        // Names from the wire can't smuggle terminal escapes
+        let mut hostile = named;
+        hostile.name = Some("Ev\x1b[2Jil".to_string());
+        assert_eq!(hostile.display_name(), "Ev[2Jil");
+    }
+
+    #[test]
+    fn peer_color_index_by_name_or_number() {
+        assert_eq!(peer_color_index("red"), Some(0));
+        assert_eq!(peer_color_index("Light_Blue"), Some(6));
+        assert_eq!(peer_color_index("3"), Some(3));
+        assert_eq!(peer_color_index("8"), None);
+        assert_eq!(peer_color_index("teal"), None);
+    }
+
+    #[test]
+    fn peer_presence_without_name_still_decodes() {
+        // Presence from a peer that predates names has no trailing field
+        #[derive(Serialize)]
+        struct OldPresence {
+            peer_id: PeerId,
+            cursor_pos: Position,
+            activity: CursorActivity,
+            color_index: u8,
+            timestamp_ms: u64,
+            active_layer_id: Option<LayerId>,
+            drag_start_ms: Option<u64>,
+        }
+        let old = OldPresence {
+            peer_id: PeerId([1; 32]),
+            cursor_pos: Position::new(3, 4),
+            activity: CursorActivity::Idle,
+            color_index: 1,
+            timestamp_ms: 0,
+            active_layer_id: None,
+            drag_start_ms: None,
+        };
+        let bytes = rmp_serde::to_vec(&old).unwrap();
+        let presence: PeerPresence = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(presence.name, None);
+        assert_eq!(presence.viewport, None);
+        assert_eq!(presence.cursor_pos, Position::new(3, 4));
+    }
+
+    #[test]
+    fn peer_viewport_follows_and_outlines() {
+        let mut theirs = Viewport::new(80, 24);
+        theirs.pan(10, -5);
+        theirs.zoom_in();
+        let presence = PeerPresence::new(
+            PeerId([1; 32]),
+            Position::new(0, 0),
+            CursorActivity::Idle,
+            None,
+            None,
+        )
+        .with_viewport(&theirs);
+        let bytes = rmp_serde::to_vec(&presence).unwrap();
+        let decoded: PeerPresence = rmp_serde::from_slice(&bytes).unwrap();
+        let view = decoded.viewport.unwrap();
+
+        // Following copies offset and zoom but keeps our own size
+        let mut ours = Viewport::new(120, 40);
+        assert!(!view.matches(&ours));
+        view.apply_to(&mut ours);
+        assert!(view.matches(&ours));
+        assert_eq!((ours.width, ours.height), (120, 40));
+
+        let (min, max) = view.bounds();
+        assert_eq!(min, Position::new(10, -5));
+        assert_eq!(max.x - min.x + 1, (80.0 / theirs.zoom) as i32);
+        assert_eq!(max.y - min.y + 1, (24.0 / theirs.zoom) as i32);
+    }
+
+    #[test]
+    fn peer_viewport_zoom_is_clamped() {
+        let view = |zoom| PeerViewport {
+            offset_x: 0,
+            offset_y: 0,
+            width: 80,
+            height: 24,
+            zoom,
+        };
+        let mut ours = Viewport::new(120, 40);
+        for (zoom, expected) in [
+            (1000.0, MAX_ZOOM),
+            (0.0, MIN_ZOOM),
+            (-2.0, MIN_ZOOM),
+            (f32::NAN, 1.0),
+            (f32::INFINITY, 1.0),
+        ] {
+            view(zoom).apply_to(&mut ours);
+            assert_eq!(ours.zoom, expected);
+            assert!(view(zoom).matches(&ours));
+        }
+
+        // The smallest zoom bounds the outline at width / MIN_ZOOM cells
+        let (min, max) = view(0.0).bounds();
+        assert_eq!(max.x - min.x + 1, (80.0 / MIN_ZOOM) as i32);
+    }
+
+    #[test]
+    fn peer_viewport_bounds_saturate_at_the_canvas_edge() {
+        let view = PeerViewport {
+            offset_x: i32::MAX - 10,
+            offset_y: i32::MAX,
+            width: u16::MAX,
+            height: u16::MAX,
+            zoom: MIN_ZOOM,
+        };
+        let (min, max) = view.bounds();
+        assert_eq!(min, Position::new(i32::MAX - 10, i32::MAX));
+        assert_eq!(max, Position::new(i32::MAX, i32::MAX));
+    }
+
     #[test]
     fn peer_presence_color_index_deterministic() {
         // Same peer_id should always get same color
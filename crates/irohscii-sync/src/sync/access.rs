@@ -0,0 +1,1390 @@
+//! Editor and viewer roles for shared documents.
+//!
+//! A document is open to every participant until we restrict it, which we
+//! only do when handing out the first viewer ticket or invite for it.
+//! Restricting generates an editor key and a reader key, kept on disk so
+//! the tickets stay valid across restarts: editor tickets carry both secret
+//! halves, viewer tickets the editor key's
+//! public half and the reader key's secret half, and invite tickets an
+//! invite signed with the editor key (see [`super::invite`]). Editors and
+//! viewers prove their role to each other participant with a grant (the
+//! editor or reader key's signature over the document and their peer ID)
+//! sent along with peer exchange; invitees present their invite, bound to
+//! their peer ID, to the editors instead.
+//!
+//! Sync connections see the store through a [`GatedStore`] for the remote
+//! peer. Documents we never shared or joined aren't served at all, and
+//! unrestricted ones are served to every participant. A restricted
+//! document is only served to peers with a verified
+//! grant or a valid invite, and changes from peers without an editor grant
+//! or editor invite are dropped before they reach the store, while ours
+//! still flow to them. Peers that presented an expired, revoked or forged
+//! invite get nothing at all.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use automerge::AutoCommit;
+use iroh::endpoint::Connection;
+use iroh::protocol::{AcceptError, ProtocolHandler};
+use iroh_base::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use aspen_automerge::{
+    ApplyResult, AutomergeError, AutomergeResult, DocumentChange, DocumentId, DocumentMetadata,
+    DocumentStore, ListOptions, ListResult,
+};
+
+use super::invite::{Invite, InviteId, PresentedInvite, Revocation, SignedInvite};
+use super::protocol::AutomergeSyncHandler;
+use super::store::SyncStore;
+use crate::peer::PeerId;
+
+/// Domain separator for editor grant signatures
+const GRANT_CONTEXT: &[u8] = b"irohscii/editor-grant/1";
+
+/// Domain separator for reader grant signatures
+const READER_GRANT_CONTEXT: &[u8] = b"irohscii/reader-grant/1";
+
+/// What a ticket lets its holder do in a document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    /// Can change the document
+    #[default]
+    Editor,
+    /// Receives changes; anything it sends is dropped by editors
+    Viewer,
+}
+
+/// Key material a ticket carries for its document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TicketAccess {
+    /// Secret halves of the editor and reader keys
+    Editor { key: [u8; 32], reader: [u8; 32] },
+    /// Public half of the editor key, to recognise editors' grants, and
+    /// secret half of the reader key, to prove we may read
+    Viewer { editor: [u8; 32], reader: [u8; 32] },
+    /// An invite signed with the editor key, granting its own role, and
+    /// the secret half of the holder key it names
+    Invite { invite: SignedInvite, key: [u8; 32] },
+}
+
+impl TicketAccess {
+    /// Role the ticket grants
+    pub fn role(&self) -> Role {
+        match self {
+            Self::Editor { .. } => Role::Editor,
+            Self::Viewer { .. } => Role::Viewer,
+            Self::Invite { invite, .. } => invite.invite.role,
+        }
+    }
+
+    /// The invite an invite ticket carries
+    pub fn invite(&self) -> Option<&Invite> {
+        match self {
+            Self::Invite { invite, .. } => Some(&invite.invite),
+            _ => None,
+        }
+    }
+}
+
+/// Proof that a peer holds a document's editor key (role `Editor`) or
+/// reader key (role `Viewer`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Grant {
+    /// App-facing document ID (as in tickets)
+    pub doc_id: String,
+    pub role: Role,
+    /// The role's key's signature over the document and the peer's ID
+    pub signature: Vec<u8>,
+}
+
+/// How we treat writes to a document
+#[derive(Debug, Clone)]
+enum DocAccess {
+    /// Shared by us through tickets without keys: everyone may write until
+    /// we restrict it
+    Shared,
+    /// Joined through a ticket without keys: everyone may write
+    Open,
+    /// We're an editor and can sign grants and invites (editor key, reader
+    /// key)
+    Editor(SecretKey, SecretKey),
+    /// We're a viewer: only peers with grants from this key may write, and
+    /// we prove we may read with the reader key
+    Viewer(PublicKey, SecretKey),
+    /// We joined with an invite signed by this key, and present it to
+    /// editors with its holder key
+    Invited(PublicKey, SignedInvite, SecretKey),
+}
+
+impl DocAccess {
+    fn from_ticket(access: Option<&TicketAccess>) -> Option<Self> {
+        match access {
+            None => Some(Self::Open),
+            Some(TicketAccess::Editor { key, reader }) => Some(Self::Editor(
+                SecretKey::from_bytes(key),
+                SecretKey::from_bytes(reader),
+            )),
+            Some(TicketAccess::Viewer { editor, reader }) => PublicKey::from_bytes(editor)
+                .ok()
+                .map(|editor| Self::Viewer(editor, SecretKey::from_bytes(reader))),
+            Some(TicketAccess::Invite { invite, key }) => PublicKey::from_bytes(&invite.editor)
+                .ok()
+                .map(|editor| Self::Invited(editor, invite.clone(), SecretKey::from_bytes(key))),
+        }
+    }
+
+    /// Public half of the editor key, if the document is restricted
+    fn editor_key(&self) -> Option<PublicKey> {
+        match self {
+            Self::Shared | Self::Open => None,
+            Self::Editor(key, _) => Some(key.public()),
+            Self::Viewer(key, _) | Self::Invited(key, ..) => Some(*key),
+        }
+    }
+
+    /// Public half of the reader key, if we know it (invitees don't)
+    fn reader_key(&self) -> Option<PublicKey> {
+        match self {
+            Self::Editor(_, reader) | Self::Viewer(_, reader) => Some(reader.public()),
+            Self::Shared | Self::Open | Self::Invited(..) => None,
+        }
+    }
+}
+
+/// A document's access and the peers we know may write to or read it
+#[derive(Debug)]
+struct DocRoles {
+    doc_id: String,
+    access: DocAccess,
+    editors: HashSet<PeerId>,
+    readers: HashSet<PeerId>,
+    /// Peers that completed a sync round for the document with us
+    syncing: HashSet<PeerId>,
+}
+
+impl DocRoles {
+    fn new(doc_id: &str, access: DocAccess) -> Self {
+        Self {
+            doc_id: doc_id.to_string(),
+            access,
+            editors: HashSet::new(),
+            readers: HashSet::new(),
+            syncing: HashSet::new(),
+        }
+    }
+
+    /// Give a document we share without keys fresh editor and reader keys.
+    /// Returns whether it was restricted just now.
+    fn restrict(&mut self) -> bool {
+        if !matches!(self.access, DocAccess::Shared) {
+            return false;
+        }
+        self.access = DocAccess::Editor(generate_key(), generate_key());
+        true
+    }
+}
+
+/// What [`Roles`] keeps on disk
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedRoles {
+    /// Document ID, editor key and reader key of each document we're an
+    /// editor of
+    editors: Vec<(String, [u8; 32], [u8; 32])>,
+    /// Invites we issued with their holder keys, oldest first
+    issued: Vec<(SignedInvite, [u8; 32])>,
+    /// Revoked by us or by an editor we sync with
+    revoked: Vec<Revocation>,
+}
+
+/// Invites we issued and invites presented to us
+#[derive(Debug, Default)]
+struct Invites {
+    /// Invites we signed with their holder keys, oldest first
+    issued: Vec<(SignedInvite, [u8; 32])>,
+    /// Revoked by us or by an editor we sync with
+    revoked: HashSet<Revocation>,
+    /// The verified invite each peer presented, per document ID
+    presented: HashMap<(PeerId, String), Invite>,
+    /// Peers that presented an invite that didn't verify
+    forged: HashSet<PeerId>,
+}
+
+impl Invites {
+    /// The invite a peer presented for a document
+    fn get(&self, peer: &PeerId, doc_id: &str) -> Option<&Invite> {
+        self.presented.get(&(*peer, doc_id.to_string()))
+    }
+
+    /// Whether an invite expired or was revoked
+    fn is_void(&self, invite: &Invite) -> bool {
+        invite.is_expired() || self.revoked.contains(&Revocation::of(invite))
+    }
+
+    /// Whether a peer forged an invite, or its invite to a document is one
+    /// we no longer honour
+    fn refuses_in(&self, peer: &PeerId, doc_id: &str) -> bool {
+        self.forged.contains(peer)
+            || self
+                .get(peer, doc_id)
+                .is_some_and(|invite| self.is_void(invite))
+    }
+
+    /// Whether a peer forged an invite, or every invite it presented is one
+    /// we no longer honour
+    fn refuses(&self, peer: &PeerId) -> bool {
+        if self.forged.contains(peer) {
+            return true;
+        }
+        let mut own = self
+            .presented
+            .iter()
+            .filter(|((presenter, _), _)| presenter == peer)
+            .map(|(_, invite)| invite)
+            .peekable();
+        own.peek().is_some() && own.all(|invite| self.is_void(invite))
+    }
+}
+
+/// Roles for every document we sync, shared by the sync thread and the
+/// [`SyncHandle`](super::SyncHandle) that mints tickets.
+///
+/// Keyed by store content key, so sync connections can look documents up
+/// by [`DocumentId`]. Locks are taken in field order.
+#[derive(Debug, Default)]
+pub struct Roles {
+    /// Serialises writes to `path`
+    saving: Mutex<()>,
+    docs: RwLock<HashMap<String, DocRoles>>,
+    invites: RwLock<Invites>,
+    /// File the keys of documents we restricted are kept in
+    path: Option<PathBuf>,
+}
+
+impl Roles {
+    /// Create an empty role table (every document open), kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the role table kept at `path`, or start an empty one there if
+    /// the file doesn't exist yet. `None` keeps it in memory.
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let roles = Self {
+            path,
+            ..Self::default()
+        };
+        let Some(path) = &roles.path else {
+            return Ok(roles);
+        };
+        let saved: SavedRoles = match fs::read(path) {
+            Ok(bytes) => postcard::from_bytes(&bytes)
+                .with_context(|| format!("Invalid roles file {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(roles),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        {
+            let mut docs = roles.docs.write().unwrap_or_else(|e| e.into_inner());
+            for (doc_id, key, reader) in saved.editors {
+                let Some(content_key) = content_key(&doc_id) else {
+                    continue;
+                };
+                let access =
+                    DocAccess::Editor(SecretKey::from_bytes(&key), SecretKey::from_bytes(&reader));
+                docs.insert(content_key, DocRoles::new(&doc_id, access));
+            }
+        }
+        {
+            let mut invites = roles.invites.write().unwrap_or_else(|e| e.into_inner());
+            invites.issued = saved.issued;
+            invites.revoked = saved.revoked.into_iter().collect();
+        }
+        Ok(roles)
+    }
+
+    /// Write the keys of the documents we're an editor of, and the invites
+    /// we issued or saw revoked, to our file if we have one
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let _saving = self.saving.lock().unwrap_or_else(|e| e.into_inner());
+        let saved = {
+            let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+            let invites = self.invites.read().unwrap_or_else(|e| e.into_inner());
+            SavedRoles {
+                editors: docs
+                    .values()
+                    .filter_map(|doc| match &doc.access {
+                        DocAccess::Editor(key, reader) => {
+                            Some((doc.doc_id.clone(), key.to_bytes(), reader.to_bytes()))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                issued: invites.issued.clone(),
+                revoked: invites.revoked.iter().cloned().collect(),
+            }
+        };
+        write_private(path, &postcard::to_allocvec(&saved)?)
+            .with_context(|| format!("Failed to write roles to {}", path.display()))
+    }
+
+    /// Start handing out tickets without keys for a document, unless we
+    /// restricted or joined it already
+    pub fn share(&self, doc_id: &str) {
+        let Some(key) = content_key(doc_id) else {
+            return;
+        };
+        let mut docs = self.docs.write().unwrap_or_else(|e| e.into_inner());
+        docs.entry(key)
+            .or_insert_with(|| DocRoles::new(doc_id, DocAccess::Shared));
+    }
+
+    /// Restrict a document we share (or haven't shared yet) to peers with
+    /// keys, generating and saving its editor and reader keys. Tickets
+    /// without keys stop working for it. Documents we joined are left alone.
+    pub fn restrict(&self, doc_id: &str) -> Result<()> {
+        let Some(key) = content_key(doc_id) else {
+            return Ok(());
+        };
+        let restricted = {
+            let mut docs = self.docs.write().unwrap_or_else(|e| e.into_inner());
+            docs.entry(key)
+                .or_insert_with(|| DocRoles::new(doc_id, DocAccess::Shared))
+                .restrict()
+        };
+        if restricted { self.save() } else { Ok(()) }
+    }
+
+    /// Record the access a joined ticket grants to its document. Editors and
+    /// readers already verified stay verified while the editor key is
+    /// unchanged.
+    pub fn register(&self, doc_id: &str, access: Option<&TicketAccess>) {
+        let (Some(key), Some(access)) = (content_key(doc_id), DocAccess::from_ticket(access))
+        else {
+            return;
+        };
+        let mut docs = self.docs.write().unwrap_or_else(|e| e.into_inner());
+        match docs.get_mut(&key) {
+            Some(doc) if doc.access.editor_key() == access.editor_key() => doc.access = access,
+            _ => {
+                docs.insert(key, DocRoles::new(doc_id, access));
+            }
+        }
+    }
+
+    /// Our role in a document
+    pub fn role(&self, doc_id: &str) -> Role {
+        let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+        match content_key(doc_id)
+            .and_then(|key| docs.get(&key))
+            .map(|doc| &doc.access)
+        {
+            Some(DocAccess::Viewer(..)) => Role::Viewer,
+            Some(DocAccess::Invited(_, signed, _)) => signed.invite.role,
+            _ => Role::Editor,
+        }
+    }
+
+    /// Key material for a ticket to a document, or `None` for a document
+    /// that isn't restricted (or a ticket asked of an invitee, or an editor
+    /// ticket of a viewer)
+    pub fn ticket_access(&self, doc_id: &str, role: Role) -> Option<TicketAccess> {
+        let key = content_key(doc_id)?;
+        let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+        let entry = docs.get(&key)?;
+        match (&entry.access, role) {
+            (DocAccess::Shared | DocAccess::Open | DocAccess::Invited(..), _) => None,
+            (DocAccess::Editor(key, reader), Role::Editor) => Some(TicketAccess::Editor {
+                key: key.to_bytes(),
+                reader: reader.to_bytes(),
+            }),
+            (DocAccess::Viewer(..), Role::Editor) => None,
+            (DocAccess::Editor(key, reader), Role::Viewer) => Some(TicketAccess::Viewer {
+                editor: *key.public().as_bytes(),
+                reader: reader.to_bytes(),
+            }),
+            (DocAccess::Viewer(editor, reader), Role::Viewer) => Some(TicketAccess::Viewer {
+                editor: *editor.as_bytes(),
+                reader: reader.to_bytes(),
+            }),
+        }
+    }
+
+    /// Sign an invite to a document we're an editor of (restricting it
+    /// first if we share it without keys), with a fresh holder key for the
+    /// invitee. `None` if we aren't an editor of the document.
+    pub fn issue_invite(
+        &self,
+        doc_id: &str,
+        role: Role,
+        label: Option<String>,
+        ttl: Duration,
+    ) -> Result<Option<TicketAccess>> {
+        self.restrict(doc_id)?;
+        let Some(key) = content_key(doc_id) else {
+            return Ok(None);
+        };
+        let (signed, key) = {
+            let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+            let Some(DocAccess::Editor(editor_key, _)) = docs.get(&key).map(|doc| &doc.access)
+            else {
+                return Ok(None);
+            };
+            let holder = generate_key();
+            let invite = Invite::new(doc_id, role, label, ttl, &holder.public());
+            let signed = SignedInvite::sign(invite, editor_key);
+            let key = holder.to_bytes();
+            let mut invites = self.invites.write().unwrap_or_else(|e| e.into_inner());
+            invites.issued.push((signed.clone(), key));
+            (signed, key)
+        };
+        self.save()?;
+        Ok(Some(TicketAccess::Invite {
+            invite: signed,
+            key,
+        }))
+    }
+
+    /// Tickets for the invites we issued for a document, with whether each
+    /// was revoked
+    pub fn issued_invites(&self, doc_id: &str) -> Vec<(TicketAccess, bool)> {
+        let invites = self.invites.read().unwrap_or_else(|e| e.into_inner());
+        invites
+            .issued
+            .iter()
+            .filter(|(signed, _)| signed.invite.doc_id == doc_id)
+            .map(|(signed, key)| {
+                let revoked = invites.revoked.contains(&Revocation::of(&signed.invite));
+                (
+                    TicketAccess::Invite {
+                        invite: signed.clone(),
+                        key: *key,
+                    },
+                    revoked,
+                )
+            })
+            .collect()
+    }
+
+    /// Revoke an invite we issued. Returns false for an unknown invite.
+    pub fn revoke_invite(&self, id: InviteId) -> Result<bool> {
+        {
+            let mut invites = self.invites.write().unwrap_or_else(|e| e.into_inner());
+            let Some(revocation) = invites
+                .issued
+                .iter()
+                .find(|(signed, _)| signed.invite.id == id)
+                .map(|(signed, _)| Revocation::of(&signed.invite))
+            else {
+                return Ok(false);
+            };
+            invites.revoked.insert(revocation);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Grants proving we may write to each document we're an editor of,
+    /// and read each one we're a viewer of
+    pub fn grants(&self, local: &PeerId) -> Vec<Grant> {
+        let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+        docs.values()
+            .filter_map(|doc| {
+                let (role, key) = match &doc.access {
+                    DocAccess::Editor(key, _) => (Role::Editor, key),
+                    DocAccess::Viewer(_, reader) => (Role::Viewer, reader),
+                    _ => return None,
+                };
+                let message = grant_message(role, &doc.doc_id, local);
+                Some(Grant {
+                    doc_id: doc.doc_id.clone(),
+                    role,
+                    signature: key.sign(&message).to_bytes().to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    /// Invites we joined with, presented as `local` to `to`. Only editors
+    /// of an invite's document get it: they're the ones who check it, and
+    /// nobody else needs to learn who was invited.
+    pub fn own_invites(&self, local: &PeerId, to: &PeerId) -> Vec<PresentedInvite> {
+        let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+        docs.values()
+            .filter(|doc| doc.editors.contains(to))
+            .filter_map(|doc| match &doc.access {
+                DocAccess::Invited(_, signed, holder) => {
+                    Some(PresentedInvite::new(signed.clone(), holder, local))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Invites revoked so far, to pass on to peers
+    pub fn revocations(&self) -> Vec<Revocation> {
+        let invites = self.invites.read().unwrap_or_else(|e| e.into_inner());
+        invites.revoked.iter().cloned().collect()
+    }
+
+    /// Remember the documents a peer proved it may write to or read.
+    /// Grants that don't verify against the document's editor or reader key
+    /// (or whose key we don't know) are ignored.
+    pub fn accept_grants(&self, peer: &PeerId, grants: &[Grant]) {
+        let mut docs = self.docs.write().unwrap_or_else(|e| e.into_inner());
+        for grant in grants {
+            let Some(doc) = content_key(&grant.doc_id).and_then(|key| docs.get_mut(&key)) else {
+                continue;
+            };
+            let key = match grant.role {
+                Role::Editor => doc.access.editor_key(),
+                Role::Viewer => doc.access.reader_key(),
+            };
+            let Some(key) = key else {
+                continue;
+            };
+            let Ok(bytes) = <[u8; 64]>::try_from(grant.signature.as_slice()) else {
+                continue;
+            };
+            let signature = Signature::from_bytes(&bytes);
+            if key
+                .verify(&grant_message(grant.role, &grant.doc_id, peer), &signature)
+                .is_ok()
+            {
+                match grant.role {
+                    Role::Editor => doc.editors.insert(*peer),
+                    Role::Viewer => doc.readers.insert(*peer),
+                };
+            }
+        }
+    }
+
+    /// Check the invites a peer presented. Invites to documents we don't
+    /// restrict are ignored; one that doesn't verify, or that its holder
+    /// presented as someone else, gets the peer refused.
+    pub fn accept_invites(&self, peer: &PeerId, presented: &[PresentedInvite]) {
+        let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+        let mut invites = self.invites.write().unwrap_or_else(|e| e.into_inner());
+        for presented in presented {
+            let invite = &presented.signed.invite;
+            let Some(editor_key) = content_key(&invite.doc_id)
+                .and_then(|key| docs.get(&key))
+                .and_then(|doc| doc.access.editor_key())
+            else {
+                continue;
+            };
+            if presented.verify(&editor_key, peer) {
+                invites
+                    .presented
+                    .insert((*peer, invite.doc_id.clone()), invite.clone());
+            } else {
+                invites.forged.insert(*peer);
+            }
+        }
+    }
+
+    /// Take each revocation only from a peer we know to be an editor of
+    /// the revoked invite's document (anyone else could use them to lock
+    /// participants out)
+    pub fn accept_revocations(&self, peer: &PeerId, revoked: &[Revocation]) {
+        if revoked.is_empty() {
+            return;
+        }
+        let mut learned = false;
+        {
+            let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+            let mut invites = self.invites.write().unwrap_or_else(|e| e.into_inner());
+            if invites.refuses(peer) {
+                return;
+            }
+            for revocation in revoked {
+                let is_editor = content_key(&revocation.doc_id)
+                    .and_then(|key| docs.get(&key))
+                    .is_some_and(|doc| doc.editors.contains(peer))
+                    || invites
+                        .get(peer, &revocation.doc_id)
+                        .is_some_and(|invite| invite.role == Role::Editor);
+                if is_editor {
+                    learned |= invites.revoked.insert(revocation.clone());
+                }
+            }
+        }
+        if learned {
+            // Editors pass revocations on again if this one doesn't stick
+            let _ = self.save();
+        }
+    }
+
+    /// Whether a peer presented a forged invite, or only expired or
+    /// revoked ones
+    pub fn is_refused(&self, peer: &PeerId) -> bool {
+        let invites = self.invites.read().unwrap_or_else(|e| e.into_inner());
+        invites.refuses(peer)
+    }
+
+    /// Whether a document may be served to a peer: only documents we shared
+    /// or joined, and restricted ones only to peers with a verified grant or
+    /// a valid invite
+    pub fn can_read(&self, id: &DocumentId, peer: &PeerId) -> bool {
+        let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+        let invites = self.invites.read().unwrap_or_else(|e| e.into_inner());
+        match docs.get(&id.content_key()) {
+            None => false,
+            Some(DocRoles {
+                access: DocAccess::Shared | DocAccess::Open,
+                ..
+            }) => !invites.refuses(peer),
+            Some(doc) => {
+                !invites.refuses_in(peer, &doc.doc_id)
+                    && (doc.editors.contains(peer)
+                        || doc.readers.contains(peer)
+                        || invites.get(peer, &doc.doc_id).is_some())
+            }
+        }
+    }
+
+    /// Note that a peer synced a document with us
+    pub fn mark_syncing(&self, id: &DocumentId, peer: &PeerId) {
+        let mut docs = self.docs.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(doc) = docs.get_mut(&id.content_key()) {
+            doc.syncing.insert(*peer);
+        }
+    }
+
+    /// Whether chat about a document may go to a peer: it syncs the
+    /// document with us and may read it
+    pub fn shares_chat(&self, doc_id: &str, peer: &PeerId) -> bool {
+        let Ok(id) = DocumentId::from_string(doc_id) else {
+            return false;
+        };
+        let syncing = {
+            let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+            docs.get(&id.content_key())
+                .is_some_and(|doc| doc.syncing.contains(peer))
+        };
+        syncing && self.can_read(&id, peer)
+    }
+
+    /// Whether changes from a peer to a document may enter the store (never
+    /// to one we didn't share or join)
+    pub fn can_write(&self, id: &DocumentId, peer: &PeerId) -> bool {
+        let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+        let invites = self.invites.read().unwrap_or_else(|e| e.into_inner());
+        match docs.get(&id.content_key()) {
+            None => false,
+            Some(DocRoles {
+                access: DocAccess::Shared | DocAccess::Open,
+                ..
+            }) => !invites.refuses(peer),
+            Some(doc) => {
+                !invites.refuses_in(peer, &doc.doc_id)
+                    && (doc.editors.contains(peer)
+                        || invites
+                            .get(peer, &doc.doc_id)
+                            .is_some_and(|invite| invite.role == Role::Editor))
+            }
+        }
+    }
+}
+
+/// Store content key for an app-facing document ID
+fn content_key(doc_id: &str) -> Option<String> {
+    DocumentId::from_string(doc_id)
+        .ok()
+        .map(|id| id.content_key())
+}
+
+/// Bytes a grant for `role` signs
+fn grant_message(role: Role, doc_id: &str, peer: &PeerId) -> Vec<u8> {
+    let context = match role {
+        Role::Editor => GRANT_CONTEXT,
+        Role::Viewer => READER_GRANT_CONTEXT,
+    };
+    let mut msg = Vec::with_capacity(context.len() + doc_id.len() + 33);
+    msg.extend_from_slice(context);
+    msg.extend_from_slice(doc_id.as_bytes());
+    msg.push(0);
+    msg.extend_from_slice(peer.as_bytes());
+    msg
+}
+
+/// Replace the file at `path` with `bytes`, readable only by its owner
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+/// Fresh secret key, straight from the OS random source
+pub(crate) fn generate_key() -> SecretKey {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).expect("OS random source unavailable");
+    SecretKey::from_bytes(&bytes)
+}
+
+/// The sync store as seen by one remote peer: restricted documents are only
+/// read for peers with a credential, writes from a peer that may not write
+/// are dropped, and a refused peer gets nothing at all
+pub struct GatedStore {
+    store: Arc<SyncStore>,
+    roles: Arc<Roles>,
+    peer: PeerId,
+    /// Whether `peer` dialed us, so each document it reads is one it syncs
+    serving: bool,
+}
+
+impl GatedStore {
+    /// Gate `store` for syncing with `peer`
+    pub fn new(store: Arc<SyncStore>, roles: Arc<Roles>, peer: PeerId) -> Self {
+        Self {
+            store,
+            roles,
+            peer,
+            serving: false,
+        }
+    }
+
+    /// Gate `store` for serving `peer`'s sync requests
+    fn serving(store: Arc<SyncStore>, roles: Arc<Roles>, peer: PeerId) -> Self {
+        Self {
+            serving: true,
+            ..Self::new(store, roles, peer)
+        }
+    }
+
+    /// Note that a sync round for a document with the peer completed
+    pub fn synced(&self, id: &DocumentId) {
+        self.roles.mark_syncing(id, &self.peer);
+    }
+
+    fn check_refused(&self) -> AutomergeResult<()> {
+        if self.roles.is_refused(&self.peer) {
+            Err(AutomergeError::AutomergeLib {
+                message: format!("peer {} presented an invalid invite", self.peer),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_read(&self, id: &DocumentId) -> AutomergeResult<()> {
+        if self.roles.can_read(id, &self.peer) {
+            Ok(())
+        } else {
+            Err(AutomergeError::AutomergeLib {
+                message: format!("{} is not shared with peer {}", id, self.peer),
+            })
+        }
+    }
+
+    fn check_write(&self, id: &DocumentId) -> AutomergeResult<()> {
+        if self.roles.can_write(id, &self.peer) {
+            Ok(())
+        } else {
+            Err(AutomergeError::AutomergeLib {
+                message: format!("{} is read-only for peer {}", id, self.peer),
+            })
+        }
+    }
+}
+
+#[async_trait]
+impl DocumentStore for GatedStore {
+    async fn create(
+        &self,
+        id: Option<DocumentId>,
+        metadata: Option<DocumentMetadata>,
+    ) -> AutomergeResult<DocumentId> {
+        // Peers only sync documents we shared or joined, never new ones
+        match &id {
+            Some(id) => self.check_write(id)?,
+            None => {
+                return Err(AutomergeError::AutomergeLib {
+                    message: format!("peer {} can't create documents", self.peer),
+                });
+            }
+        }
+        self.store.create(id, metadata).await
+    }
+
+    async fn get(&self, id: &DocumentId) -> AutomergeResult<Option<AutoCommit>> {
+        self.check_read(id)?;
+        if self.serving {
+            self.synced(id);
+        }
+        self.store.get(id).await
+    }
+
+    async fn get_metadata(&self, id: &DocumentId) -> AutomergeResult<Option<DocumentMetadata>> {
+        self.check_read(id)?;
+        self.store.get_metadata(id).await
+    }
+
+    async fn save(&self, id: &DocumentId, doc: &mut AutoCommit) -> AutomergeResult<()> {
+        // Dropped rather than failed, so the sync round still sends the
+        // peer our changes
+        if !self.roles.can_write(id, &self.peer) {
+            return Ok(());
+        }
+        self.store.save(id, doc).await
+    }
+
+    async fn delete(&self, id: &DocumentId) -> AutomergeResult<bool> {
+        self.check_write(id)?;
+        self.store.delete(id).await
+    }
+
+    async fn exists(&self, id: &DocumentId) -> AutomergeResult<bool> {
+        if !self.roles.can_read(id, &self.peer) {
+            return Ok(false);
+        }
+        self.store.exists(id).await
+    }
+
+    async fn apply_changes(
+        &self,
+        id: &DocumentId,
+        changes: Vec<DocumentChange>,
+    ) -> AutomergeResult<ApplyResult> {
+        self.check_write(id)?;
+        self.store.apply_changes(id, changes).await
+    }
+
+    async fn get_heads(&self, id: &DocumentId) -> AutomergeResult<Vec<String>> {
+        self.check_read(id)?;
+        self.store.get_heads(id).await
+    }
+
+    async fn merge(
+        &self,
+        target_id: &DocumentId,
+        source_id: &DocumentId,
+    ) -> AutomergeResult<ApplyResult> {
+        self.check_write(target_id)?;
+        self.store.merge(target_id, source_id).await
+    }
+
+    async fn list(&self, options: ListOptions) -> AutomergeResult<ListResult> {
+        self.check_refused()?;
+        self.store.list(options).await
+    }
+
+    async fn list_ids(
+        &self,
+        namespace: Option<&str>,
+        limit: u32,
+    ) -> AutomergeResult<Vec<DocumentId>> {
+        let ids = self.store.list_ids(namespace, limit).await?;
+        Ok(ids
+            .into_iter()
+            .filter(|id| self.roles.can_read(id, &self.peer))
+            .collect())
+    }
+}
+
+/// Automerge sync protocol handler that serves each connection through a
+/// [`GatedStore`] for the dialing peer, and hangs up on refused peers.
+/// Every connection it serves is passed on to the sync loop, which reports
+/// it as a link.
+#[derive(Clone)]
+pub struct SyncGate {
+    store: Arc<SyncStore>,
+    roles: Arc<Roles>,
+    inbound_tx: tokio::sync::mpsc::Sender<Connection>,
+}
+
+impl std::fmt::Debug for SyncGate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncGate").finish()
+    }
+}
+
+impl SyncGate {
+    /// Create a gate over the sync store, sending each connection it
+    /// serves to `inbound_tx`
+    pub fn new(
+        store: Arc<SyncStore>,
+        roles: Arc<Roles>,
+        inbound_tx: tokio::sync::mpsc::Sender<Connection>,
+    ) -> Self {
+        Self {
+            store,
+            roles,
+            inbound_tx,
+        }
+    }
+}
+
+impl ProtocolHandler for SyncGate {
+    fn accept(&self, conn: Connection) -> impl Future<Output = Result<(), AcceptError>> + Send {
+        let store = self.store.clone();
+        let roles = self.roles.clone();
+        let inbound_tx = self.inbound_tx.clone();
+        async move {
+            let peer = PeerId::from_bytes(conn.remote_id().as_bytes()).ok_or_else(|| {
+                AcceptError::from_err(std::io::Error::other("invalid remote endpoint id"))
+            })?;
+            if roles.is_refused(&peer) {
+                conn.close(0u32.into(), b"invite refused");
+                return Ok(());
+            }
+            let _ = inbound_tx.send(conn.clone()).await;
+            let handler =
+                AutomergeSyncHandler::new(Arc::new(GatedStore::serving(store, roles, peer)));
+            handler.accept(conn).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "design";
+
+    fn peer(n: u8) -> PeerId {
+        PeerId([n; 32])
+    }
+
+    fn doc_id() -> DocumentId {
+        DocumentId::from_string(DOC).unwrap()
+    }
+
+    /// An invite ticket's invite, presented by `presenter`
+    fn present(access: &TicketAccess, presenter: &PeerId) -> PresentedInvite {
+        let TicketAccess::Invite { invite, key } = access else {
+            panic!("expected an invite ticket");
+        };
+        PresentedInvite::new(invite.clone(), &SecretKey::from_bytes(key), presenter)
+    }
+
+    #[test]
+    fn test_unshared_documents_are_refused() {
+        let roles = Roles::new();
+        assert!(!roles.can_read(&doc_id(), &peer(1)));
+        assert!(!roles.can_write(&doc_id(), &peer(1)));
+        assert_eq!(roles.role(DOC), Role::Editor);
+    }
+
+    #[test]
+    fn test_restricting_admits_only_editors() {
+        let host = Roles::new();
+        host.restrict(DOC).unwrap();
+        let access = host.ticket_access(DOC, Role::Editor).unwrap();
+        assert_eq!(access.role(), Role::Editor);
+        assert!(!host.can_write(&doc_id(), &peer(2)));
+
+        // An editor joining with the ticket proves it to the host
+        let editor = Roles::new();
+        editor.register(DOC, Some(&access));
+        host.accept_grants(&peer(2), &editor.grants(&peer(2)));
+        assert!(host.can_write(&doc_id(), &peer(2)));
+    }
+
+    #[test]
+    fn test_viewer_grants_are_rejected() {
+        let host = Roles::new();
+        host.restrict(DOC).unwrap();
+        let access = host.ticket_access(DOC, Role::Viewer).unwrap();
+        assert_eq!(access.role(), Role::Viewer);
+
+        let viewer = Roles::new();
+        viewer.register(DOC, Some(&access));
+        assert_eq!(viewer.role(DOC), Role::Viewer);
+        assert!(viewer.ticket_access(DOC, Role::Editor).is_none());
+
+        // A viewer's grant lets it read but not write
+        host.accept_grants(&peer(3), &viewer.grants(&peer(3)));
+        assert!(host.can_read(&doc_id(), &peer(3)));
+        assert!(!host.can_write(&doc_id(), &peer(3)));
+
+        // A grant signed for someone else doesn't transfer
+        let grants = host.grants(&peer(1));
+        host.accept_grants(&peer(3), &grants);
+        assert!(!host.can_write(&doc_id(), &peer(3)));
+
+        // The viewer accepts the host's changes once it has the host's grant
+        viewer.accept_grants(&peer(1), &grants);
+        assert!(viewer.can_write(&doc_id(), &peer(1)));
+        assert!(!viewer.can_write(&doc_id(), &peer(3)));
+    }
+
+    #[test]
+    fn test_keyless_tickets_stay_open() {
+        let roles = Roles::new();
+        roles.register(DOC, None);
+        assert!(roles.ticket_access(DOC, Role::Editor).is_none());
+        assert!(roles.ticket_access(DOC, Role::Viewer).is_none());
+        assert!(roles.can_write(&doc_id(), &peer(4)));
+        assert!(roles.can_read(&doc_id(), &peer(4)));
+
+        // Only the document's host can restrict it
+        roles.restrict(DOC).unwrap();
+        assert!(roles.ticket_access(DOC, Role::Viewer).is_none());
+        assert!(roles.can_write(&doc_id(), &peer(4)));
+    }
+
+    #[test]
+    fn test_shared_documents_stay_open_until_restricted() {
+        let host = Roles::new();
+        host.share(DOC);
+        assert!(host.ticket_access(DOC, Role::Editor).is_none());
+        assert!(host.can_write(&doc_id(), &peer(4)));
+
+        host.restrict(DOC).unwrap();
+        assert_eq!(
+            host.ticket_access(DOC, Role::Editor)
+                .map(|access| access.role()),
+            Some(Role::Editor)
+        );
+        assert!(!host.can_write(&doc_id(), &peer(4)));
+
+        // Sharing again doesn't undo the restriction
+        host.share(DOC);
+        assert!(host.ticket_access(DOC, Role::Viewer).is_some());
+    }
+
+    #[test]
+    fn test_chat_goes_only_to_readers_syncing_the_document() {
+        let host = Roles::new();
+        host.share(DOC);
+        assert!(!host.shares_chat(DOC, &peer(4)));
+
+        host.mark_syncing(&doc_id(), &peer(4));
+        assert!(host.shares_chat(DOC, &peer(4)));
+        assert!(!host.shares_chat(DOC, &peer(5)));
+        assert!(!host.shares_chat("notes", &peer(4)));
+
+        // Restricting drops peers that synced without keys
+        host.restrict(DOC).unwrap();
+        host.mark_syncing(&doc_id(), &peer(4));
+        assert!(!host.shares_chat(DOC, &peer(4)));
+    }
+
+    #[test]
+    fn test_keys_survive_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("roles.key");
+        let host = Roles::load(Some(path.clone())).unwrap();
+        host.restrict(DOC).unwrap();
+        let access = host.ticket_access(DOC, Role::Editor);
+        assert!(access.is_some());
+
+        let restarted = Roles::load(Some(path)).unwrap();
+        assert_eq!(restarted.ticket_access(DOC, Role::Editor), access);
+        assert!(restarted.ticket_access("notes", Role::Editor).is_none());
+    }
+
+    #[test]
+    fn test_invites_survive_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("roles.key");
+        let host = Roles::load(Some(path.clone())).unwrap();
+        let kept = host
+            .issue_invite(DOC, Role::Editor, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        let revoked = host
+            .issue_invite(DOC, Role::Viewer, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        assert!(host.revoke_invite(revoked.invite().unwrap().id).unwrap());
+
+        // Before anyone connects, the restarted host knows both invites and
+        // still refuses the revoked one
+        let restarted = Roles::load(Some(path)).unwrap();
+        let issued = restarted.issued_invites(DOC);
+        assert_eq!(issued, vec![(kept.clone(), false), (revoked.clone(), true)]);
+        restarted.accept_invites(&peer(17), &[present(&kept, &peer(17))]);
+        assert!(restarted.can_write(&doc_id(), &peer(17)));
+        restarted.accept_invites(&peer(18), &[present(&revoked, &peer(18))]);
+        assert!(restarted.is_refused(&peer(18)));
+    }
+
+    #[test]
+    fn test_restricted_documents_need_a_credential_to_read() {
+        let host = Roles::new();
+        host.share(DOC);
+        assert!(host.can_read(&doc_id(), &peer(15)));
+        host.restrict(DOC).unwrap();
+        let access = host.ticket_access(DOC, Role::Viewer).unwrap();
+        assert!(!host.can_read(&doc_id(), &peer(15)));
+
+        // Knowing only the editor key's public half proves nothing
+        let TicketAccess::Viewer { editor, .. } = access else {
+            panic!("expected a viewer ticket");
+        };
+        let impostor = Roles::new();
+        let forged = TicketAccess::Viewer {
+            editor,
+            reader: generate_key().to_bytes(),
+        };
+        impostor.register(DOC, Some(&forged));
+        host.accept_grants(&peer(15), &impostor.grants(&peer(15)));
+        assert!(!host.can_read(&doc_id(), &peer(15)));
+
+        // Documents we never shared aren't served at all
+        let notes_id = DocumentId::from_string("notes").unwrap();
+        assert!(!host.can_read(&notes_id, &peer(15)));
+    }
+
+    #[test]
+    fn test_viewer_changes_never_reach_the_store() {
+        tokio_test::block_on(async {
+            let (tx, _rx) = tokio::sync::mpsc::channel(64);
+            let store = Arc::new(SyncStore::open(None, tx).unwrap());
+            store.create(Some(doc_id()), None).await.unwrap();
+            let host = Arc::new(Roles::new());
+            host.restrict(DOC).unwrap();
+            let access = host.ticket_access(DOC, Role::Viewer).unwrap();
+            let viewer = Roles::new();
+            viewer.register(DOC, Some(&access));
+            host.accept_grants(&peer(16), &viewer.grants(&peer(16)));
+            let gated = GatedStore::new(store.clone(), host, peer(16));
+
+            // What the sync handler does with a round's changes
+            let mut doc = gated.get(&doc_id()).await.unwrap().unwrap();
+            let before = doc.get_heads();
+            doc.put(automerge::ROOT, "viewer", 1).unwrap();
+            doc.commit();
+            gated.save(&doc_id(), &mut doc).await.unwrap();
+            assert!(gated.apply_changes(&doc_id(), Vec::new()).await.is_err());
+            let stored = store.get(&doc_id()).await.unwrap().unwrap();
+            assert_eq!(stored.get_heads(), before);
+        });
+    }
+
+    #[test]
+    fn test_revoked_invitees_cant_read() {
+        let host = Roles::new();
+        let access = host
+            .issue_invite(DOC, Role::Viewer, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        assert!(!host.can_read(&doc_id(), &peer(16)));
+        host.accept_invites(&peer(16), &[present(&access, &peer(16))]);
+        assert!(host.can_read(&doc_id(), &peer(16)));
+
+        assert!(host.revoke_invite(access.invite().unwrap().id).unwrap());
+        assert!(!host.can_read(&doc_id(), &peer(16)));
+    }
+
+    #[test]
+    fn test_editor_invites_allow_writes() {
+        let host = Roles::new();
+        let access = host
+            .issue_invite(
+                DOC,
+                Role::Editor,
+                Some("alice".to_string()),
+                Duration::from_secs(60),
+            )
+            .unwrap()
+            .unwrap();
+        assert!(!host.can_write(&doc_id(), &peer(5)));
+
+        let invitee = Roles::new();
+        invitee.register(DOC, Some(&access));
+        assert_eq!(invitee.role(DOC), Role::Editor);
+        assert!(invitee.ticket_access(DOC, Role::Editor).is_none());
+
+        // The invite is only presented once the host proved it's an editor
+        assert!(invitee.own_invites(&peer(5), &peer(1)).is_empty());
+        invitee.accept_grants(&peer(1), &host.grants(&peer(1)));
+        assert!(invitee.own_invites(&peer(5), &peer(9)).is_empty());
+        host.accept_invites(&peer(5), &invitee.own_invites(&peer(5), &peer(1)));
+        assert!(host.can_write(&doc_id(), &peer(5)));
+        assert!(!host.is_refused(&peer(5)));
+
+        let issued = host.issued_invites(DOC);
+        assert_eq!(issued.len(), 1);
+        assert_eq!(issued[0].0, access);
+        assert_eq!(
+            issued[0].0.invite().unwrap().label.as_deref(),
+            Some("alice")
+        );
+        assert!(!issued[0].1);
+    }
+
+    #[test]
+    fn test_viewer_invites_stay_read_only() {
+        let host = Roles::new();
+        let access = host
+            .issue_invite(DOC, Role::Viewer, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        host.accept_invites(&peer(6), &[present(&access, &peer(6))]);
+        assert!(!host.is_refused(&peer(6)));
+        assert!(!host.can_write(&doc_id(), &peer(6)));
+    }
+
+    #[test]
+    fn test_expired_invites_are_refused() {
+        let host = Roles::new();
+        let access = host
+            .issue_invite(DOC, Role::Editor, None, Duration::ZERO)
+            .unwrap()
+            .unwrap();
+        assert!(access.invite().unwrap().is_expired());
+        host.accept_invites(&peer(7), &[present(&access, &peer(7))]);
+        assert!(host.is_refused(&peer(7)));
+        assert!(!host.can_write(&doc_id(), &peer(7)));
+    }
+
+    #[test]
+    fn test_revoked_invites_are_refused() {
+        let host = Roles::new();
+        let access = host
+            .issue_invite(DOC, Role::Editor, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        host.accept_invites(&peer(8), &[present(&access, &peer(8))]);
+        assert!(host.can_write(&doc_id(), &peer(8)));
+
+        assert!(host.revoke_invite(access.invite().unwrap().id).unwrap());
+        assert!(!host.revoke_invite(InviteId::new_v4()).unwrap());
+        assert!(host.is_refused(&peer(8)));
+        assert!(!host.can_write(&doc_id(), &peer(8)));
+        assert!(host.issued_invites(DOC)[0].1);
+
+        // Other participants learn the revocation from an editor
+        let editor = Roles::new();
+        editor.register(DOC, host.ticket_access(DOC, Role::Editor).as_ref());
+        editor.accept_grants(&peer(1), &host.grants(&peer(1)));
+        editor.accept_invites(&peer(8), &[present(&access, &peer(8))]);
+        assert!(!editor.is_refused(&peer(8)));
+        editor.accept_revocations(&peer(9), &host.revocations());
+        assert!(!editor.is_refused(&peer(8)));
+        editor.accept_revocations(&peer(1), &host.revocations());
+        assert!(editor.is_refused(&peer(8)));
+    }
+
+    #[test]
+    fn test_revocations_only_count_from_the_documents_editors() {
+        let host = Roles::new();
+        let access = host
+            .issue_invite(DOC, Role::Editor, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        let id = access.invite().unwrap().id;
+        host.accept_invites(&peer(8), &[present(&access, &peer(8))]);
+
+        // Peer 2 edits another document we sync, but not this one
+        let other = Roles::new();
+        other.restrict("other").unwrap();
+        host.register("other", other.ticket_access("other", Role::Editor).as_ref());
+        host.accept_grants(&peer(2), &other.grants(&peer(2)));
+        host.accept_revocations(
+            &peer(2),
+            &[Revocation {
+                id,
+                doc_id: DOC.to_string(),
+            }],
+        );
+        assert!(!host.is_refused(&peer(8)));
+
+        // Nor can it pass the invite off as one to its own document
+        host.accept_revocations(
+            &peer(2),
+            &[Revocation {
+                id,
+                doc_id: "other".to_string(),
+            }],
+        );
+        assert!(!host.is_refused(&peer(8)));
+        assert!(host.can_write(&doc_id(), &peer(8)));
+    }
+
+    #[test]
+    fn test_forged_invites_are_refused() {
+        let host = Roles::new();
+        host.restrict(DOC).unwrap();
+        let other = Roles::new();
+        let access = other
+            .issue_invite(DOC, Role::Editor, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        host.accept_invites(&peer(10), &[present(&access, &peer(10))]);
+        assert!(host.is_refused(&peer(10)));
+
+        // Tampering with a genuine invite breaks its signature
+        let mut genuine = host
+            .issue_invite(DOC, Role::Viewer, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        if let TicketAccess::Invite { invite, .. } = &mut genuine {
+            invite.invite.role = Role::Editor;
+        }
+        host.accept_invites(&peer(11), &[present(&genuine, &peer(11))]);
+        assert!(host.is_refused(&peer(11)));
+    }
+
+    #[test]
+    fn test_invites_to_several_documents_are_kept_apart() {
+        let host = Roles::new();
+        let design = host
+            .issue_invite(DOC, Role::Editor, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        let notes = host
+            .issue_invite("notes", Role::Viewer, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        host.accept_invites(
+            &peer(14),
+            &[present(&design, &peer(14)), present(&notes, &peer(14))],
+        );
+        let notes_id = DocumentId::from_string("notes").unwrap();
+        assert!(host.can_write(&doc_id(), &peer(14)));
+        assert!(!host.can_write(&notes_id, &peer(14)));
+
+        // Revoking one invite leaves the other standing
+        assert!(host.revoke_invite(notes.invite().unwrap().id).unwrap());
+        assert!(!host.is_refused(&peer(14)));
+        assert!(host.can_write(&doc_id(), &peer(14)));
+        assert!(host.revoke_invite(design.invite().unwrap().id).unwrap());
+        assert!(host.is_refused(&peer(14)));
+        assert!(!host.can_write(&doc_id(), &peer(14)));
+    }
+
+    #[test]
+    fn test_presented_invites_cant_be_replayed() {
+        let host = Roles::new();
+        let access = host
+            .issue_invite(DOC, Role::Editor, None, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        let presented = present(&access, &peer(12));
+
+        // Someone who saw the presentation can't pass it off as theirs
+        host.accept_invites(&peer(13), &[presented.clone()]);
+        assert!(host.is_refused(&peer(13)));
+        assert!(!host.can_write(&doc_id(), &peer(13)));
+
+        host.accept_invites(&peer(12), &[presented]);
+        assert!(!host.is_refused(&peer(12)));
+        assert!(host.can_write(&doc_id(), &peer(12)));
+    }
+}
@@ -0,0 +1,262 @@
+//! In-session text chat between participants
+//!
+//! Uses ALPN "irohscii/chat/1" over iroh connections, with the same
+//! length-prefixed msgpack framing as presence. Each link gets its own chat
+//! connection; messages go straight to every participant that syncs their
+//! document and may read it (the mesh is full), so nothing is relayed and
+//! the sender is always the connection's peer.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::Result;
+use iroh::endpoint::Connection;
+use iroh::protocol::{AcceptError, ProtocolHandler};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use irohscii_core::Position;
+
+use super::access::Roles;
+use crate::peer::PeerId;
+
+/// Protocol identifier for chat
+pub const CHAT_ALPN: &[u8] = b"irohscii/chat/1";
+
+/// Longest message text accepted, in characters
+pub const MAX_CHAT_TEXT_LEN: usize = 1000;
+
+/// Largest encoded message accepted off the wire
+const MAX_MESSAGE_SIZE: u32 = 64 * 1024;
+
+/// A chat message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub id: Uuid,
+    /// Document (session) the message belongs to
+    pub doc_id: String,
+    /// Sender; the receiver overwrites it with the connection's peer
+    pub from: PeerId,
+    /// Sender's display name
+    pub name: Option<String>,
+    /// Sender's cursor color (index into the presence palette)
+    pub color_index: u8,
+    pub text: String,
+    /// Unix timestamp in milliseconds
+    pub timestamp_ms: u64,
+    /// Canvas position the message points at, if pinned
+    pub pin: Option<Position>,
+}
+
+/// Chat protocol handler for iroh
+#[derive(Clone)]
+pub struct ChatProtocol {
+    inner: Arc<ChatInner>,
+}
+
+impl std::fmt::Debug for ChatProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChatProtocol").finish()
+    }
+}
+
+struct ChatInner {
+    /// Broadcast channel for outgoing messages
+    outgoing_tx: broadcast::Sender<ChatMessage>,
+    /// Channel to notify main thread of incoming messages
+    incoming_tx: mpsc::Sender<ChatMessage>,
+    /// Decides which peers get each message
+    roles: Arc<Roles>,
+}
+
+impl ChatProtocol {
+    /// Protocol identifier
+    pub const ALPN: &'static [u8] = CHAT_ALPN;
+
+    /// Create a new chat protocol handler
+    pub fn new(incoming_tx: mpsc::Sender<ChatMessage>, roles: Arc<Roles>) -> Self {
+        let (outgoing_tx, _) = broadcast::channel(64);
+        Self {
+            inner: Arc::new(ChatInner {
+                outgoing_tx,
+                incoming_tx,
+                roles,
+            }),
+        }
+    }
+
+    /// Send a message to every connected participant syncing its document
+    pub fn send(&self, msg: ChatMessage) {
+        let _ = self.inner.outgoing_tx.send(msg);
+    }
+
+    /// Handle incoming connection (as acceptor/server)
+    async fn handle_peer(&self, conn: Connection) -> Result<()> {
+        let peer = remote_peer_id(&conn)?;
+        let (mut send, mut recv) = conn.accept_bi().await?;
+        self.run_chat(peer, &mut send, &mut recv).await
+    }
+
+    /// Run the chat loop as initiator (client)
+    pub async fn run_chat_loop(&self, conn: Connection) -> Result<()> {
+        let peer = remote_peer_id(&conn)?;
+        let (mut send, mut recv) = conn.open_bi().await?;
+        // The acceptor only sees the stream once something is sent on it
+        send.write_all(&0u32.to_le_bytes()).await?;
+        self.run_chat(peer, &mut send, &mut recv).await
+    }
+
+    /// Relay our messages to the peer and theirs to the main thread
+    async fn run_chat<S, R>(&self, peer: PeerId, send: &mut S, recv: &mut R) -> Result<()>
+    where
+        S: AsyncWriteExt + Unpin,
+        R: AsyncReadExt + Unpin,
+    {
+        let mut outgoing_rx = self.inner.outgoing_tx.subscribe();
+        // Whole loops race each other, so a send never interrupts a
+        // half-read frame
+        let outgoing = async {
+            loop {
+                match outgoing_rx.recv().await {
+                    Ok(msg) if self.inner.roles.shares_chat(&msg.doc_id, &peer) => {
+                        send_chat_msg(send, &msg).await?
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Closed) => return Ok::<(), anyhow::Error>(()),
+                    // Chat is best-effort, like presence
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                }
+            }
+        };
+        let incoming = async {
+            // Ends when the connection closes
+            while let Ok(mut msg) = recv_chat_msg(recv).await {
+                if msg.text.chars().count() > MAX_CHAT_TEXT_LEN {
+                    continue;
+                }
+                msg.from = peer;
+                let _ = self.inner.incoming_tx.send(msg).await;
+            }
+            Ok::<(), anyhow::Error>(())
+        };
+        tokio::select! {
+            result = outgoing => result,
+            result = incoming => result,
+        }
+    }
+}
+
+impl ProtocolHandler for ChatProtocol {
+    fn accept(&self, conn: Connection) -> impl Future<Output = Result<(), AcceptError>> + Send {
+        let this = self.clone();
+        async move {
+            this.handle_peer(conn)
+                .await
+                .map_err(|e| AcceptError::from_err(std::io::Error::other(e.to_string())))
+        }
+    }
+}
+
+/// The peer on the other end of a connection
+fn remote_peer_id(conn: &Connection) -> Result<PeerId> {
+    PeerId::from_bytes(conn.remote_id().as_bytes())
+        .ok_or_else(|| anyhow::anyhow!("invalid remote endpoint id"))
+}
+
+/// Send a chat message (length-prefixed msgpack)
+async fn send_chat_msg<W: AsyncWriteExt + Unpin>(writer: &mut W, msg: &ChatMessage) -> Result<()> {
+    let data = rmp_serde::to_vec(msg)?;
+    let len = u32::try_from(data.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| anyhow::anyhow!("Chat message too large: {} bytes", data.len()))?;
+    writer.write_all(&len.to_le_bytes()).await?;
+    writer.write_all(&data).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Receive a chat message, skipping empty frames (sent to open the stream)
+async fn recv_chat_msg<R: AsyncReadExt + Unpin>(reader: &mut R) -> Result<ChatMessage> {
+    let mut len_bytes = [0u8; 4];
+    let mut len = 0;
+    while len == 0 {
+        reader.read_exact(&mut len_bytes).await?;
+        len = u32::from_le_bytes(len_bytes);
+    }
+    if len > MAX_MESSAGE_SIZE {
+        anyhow::bail!("Chat message too large: {} bytes", len);
+    }
+    let mut data = vec![0u8; len as usize];
+    reader.read_exact(&mut data).await?;
+    Ok(rmp_serde::from_slice(&data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use aspen_automerge::DocumentId;
+
+    use super::*;
+
+    fn message(text: &str) -> ChatMessage {
+        ChatMessage {
+            id: Uuid::new_v4(),
+            doc_id: "doc".to_string(),
+            from: PeerId([1; 32]),
+            name: Some("Ada".to_string()),
+            color_index: 2,
+            text: text.to_string(),
+            timestamp_ms: 1,
+            pin: Some(Position::new(3, 4)),
+        }
+    }
+
+    #[tokio::test]
+    async fn chat_message_round_trips_over_the_wire() {
+        let msg = message("look at the box on the left");
+        let mut buf = 0u32.to_le_bytes().to_vec();
+        send_chat_msg(&mut buf, &msg).await.unwrap();
+        let decoded = recv_chat_msg(&mut buf.as_slice()).await.unwrap();
+        assert_eq!(decoded, msg);
+    }
+
+    #[tokio::test]
+    async fn chat_goes_only_to_peers_syncing_its_document() {
+        let peer = PeerId([1; 32]);
+        let roles = Arc::new(Roles::new());
+        roles.share("doc");
+        roles.share("notes");
+        roles.mark_syncing(&DocumentId::from_string("doc").unwrap(), &peer);
+        let (incoming_tx, _incoming_rx) = mpsc::channel(4);
+        let chat = ChatProtocol::new(incoming_tx, roles);
+
+        let (mut send, mut wire) = tokio::io::duplex(4096);
+        let (_quiet, mut recv) = tokio::io::duplex(64);
+        let task = {
+            let chat = chat.clone();
+            tokio::spawn(async move { chat.run_chat(peer, &mut send, &mut recv).await })
+        };
+        while chat.inner.outgoing_tx.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        chat.send(ChatMessage {
+            doc_id: "notes".to_string(),
+            ..message("not for this peer")
+        });
+        chat.send(message("hello"));
+        let received = recv_chat_msg(&mut wire).await.unwrap();
+        assert_eq!(received.text, "hello");
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn oversized_chat_message_is_rejected() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(MAX_MESSAGE_SIZE + 1).to_le_bytes());
+        assert!(recv_chat_msg(&mut buf.as_slice()).await.is_err());
+        assert!(send_chat_msg(&mut Vec::new(), &message(&"x".repeat(100_000))).await.is_err());
+    }
+}
@@ -0,0 +1,678 @@
+//! On-disk document store for irohscii.
+//!
+//! Implements the aspen-automerge DocumentStore trait on top of a directory,
+//! so documents survive restarts and a crashed sync thread. Each document is
+//! an Automerge snapshot followed by the change chunks appended on every save
+//! (`save_after` the heads already on disk); the file is compacted back into
+//! a single snapshot once the appended chunks outgrow it. A chunk cut short
+//! by a crash mid-append is dropped on load, and the file truncated to the
+//! last good chunk.
+//!
+//! Only open documents are served: `create` loads a document back from disk
+//! if it was saved before, and `close` drops it from memory but keeps its
+//! files.
+//!
+//! Layout (directory names are the hex-encoded document content key):
+//! ```text
+//! <dir>/<key>/doc.automerge      snapshot + incremental change chunks
+//! <dir>/<key>/peers/<peer>.sync  Automerge sync::State per remote peer
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use automerge::{AutoCommit, ChangeHash, sync};
+use tokio::sync::{RwLock, mpsc};
+
+use aspen_automerge::{
+    ApplyResult, AutomergeError, AutomergeResult, DocumentChange, DocumentId,
+    DocumentMetadata, DocumentStore, ListOptions, ListResult,
+};
+
+use crate::peer::PeerId;
+
+/// Document file inside each document directory
+const DOC_FILE: &str = "doc.automerge";
+
+/// Directory holding per-peer sync states
+const PEERS_DIR: &str = "peers";
+
+/// Appended chunks smaller than this never trigger compaction
+const MIN_COMPACT_BYTES: usize = 64 * 1024;
+
+/// Bytes of an Automerge chunk header before its LEB128 data length: magic
+/// bytes, checksum and chunk type
+const CHUNK_PREFIX_LEN: usize = 4 + 4 + 1;
+
+/// A live document and what of it is on disk
+struct StoredDoc {
+    doc: AutoCommit,
+    /// Heads of everything already written to the file
+    heads: Vec<ChangeHash>,
+    /// Length of the snapshot at the start of the file
+    snapshot_len: usize,
+    /// Bytes of change chunks appended after the snapshot
+    appended_len: usize,
+}
+
+/// Disk-backed document store.
+///
+/// Behaves like [`LocalDocumentStore`](super::local_store::LocalDocumentStore)
+/// (documents kept live in memory, change notifications only for saves that
+/// changed content), but writes every save through to disk.
+pub struct DiskDocumentStore {
+    /// Root directory of the store
+    dir: PathBuf,
+    /// Document storage: content_key -> stored document
+    docs: RwLock<HashMap<String, StoredDoc>>,
+    /// Metadata storage: doc_id string -> metadata
+    metas: RwLock<HashMap<String, DocumentMetadata>>,
+    /// Channel to notify the application of document changes from sync.
+    /// Sends the document ID when a document is saved (e.g. after sync).
+    change_tx: mpsc::Sender<String>,
+}
+
+impl DiskDocumentStore {
+    /// Open (or create) a store rooted at `dir`.
+    pub fn open(dir: impl Into<PathBuf>, change_tx: mpsc::Sender<String>) -> AutomergeResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(io_error)?;
+        Ok(Self {
+            dir,
+            docs: RwLock::new(HashMap::new()),
+            metas: RwLock::new(HashMap::new()),
+            change_tx,
+        })
+    }
+
+    /// Drop a document from memory, keeping its files for the next `create`.
+    pub async fn close(&self, id: &DocumentId) {
+        self.docs.write().await.remove(&id.content_key());
+        self.metas.write().await.remove(&id.to_string());
+    }
+
+    /// Update a document from external source (e.g., the application's local changes).
+    /// This does NOT trigger the change notification (to avoid feedback loops).
+    ///
+    /// The app's changes are merged into the stored document rather than
+    /// replacing it, so synced changes the app hasn't merged yet stay on disk.
+    pub async fn update_from_app(&self, id: &DocumentId, doc_bytes: &[u8]) -> AutomergeResult<()> {
+        self.apply_from_app(id, doc_bytes).await
+    }
+
+    /// Merge encoded changes from the application into a document
+    /// (no change notification). Accepts a saved document as well.
+    pub async fn apply_from_app(&self, id: &DocumentId, changes: &[u8]) -> AutomergeResult<()> {
+        let key = id.content_key();
+        let mut docs = self.docs.write().await;
+        let stored = docs.entry(key.clone()).or_insert_with(StoredDoc::empty);
+        stored.doc.load_incremental(changes).map_err(|e| AutomergeError::AutomergeLib {
+            message: e.to_string(),
+        })?;
+        self.persist(&key, stored)
+    }
+
+    /// Changes to a document since `heads` (for sending to the application),
+    /// with the heads they bring it up to.
+    pub async fn changes_since(&self, id: &DocumentId, heads: &[ChangeHash]) -> Option<(Vec<u8>, Vec<ChangeHash>)> {
+        let key = id.content_key();
+        let mut docs = self.docs.write().await;
+        let stored = docs.get_mut(&key)?;
+        Some((stored.doc.save_after(heads), stored.doc.get_heads()))
+    }
+
+    /// Sync state last saved for a peer, so a reconnect resumes from the
+    /// heads both sides already share instead of starting over.
+    pub fn load_sync_state(&self, id: &DocumentId, peer: &PeerId) -> Option<sync::State> {
+        let bytes = fs::read(self.sync_state_path(id, peer)).ok()?;
+        sync::State::decode(&bytes).ok()
+    }
+
+    /// Save the sync state reached with a peer.
+    pub fn save_sync_state(&self, id: &DocumentId, peer: &PeerId, state: &sync::State) -> AutomergeResult<()> {
+        let path = self.sync_state_path(id, peer);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        write_atomic(&path, &state.encode())
+    }
+
+    /// Directory of a document
+    fn doc_dir(&self, key: &str) -> PathBuf {
+        self.dir.join(hex::encode(key))
+    }
+
+    /// Read a previously saved document from disk, truncating the file to
+    /// its last good chunk if the tail is damaged
+    fn load_from_disk(&self, key: &str) -> AutomergeResult<Option<StoredDoc>> {
+        let path = self.doc_dir(key).join(DOC_FILE);
+        let file_bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_error(e)),
+        };
+        let (doc, good_len) = load_good_chunks(&file_bytes)?;
+        if good_len < file_bytes.len() {
+            let file = fs::OpenOptions::new().write(true).open(&path).map_err(io_error)?;
+            file.set_len(good_len as u64).map_err(io_error)?;
+            file.sync_data().map_err(io_error)?;
+        }
+        let mut stored = StoredDoc {
+            doc,
+            heads: Vec::new(),
+            snapshot_len: good_len,
+            appended_len: 0,
+        };
+        stored.heads = stored.doc.get_heads();
+        Ok(Some(stored))
+    }
+
+    /// File holding the sync state with one peer
+    fn sync_state_path(&self, id: &DocumentId, peer: &PeerId) -> PathBuf {
+        self.doc_dir(&id.content_key())
+            .join(PEERS_DIR)
+            .join(format!("{}.sync", hex::encode(peer.as_bytes())))
+    }
+
+    /// Write the changes that aren't on disk yet, appending them as change
+    /// chunks or compacting the file into a fresh snapshot.
+    fn persist(&self, key: &str, stored: &mut StoredDoc) -> AutomergeResult<()> {
+        let delta = stored.doc.save_after(&stored.heads);
+        if delta.is_empty() && !stored.heads.is_empty() {
+            return Ok(());
+        }
+
+        let dir = self.doc_dir(key);
+        fs::create_dir_all(&dir).map_err(io_error)?;
+        let path = dir.join(DOC_FILE);
+
+        let appended = stored.appended_len.saturating_add(delta.len());
+        if stored.heads.is_empty() || appended > stored.snapshot_len.max(MIN_COMPACT_BYTES) {
+            let snapshot = stored.doc.save();
+            write_atomic(&path, &snapshot)?;
+            stored.snapshot_len = snapshot.len();
+            stored.appended_len = 0;
+        } else {
+            let mut file = fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .map_err(io_error)?;
+            file.write_all(&delta).map_err(io_error)?;
+            file.sync_data().map_err(io_error)?;
+            stored.appended_len = appended;
+        }
+        stored.heads = stored.doc.get_heads();
+        Ok(())
+    }
+}
+
+impl StoredDoc {
+    /// A document with nothing on disk yet
+    fn empty() -> Self {
+        Self {
+            doc: AutoCommit::new(),
+            heads: Vec::new(),
+            snapshot_len: 0,
+            appended_len: 0,
+        }
+    }
+}
+
+#[async_trait]
+impl DocumentStore for DiskDocumentStore {
+    async fn create(&self, id: Option<DocumentId>, metadata: Option<DocumentMetadata>) -> AutomergeResult<DocumentId> {
+        let doc_id = id.unwrap_or_default();
+        let key = doc_id.content_key();
+
+        let mut docs = self.docs.write().await;
+        if docs.contains_key(&key) {
+            return Err(AutomergeError::DocumentAlreadyExists {
+                document_id: doc_id.to_string(),
+            });
+        }
+
+        // Reopen the saved copy, or create an empty document
+        let stored = match self.load_from_disk(&key)? {
+            Some(stored) => stored,
+            None => {
+                let mut stored = StoredDoc::empty();
+                self.persist(&key, &mut stored)?;
+                stored
+            }
+        };
+        docs.insert(key, stored);
+        drop(docs);
+
+        // Store metadata
+        let meta = metadata.unwrap_or_else(|| DocumentMetadata::new(doc_id.clone()));
+        self.metas.write().await.insert(doc_id.to_string(), meta);
+
+        Ok(doc_id)
+    }
+
+    async fn get(&self, id: &DocumentId) -> AutomergeResult<Option<AutoCommit>> {
+        let key = id.content_key();
+        Ok(self.docs.read().await.get(&key).map(|stored| stored.doc.clone()))
+    }
+
+    async fn get_metadata(&self, id: &DocumentId) -> AutomergeResult<Option<DocumentMetadata>> {
+        Ok(self.metas.read().await.get(&id.to_string()).cloned())
+    }
+
+    async fn save(&self, id: &DocumentId, doc: &mut AutoCommit) -> AutomergeResult<()> {
+        let key = id.content_key();
+
+        // Merged rather than replaced, so app changes applied since `doc` was
+        // taken stay. Only notify when the document actually changed (avoids
+        // spurious events from sync rounds that exchange no data, e.g. initial
+        // sync of two empty docs).
+        let (changed, file_len) = {
+            let mut docs = self.docs.write().await;
+            let stored = docs.entry(key.clone()).or_insert_with(StoredDoc::empty);
+            let before = stored.doc.get_heads();
+            stored.doc.merge(doc).map_err(|e| AutomergeError::AutomergeLib {
+                message: e.to_string(),
+            })?;
+            let changed = stored.doc.get_heads() != before;
+            if changed {
+                self.persist(&key, stored)?;
+            }
+            (changed, stored.snapshot_len.saturating_add(stored.appended_len))
+        };
+
+        if changed {
+            if let Some(meta) = self.metas.write().await.get_mut(&id.to_string()) {
+                meta.touch();
+                meta.size_bytes = file_len as u64;
+            }
+            let _ = self.change_tx.send(id.to_string()).await;
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &DocumentId) -> AutomergeResult<bool> {
+        let key = id.content_key();
+        let existed = self.docs.write().await.remove(&key).is_some();
+        self.metas.write().await.remove(&id.to_string());
+        let dir = self.doc_dir(&key);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).map_err(io_error)?;
+        }
+        Ok(existed)
+    }
+
+    async fn exists(&self, id: &DocumentId) -> AutomergeResult<bool> {
+        let key = id.content_key();
+        Ok(self.docs.read().await.contains_key(&key))
+    }
+
+    async fn apply_changes(&self, id: &DocumentId, changes: Vec<DocumentChange>) -> AutomergeResult<ApplyResult> {
+        let mut doc = self.get(id).await?.unwrap_or_else(AutoCommit::new);
+        let mut applied: u32 = 0;
+        for change in &changes {
+            if let Ok(n) = doc.load_incremental(&change.bytes) {
+                let n_u32 = n.min(u32::MAX as usize);
+                debug_assert!(n == n_u32, "applied changes count overflow");
+                applied = applied.saturating_add(n_u32 as u32);
+            }
+        }
+        self.save(id, &mut doc).await?;
+        let new_heads: Vec<String> = doc.get_heads().iter().map(|h| hex::encode(h.0)).collect();
+        let doc_len = doc.save().len();
+        debug_assert!(doc_len <= u64::MAX as usize, "document too large for u64");
+        let new_size = doc_len as u64;
+        Ok(ApplyResult {
+            changes_applied: applied > 0,
+            change_count: applied,
+            new_heads,
+            new_size,
+        })
+    }
+
+    async fn get_heads(&self, id: &DocumentId) -> AutomergeResult<Vec<String>> {
+        match self.docs.write().await.get_mut(&id.content_key()) {
+            Some(stored) => Ok(stored.doc.get_heads().iter().map(|h| hex::encode(h.0)).collect()),
+            None => Err(AutomergeError::DocumentNotFound {
+                document_id: id.to_string(),
+            }),
+        }
+    }
+
+    async fn merge(&self, target_id: &DocumentId, source_id: &DocumentId) -> AutomergeResult<ApplyResult> {
+        let mut target = self.get(target_id).await?.ok_or_else(|| AutomergeError::DocumentNotFound {
+            document_id: target_id.to_string(),
+        })?;
+        let mut source = self.get(source_id).await?.ok_or_else(|| AutomergeError::DocumentNotFound {
+            document_id: source_id.to_string(),
+        })?;
+        target.merge(&mut source).map_err(|e| AutomergeError::AutomergeLib {
+            message: e.to_string(),
+        })?;
+        self.save(target_id, &mut target).await?;
+        let new_heads: Vec<String> = target.get_heads().iter().map(|h| hex::encode(h.0)).collect();
+        let doc_len = target.save().len();
+        debug_assert!(doc_len <= u64::MAX as usize, "document too large for u64");
+        let new_size = doc_len as u64;
+        Ok(ApplyResult {
+            changes_applied: true,
+            change_count: 1,
+            new_heads,
+            new_size,
+        })
+    }
+
+    async fn list(&self, _options: ListOptions) -> AutomergeResult<ListResult> {
+        let metas = self.metas.read().await;
+        let documents: Vec<DocumentMetadata> = metas.values().cloned().collect();
+        Ok(ListResult {
+            documents,
+            has_more: false,
+            continuation_token: None,
+        })
+    }
+
+    async fn list_ids(&self, _namespace: Option<&str>, _limit: u32) -> AutomergeResult<Vec<DocumentId>> {
+        let docs = self.docs.read().await;
+        let ids: Vec<DocumentId> = docs.keys()
+            .filter_map(|key| DocumentId::from_content_key(key))
+            .collect();
+        Ok(ids)
+    }
+}
+
+/// Load an Automerge document (a snapshot and/or change chunks)
+fn load_doc(bytes: &[u8]) -> AutomergeResult<AutoCommit> {
+    AutoCommit::load(bytes).map_err(|e| AutomergeError::AutomergeLib {
+        message: e.to_string(),
+    })
+}
+
+/// Load a document file, skipping everything from the first chunk that is
+/// cut short or doesn't load. Returns the document and the length of the
+/// chunks it was loaded from.
+fn load_good_chunks(bytes: &[u8]) -> AutomergeResult<(AutoCommit, usize)> {
+    if let Ok(doc) = AutoCommit::load(bytes) {
+        return Ok((doc, bytes.len()));
+    }
+    // The snapshot is written atomically, so only appended chunks can be
+    // damaged
+    let snapshot_len = chunk_len(bytes).unwrap_or(bytes.len());
+    let mut doc = load_doc(&bytes[..snapshot_len])?;
+    let mut good_len = snapshot_len;
+    while let Some(len) = chunk_len(&bytes[good_len..]) {
+        let end = good_len + len;
+        // A chunk that fails its checksum loads as nothing
+        let heads = doc.get_heads();
+        if doc.load_incremental(&bytes[good_len..end]).is_err() || doc.get_heads() == heads {
+            break;
+        }
+        good_len = end;
+    }
+    Ok((doc, good_len))
+}
+
+/// Length of the chunk at the start of `bytes`, or `None` if it is cut short
+fn chunk_len(bytes: &[u8]) -> Option<usize> {
+    let mut data_len: u64 = 0;
+    for (i, byte) in bytes.get(CHUNK_PREFIX_LEN..)?.iter().take(10).enumerate() {
+        data_len |= u64::from(byte & 0x7f).checked_shl(7 * i as u32)?;
+        if byte & 0x80 == 0 {
+            let len = usize::try_from(data_len)
+                .ok()?
+                .checked_add(CHUNK_PREFIX_LEN + i + 1)?;
+            return (len <= bytes.len()).then_some(len);
+        }
+    }
+    None
+}
+
+/// Replace a file without leaving a half-written one behind on a crash
+fn write_atomic(path: &Path, bytes: &[u8]) -> AutomergeResult<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes).map_err(io_error)?;
+    fs::rename(&tmp, path).map_err(io_error)
+}
+
+fn io_error(e: std::io::Error) -> AutomergeError {
+    AutomergeError::AutomergeLib {
+        message: format!("disk store: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use automerge::transaction::Transactable;
+    use automerge::{ReadDoc, ScalarValue};
+
+    fn open(dir: &Path) -> (DiskDocumentStore, mpsc::Receiver<String>) {
+        let (tx, rx) = mpsc::channel(16);
+        (DiskDocumentStore::open(dir, tx).unwrap(), rx)
+    }
+
+    fn read_title(doc: &AutoCommit) -> Option<String> {
+        match doc.get(automerge::ROOT, "title").unwrap() {
+            Some((automerge::Value::Scalar(s), _)) => match s.as_ref() {
+                ScalarValue::Str(t) => Some(t.to_string()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_documents_survive_reopen() {
+        let tmp = tempfile::tempdir().unwrap();
+        let id = DocumentId::from_string("design").unwrap();
+
+        tokio_test::block_on(async {
+            let (store, _rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+            let mut doc = store.get(&id).await.unwrap().unwrap();
+            doc.put(automerge::ROOT, "title", "Server").unwrap();
+            store.save(&id, &mut doc).await.unwrap();
+        });
+
+        tokio_test::block_on(async {
+            let (store, _rx) = open(tmp.path());
+            assert!(!store.exists(&id).await.unwrap());
+            store.create(Some(id.clone()), None).await.unwrap();
+            let doc = store.get(&id).await.unwrap().unwrap();
+            assert_eq!(read_title(&doc).as_deref(), Some("Server"));
+        });
+    }
+
+    #[test]
+    fn test_saves_append_incrementally() {
+        let tmp = tempfile::tempdir().unwrap();
+        let id = DocumentId::from_string("design").unwrap();
+        let path = tmp.path().join(hex::encode(id.content_key())).join(DOC_FILE);
+
+        tokio_test::block_on(async {
+            let (store, mut rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+            let mut doc = store.get(&id).await.unwrap().unwrap();
+            doc.put(automerge::ROOT, "title", "Server").unwrap();
+            store.save(&id, &mut doc).await.unwrap();
+            assert_eq!(rx.recv().await.as_deref(), Some(id.to_string().as_str()));
+            let saved_len = fs::read(&path).unwrap().len();
+            let saved_heads = doc.get_heads();
+
+            doc.put(automerge::ROOT, "title", "Cache").unwrap();
+            store.save(&id, &mut doc).await.unwrap();
+            assert!(rx.recv().await.is_some());
+
+            // Only the new change is appended to the file
+            let file = fs::read(&path).unwrap();
+            let delta = doc.save_after(&saved_heads);
+            assert_eq!(file.len(), saved_len + delta.len());
+            assert_eq!(read_title(&load_doc(&file).unwrap()).as_deref(), Some("Cache"));
+
+            // Saving again without changes writes nothing and notifies nobody
+            store.save(&id, &mut doc).await.unwrap();
+            assert_eq!(fs::read(&path).unwrap().len(), file.len());
+            assert!(rx.try_recv().is_err());
+        });
+    }
+
+    #[test]
+    fn test_truncated_tail_is_dropped_on_load() {
+        let tmp = tempfile::tempdir().unwrap();
+        let id = DocumentId::from_string("design").unwrap();
+        let path = tmp.path().join(hex::encode(id.content_key())).join(DOC_FILE);
+
+        tokio_test::block_on(async {
+            let (store, _rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+            let mut doc = store.get(&id).await.unwrap().unwrap();
+            doc.put(automerge::ROOT, "title", "Server").unwrap();
+            store.save(&id, &mut doc).await.unwrap();
+            doc.put(automerge::ROOT, "title", "Cache").unwrap();
+            store.save(&id, &mut doc).await.unwrap();
+        });
+
+        // A crash cut the last append short
+        let file = fs::read(&path).unwrap();
+        fs::write(&path, &file[..file.len() - 3]).unwrap();
+
+        tokio_test::block_on(async {
+            let (store, _rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+            let mut doc = store.get(&id).await.unwrap().unwrap();
+            assert_eq!(read_title(&doc).as_deref(), Some("Server"));
+
+            // Later appends land after the last good chunk and load again
+            doc.put(automerge::ROOT, "title", "Queue").unwrap();
+            store.save(&id, &mut doc).await.unwrap();
+        });
+
+        tokio_test::block_on(async {
+            let (store, _rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+            let doc = store.get(&id).await.unwrap().unwrap();
+            assert_eq!(read_title(&doc).as_deref(), Some("Queue"));
+        });
+    }
+
+    #[test]
+    fn test_update_from_app_persists_without_notifying() {
+        let tmp = tempfile::tempdir().unwrap();
+        let id = DocumentId::from_string("design").unwrap();
+
+        let mut app_doc = AutoCommit::new();
+        app_doc.put(automerge::ROOT, "title", "App").unwrap();
+
+        tokio_test::block_on(async {
+            let (store, mut rx) = open(tmp.path());
+            store.update_from_app(&id, &app_doc.save()).await.unwrap();
+            assert!(rx.try_recv().is_err());
+        });
+
+        tokio_test::block_on(async {
+            let (store, _rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+            let doc = store.get(&id).await.unwrap().unwrap();
+            assert_eq!(read_title(&doc).as_deref(), Some("App"));
+        });
+    }
+
+    #[test]
+    fn test_apply_from_app_merges_deltas() {
+        let tmp = tempfile::tempdir().unwrap();
+        let id = DocumentId::from_string("design").unwrap();
+
+        let mut app_doc = AutoCommit::new();
+        app_doc.put(automerge::ROOT, "title", "First").unwrap();
+        let first = app_doc.save_after(&[]);
+        let heads = app_doc.get_heads();
+        app_doc.put(automerge::ROOT, "title", "Second").unwrap();
+        let second = app_doc.save_after(&heads);
+
+        tokio_test::block_on(async {
+            let (store, mut rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+            store.apply_from_app(&id, &first).await.unwrap();
+            store.apply_from_app(&id, &second).await.unwrap();
+            assert!(rx.try_recv().is_err());
+
+            let doc = store.get(&id).await.unwrap().unwrap();
+            assert_eq!(read_title(&doc).as_deref(), Some("Second"));
+        });
+    }
+
+    #[test]
+    fn test_sync_saves_keep_app_deltas_and_report_only_new_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let id = DocumentId::from_string("design").unwrap();
+
+        tokio_test::block_on(async {
+            let (store, mut rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+            let (_, reported) = store.changes_since(&id, &[]).await.unwrap();
+
+            // A sync round takes a copy, then the app sends a delta before the
+            // round saves
+            let mut synced = store.get(&id).await.unwrap().unwrap();
+            let mut app_doc = AutoCommit::new();
+            app_doc.put(automerge::ROOT, "title", "App").unwrap();
+            store.apply_from_app(&id, &app_doc.save_after(&[])).await.unwrap();
+            synced.put(automerge::ROOT, "owner", "Peer").unwrap();
+            store.save(&id, &mut synced).await.unwrap();
+            assert_eq!(rx.try_recv().unwrap(), id.to_string());
+
+            let doc = store.get(&id).await.unwrap().unwrap();
+            assert_eq!(read_title(&doc).as_deref(), Some("App"));
+            assert!(doc.get(automerge::ROOT, "owner").unwrap().is_some());
+
+            let (changes, heads) = store.changes_since(&id, &reported).await.unwrap();
+            let mut replica = AutoCommit::new();
+            assert_eq!(replica.load_incremental(&changes).unwrap(), 2);
+            let (changes, _) = store.changes_since(&id, &heads).await.unwrap();
+            assert!(changes.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_sync_state_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (store, _rx) = open(tmp.path());
+        let id = DocumentId::from_string("design").unwrap();
+        let peer = PeerId([7; 32]);
+
+        assert!(store.load_sync_state(&id, &peer).is_none());
+
+        let mut doc = AutoCommit::new();
+        doc.put(automerge::ROOT, "title", "Synced").unwrap();
+        let mut state = sync::State::new();
+        state.shared_heads = doc.get_heads();
+        store.save_sync_state(&id, &peer, &state).unwrap();
+
+        let loaded = store.load_sync_state(&id, &peer).unwrap();
+        assert_eq!(loaded.shared_heads, state.shared_heads);
+    }
+
+    #[test]
+    fn test_close_keeps_files_and_delete_removes_them() {
+        let tmp = tempfile::tempdir().unwrap();
+        let id = DocumentId::from_string("design").unwrap();
+        let dir = tmp.path().join(hex::encode(id.content_key()));
+
+        tokio_test::block_on(async {
+            let (store, _rx) = open(tmp.path());
+            store.create(Some(id.clone()), None).await.unwrap();
+
+            // Closed documents are no longer served, but stay on disk
+            store.close(&id).await;
+            assert!(!store.exists(&id).await.unwrap());
+            assert!(dir.join(DOC_FILE).exists());
+
+            store.create(Some(id.clone()), None).await.unwrap();
+            assert!(store.delete(&id).await.unwrap());
+            assert!(!dir.exists());
+        });
+    }
+}
@@ -0,0 +1,88 @@
+//! Persistent endpoint identity.
+//!
+//! Our [`PeerId`](crate::PeerId) is the public half of the endpoint's secret
+//! key. Keeping the key in a file (hex, readable only by its owner) gives the
+//! same peer ID on every run, so collaborators, session metadata and grants
+//! keep recognising us across restarts.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use iroh_base::SecretKey;
+
+use super::access::generate_key;
+
+/// Load the secret key stored at `path`, creating the file with a fresh key
+/// if it doesn't exist yet
+pub fn load_or_create_secret_key(path: &Path) -> Result<SecretKey> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_secret_key(&content)
+            .with_context(|| format!("Invalid identity key in {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key = generate_key();
+            write_secret_key(path, &key)
+                .with_context(|| format!("Failed to write identity key to {}", path.display()))?;
+            Ok(key)
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+fn parse_secret_key(content: &str) -> Result<SecretKey> {
+    let bytes = hex::decode(content.trim())?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("expected 32 bytes"))?;
+    Ok(SecretKey::from_bytes(&bytes))
+}
+
+fn write_secret_key(path: &Path, key: &SecretKey) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", hex::encode(key.to_bytes()))?;
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("identity.key");
+
+        let created = load_or_create_secret_key(&path).unwrap();
+        let loaded = load_or_create_secret_key(&path).unwrap();
+        assert_eq!(created.public(), loaded.public());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_key_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("identity.key");
+        fs::write(&path, "not a key").unwrap();
+        assert!(load_or_create_secret_key(&path).is_err());
+        // The file is left alone rather than replaced with a new identity
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not a key");
+    }
+}
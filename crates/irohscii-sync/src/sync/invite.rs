@@ -0,0 +1,186 @@
+//! Signed invites: per-invitee, expiring and revocable access to a document.
+//!
+//! An invite names the role it grants, an optional label for the invitee, an
+//! expiry time and a holder key, and is signed with the document's editor
+//! key (see [`super::access`]). It travels in an `irohscii3` ticket along
+//! with the secret half of the holder key. The invitee presents it to the
+//! document's editors with a signature over its own peer ID made with that
+//! key, so a presentation can't be replayed by anyone who sees it. Peers
+//! presenting an expired, revoked or forged invite are refused.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use iroh_base::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::access::Role;
+use crate::peer::PeerId;
+
+/// Domain separator for invite signatures
+const INVITE_CONTEXT: &[u8] = b"irohscii/invite/2";
+
+/// Domain separator for holder key signatures over the presenter's ID
+const PRESENT_CONTEXT: &[u8] = b"irohscii/invite-holder/1";
+
+/// Identifies an invite (for listing and revocation)
+pub type InviteId = Uuid;
+
+/// What an invite grants, and to whom
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invite {
+    pub id: InviteId,
+    /// App-facing document ID (as in tickets)
+    pub doc_id: String,
+    pub role: Role,
+    /// Who the invite is for, as entered by the host
+    pub label: Option<String>,
+    /// Seconds since the Unix epoch after which the invite is refused
+    pub expires_at: u64,
+    /// Public half of the key only the invitee holds
+    pub holder: [u8; 32],
+}
+
+impl Invite {
+    /// New invite to a document for whoever holds `holder`'s secret half,
+    /// valid for `ttl` from now
+    pub fn new(
+        doc_id: &str,
+        role: Role,
+        label: Option<String>,
+        ttl: Duration,
+        holder: &PublicKey,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            doc_id: doc_id.to_string(),
+            role,
+            label,
+            expires_at: unix_now().saturating_add(ttl.as_secs()),
+            holder: *holder.as_bytes(),
+        }
+    }
+
+    /// Whether the invite is past its expiry time
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= unix_now()
+    }
+
+    /// Time left before the invite expires (zero once expired)
+    pub fn remaining(&self) -> Duration {
+        Duration::from_secs(self.expires_at.saturating_sub(unix_now()))
+    }
+}
+
+/// An invite plus the editor key signature that vouches for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedInvite {
+    pub invite: Invite,
+    /// Public half of the editor key that signed it
+    pub editor: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+impl SignedInvite {
+    /// Sign an invite with a document's editor key
+    pub(crate) fn sign(invite: Invite, key: &SecretKey) -> Self {
+        let signature = key.sign(&signed_bytes(&invite)).to_bytes().to_vec();
+        Self {
+            invite,
+            editor: *key.public().as_bytes(),
+            signature,
+        }
+    }
+
+    /// Whether the invite was signed by `editor` (expiry and revocation
+    /// are checked separately)
+    pub fn verify(&self, editor: &PublicKey) -> bool {
+        let Ok(bytes) = <[u8; 64]>::try_from(self.signature.as_slice()) else {
+            return false;
+        };
+        self.editor == *editor.as_bytes()
+            && editor
+                .verify(&signed_bytes(&self.invite), &Signature::from_bytes(&bytes))
+                .is_ok()
+    }
+}
+
+/// A revoked invite, as passed on to peers. It names the invite's document
+/// so only that document's editors are believed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Revocation {
+    pub id: InviteId,
+    pub doc_id: String,
+}
+
+impl Revocation {
+    /// Revocation of an invite
+    pub fn of(invite: &Invite) -> Self {
+        Self {
+            id: invite.id,
+            doc_id: invite.doc_id.clone(),
+        }
+    }
+}
+
+/// An invite as presented to a peer: the signed invite, plus the holder
+/// key's signature over the presenter's peer ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresentedInvite {
+    pub signed: SignedInvite,
+    pub proof: Vec<u8>,
+}
+
+impl PresentedInvite {
+    /// Present an invite as `presenter`, proving we hold its key
+    pub(crate) fn new(signed: SignedInvite, holder: &SecretKey, presenter: &PeerId) -> Self {
+        let proof = holder.sign(&presenter_bytes(presenter)).to_bytes().to_vec();
+        Self { signed, proof }
+    }
+
+    /// Whether `editor` signed the invite and its holder presented it as
+    /// `presenter` (expiry and revocation are checked separately)
+    pub fn verify(&self, editor: &PublicKey, presenter: &PeerId) -> bool {
+        let Ok(holder) = PublicKey::from_bytes(&self.signed.invite.holder) else {
+            return false;
+        };
+        let Ok(bytes) = <[u8; 64]>::try_from(self.proof.as_slice()) else {
+            return false;
+        };
+        self.signed.verify(editor)
+            && holder
+                .verify(&presenter_bytes(presenter), &Signature::from_bytes(&bytes))
+                .is_ok()
+    }
+}
+
+/// An invite we issued, as listed for the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InviteInfo {
+    pub invite: Invite,
+    /// Ticket to hand to the invitee
+    pub ticket: String,
+    pub revoked: bool,
+}
+
+/// Bytes an invite signature covers
+fn signed_bytes(invite: &Invite) -> Vec<u8> {
+    let mut msg = INVITE_CONTEXT.to_vec();
+    msg.extend(postcard::to_stdvec(invite).expect("Invite serialization should not fail"));
+    msg
+}
+
+/// Bytes a holder key signs when presenting an invite
+fn presenter_bytes(presenter: &PeerId) -> Vec<u8> {
+    let mut msg = PRESENT_CONTEXT.to_vec();
+    msg.extend_from_slice(presenter.as_bytes());
+    msg
+}
+
+/// Current time in seconds since the Unix epoch
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
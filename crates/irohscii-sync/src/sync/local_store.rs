@@ -2,12 +2,14 @@
 //!
 //! Implements the aspen-automerge DocumentStore trait using in-memory storage,
 //! suitable for a single-user or small collaborative editing session.
-//! Documents are stored as serialized bytes and loaded on demand.
+//! Documents are kept live, so applying the app's deltas and reading back
+//! the changes since some heads cost in proportion to the change, not the
+//! document.
 
 use std::collections::HashMap;
 
 use async_trait::async_trait;
-use automerge::AutoCommit;
+use automerge::{AutoCommit, ChangeHash};
 use tokio::sync::{RwLock, mpsc};
 
 use aspen_automerge::{
@@ -17,12 +19,12 @@ use aspen_automerge::{
 
 /// Local in-memory document store.
 ///
-/// Stores Automerge documents as serialized bytes in memory.
+/// Stores Automerge documents in memory.
 /// When the sync protocol saves a document (after merging remote changes),
 /// it notifies the application via a channel.
 pub struct LocalDocumentStore {
-    /// Document storage: content_key -> automerge document bytes
-    docs: RwLock<HashMap<String, Vec<u8>>>,
+    /// Document storage: content_key -> automerge document
+    docs: RwLock<HashMap<String, AutoCommit>>,
     /// Metadata storage: doc_id string -> metadata
     metas: RwLock<HashMap<String, DocumentMetadata>>,
     /// Channel to notify the application of document changes from sync.
@@ -44,14 +46,33 @@ impl LocalDocumentStore {
     /// This does NOT trigger the change notification (to avoid feedback loops).
     pub async fn update_from_app(&self, id: &DocumentId, doc_bytes: &[u8]) -> AutomergeResult<()> {
         let key = id.content_key();
-        self.docs.write().await.insert(key, doc_bytes.to_vec());
+        let doc = AutoCommit::load(doc_bytes).map_err(|e| AutomergeError::AutomergeLib {
+            message: e.to_string(),
+        })?;
+        self.docs.write().await.insert(key, doc);
+        Ok(())
+    }
+
+    /// Merge encoded changes from the application into a document.
+    /// Like [`update_from_app`](Self::update_from_app), this does NOT
+    /// trigger the change notification.
+    pub async fn apply_from_app(&self, id: &DocumentId, changes: &[u8]) -> AutomergeResult<()> {
+        let key = id.content_key();
+        let mut docs = self.docs.write().await;
+        let doc = docs.entry(key).or_default();
+        doc.load_incremental(changes).map_err(|e| AutomergeError::AutomergeLib {
+            message: e.to_string(),
+        })?;
         Ok(())
     }
 
-    /// Get raw document bytes (for sending to the application).
-    pub async fn get_bytes(&self, id: &DocumentId) -> Option<Vec<u8>> {
+    /// Changes to a document since `heads` (for sending to the application),
+    /// with the heads they bring it up to.
+    pub async fn changes_since(&self, id: &DocumentId, heads: &[ChangeHash]) -> Option<(Vec<u8>, Vec<ChangeHash>)> {
         let key = id.content_key();
-        self.docs.read().await.get(&key).cloned()
+        let mut docs = self.docs.write().await;
+        let doc = docs.get_mut(&key)?;
+        Some((doc.save_after(heads), doc.get_heads()))
     }
 }
 
@@ -69,10 +90,7 @@ impl DocumentStore for LocalDocumentStore {
         }
 
         // Create empty document
-        let mut doc = AutoCommit::new();
-        let bytes = doc.save();
-
-        self.docs.write().await.insert(key, bytes);
+        self.docs.write().await.insert(key, AutoCommit::new());
 
         // Store metadata
         let meta = metadata.unwrap_or_else(|| DocumentMetadata::new(doc_id.clone()));
@@ -83,16 +101,7 @@ impl DocumentStore for LocalDocumentStore {
 
     async fn get(&self, id: &DocumentId) -> AutomergeResult<Option<AutoCommit>> {
         let key = id.content_key();
-        let docs = self.docs.read().await;
-        match docs.get(&key) {
-            Some(bytes) => {
-                let doc = AutoCommit::load(bytes).map_err(|e| AutomergeError::AutomergeLib {
-                    message: e.to_string(),
-                })?;
-                Ok(Some(doc))
-            }
-            None => Ok(None),
-        }
+        Ok(self.docs.read().await.get(&key).cloned())
     }
 
     async fn get_metadata(&self, id: &DocumentId) -> AutomergeResult<Option<DocumentMetadata>> {
@@ -101,27 +110,25 @@ impl DocumentStore for LocalDocumentStore {
 
     async fn save(&self, id: &DocumentId, doc: &mut AutoCommit) -> AutomergeResult<()> {
         let key = id.content_key();
-        let bytes = doc.save();
 
-        // Only notify when the document actually changed (avoids spurious events
-        // from sync rounds that exchange no data, e.g. initial sync of two empty docs).
+        // Merged rather than replaced, so app changes applied since `doc` was
+        // taken stay. Only notify when the document actually changed (avoids
+        // spurious events from sync rounds that exchange no data, e.g. initial
+        // sync of two empty docs).
         let changed = {
             let mut docs = self.docs.write().await;
-            let changed = match docs.get(&key) {
-                Some(existing) => *existing != bytes,
-                None => true,
-            };
-            docs.insert(key, bytes);
-            changed
+            let existing = docs.entry(key).or_default();
+            let before = existing.get_heads();
+            existing.merge(doc).map_err(|e| AutomergeError::AutomergeLib {
+                message: e.to_string(),
+            })?;
+            existing.get_heads() != before
         };
 
         // Update metadata (decomposed compound condition)
         if changed {
             if let Some(meta) = self.metas.write().await.get_mut(&id.to_string()) {
                 meta.touch();
-                let doc_len = doc.save().len();
-                debug_assert!(doc_len <= u64::MAX as usize, "document too large for u64");
-                meta.size_bytes = doc_len as u64;
             }
         }
 
@@ -169,8 +176,8 @@ impl DocumentStore for LocalDocumentStore {
     }
 
     async fn get_heads(&self, id: &DocumentId) -> AutomergeResult<Vec<String>> {
-        match self.get(id).await? {
-            Some(mut doc) => Ok(doc.get_heads().iter().map(|h| hex::encode(h.0)).collect()),
+        match self.docs.write().await.get_mut(&id.content_key()) {
+            Some(doc) => Ok(doc.get_heads().iter().map(|h| hex::encode(h.0)).collect()),
             None => Err(AutomergeError::DocumentNotFound {
                 document_id: id.to_string(),
             }),
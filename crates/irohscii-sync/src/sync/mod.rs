@@ -3,13 +3,24 @@
 //! This module provides real-time collaborative editing via:
 //! - iroh: P2P networking with cryptographic identity
 //! - aspen-automerge: CRDT-based conflict-free data synchronization
+//! - peer exchange: a full mesh of links, so any participant can leave
+//! - presence and chat: side channels on their own ALPNs
 //!
 //! The automerge document IS the source of truth - this module just syncs it.
 
+pub mod access;
+pub mod chat_protocol;
+pub mod disk_store;
+pub mod identity;
+pub mod invite;
 pub mod local_store;
+pub mod peer_exchange;
 pub mod presence_protocol;
 pub mod protocol;
+pub mod store;
 
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc as std_mpsc;
 use std::sync::Arc;
@@ -17,29 +28,49 @@ use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
 use anyhow::Result;
-use automerge::Automerge;
-use iroh::Endpoint;
+use automerge::{Automerge, ChangeHash};
+use iroh::{Endpoint, Watcher};
 use iroh::endpoint::presets;
-use iroh::endpoint::Connection;
+use iroh::endpoint::{Connection, ConnectionError};
 use iroh_base::EndpointAddr;
 use tokio::sync::mpsc as tokio_mpsc;
 
 use aspen_automerge::{DocumentId, DocumentStore};
 
 use crate::{PeerId, PeerPresence, PresenceMessage};
-use local_store::LocalDocumentStore;
+use access::{GatedStore, Role, Roles, SyncGate, TicketAccess};
+use chat_protocol::{ChatMessage, ChatProtocol};
+use invite::{InviteId, InviteInfo};
+use peer_exchange::{DiscoveredPeer, PeerExchange, peer_id_of};
 use presence_protocol::PresenceProtocol;
+use store::SyncStore;
 pub use protocol::AutomergeSyncTicket;
 pub use protocol::CapabilityToken;
-use protocol::{AUTOMERGE_SYNC_ALPN, AutomergeSyncHandler, sync_with_peer, sync_with_peer_cap};
+use protocol::{AUTOMERGE_SYNC_ALPN, sync_with_peer, sync_with_peer_cap};
 
-/// Fixed document ID for the irohscii session
-const IROHSCII_DOC_ID: &str = "irohscii-session";
+/// Document ID for tickets that name no document (tickets from before
+/// per-session documents), and the default document to open
+pub const DEFAULT_DOC_ID: &str = "irohscii-session";
+
+/// How often linked peers swap participant lists, so late joiners are
+/// meshed in while the peer that introduced them is still around
+const PEER_EXCHANGE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Delay before redialing a dropped link, doubled after each failed attempt
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between reconnection attempts
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Failed attempts after which a dropped peer is given up on
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
 
 /// Configuration for sync behavior
 #[derive(Debug, Clone)]
 pub struct SyncConfig {
     pub mode: SyncMode,
+    /// Document to open at startup (normally the current session's ID)
+    pub doc_id: String,
     /// Ticket for an aspen cluster node to persist documents to
     pub cluster_ticket: Option<String>,
     /// Capability token for authenticating with the cluster node.
@@ -47,15 +78,30 @@ pub struct SyncConfig {
     pub cluster_capability: Option<CapabilityToken>,
     /// Disable DNS/Pkarr discovery (for test isolation)
     pub disable_discovery: bool,
+    /// Directory for the on-disk document store, so documents and their
+    /// sync progress survive restarts. `None` keeps documents in memory.
+    pub store_dir: Option<PathBuf>,
+    /// File holding our secret key, so our peer ID survives restarts.
+    /// `None` uses a fresh key (and peer ID) every run.
+    pub identity_path: Option<PathBuf>,
+    /// File holding the keys of documents we restricted and the invites we
+    /// issued or saw revoked, so tickets we handed out keep working (and
+    /// revoked invites stay revoked) across restarts. `None` keeps them in
+    /// memory.
+    pub roles_path: Option<PathBuf>,
 }
 
 impl Default for SyncConfig {
     fn default() -> Self {
         Self {
             mode: SyncMode::Active { join_ticket: None },
+            doc_id: DEFAULT_DOC_ID.to_string(),
             cluster_ticket: None,
             cluster_capability: None,
             disable_discovery: false,
+            store_dir: None,
+            identity_path: None,
+            roles_path: None,
         }
     }
 }
@@ -77,29 +123,58 @@ pub enum SyncEvent {
         endpoint_id: String,
         local_peer_id: PeerId,
     },
-    /// Remote changes received (boxed to reduce enum size)
-    RemoteChanges { doc: Box<Automerge> },
+    /// Changes to an open document the app hasn't seen yet, encoded for
+    /// `Document::apply_changes`
+    RemoteChanges { doc_id: String, changes: Vec<u8> },
     /// Presence update from remote peer
     PresenceUpdate(PeerPresence),
     /// Peer presence removed (disconnect or leave)
     PresenceRemoved { peer_id: PeerId },
+    /// Chat message from a participant
+    Chat(ChatMessage),
+    /// A link to a peer was established (or re-established), whichever
+    /// side dialed
+    PeerConnected { peer_id: PeerId, kind: ConnectionType },
+    /// A link to a peer dropped, or reconnecting to it was given up on
+    PeerDisconnected { peer_id: PeerId },
+    /// Redialing a dropped link (attempts count from 1). Links the peer
+    /// closed on purpose, or that it dialed, aren't redialed.
+    Reconnecting { peer_id: PeerId, attempt: u32 },
+    /// A link switched between a direct path and a relay
+    ConnectionType { peer_id: PeerId, kind: ConnectionType },
     /// Error occurred
     Error(String),
 }
 
+/// How a link currently reaches its peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionType {
+    /// Direct UDP path (same network or hole-punched)
+    Direct,
+    /// Forwarded through an iroh relay server
+    Relay,
+}
+
 /// Commands from main thread to sync thread
 #[derive(Debug)]
 pub enum SyncCommand {
-    /// Send local document state (boxed to reduce enum size)
-    SyncDoc { doc: Box<Automerge> },
+    /// Send local changes made since the last flush (from
+    /// `Document::changes_since`), opening the document if needed
+    SyncDoc { doc_id: String, changes: Vec<u8> },
+    /// Start syncing a document with every peer, seeded with local state
+    OpenDocument { doc_id: String, doc: Box<Automerge> },
+    /// Stop syncing a document and stop serving it from the sync store
+    CloseDocument { doc_id: String },
     /// Broadcast local presence to all peers
     BroadcastPresence(PeerPresence),
+    /// Send a chat message to all peers
+    SendChat(ChatMessage),
     /// Connect to an aspen cluster node for document persistence
     ConnectCluster {
         ticket: String,
         capability: Option<CapabilityToken>,
     },
-    /// Connect to a peer for real-time sync
+    /// Connect to a peer for real-time sync (in addition to existing peers)
     ConnectPeer {
         ticket: String,
     },
@@ -115,6 +190,8 @@ pub struct SyncHandle {
     pub event_rx: std_mpsc::Receiver<SyncEvent>,
     /// Our session endpoint ID (for others to join)
     pub endpoint_id: Option<String>,
+    /// Document roles, shared with the sync thread
+    roles: Arc<Roles>,
     /// Thread handle
     _thread: JoinHandle<()>,
 }
@@ -131,6 +208,100 @@ impl SyncHandle {
             .try_send(cmd)
             .map_err(|e| anyhow::anyhow!("Failed to send sync command: {}", e))
     }
+
+    /// Our address for tickets, once the endpoint is up
+    fn addr(&self) -> Result<EndpointAddr> {
+        let endpoint_id = self
+            .endpoint_id
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Sync endpoint isn't ready"))?;
+        decode_ticket(endpoint_id)
+    }
+
+    /// Ticket for others to join one of our documents with our own role,
+    /// sharing the document. It carries no keys until the document is
+    /// restricted (see [`viewer_ticket`](Self::viewer_ticket)).
+    pub fn doc_ticket(&self, doc_id: &str) -> Option<String> {
+        let addr = decode_ticket(self.endpoint_id.as_deref()?).ok()?;
+        self.roles.share(doc_id);
+        let access = self.roles.ticket_access(doc_id, self.roles.role(doc_id));
+        Some(match access {
+            Some(access) => encode_access_ticket(&addr, doc_id, &access),
+            None => encode_doc_ticket(&addr, doc_id),
+        })
+    }
+
+    /// Ticket for others to watch one of our documents without changing it.
+    /// The first one restricts a document we share: tickets without keys
+    /// stop working for it, and [`doc_ticket`](Self::doc_ticket) hands out
+    /// editor tickets from then on.
+    pub fn viewer_ticket(&self, doc_id: &str) -> Result<String> {
+        let addr = self.addr()?;
+        self.roles.restrict(doc_id)?;
+        let access = self
+            .roles
+            .ticket_access(doc_id, Role::Viewer)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Joined without keys: ask an editor for a viewer ticket")
+            })?;
+        Ok(encode_access_ticket(&addr, doc_id, &access))
+    }
+
+    /// Record the role a ticket grants before joining with it (the sync
+    /// thread does the same for a join ticket passed at startup), returning
+    /// that role
+    pub fn register_ticket(&self, ticket: &str) -> Result<Role> {
+        let (_, doc_id, access) = decode_ticket_access(ticket)?;
+        self.roles.register(&doc_id, access.as_ref());
+        Ok(self.roles.role(&doc_id))
+    }
+
+    /// Our role in a document
+    pub fn role(&self, doc_id: &str) -> Role {
+        self.roles.role(doc_id)
+    }
+
+    /// Ticket carrying a new invite to one of our documents, valid for
+    /// `ttl`. Restricts the document like [`viewer_ticket`](Self::viewer_ticket).
+    /// Fails unless we're an editor of the document.
+    pub fn create_invite(
+        &self,
+        doc_id: &str,
+        role: Role,
+        label: Option<String>,
+        ttl: Duration,
+    ) -> Result<String> {
+        let addr = self.addr()?;
+        let access = self
+            .roles
+            .issue_invite(doc_id, role, label, ttl)?
+            .ok_or_else(|| anyhow::anyhow!("Only editors can invite others"))?;
+        Ok(encode_access_ticket(&addr, doc_id, &access))
+    }
+
+    /// Invites we issued for a document, oldest first
+    pub fn invites(&self, doc_id: &str) -> Vec<InviteInfo> {
+        let Ok(addr) = self.addr() else {
+            return Vec::new();
+        };
+        self.roles
+            .issued_invites(doc_id)
+            .into_iter()
+            .filter_map(|(access, revoked)| {
+                Some(InviteInfo {
+                    invite: access.invite()?.clone(),
+                    ticket: encode_access_ticket(&addr, doc_id, &access),
+                    revoked,
+                })
+            })
+            .collect()
+    }
+
+    /// Revoke an invite we issued. Peers that joined with it are
+    /// disconnected and refused from then on.
+    pub fn revoke_invite(&self, id: InviteId) -> Result<bool> {
+        self.roles.revoke_invite(id)
+    }
 }
 
 /// Start the sync thread with the given configuration
@@ -141,6 +312,21 @@ pub fn start_sync_thread(config: SyncConfig) -> Result<SyncHandle> {
     // Channel to get the endpoint ID back from the async context
     let (endpoint_id_tx, endpoint_id_rx) = std_mpsc::channel();
 
+    // Loaded before the router starts accepting, so tickets from earlier
+    // runs are honoured from the first connection
+    let roles = Arc::new(Roles::load(config.roles_path.clone())?);
+    // Our endpoint ticket (`endpoint_id`) joins the default document, so
+    // that one is shared whenever we start with it
+    if config.doc_id == DEFAULT_DOC_ID {
+        roles.share(DEFAULT_DOC_ID);
+    }
+    if let SyncMode::Active { join_ticket: Some(ticket) } = &config.mode
+        && let Ok((_, doc_id, access)) = decode_ticket_access(ticket)
+    {
+        roles.register(&doc_id, access.as_ref());
+    }
+    let thread_roles = roles.clone();
+
     let thread = thread::spawn(move || {
         let rt = match tokio::runtime::Builder::new_current_thread()
             .enable_all()
@@ -148,21 +334,17 @@ pub fn start_sync_thread(config: SyncConfig) -> Result<SyncHandle> {
         {
             Ok(rt) => rt,
             Err(e) => {
-                if let Err(send_err) = event_tx.send(SyncEvent::Error(format!(
+                let _ = event_tx.send(SyncEvent::Error(format!(
                     "Failed to create tokio runtime: {}",
                     e
-                ))) {
-                    eprintln!("Failed to send runtime error event: {}", send_err);
-                }
+                )));
                 return;
             }
         };
 
         rt.block_on(async move {
-            if let Err(e) = run_sync(config, event_tx.clone(), command_rx, endpoint_id_tx).await {
-                if let Err(send_err) = event_tx.send(SyncEvent::Error(e.to_string())) {
-                    eprintln!("Failed to send sync error event: {}", send_err);
-                }
+            if let Err(e) = run_sync(config, thread_roles, event_tx.clone(), command_rx, endpoint_id_tx).await {
+                let _ = event_tx.send(SyncEvent::Error(e.to_string()));
             }
         });
     });
@@ -174,6 +356,7 @@ pub fn start_sync_thread(config: SyncConfig) -> Result<SyncHandle> {
         command_tx,
         event_rx,
         endpoint_id,
+        roles,
         _thread: thread,
     })
 }
@@ -203,9 +386,61 @@ pub fn encode_ticket(addr: &EndpointAddr) -> String {
     format!("irohscii1{}", data_encoding::BASE32_NOPAD.encode(&bytes))
 }
 
+/// Encode an EndpointAddr plus the document to join as a ticket string
+pub fn encode_doc_ticket(addr: &EndpointAddr, doc_id: &str) -> String {
+    let bytes =
+        postcard::to_stdvec(&(addr, doc_id)).expect("Ticket serialization should not fail");
+    format!("irohscii2{}", data_encoding::BASE32_NOPAD.encode(&bytes))
+}
+
+/// Encode an EndpointAddr, the document to join, and the role's key
+/// material as a ticket string
+pub fn encode_access_ticket(addr: &EndpointAddr, doc_id: &str, access: &TicketAccess) -> String {
+    let bytes = postcard::to_stdvec(&(addr, doc_id, access))
+        .expect("Ticket serialization should not fail");
+    format!("irohscii3{}", data_encoding::BASE32_NOPAD.encode(&bytes))
+}
+
+/// Decode a ticket string into the peer's address, the document to join,
+/// and the key material for its role. Tickets without keys give `None`
+/// (the document is open to everyone who joins).
+pub fn decode_ticket_access(ticket: &str) -> Result<(EndpointAddr, String, Option<TicketAccess>)> {
+    if let Some(data) = ticket.strip_prefix("irohscii3") {
+        let bytes = data_encoding::BASE32_NOPAD
+            .decode(data.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Invalid ticket encoding: {}", e))?;
+        let (addr, doc_id, access): (EndpointAddr, String, TicketAccess) =
+            postcard::from_bytes(&bytes)
+                .map_err(|e| anyhow::anyhow!("Invalid ticket data: {}", e))?;
+        Ok((addr, doc_id, Some(access)))
+    } else {
+        let (addr, doc_id) = decode_doc_ticket(ticket)?;
+        Ok((addr, doc_id, None))
+    }
+}
+
+/// Decode a ticket string into the peer's address and the document to join.
+/// Tickets without a document join [`DEFAULT_DOC_ID`].
+pub fn decode_doc_ticket(ticket: &str) -> Result<(EndpointAddr, String)> {
+    if ticket.starts_with("irohscii3") {
+        decode_ticket_access(ticket).map(|(addr, doc_id, _)| (addr, doc_id))
+    } else if let Some(data) = ticket.strip_prefix("irohscii2") {
+        let bytes = data_encoding::BASE32_NOPAD
+            .decode(data.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Invalid ticket encoding: {}", e))?;
+        let (addr, doc_id): (EndpointAddr, String) = postcard::from_bytes(&bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid ticket data: {}", e))?;
+        Ok((addr, doc_id))
+    } else {
+        Ok((decode_ticket(ticket)?, DEFAULT_DOC_ID.to_string()))
+    }
+}
+
 /// Decode a ticket string back to EndpointAddr
 pub fn decode_ticket(ticket: &str) -> Result<EndpointAddr> {
-    if let Some(data) = ticket.strip_prefix("irohscii1") {
+    if ticket.starts_with("irohscii2") || ticket.starts_with("irohscii3") {
+        decode_doc_ticket(ticket).map(|(addr, _)| addr)
+    } else if let Some(data) = ticket.strip_prefix("irohscii1") {
         let bytes = data_encoding::BASE32_NOPAD
             .decode(data.as_bytes())
             .map_err(|e| anyhow::anyhow!("Invalid ticket encoding: {}", e))?;
@@ -221,47 +456,95 @@ pub fn decode_ticket(ticket: &str) -> Result<EndpointAddr> {
     }
 }
 
-/// Trigger a sync with a peer, silently ignoring errors.
+/// Trigger a sync with a peer, returning whether it completed. Errors are
+/// otherwise ignored (a dropped connection is reported through the link
+/// lifecycle events instead, and stderr belongs to the TUI).
 /// Clones the connection handle (cheap, QUIC connections are multiplexed).
-async fn do_sync(
-    store: &Arc<LocalDocumentStore>,
+async fn do_sync<S: DocumentStore>(
+    store: &Arc<S>,
     doc_id: &DocumentId,
     conn: &Connection,
     capability: Option<&CapabilityToken>,
-) {
+) -> bool {
     let result = match capability {
         Some(cap) => sync_with_peer_cap(store.as_ref(), doc_id, conn, Some(cap)).await,
         None => sync_with_peer(store.as_ref(), doc_id, conn).await,
     };
-    if let Err(e) = result {
-        // Only log non-connection errors (connection errors are expected during shutdown)
-        let msg = e.to_string();
-        if !msg.contains("closed") && !msg.contains("refused") {
-            eprintln!("Sync error: {}", e);
+    result.is_ok()
+}
+
+/// Sync each of the given documents with a peer in turn
+async fn sync_docs<S: DocumentStore>(
+    store: &Arc<S>,
+    doc_ids: &[DocumentId],
+    conn: &Connection,
+    capability: Option<&CapabilityToken>,
+) {
+    for doc_id in doc_ids {
+        do_sync(store, doc_id, conn, capability).await;
+    }
+}
+
+/// Sync each of the given documents with a linked peer in turn, noting the
+/// ones it synced so chat about them reaches it
+async fn sync_peer_docs(store: &Arc<GatedStore>, doc_ids: &[DocumentId], conn: &Connection) {
+    for doc_id in doc_ids {
+        if do_sync(store, doc_id, conn, None).await {
+            store.synced(doc_id);
         }
     }
 }
 
+/// Open a document for syncing, creating it empty in the store if needed
+async fn open_document(
+    store: &Arc<SyncStore>,
+    docs: &mut HashMap<String, DocumentId>,
+    doc_id: &str,
+) -> Result<DocumentId> {
+    if let Some(id) = docs.get(doc_id) {
+        return Ok(id.clone());
+    }
+    let id = DocumentId::from_string(doc_id)?;
+    if !store.as_ref().exists(&id).await? {
+        store.as_ref().create(Some(id.clone()), None).await?;
+    }
+    docs.insert(doc_id.to_string(), id.clone());
+    Ok(id)
+}
+
 /// Setup context for the sync session
 struct SyncSession {
     endpoint: Endpoint,
-    store: Arc<LocalDocumentStore>,
-    doc_id: DocumentId,
+    store: Arc<SyncStore>,
+    roles: Arc<Roles>,
+    /// Open documents: app-facing ID (the session ID) -> store ID
+    docs: HashMap<String, DocumentId>,
     local_peer_id: PeerId,
     presence_protocol: PresenceProtocol,
+    chat_protocol: ChatProtocol,
+    peer_exchange: PeerExchange,
     store_change_rx: tokio_mpsc::Receiver<String>,
     presence_rx: tokio_mpsc::Receiver<PresenceMessage>,
+    chat_rx: tokio_mpsc::Receiver<ChatMessage>,
+    discovered_rx: tokio_mpsc::Receiver<DiscoveredPeer>,
+    /// Sync connections participants opened to us
+    inbound_rx: tokio_mpsc::Receiver<Connection>,
     router: iroh::protocol::Router,
 }
 
 /// Initialize the sync session - create endpoint, store, and protocol handlers
 async fn setup_sync_session(
     config: &SyncConfig,
+    roles: Arc<Roles>,
     endpoint_id_tx: &std_mpsc::Sender<String>,
     event_tx: &std_mpsc::Sender<SyncEvent>,
 ) -> Result<SyncSession> {
-    // Create iroh endpoint, optionally with n0 discovery (DNS + Pkarr)
-    let builder = Endpoint::builder(presets::N0);
+    // Create iroh endpoint, optionally with n0 discovery (DNS + Pkarr), under
+    // our stored identity when there is one
+    let mut builder = Endpoint::builder(presets::N0);
+    if let Some(path) = &config.identity_path {
+        builder = builder.secret_key(identity::load_or_create_secret_key(path)?);
+    }
     let endpoint = builder.bind().await?;
 
     // Create a ticket from the endpoint address (includes relay URL and direct addresses)
@@ -279,42 +562,46 @@ async fn setup_sync_session(
         .ok_or_else(|| anyhow::anyhow!("PublicKey must be 32 bytes"))?;
 
     // Send our ticket string back to main thread
-    if let Err(e) = endpoint_id_tx.send(ticket_string.clone()) {
-        eprintln!("Failed to send endpoint ID to main thread: {}", e);
-    }
+    let _ = endpoint_id_tx.send(ticket_string.clone());
 
     // Create the document store with a channel for sync notifications
     let (store_change_tx, store_change_rx) = tokio_mpsc::channel(10);
-    let store = Arc::new(LocalDocumentStore::new(store_change_tx));
+    let store = Arc::new(SyncStore::open(config.store_dir.clone(), store_change_tx)?);
 
-    // Create the document ID for our session
-    let doc_id = DocumentId::from_string(IROHSCII_DOC_ID)?;
+    // Open the startup document (created empty until the app seeds it)
+    let mut docs = HashMap::new();
+    let doc_id = open_document(&store, &mut docs, &config.doc_id).await?;
 
-    // Initialize an empty document in the store if it doesn't exist
-    if !store.as_ref().exists(&doc_id).await? {
-        store.as_ref().create(Some(doc_id.clone()), None).await?;
-    }
-
-    // Create the automerge sync handler (for accepting incoming connections via router)
-    let sync_handler = Arc::new(AutomergeSyncHandler::new(store.clone()));
+    // Create the automerge sync handler (for accepting incoming connections
+    // via router), dropping changes from peers that may not write
+    let (inbound_tx, inbound_rx) = tokio_mpsc::channel(16);
+    let sync_gate = SyncGate::new(store.clone(), roles.clone(), inbound_tx);
 
     // Create the presence protocol handler
     let (presence_tx, presence_rx) = tokio_mpsc::channel(64);
     let presence_protocol = PresenceProtocol::new(local_peer_id, presence_tx);
 
-    // Build the router with both protocols
+    // Create the chat protocol handler
+    let (chat_tx, chat_rx) = tokio_mpsc::channel(64);
+    let chat_protocol = ChatProtocol::new(chat_tx, roles.clone());
+
+    // Create the peer exchange handler (learns the other participants)
+    let (discovered_tx, discovered_rx) = tokio_mpsc::channel(64);
+    let peer_exchange = PeerExchange::new(endpoint.clone(), roles.clone(), discovered_tx);
+
+    // Build the router with all four protocols
     let router = iroh::protocol::Router::builder(endpoint.clone())
-        .accept(AUTOMERGE_SYNC_ALPN, sync_handler.clone())
+        .accept(AUTOMERGE_SYNC_ALPN, sync_gate)
         .accept(PresenceProtocol::ALPN, presence_protocol.clone())
+        .accept(ChatProtocol::ALPN, chat_protocol.clone())
+        .accept(PeerExchange::ALPN, peer_exchange.clone())
         .spawn();
 
     // Notify that we're ready with our peer ID
-    if let Err(e) = event_tx.send(SyncEvent::Ready {
+    let _ = event_tx.send(SyncEvent::Ready {
         endpoint_id: ticket_string.clone(),
         local_peer_id,
-    }) {
-        eprintln!("Failed to send Ready event: {}", e);
-    }
+    });
 
     debug_assert!(
         store.exists(&doc_id).await?,
@@ -324,46 +611,203 @@ async fn setup_sync_session(
     Ok(SyncSession {
         endpoint,
         store,
-        doc_id,
+        roles,
+        docs,
         local_peer_id,
         presence_protocol,
+        chat_protocol,
+        peer_exchange,
         store_change_rx,
         presence_rx,
+        chat_rx,
+        discovered_rx,
+        inbound_rx,
         router,
     })
 }
 
-/// Establish initial peer connection (if join ticket provided)
-async fn connect_to_peer(
+/// A participant we dialed: the sync connection plus its presence and chat
+/// loops. Participants that dialed us sync over their own link instead.
+struct PeerLink {
+    addr: EndpointAddr,
+    conn: Connection,
+    presence: Option<tokio::task::JoinHandle<()>>,
+    chat: Option<tokio::task::JoinHandle<()>>,
+    /// Path last reported to the main thread
+    kind: ConnectionType,
+}
+
+impl PeerLink {
+    /// Stop the presence and chat loops and close the sync connection
+    fn close(&self, reason: &[u8]) {
+        for h in self.presence.iter().chain(&self.chat) {
+            h.abort();
+        }
+        self.conn.close(0u32.into(), reason);
+    }
+}
+
+/// Dial a participant for sync, plus presence and chat on separate
+/// connections
+async fn open_link(
     endpoint: &Endpoint,
-    ticket: &str,
+    addr: EndpointAddr,
+    presence_protocol: &PresenceProtocol,
+    chat_protocol: &ChatProtocol,
+) -> Result<PeerLink> {
+    let conn = endpoint.connect(addr.clone(), AUTOMERGE_SYNC_ALPN).await?;
+    let presence = connect_presence(endpoint, addr.clone(), presence_protocol).await;
+    let chat = connect_chat(endpoint, addr.clone(), chat_protocol).await;
+    let kind = connection_type(&conn);
+    Ok(PeerLink {
+        addr,
+        conn,
+        presence,
+        chat,
+        kind,
+    })
+}
+
+/// Whether a connection currently runs over a direct path or a relay
+fn connection_type(conn: &Connection) -> ConnectionType {
+    let direct = conn
+        .paths()
+        .get()
+        .iter()
+        .any(|path| path.is_selected() && path.is_ip());
+    if direct {
+        ConnectionType::Direct
+    } else {
+        ConnectionType::Relay
+    }
+}
+
+/// Redial a dropped link with exponential backoff, handing the result to the
+/// sync loop like any other new link. Gives up (sending the last error)
+/// after [`MAX_RECONNECT_ATTEMPTS`].
+fn spawn_reconnect(
+    endpoint: &Endpoint,
+    presence_protocol: &PresenceProtocol,
+    chat_protocol: &ChatProtocol,
+    addr: EndpointAddr,
     event_tx: &std_mpsc::Sender<SyncEvent>,
-) -> Option<Connection> {
-    match decode_ticket(ticket) {
-        Ok(endpoint_addr) => {
-            match endpoint.connect(endpoint_addr, AUTOMERGE_SYNC_ALPN).await {
-                Ok(conn) => Some(conn),
-                Err(e) => {
-                    if let Err(send_err) = event_tx.send(SyncEvent::Error(format!(
-                        "Failed to connect to peer: {}", e
-                    ))) {
-                        eprintln!("Failed to send peer connection error: {}", send_err);
-                    }
-                    None
+    link_tx: &tokio_mpsc::Sender<(EndpointAddr, Result<PeerLink>)>,
+    shutting_down: &Arc<AtomicBool>,
+) {
+    let Some(peer_id) = peer_id_of(&addr) else {
+        return;
+    };
+    let endpoint = endpoint.clone();
+    let presence_protocol = presence_protocol.clone();
+    let chat_protocol = chat_protocol.clone();
+    let event_tx = event_tx.clone();
+    let link_tx = link_tx.clone();
+    let shutting_down = shutting_down.clone();
+    tokio::spawn(async move {
+        let mut delay = RECONNECT_INITIAL_DELAY;
+        let mut last_err = anyhow::anyhow!("not attempted");
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            if shutting_down.load(Ordering::Relaxed) {
+                return;
+            }
+            let _ = event_tx.send(SyncEvent::Reconnecting { peer_id, attempt });
+            match open_link(&endpoint, addr.clone(), &presence_protocol, &chat_protocol).await {
+                Ok(link) => {
+                    let _ = link_tx.send((addr, Ok(link))).await;
+                    return;
                 }
+                Err(e) => last_err = e,
             }
+            delay = delay.saturating_mul(2).min(RECONNECT_MAX_DELAY);
         }
+        let _ = link_tx.send((addr, Err(last_err))).await;
+    });
+}
+
+/// Establish a peer link from a ticket (join ticket or ConnectPeer),
+/// returning it with the ID of the document the ticket names
+async fn connect_to_peer(
+    endpoint: &Endpoint,
+    ticket: &str,
+    presence_protocol: &PresenceProtocol,
+    chat_protocol: &ChatProtocol,
+    event_tx: &std_mpsc::Sender<SyncEvent>,
+) -> Option<(PeerLink, String)> {
+    match decode_doc_ticket(ticket) {
+        Ok((endpoint_addr, doc_id)) => match open_link(endpoint, endpoint_addr, presence_protocol, chat_protocol).await {
+            Ok(link) => Some((link, doc_id)),
+            Err(e) => {
+                let _ = event_tx.send(SyncEvent::Error(format!(
+                    "Failed to connect to peer: {}", e
+                )));
+                None
+            }
+        },
         Err(e) => {
-            if let Err(send_err) = event_tx.send(SyncEvent::Error(format!(
+            let _ = event_tx.send(SyncEvent::Error(format!(
                 "Invalid peer ticket: {}", e
-            ))) {
-                eprintln!("Failed to send ticket decode error: {}", send_err);
-            }
+            )));
             None
         }
     }
 }
 
+/// Whether to dial a participant learned through peer exchange. Exactly one
+/// side of each pair dials: a peer that dialed us already holds the link,
+/// and of two peers introduced by a third, the one with the lower ID dials.
+fn should_dial(local_peer_id: &PeerId, peer: &DiscoveredPeer) -> bool {
+    !peer.inbound && peer_id_of(&peer.addr).is_some_and(|id| local_peer_id.0 < id.0)
+}
+
+/// Report when a link's connection closes, and whether either side closed
+/// it on purpose (rather than it timing out or failing). The stable ID keeps
+/// a late report from removing a newer link to the same peer.
+fn watch_link(
+    peer_id: PeerId,
+    conn: Connection,
+    gone_tx: tokio_mpsc::Sender<(PeerId, usize, bool)>,
+) {
+    tokio::spawn(async move {
+        let clean = matches!(
+            conn.closed().await,
+            ConnectionError::ApplicationClosed(_) | ConnectionError::LocallyClosed
+        );
+        let _ = gone_tx.send((peer_id, conn.stable_id(), clean)).await;
+    });
+}
+
+/// The store as seen by a linked peer: changes it may not make are dropped
+fn gated_store(store: &Arc<SyncStore>, roles: &Arc<Roles>, peer_id: PeerId) -> Arc<GatedStore> {
+    Arc::new(GatedStore::new(store.clone(), roles.clone(), peer_id))
+}
+
+/// Sync documents with a linked peer in the background, unless shutting down
+fn spawn_sync(
+    store: Arc<GatedStore>,
+    doc_ids: Vec<DocumentId>,
+    conn: &Connection,
+    shutting_down: &Arc<AtomicBool>,
+) {
+    let conn_clone = conn.clone();
+    let shutting_down_clone = shutting_down.clone();
+    tokio::spawn(async move {
+        if !shutting_down_clone.load(Ordering::Relaxed) {
+            sync_peer_docs(&store, &doc_ids, &conn_clone).await;
+        }
+    });
+}
+
+/// Swap participant lists with a linked peer in the background
+fn spawn_exchange(peer_exchange: &PeerExchange, addr: &EndpointAddr) {
+    let peer_exchange = peer_exchange.clone();
+    let addr = addr.clone();
+    tokio::spawn(async move {
+        // A failed exchange is retried on the next interval
+        let _ = peer_exchange.exchange(addr).await;
+    });
+}
+
 /// Establish initial cluster connection (if cluster ticket provided)
 async fn connect_to_cluster(
     endpoint: &Endpoint,
@@ -375,21 +819,17 @@ async fn connect_to_cluster(
             match endpoint.connect(cluster_addr, AUTOMERGE_SYNC_ALPN).await {
                 Ok(conn) => (Some(conn), ticket_cap),
                 Err(e) => {
-                    if let Err(send_err) = event_tx.send(SyncEvent::Error(format!(
+                    let _ = event_tx.send(SyncEvent::Error(format!(
                         "Failed to connect to cluster: {}", e
-                    ))) {
-                        eprintln!("Failed to send cluster connection error: {}", send_err);
-                    }
+                    )));
                     (None, ticket_cap)
                 }
             }
         }
         Err(e) => {
-            if let Err(send_err) = event_tx.send(SyncEvent::Error(format!(
+            let _ = event_tx.send(SyncEvent::Error(format!(
                 "Invalid cluster ticket: {}", e
-            ))) {
-                eprintln!("Failed to send cluster ticket error: {}", send_err);
-            }
+            )));
             (None, None)
         }
     }
@@ -398,49 +838,122 @@ async fn connect_to_cluster(
 /// Connect presence protocol to a peer
 async fn connect_presence(
     endpoint: &Endpoint,
-    ticket: &str,
+    endpoint_addr: EndpointAddr,
     presence_protocol: &PresenceProtocol,
 ) -> Option<tokio::task::JoinHandle<()>> {
-    match decode_ticket(ticket) {
-        Ok(endpoint_addr) => {
-            match endpoint.connect(endpoint_addr, PresenceProtocol::ALPN).await {
-                Ok(conn) => {
-                    let presence_clone = presence_protocol.clone();
-                    Some(tokio::spawn(async move {
-                        if let Err(e) = presence_clone.run_presence_loop(conn).await {
-                            eprintln!("Presence loop error: {}", e);
-                        }
-                    }))
-                }
-                Err(e) => {
-                    eprintln!("Failed to connect presence protocol: {}", e);
-                    None
-                }
-            }
+    match endpoint.connect(endpoint_addr, PresenceProtocol::ALPN).await {
+        Ok(conn) => {
+            let presence_clone = presence_protocol.clone();
+            Some(tokio::spawn(async move {
+                // Ends when the peer goes away; the sync link reports that
+                let _ = presence_clone.run_presence_loop(conn).await;
+            }))
+        }
+        // Presence is best-effort: the link still syncs without it
+        Err(_) => None,
+    }
+}
+
+/// Connect chat protocol to a peer
+async fn connect_chat(
+    endpoint: &Endpoint,
+    endpoint_addr: EndpointAddr,
+    chat_protocol: &ChatProtocol,
+) -> Option<tokio::task::JoinHandle<()>> {
+    match endpoint.connect(endpoint_addr, ChatProtocol::ALPN).await {
+        Ok(conn) => {
+            let chat_clone = chat_protocol.clone();
+            Some(tokio::spawn(async move {
+                // Ends when the peer goes away; the sync link reports that
+                let _ = chat_clone.run_chat_loop(conn).await;
+            }))
         }
+        // Chat is best-effort, like presence
+        Err(_) => None,
+    }
+}
+
+/// Store IDs of every open document
+fn doc_ids(docs: &HashMap<String, DocumentId>) -> Vec<DocumentId> {
+    docs.values().cloned().collect()
+}
+
+/// Open a document and merge the app's changes into its store copy,
+/// reporting failures to the main thread
+async fn seed_document(
+    store: &Arc<SyncStore>,
+    docs: &mut HashMap<String, DocumentId>,
+    doc_id: &str,
+    changes: &[u8],
+    event_tx: &std_mpsc::Sender<SyncEvent>,
+) -> Option<DocumentId> {
+    let result = match open_document(store, docs, doc_id).await {
+        Ok(id) => store
+            .apply_from_app(&id, changes)
+            .await
+            .map(|()| id)
+            .map_err(anyhow::Error::from),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(id) => Some(id),
         Err(e) => {
-            eprintln!("Invalid ticket for presence connection: {}", e);
+            let _ = event_tx.send(SyncEvent::Error(format!(
+                "Failed to update store: {}", e
+            )));
             None
         }
     }
 }
 
+/// Send the app the changes to an open document it hasn't seen yet.
+///
+/// `reported` holds the heads the app was last brought up to. The store
+/// copy always contains them (it is a superset of everything the app sent
+/// and everything reported), so the delta is exact.
+async fn report_changes(
+    store: &Arc<SyncStore>,
+    app_id: &str,
+    doc_id: &DocumentId,
+    reported: &mut HashMap<String, Vec<ChangeHash>>,
+    event_tx: &std_mpsc::Sender<SyncEvent>,
+) {
+    let heads = reported.entry(app_id.to_string()).or_default();
+    let Some((changes, new_heads)) = store.changes_since(doc_id, heads).await else {
+        return;
+    };
+    *heads = new_heads;
+    if !changes.is_empty() {
+        let _ = event_tx.send(SyncEvent::RemoteChanges {
+            doc_id: app_id.to_string(),
+            changes,
+        });
+    }
+}
+
 /// Main async sync loop
 async fn run_sync(
     config: SyncConfig,
+    roles: Arc<Roles>,
     event_tx: std_mpsc::Sender<SyncEvent>,
     mut command_rx: tokio_mpsc::Receiver<SyncCommand>,
     endpoint_id_tx: std_mpsc::Sender<String>,
 ) -> Result<()> {
-    let session = setup_sync_session(&config, &endpoint_id_tx, &event_tx).await?;
+    let session = setup_sync_session(&config, roles, &endpoint_id_tx, &event_tx).await?;
     let SyncSession {
         endpoint,
         store,
-        doc_id,
+        roles,
+        mut docs,
         local_peer_id,
         presence_protocol,
+        chat_protocol,
+        peer_exchange,
         mut store_change_rx,
         mut presence_rx,
+        mut chat_rx,
+        mut discovered_rx,
+        mut inbound_rx,
         router,
     } = session;
     
@@ -451,12 +964,46 @@ async fn run_sync(
             // Shutdown flag for clean periodic sync termination
             let shutting_down = Arc::new(AtomicBool::new(false));
 
-            // Establish a persistent sync connection to the peer (if joining)
-            let mut sync_conn: Option<Connection> = if let Some(ref remote_ticket) = join_ticket {
-                connect_to_peer(&endpoint, remote_ticket, &event_tx).await
-            } else {
-                None
-            };
+            // Peers we dialed, keyed by peer ID. Every participant learned
+            // through peer exchange gets a link from one side of the pair,
+            // so the session survives any single peer (the host included)
+            // leaving.
+            let mut links: HashMap<PeerId, PeerLink> = HashMap::new();
+            let (link_tx, mut link_rx) =
+                tokio_mpsc::channel::<(EndpointAddr, Result<PeerLink>)>(16);
+            let (gone_tx, mut gone_rx) = tokio_mpsc::channel::<(PeerId, usize, bool)>(16);
+            // Participants that dialed us, with the path last reported.
+            // Their links are theirs to redial.
+            let mut inbound: HashMap<PeerId, (Connection, ConnectionType)> = HashMap::new();
+
+            // Establish a persistent link to the peer (if joining)
+            if let Some(ref remote_ticket) = join_ticket
+                && let Some((link, remote_doc)) =
+                    connect_to_peer(&endpoint, remote_ticket, &presence_protocol, &chat_protocol, &event_tx).await
+                && let Some(peer_id) = peer_id_of(&link.addr)
+            {
+                // Open the ticket's document too (normally the startup one)
+                if let Err(e) = open_document(&store, &mut docs, &remote_doc).await {
+                    let _ = event_tx.send(SyncEvent::Error(format!(
+                        "Invalid document in ticket: {}", e
+                    )));
+                }
+                // Learn the other participants and who may edit, then pull
+                // any existing state
+                peer_exchange.insert(link.addr.clone()).await;
+                // A failed exchange is retried on the next interval
+                let _ = peer_exchange.exchange(link.addr.clone()).await;
+                sync_peer_docs(&gated_store(&store, &roles, peer_id), &doc_ids(&docs), &link.conn).await;
+                watch_link(peer_id, link.conn.clone(), gone_tx.clone());
+                let _ = event_tx.send(SyncEvent::PeerConnected { peer_id, kind: link.kind });
+                links.insert(peer_id, link);
+            } else if let Some(ref remote_ticket) = join_ticket
+                && let Ok((addr, _)) = decode_doc_ticket(remote_ticket)
+            {
+                // The host may just be starting up or briefly offline
+                peer_exchange.insert(addr.clone()).await;
+                spawn_reconnect(&endpoint, &presence_protocol, &chat_protocol, addr, &event_tx, &link_tx, &shutting_down);
+            }
 
             // Capability token for authenticating with the cluster. Mutable so
             // ConnectCluster can replace it mid-session.
@@ -473,32 +1020,31 @@ async fn run_sync(
                 None
             };
 
-            // Do initial sync to pull any existing state from the peer
-            if let Some(ref conn) = sync_conn {
-                do_sync(&store, &doc_id, conn, None).await;
-            }
-
             // Pull any existing state from the cluster
             if let Some(ref conn) = cluster_conn {
-                do_sync(&store, &doc_id, conn, cluster_cap.as_ref()).await;
+                sync_docs(&store, &doc_ids(&docs), conn, cluster_cap.as_ref()).await;
             }
 
-            // Connect presence protocol on a separate connection (different ALPN)
-            let mut presence_handle = if let Some(ref remote_ticket) = join_ticket {
-                connect_presence(&endpoint, remote_ticket, &presence_protocol).await
-            } else {
-                None
-            };
-
-            // Periodic peer sync (every 1s) to pull remote changes from the host.
-            // The joiner pushes immediately on SyncDoc commands, but the host has no
-            // outgoing connection, so the joiner must periodically poll to discover
-            // host-side changes. Skipped when a SyncDoc push happened recently to
-            // avoid redundant round-trips during active editing.
+            // Periodic peer sync (every 1s) to pull remote changes over our links.
+            // We push immediately on SyncDoc commands, but peers that dialed us
+            // have no link of ours to push over, so each side periodically polls
+            // its links to discover changes made on the other end. Skipped when a
+            // SyncDoc push happened recently to avoid redundant round-trips
+            // during active editing.
             let mut sync_interval = tokio::time::interval(Duration::from_secs(1));
             sync_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
             let mut last_explicit_sync = tokio::time::Instant::now();
 
+            // Heads of each open document the app has been told about, so
+            // RemoteChanges carries only what it hasn't seen. Documents the
+            // app never seeded start empty and get everything first time.
+            let mut reported: HashMap<String, Vec<ChangeHash>> = HashMap::new();
+
+            // Peer exchange timer, so links reach participants who joined
+            // through someone else
+            let mut exchange_interval = tokio::time::interval(PEER_EXCHANGE_INTERVAL);
+            exchange_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
             // Cluster sync timer (every 2s) — less frequent since it's for persistence
             let mut cluster_interval = tokio::time::interval(Duration::from_secs(2));
             cluster_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
@@ -510,64 +1056,175 @@ async fn run_sync(
             loop {
                 loop_count = loop_count.saturating_add(1);
                 if loop_count >= MAX_LOOP_ITERATIONS {
-                    eprintln!("Sync loop exceeded max iterations, shutting down");
+                    let _ = event_tx.send(SyncEvent::Error(
+                        "Sync loop exceeded max iterations, shutting down".to_string(),
+                    ));
                     break;
                 }
                 tokio::select! {
                     // Store notified us of changes from sync
-                    Some(_doc_id_str) = store_change_rx.recv() => {
-                        if let Some(bytes) = store.get_bytes(&doc_id).await {
-                            match Automerge::load(&bytes) {
-                                Ok(doc) => {
-                                    if let Err(send_err) = event_tx.send(SyncEvent::RemoteChanges {
-                                        doc: Box::new(doc),
-                                    }) {
-                                        eprintln!("Failed to send RemoteChanges event: {}", send_err);
-                                    }
-                                }
-                                Err(e) => {
-                                    if let Err(send_err) = event_tx.send(SyncEvent::Error(
-                                        format!("Failed to load doc: {}", e),
-                                    )) {
-                                        eprintln!("Failed to send doc load error: {}", send_err);
-                                    }
-                                }
-                            }
-                        }
+                    Some(changed_id) = store_change_rx.recv() => {
+                        // Only open documents are reported (closed ones may
+                        // still be mid-sync)
+                        let Some((app_id, doc_id)) = docs
+                            .iter()
+                            .find(|(_, id)| id.to_string() == changed_id)
+                        else {
+                            continue;
+                        };
+                        report_changes(&store, app_id, doc_id, &mut reported, &event_tx).await;
                     }
                     // Presence messages from peers
                     Some(msg) = presence_rx.recv() => {
                         match msg {
                             PresenceMessage::Update(presence) => {
-                                if let Err(e) = event_tx.send(SyncEvent::PresenceUpdate(presence)) {
-                                    eprintln!("Failed to send PresenceUpdate event: {}", e);
+                                if !roles.is_refused(&presence.peer_id) {
+                                    let _ = event_tx.send(SyncEvent::PresenceUpdate(presence));
                                 }
                             }
                             PresenceMessage::Leave { peer_id } => {
-                                if let Err(e) = event_tx.send(SyncEvent::PresenceRemoved { peer_id }) {
-                                    eprintln!("Failed to send PresenceRemoved event: {}", e);
+                                // Don't hand a departed peer to newcomers
+                                peer_exchange.forget(&peer_id).await;
+                                let dialed = links.remove(&peer_id);
+                                if let Some(link) = &dialed {
+                                    link.close(b"peer left");
                                 }
+                                let dialed_us = inbound.remove(&peer_id);
+                                if dialed.is_some() || dialed_us.is_some() {
+                                    let _ = event_tx.send(SyncEvent::PeerDisconnected { peer_id });
+                                }
+                                let _ = event_tx.send(SyncEvent::PresenceRemoved { peer_id });
                             }
                             PresenceMessage::RequestAll => {}
                         }
                     }
-                    // Periodic sync with peer — pull remote changes (skipped if recent push)
+                    // Chat messages from peers (sender already checked
+                    // against the connection), only for documents we sync
+                    // and the sender may read
+                    Some(msg) = chat_rx.recv() => {
+                        if docs.get(&msg.doc_id).is_some_and(|id| roles.can_read(id, &msg.from)) {
+                            let _ = event_tx.send(SyncEvent::Chat(msg));
+                        }
+                    }
+                    // Participant learned through peer exchange
+                    Some(peer) = discovered_rx.recv() => {
+                        let already_linked = peer_id_of(&peer.addr).is_some_and(|id| links.contains_key(&id));
+                        if !already_linked && should_dial(&local_peer_id, &peer) {
+                            let endpoint_clone = endpoint.clone();
+                            let presence_clone = presence_protocol.clone();
+                            let chat_clone = chat_protocol.clone();
+                            let link_tx_clone = link_tx.clone();
+                            tokio::spawn(async move {
+                                let result = open_link(&endpoint_clone, peer.addr.clone(), &presence_clone, &chat_clone).await;
+                                let _ = link_tx_clone.send((peer.addr, result)).await;
+                            });
+                        }
+                    }
+                    // Link to a discovered participant finished connecting
+                    Some((addr, result)) = link_rx.recv() => {
+                        let Some(peer_id) = peer_id_of(&addr) else { continue; };
+                        match result {
+                            Ok(link) => {
+                                spawn_sync(gated_store(&store, &roles, peer_id), doc_ids(&docs), &link.conn, &shutting_down);
+                                spawn_exchange(&peer_exchange, &link.addr);
+                                watch_link(peer_id, link.conn.clone(), gone_tx.clone());
+                                let _ = event_tx.send(SyncEvent::PeerConnected { peer_id, kind: link.kind });
+                                if let Some(old) = links.insert(peer_id, link) {
+                                    old.close(b"replaced");
+                                }
+                            }
+                            Err(_) => {
+                                // Unreachable (likely already gone): stop advertising it
+                                peer_exchange.forget(&peer_id).await;
+                                let _ = event_tx.send(SyncEvent::PeerDisconnected { peer_id });
+                            }
+                        }
+                    }
+                    // Participant dialed us: report its link like one of ours
+                    Some(conn) = inbound_rx.recv() => {
+                        let Some(peer_id) = PeerId::from_bytes(conn.remote_id().as_bytes()) else { continue; };
+                        let kind = connection_type(&conn);
+                        watch_link(peer_id, conn.clone(), gone_tx.clone());
+                        inbound.insert(peer_id, (conn, kind));
+                        if !links.contains_key(&peer_id) {
+                            let _ = event_tx.send(SyncEvent::PeerConnected { peer_id, kind });
+                        }
+                    }
+                    // A link's connection closed (peer left or network loss)
+                    Some((peer_id, stable_id, clean)) = gone_rx.recv() => {
+                        let is_inbound = inbound
+                            .get(&peer_id)
+                            .is_some_and(|(conn, _)| conn.stable_id() == stable_id);
+                        if is_inbound {
+                            inbound.remove(&peer_id);
+                            if !links.contains_key(&peer_id) {
+                                let _ = event_tx.send(SyncEvent::PeerDisconnected { peer_id });
+                            }
+                            continue;
+                        }
+                        let is_current = links
+                            .get(&peer_id)
+                            .is_some_and(|link| link.conn.stable_id() == stable_id);
+                        if is_current && let Some(link) = links.remove(&peer_id) {
+                            link.close(b"closed");
+                            if !inbound.contains_key(&peer_id) {
+                                let _ = event_tx.send(SyncEvent::PeerDisconnected { peer_id });
+                            }
+                            // Keep advertising it while we try to get it back,
+                            // unless it hung up on purpose (leaving, or
+                            // refusing us)
+                            if !clean && !shutting_down.load(Ordering::Relaxed) {
+                                spawn_reconnect(&endpoint, &presence_protocol, &chat_protocol, link.addr, &event_tx, &link_tx, &shutting_down);
+                            }
+                        }
+                    }
+                    // Periodic sync with peers — pull remote changes (skipped if recent push)
                     _ = sync_interval.tick() => {
                         let not_shutting_down = !shutting_down.load(Ordering::Relaxed);
                         let not_recent_sync = last_explicit_sync.elapsed() >= Duration::from_millis(500);
-                        let has_connection = sync_conn.is_some();
-                        
-                        if not_shutting_down && not_recent_sync && has_connection {
-                            let Some(ref conn) = sync_conn else { continue; };
-                            let store_clone = store.clone();
-                            let doc_id_clone = doc_id.clone();
-                            let conn_clone = conn.clone();
-                            let shutting_down_clone = shutting_down.clone();
-                            tokio::spawn(async move {
-                                if !shutting_down_clone.load(Ordering::Relaxed) {
-                                    do_sync(&store_clone, &doc_id_clone, &conn_clone, None).await;
-                                }
-                            });
+
+                        // Drop peers whose invite expired or was revoked
+                        let refused: HashSet<PeerId> = links.keys().chain(inbound.keys()).filter(|id| roles.is_refused(id)).copied().collect();
+                        for peer_id in refused {
+                            peer_exchange.forget(&peer_id).await;
+                            if let Some(link) = links.remove(&peer_id) {
+                                link.close(b"invite refused");
+                            }
+                            if let Some((conn, _)) = inbound.remove(&peer_id) {
+                                conn.close(0u32.into(), b"invite refused");
+                            }
+                            let _ = event_tx.send(SyncEvent::PeerDisconnected { peer_id });
+                            let _ = event_tx.send(SyncEvent::PresenceRemoved { peer_id });
+                        }
+
+                        if not_shutting_down && not_recent_sync {
+                            for (peer_id, link) in links.iter() {
+                                spawn_sync(gated_store(&store, &roles, *peer_id), doc_ids(&docs), &link.conn, &shutting_down);
+                            }
+                        }
+
+                        // Report links that moved between a relay and a direct path
+                        for (peer_id, link) in links.iter_mut() {
+                            let kind = connection_type(&link.conn);
+                            if kind != link.kind {
+                                link.kind = kind;
+                                let _ = event_tx.send(SyncEvent::ConnectionType { peer_id: *peer_id, kind });
+                            }
+                        }
+                        for (peer_id, (conn, reported)) in inbound.iter_mut() {
+                            let kind = connection_type(conn);
+                            if kind != *reported && !links.contains_key(peer_id) {
+                                *reported = kind;
+                                let _ = event_tx.send(SyncEvent::ConnectionType { peer_id: *peer_id, kind });
+                            }
+                        }
+                    }
+                    // Periodic peer exchange — learn participants who joined elsewhere
+                    _ = exchange_interval.tick() => {
+                        if !shutting_down.load(Ordering::Relaxed) {
+                            for link in links.values() {
+                                spawn_exchange(&peer_exchange, &link.addr);
+                            }
                         }
                     }
                     // Periodic sync with cluster (less frequent, for persistence)
@@ -578,13 +1235,13 @@ async fn run_sync(
                         if not_shutting_down && has_cluster {
                             let Some(ref conn) = cluster_conn else { continue; };
                             let store_clone = store.clone();
-                            let doc_id_clone = doc_id.clone();
+                            let doc_ids_clone = doc_ids(&docs);
                             let conn_clone = conn.clone();
                             let cap_clone = cluster_cap.clone();
                             let shutting_down_clone = shutting_down.clone();
                             tokio::spawn(async move {
                                 if !shutting_down_clone.load(Ordering::Relaxed) {
-                                    do_sync(&store_clone, &doc_id_clone, &conn_clone, cap_clone.as_ref()).await;
+                                    sync_docs(&store_clone, &doc_ids_clone, &conn_clone, cap_clone.as_ref()).await;
                                 }
                             });
                         }
@@ -592,44 +1249,62 @@ async fn run_sync(
                     // Commands from main thread (immediate, no polling delay)
                     cmd = command_rx.recv() => {
                         match cmd {
-                            Some(SyncCommand::SyncDoc { doc }) => {
+                            Some(SyncCommand::SyncDoc { doc_id, changes }) => {
                                 // Update the local store (no notification to avoid feedback loop)
-                                let bytes = doc.save();
-                                if let Err(e) = store.update_from_app(&doc_id, &bytes).await {
-                                    if let Err(send_err) = event_tx.send(SyncEvent::Error(
-                                        format!("Failed to update store: {}", e),
-                                    )) {
-                                        eprintln!("Failed to send store update error: {}", send_err);
-                                    }
-                                }
+                                let Some(doc_id) = seed_document(&store, &mut docs, &doc_id, &changes, &event_tx).await else {
+                                    continue;
+                                };
 
                                 // Record that we just synced (suppresses redundant periodic pull)
                                 last_explicit_sync = tokio::time::Instant::now();
 
-                                // Push changes to peer via the persistent connection
-                                if let Some(ref conn) = sync_conn {
-                                    let store_clone = store.clone();
-                                    let doc_id_clone = doc_id.clone();
-                                    let conn_clone = conn.clone();
-                                    tokio::spawn(async move {
-                                        do_sync(&store_clone, &doc_id_clone, &conn_clone, None).await;
-                                    });
+                                // Push changes to every linked peer
+                                for (peer_id, link) in links.iter() {
+                                    spawn_sync(gated_store(&store, &roles, *peer_id), vec![doc_id.clone()], &link.conn, &shutting_down);
                                 }
 
                                 // Push changes to cluster
                                 if let Some(ref conn) = cluster_conn {
                                     let store_clone = store.clone();
-                                    let doc_id_clone = doc_id.clone();
                                     let conn_clone = conn.clone();
                                     let cap_clone = cluster_cap.clone();
                                     tokio::spawn(async move {
-                                        do_sync(&store_clone, &doc_id_clone, &conn_clone, cap_clone.as_ref()).await;
+                                        do_sync(&store_clone, &doc_id, &conn_clone, cap_clone.as_ref()).await;
                                     });
                                 }
                             }
+                            Some(SyncCommand::OpenDocument { doc_id: app_id, doc }) => {
+                                let Some(doc_id) = seed_document(&store, &mut docs, &app_id, &doc.save(), &event_tx).await else {
+                                    continue;
+                                };
+
+                                // The app is now up to date with its own
+                                // state; hand it anything the store already
+                                // had (e.g. from disk)
+                                reported.insert(app_id.clone(), doc.get_heads());
+                                report_changes(&store, &app_id, &doc_id, &mut reported, &event_tx).await;
+
+                                // Merge with whatever peers already have
+                                for (peer_id, link) in links.iter() {
+                                    spawn_sync(gated_store(&store, &roles, *peer_id), vec![doc_id.clone()], &link.conn, &shutting_down);
+                                }
+                                if let Some(ref conn) = cluster_conn {
+                                    do_sync(&store, &doc_id, conn, cluster_cap.as_ref()).await;
+                                }
+                            }
+                            Some(SyncCommand::CloseDocument { doc_id }) => {
+                                reported.remove(&doc_id);
+                                if let Some(id) = docs.remove(&doc_id) {
+                                    // Stop serving it to peers as well
+                                    store.close(&id).await;
+                                }
+                            }
                             Some(SyncCommand::BroadcastPresence(presence)) => {
                                 presence_protocol.broadcast(presence);
                             }
+                            Some(SyncCommand::SendChat(msg)) => {
+                                chat_protocol.send(msg);
+                            }
                             Some(SyncCommand::ConnectCluster { ticket, capability }) => {
                                 match decode_cluster_ticket(&ticket) {
                                     Ok((addr, ticket_cap)) => {
@@ -638,75 +1313,44 @@ async fn run_sync(
                                                 // Prefer explicit capability, fall back to ticket's
                                                 cluster_cap = capability.or(ticket_cap);
                                                 // Pull existing state from cluster
-                                                do_sync(&store, &doc_id, &conn, cluster_cap.as_ref()).await;
+                                                sync_docs(&store, &doc_ids(&docs), &conn, cluster_cap.as_ref()).await;
                                                 cluster_conn = Some(conn);
                                             }
                                             Err(e) => {
-                                                if let Err(send_err) = event_tx.send(SyncEvent::Error(
+                                                let _ = event_tx.send(SyncEvent::Error(
                                                     format!("Failed to connect to cluster: {}", e),
-                                                )) {
-                                                    eprintln!("Failed to send cluster connect error: {}", send_err);
-                                                }
+                                                ));
                                             }
                                         }
                                     }
                                     Err(e) => {
-                                        if let Err(send_err) = event_tx.send(SyncEvent::Error(
+                                        let _ = event_tx.send(SyncEvent::Error(
                                             format!("Invalid cluster ticket: {}", e),
-                                        )) {
-                                            eprintln!("Failed to send cluster ticket error: {}", send_err);
-                                        }
+                                        ));
                                     }
                                 }
                             }
                             Some(SyncCommand::ConnectPeer { ticket }) => {
-                                match decode_ticket(&ticket) {
-                                    Ok(addr) => {
-                                        // Close old presence connection if exists
-                                        if let Some(h) = &presence_handle {
-                                            h.abort();
-                                        }
-                                        // Connect to new peer for sync
-                                        match endpoint.connect(addr.clone(), AUTOMERGE_SYNC_ALPN).await {
-                                            Ok(conn) => {
-                                                // Pull existing state from peer
-                                                do_sync(&store, &doc_id, &conn, None).await;
-                                                sync_conn = Some(conn);
-                                            }
-                                            Err(e) => {
-                                                if let Err(send_err) = event_tx.send(SyncEvent::Error(
-                                                    format!("Failed to connect to peer: {}", e),
-                                                )) {
-                                                    eprintln!("Failed to send peer connect error: {}", send_err);
-                                                }
-                                                // Don't return early, still try to connect presence
-                                            }
-                                        }
-                                        // Connect presence protocol on a separate connection
-                                        match endpoint.connect(addr, PresenceProtocol::ALPN).await {
-                                            Ok(conn) => {
-                                                let presence_clone = presence_protocol.clone();
-                                                presence_handle = Some(tokio::spawn(async move {
-                                                    if let Err(e) = presence_clone.run_presence_loop(conn).await {
-                                                        eprintln!("Presence loop error: {}", e);
-                                                    }
-                                                }));
-                                            }
-                                            Err(e) => {
-                                                if let Err(send_err) = event_tx.send(SyncEvent::Error(
-                                                    format!("Failed to connect presence: {}", e),
-                                                )) {
-                                                    eprintln!("Failed to send presence connect error: {}", send_err);
-                                                }
-                                            }
-                                        }
+                                if let Ok((_, doc_id, access)) = decode_ticket_access(&ticket) {
+                                    roles.register(&doc_id, access.as_ref());
+                                }
+                                if let Some((link, remote_doc)) = connect_to_peer(&endpoint, &ticket, &presence_protocol, &chat_protocol, &event_tx).await
+                                    && let Some(peer_id) = peer_id_of(&link.addr)
+                                {
+                                    if let Err(e) = open_document(&store, &mut docs, &remote_doc).await {
+                                        let _ = event_tx.send(SyncEvent::Error(
+                                            format!("Invalid document in ticket: {}", e),
+                                        ));
                                     }
-                                    Err(e) => {
-                                        if let Err(send_err) = event_tx.send(SyncEvent::Error(
-                                            format!("Invalid peer ticket: {}", e),
-                                        )) {
-                                            eprintln!("Failed to send peer ticket error: {}", send_err);
-                                        }
+                                    // Mesh with its peers and learn who may edit, then
+                                    // pull existing state
+                                    peer_exchange.insert(link.addr.clone()).await;
+                                    let _ = peer_exchange.exchange(link.addr.clone()).await;
+                                    sync_peer_docs(&gated_store(&store, &roles, peer_id), &doc_ids(&docs), &link.conn).await;
+                                    watch_link(peer_id, link.conn.clone(), gone_tx.clone());
+                                    let _ = event_tx.send(SyncEvent::PeerConnected { peer_id, kind: link.kind });
+                                    if let Some(old) = links.insert(peer_id, link) {
+                                        old.close(b"replaced");
                                     }
                                 }
                             }
@@ -714,12 +1358,9 @@ async fn run_sync(
                                 // Shutdown command or channel closed (main thread dropped sender)
                                 shutting_down.store(true, Ordering::Relaxed);
                                 presence_protocol.broadcast_leave();
-                                if let Some(h) = &presence_handle {
-                                    h.abort();
-                                }
                                 // Close connections gracefully
-                                if let Some(ref conn) = sync_conn {
-                                    conn.close(0u32.into(), b"shutdown");
+                                for link in links.values() {
+                                    link.close(b"shutdown");
                                 }
                                 if let Some(ref conn) = cluster_conn {
                                     conn.close(0u32.into(), b"shutdown");
@@ -739,7 +1380,6 @@ async fn run_sync(
             loop {
                 iteration_count = iteration_count.saturating_add(1);
                 if iteration_count >= MAX_DISABLED_ITERATIONS {
-                    eprintln!("Disabled sync loop exceeded max iterations");
                     break;
                 }
                 
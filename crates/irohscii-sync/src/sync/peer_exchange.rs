@@ -0,0 +1,290 @@
+//! Peer exchange protocol for full-mesh sessions
+//!
+//! Uses ALPN "irohscii/peers/4" over iroh connections.
+//! Each exchange is a single round trip on a short-lived connection: the
+//! dialer sends every participant it knows, the acceptor answers with its
+//! own list, and both remember anyone new. The sync loop dials newcomers,
+//! so each participant ends up directly connected to every other one and
+//! any of them can leave without splitting the session.
+//!
+//! Lists also carry the sender's editor grants (see [`super::access`]), the
+//! invites it joined with (only in lists sent to editors of their
+//! documents) and the invites revoked so far, so every participant learns
+//! who may change each document. Lists are only taken from the peer on the
+//! other end of the connection. A sender whose invite is expired, revoked or
+//! forged is refused: its list is dropped and the exchange fails.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use iroh::Endpoint;
+use iroh::endpoint::Connection;
+use iroh::protocol::{AcceptError, ProtocolHandler};
+use iroh_base::EndpointAddr;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{RwLock, mpsc};
+
+use super::access::{Grant, Roles};
+use super::invite::{PresentedInvite, Revocation};
+use crate::peer::PeerId;
+
+/// Protocol identifier for peer exchange
+pub const PEER_EXCHANGE_ALPN: &[u8] = b"irohscii/peers/4";
+
+/// Most peer addresses taken from a single exchange
+const MAX_PEERS: usize = 256;
+
+/// How long the acceptor waits for the dialer to read its reply
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Peer list sent in each direction of an exchange
+#[derive(Debug, Serialize, Deserialize)]
+struct PeerList {
+    /// Address of the sender itself
+    from: EndpointAddr,
+    /// Other participants the sender knows about
+    peers: Vec<EndpointAddr>,
+    /// Documents the sender may change
+    grants: Vec<Grant>,
+    /// Invites the sender joined with, if the receiver edits their documents
+    invites: Vec<PresentedInvite>,
+    /// Invites revoked so far
+    revoked: Vec<Revocation>,
+}
+
+/// A participant learned through peer exchange
+#[derive(Debug, Clone)]
+pub struct DiscoveredPeer {
+    pub addr: EndpointAddr,
+    /// The peer dialed us itself, so it already holds a link to us
+    pub inbound: bool,
+}
+
+/// Peer exchange protocol handler for iroh
+#[derive(Clone)]
+pub struct PeerExchange {
+    inner: Arc<PeerExchangeInner>,
+}
+
+impl std::fmt::Debug for PeerExchange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PeerExchange").finish()
+    }
+}
+
+struct PeerExchangeInner {
+    /// Our endpoint (for our current address and outgoing exchanges)
+    endpoint: Endpoint,
+    /// Every other participant we know about
+    peers: RwLock<HashMap<PeerId, EndpointAddr>>,
+    /// Document roles (our grants and invites out, senders' in)
+    roles: Arc<Roles>,
+    /// Channel to notify the sync loop of newly learned participants
+    discovered_tx: mpsc::Sender<DiscoveredPeer>,
+}
+
+impl PeerExchange {
+    /// Protocol identifier
+    pub const ALPN: &'static [u8] = PEER_EXCHANGE_ALPN;
+
+    /// Create a new peer exchange handler
+    pub fn new(
+        endpoint: Endpoint,
+        roles: Arc<Roles>,
+        discovered_tx: mpsc::Sender<DiscoveredPeer>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(PeerExchangeInner {
+                endpoint,
+                peers: RwLock::new(HashMap::new()),
+                roles,
+                discovered_tx,
+            }),
+        }
+    }
+
+    /// Remember a participant we dialed ourselves (no discovery event)
+    pub async fn insert(&self, addr: EndpointAddr) {
+        if let Some(peer_id) = peer_id_of(&addr) {
+            self.inner.peers.write().await.insert(peer_id, addr);
+        }
+    }
+
+    /// Forget a participant that left, so it isn't handed to newcomers
+    pub async fn forget(&self, peer_id: &PeerId) {
+        self.inner.peers.write().await.remove(peer_id);
+    }
+
+    /// Exchange peer lists with a participant (as dialer)
+    pub async fn exchange(&self, addr: EndpointAddr) -> Result<()> {
+        let remote = peer_id_of(&addr).ok_or_else(|| anyhow::anyhow!("invalid peer address"))?;
+        let list = self.local_list(&remote).await;
+        let presented = list.invites.len();
+        self.exchange_once(addr.clone(), list).await?;
+        // The reply may be what told us the peer is an editor; if so, go
+        // again to present our invites to it
+        let list = self.local_list(&remote).await;
+        if list.invites.len() > presented {
+            self.exchange_once(addr, list).await?;
+        }
+        Ok(())
+    }
+
+    /// One round trip of an exchange, sending `list`
+    async fn exchange_once(&self, addr: EndpointAddr, list: PeerList) -> Result<()> {
+        let conn = self
+            .inner
+            .endpoint
+            .connect(addr, PEER_EXCHANGE_ALPN)
+            .await?;
+        let remote = remote_peer_id(&conn)?;
+        let (mut send, mut recv) = conn.open_bi().await?;
+        send_peer_list(&mut send, &list).await?;
+        let list = recv_peer_list(&mut recv).await?;
+        conn.close(0u32.into(), b"done");
+        self.merge(list, remote, false).await
+    }
+
+    /// Handle incoming connection (as acceptor)
+    async fn handle_peer(&self, conn: Connection) -> Result<()> {
+        let remote = remote_peer_id(&conn)?;
+        let (mut send, mut recv) = conn.accept_bi().await?;
+        let list = recv_peer_list(&mut recv).await?;
+        if let Err(e) = self.merge(list, remote, true).await {
+            conn.close(0u32.into(), b"refused");
+            return Err(e);
+        }
+        // After merging, so a dialing editor's grants already count
+        send_peer_list(&mut send, &self.local_list(&remote).await).await?;
+        // Keep the connection open until the dialer has read our reply
+        let _ = tokio::time::timeout(REPLY_TIMEOUT, conn.closed()).await;
+        Ok(())
+    }
+
+    /// Our address, every participant we know, our editor grants, the
+    /// invites `to` should check, and the revocations we know of
+    async fn local_list(&self, to: &PeerId) -> PeerList {
+        let from = self.inner.endpoint.addr();
+        let roles = &self.inner.roles;
+        let (grants, invites) = peer_id_of(&from)
+            .map(|local| (roles.grants(&local), roles.own_invites(&local, to)))
+            .unwrap_or_default();
+        PeerList {
+            from,
+            peers: self.inner.peers.read().await.values().cloned().collect(),
+            grants,
+            invites,
+            revoked: roles.revocations(),
+        }
+    }
+
+    /// Remember the participants in a list received from `sender` (the
+    /// peer on the other end of the connection) and report new ones.
+    /// `inbound` marks the sender as having dialed us. Fails (recording
+    /// nothing) if the list claims to be from someone else or the sender is
+    /// refused.
+    async fn merge(&self, list: PeerList, sender: PeerId, inbound: bool) -> Result<()> {
+        let local_id = self.inner.endpoint.id();
+        // Otherwise anyone could present invites, grants or revocations in
+        // someone else's name
+        if peer_id_of(&list.from) != Some(sender) {
+            anyhow::bail!("Peer list sent on behalf of another peer");
+        }
+        let roles = &self.inner.roles;
+        roles.accept_grants(&sender, &list.grants);
+        roles.accept_invites(&sender, &list.invites);
+        roles.accept_revocations(&sender, &list.revoked);
+        if roles.is_refused(&sender) {
+            anyhow::bail!("Peer presented an invalid invite");
+        }
+        let sender = std::iter::once((list.from, inbound));
+        let others = list
+            .peers
+            .into_iter()
+            .take(MAX_PEERS)
+            .map(|addr| (addr, false));
+
+        let mut discovered = Vec::new();
+        {
+            let mut peers = self.inner.peers.write().await;
+            for (addr, inbound) in sender.chain(others) {
+                if addr.id == local_id {
+                    continue;
+                }
+                let Some(peer_id) = peer_id_of(&addr) else {
+                    continue;
+                };
+                if peers.contains_key(&peer_id) {
+                    continue;
+                }
+                peers.insert(peer_id, addr.clone());
+                discovered.push(DiscoveredPeer { addr, inbound });
+            }
+        }
+
+        // Notify outside the lock: the sync loop may call forget() meanwhile
+        for peer in discovered {
+            let _ = self.inner.discovered_tx.send(peer).await;
+        }
+        Ok(())
+    }
+}
+
+impl ProtocolHandler for PeerExchange {
+    fn accept(&self, conn: Connection) -> impl Future<Output = Result<(), AcceptError>> + Send {
+        let this = self.clone();
+        async move {
+            this.handle_peer(conn)
+                .await
+                .map_err(|e| AcceptError::from_err(std::io::Error::other(e.to_string())))
+        }
+    }
+}
+
+/// The peer on the other end of a connection
+fn remote_peer_id(conn: &Connection) -> Result<PeerId> {
+    PeerId::from_bytes(conn.remote_id().as_bytes())
+        .ok_or_else(|| anyhow::anyhow!("invalid remote endpoint id"))
+}
+
+/// Peer ID of the participant at an address
+pub fn peer_id_of(addr: &EndpointAddr) -> Option<PeerId> {
+    PeerId::from_bytes(addr.id.as_bytes())
+}
+
+/// Send a peer list (length-prefixed postcard)
+async fn send_peer_list<W: AsyncWriteExt + Unpin>(writer: &mut W, list: &PeerList) -> Result<()> {
+    let data = postcard::to_stdvec(list)?;
+    let data_len = data.len();
+    if data_len > u32::MAX as usize {
+        anyhow::bail!("Peer list too large: {} bytes", data_len);
+    }
+    let len = data_len as u32;
+    writer.write_all(&len.to_le_bytes()).await?;
+    writer.write_all(&data).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Receive a peer list
+async fn recv_peer_list<R: AsyncReadExt + Unpin>(reader: &mut R) -> Result<PeerList> {
+    let mut len_bytes = [0u8; 4];
+    reader.read_exact(&mut len_bytes).await?;
+    let len_u32 = u32::from_le_bytes(len_bytes);
+
+    // Sanity check message size (1MB limit)
+    const MAX_MESSAGE_SIZE: u32 = 1024 * 1024;
+    if len_u32 > MAX_MESSAGE_SIZE {
+        anyhow::bail!("Peer list too large: {} bytes", len_u32);
+    }
+
+    let len = len_u32 as usize;
+    let mut data = vec![0u8; len];
+    reader.read_exact(&mut data).await?;
+
+    Ok(postcard::from_bytes(&data)?)
+}
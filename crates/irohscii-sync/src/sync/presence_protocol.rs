@@ -114,7 +114,6 @@ impl PresenceProtocol {
         loop {
             iteration_count = iteration_count.saturating_add(1);
             if iteration_count >= MAX_ITERATIONS {
-                eprintln!("Presence sync loop exceeded max iterations, closing");
                 break;
             }
             
@@ -0,0 +1,173 @@
+//! Document store used by the sync thread.
+//!
+//! Wraps the in-memory and on-disk stores so [`SyncConfig`](super::SyncConfig)
+//! can pick one at startup while the sync loop and protocol handler work
+//! with a single concrete type.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use automerge::{AutoCommit, ChangeHash};
+use tokio::sync::mpsc;
+
+use aspen_automerge::{
+    ApplyResult, AutomergeResult, DocumentChange, DocumentId, DocumentMetadata, DocumentStore,
+    ListOptions, ListResult,
+};
+
+use super::disk_store::DiskDocumentStore;
+use super::local_store::LocalDocumentStore;
+
+/// The sync thread's document store
+pub enum SyncStore {
+    /// Documents live only as long as the sync thread
+    Memory(LocalDocumentStore),
+    /// Documents are written through to a directory
+    Disk(DiskDocumentStore),
+}
+
+impl SyncStore {
+    /// Open the store for a config: on disk under `dir`, else in memory
+    pub fn open(dir: Option<PathBuf>, change_tx: mpsc::Sender<String>) -> AutomergeResult<Self> {
+        match dir {
+            Some(dir) => Ok(Self::Disk(DiskDocumentStore::open(dir, change_tx)?)),
+            None => Ok(Self::Memory(LocalDocumentStore::new(change_tx))),
+        }
+    }
+
+    /// Update a document from the application (no change notification)
+    pub async fn update_from_app(&self, id: &DocumentId, doc_bytes: &[u8]) -> AutomergeResult<()> {
+        match self {
+            Self::Memory(store) => store.update_from_app(id, doc_bytes).await,
+            Self::Disk(store) => store.update_from_app(id, doc_bytes).await,
+        }
+    }
+
+    /// Merge encoded changes from the application (no change notification)
+    pub async fn apply_from_app(&self, id: &DocumentId, changes: &[u8]) -> AutomergeResult<()> {
+        match self {
+            Self::Memory(store) => store.apply_from_app(id, changes).await,
+            Self::Disk(store) => store.apply_from_app(id, changes).await,
+        }
+    }
+
+    /// Changes since `heads` (for sending to the application), with the
+    /// heads they bring the document up to
+    pub async fn changes_since(
+        &self,
+        id: &DocumentId,
+        heads: &[ChangeHash],
+    ) -> Option<(Vec<u8>, Vec<ChangeHash>)> {
+        match self {
+            Self::Memory(store) => store.changes_since(id, heads).await,
+            Self::Disk(store) => store.changes_since(id, heads).await,
+        }
+    }
+
+    /// Stop serving a document. The in-memory store drops it; the disk
+    /// store keeps its files so reopening the session resumes from them.
+    pub async fn close(&self, id: &DocumentId) {
+        match self {
+            Self::Memory(store) => {
+                let _ = store.delete(id).await;
+            }
+            Self::Disk(store) => store.close(id).await,
+        }
+    }
+}
+
+#[async_trait]
+impl DocumentStore for SyncStore {
+    async fn create(
+        &self,
+        id: Option<DocumentId>,
+        metadata: Option<DocumentMetadata>,
+    ) -> AutomergeResult<DocumentId> {
+        match self {
+            Self::Memory(store) => store.create(id, metadata).await,
+            Self::Disk(store) => store.create(id, metadata).await,
+        }
+    }
+
+    async fn get(&self, id: &DocumentId) -> AutomergeResult<Option<AutoCommit>> {
+        match self {
+            Self::Memory(store) => store.get(id).await,
+            Self::Disk(store) => store.get(id).await,
+        }
+    }
+
+    async fn get_metadata(&self, id: &DocumentId) -> AutomergeResult<Option<DocumentMetadata>> {
+        match self {
+            Self::Memory(store) => store.get_metadata(id).await,
+            Self::Disk(store) => store.get_metadata(id).await,
+        }
+    }
+
+    async fn save(&self, id: &DocumentId, doc: &mut AutoCommit) -> AutomergeResult<()> {
+        match self {
+            Self::Memory(store) => store.save(id, doc).await,
+            Self::Disk(store) => store.save(id, doc).await,
+        }
+    }
+
+    async fn delete(&self, id: &DocumentId) -> AutomergeResult<bool> {
+        match self {
+            Self::Memory(store) => store.delete(id).await,
+            Self::Disk(store) => store.delete(id).await,
+        }
+    }
+
+    async fn exists(&self, id: &DocumentId) -> AutomergeResult<bool> {
+        match self {
+            Self::Memory(store) => store.exists(id).await,
+            Self::Disk(store) => store.exists(id).await,
+        }
+    }
+
+    async fn apply_changes(
+        &self,
+        id: &DocumentId,
+        changes: Vec<DocumentChange>,
+    ) -> AutomergeResult<ApplyResult> {
+        match self {
+            Self::Memory(store) => store.apply_changes(id, changes).await,
+            Self::Disk(store) => store.apply_changes(id, changes).await,
+        }
+    }
+
+    async fn get_heads(&self, id: &DocumentId) -> AutomergeResult<Vec<String>> {
+        match self {
+            Self::Memory(store) => store.get_heads(id).await,
+            Self::Disk(store) => store.get_heads(id).await,
+        }
+    }
+
+    async fn merge(
+        &self,
+        target_id: &DocumentId,
+        source_id: &DocumentId,
+    ) -> AutomergeResult<ApplyResult> {
+        match self {
+            Self::Memory(store) => store.merge(target_id, source_id).await,
+            Self::Disk(store) => store.merge(target_id, source_id).await,
+        }
+    }
+
+    async fn list(&self, options: ListOptions) -> AutomergeResult<ListResult> {
+        match self {
+            Self::Memory(store) => store.list(options).await,
+            Self::Disk(store) => store.list(options).await,
+        }
+    }
+
+    async fn list_ids(
+        &self,
+        namespace: Option<&str>,
+        limit: u32,
+    ) -> AutomergeResult<Vec<DocumentId>> {
+        match self {
+            Self::Memory(store) => store.list_ids(namespace, limit).await,
+            Self::Disk(store) => store.list_ids(namespace, limit).await,
+        }
+    }
+}
@@ -43,6 +43,10 @@ pub enum Action {
     CloudTool,
     /// Set the current tool to Star
     StarTool,
+    /// Set the current tool to Callout
+    CalloutTool,
+    /// Set the current tool to Polygon
+    PolygonTool,
 
     // Popup commands
     /// Open tool selection popup
@@ -57,7 +61,7 @@ pub enum Action {
     FileSave,
     /// Open file (prompts for path)
     FileOpen,
-    /// Export to SVG (prompts for path)
+    /// Export to SVG or another format (prompts for path and options)
     SvgExport,
     /// Create new document
     NewDocument,
@@ -65,6 +69,10 @@ pub enum Action {
     // Sync/collaboration operations
     /// Copy sync ticket to clipboard
     CopyTicket,
+    /// Copy a read-only viewer ticket to clipboard
+    CopyViewerTicket,
+    /// List, issue and revoke invites for the current session
+    ManageInvites,
     /// Show QR code for current ticket
     ShowQrCode,
     /// Decode QR code from image (prompts for path)
@@ -81,6 +89,14 @@ pub enum Action {
     ToggleLayers,
     /// Toggle participants panel visibility
     ToggleParticipants,
+    /// Follow the next participant's viewport (or stop after the last)
+    FollowPeer,
+    /// Toggle outlines of other participants' viewports
+    TogglePeerViewports,
+    /// Write a chat message to the session
+    Chat,
+    /// Toggle chat panel visibility
+    ToggleChat,
 
     // App operations
     /// Show help screen
@@ -111,6 +127,18 @@ pub enum Action {
     UngroupSelection,
     /// Cycle line style
     CycleLineStyle,
+    /// Toggle vertex-edit mode for the select tool
+    ToggleVertexEdit,
+    /// Replace selected freehand strokes with recognized shapes
+    RecognizeShapes,
+    /// Toggle automatic recognition of freehand strokes
+    ToggleAutoRecognize,
+    /// Toggle smoothing of freehand strokes
+    ToggleStrokeSmoothing,
+    /// Convert selected freehand strokes to polylines
+    ConvertToPolyline,
+    /// Toggle braille rendering of curves and freehand strokes
+    ToggleBraille,
 
     // Z-order operations
     /// Bring selected shapes forward
@@ -228,14 +256,18 @@ impl Action {
             Action::CylinderTool => "Cylinder tool",
             Action::CloudTool => "Cloud tool",
             Action::StarTool => "Star tool",
+            Action::CalloutTool => "Callout tool",
+            Action::PolygonTool => "Polygon tool",
             Action::ToolPopup => "Tool picker",
             Action::ColorPopup => "Color picker",
             Action::BrushPopup => "Brush picker",
             Action::FileSave => "Save file",
             Action::FileOpen => "Open file",
-            Action::SvgExport => "Export SVG",
+            Action::SvgExport => "Export",
             Action::NewDocument => "New document",
             Action::CopyTicket => "Copy ticket",
+            Action::CopyViewerTicket => "Copy viewer ticket",
+            Action::ManageInvites => "Manage invites",
             Action::ShowQrCode => "Show QR code",
             Action::DecodeQr => "Decode QR",
             Action::ClusterConnect => "Connect cluster",
@@ -243,6 +275,10 @@ impl Action {
             Action::ToggleGrid => "Toggle grid",
             Action::ToggleLayers => "Toggle layers",
             Action::ToggleParticipants => "Toggle participants",
+            Action::FollowPeer => "Follow participant",
+            Action::TogglePeerViewports => "Toggle peer viewports",
+            Action::Chat => "Send chat message",
+            Action::ToggleChat => "Toggle chat",
             Action::ShowHelp => "Show help",
             Action::Quit => "Quit",
             Action::Undo => "Undo",
@@ -256,6 +292,12 @@ impl Action {
             Action::GroupSelection => "Group",
             Action::UngroupSelection => "Ungroup",
             Action::CycleLineStyle => "Cycle line style",
+            Action::ToggleVertexEdit => "Toggle vertex edit",
+            Action::RecognizeShapes => "Recognize shapes",
+            Action::ToggleAutoRecognize => "Toggle auto-recognize",
+            Action::ToggleStrokeSmoothing => "Toggle stroke smoothing",
+            Action::ConvertToPolyline => "Convert to polyline",
+            Action::ToggleBraille => "Toggle braille rendering",
             Action::BringForward => "Bring forward",
             Action::SendBackward => "Send backward",
             Action::BringToFront => "Bring to front",
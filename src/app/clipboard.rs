@@ -7,56 +7,32 @@ use super::App;
 impl App {
     /// Copy sync ticket to system clipboard
     pub fn copy_ticket_to_clipboard(&mut self) {
-        if let Some(ref ticket) = self.sync_ticket {
-            use std::io::Write;
-            use std::process::{Command, Stdio};
-
-            debug_assert!(!ticket.is_empty(), "precondition: ticket is non-empty");
-
-            // macOS: use pbcopy
-            if cfg!(target_os = "macos") {
-                let spawn_result = Command::new("pbcopy")
-                    .stdin(Stdio::piped())
-                    .stdout(Stdio::null())
-                    .stderr(Stdio::null())
-                    .spawn();
-                
-                if let Ok(mut child) = spawn_result {
-                    let stdin_option = child.stdin.take();
-                    if let Some(mut stdin) = stdin_option {
-                        let ticket_clone = ticket.clone();
-                        std::thread::spawn(move || {
-                            let _ = stdin.write_all(ticket_clone.as_bytes());
-                            drop(stdin);
-                            let _ = child.wait();
-                        });
-                        self.set_status(format!("Copied: {}", ticket));
-                        return;
-                    }
-                }
-                self.set_status("pbcopy not available");
-                return;
-            }
+        match self.sync_ticket.clone() {
+            Some(ticket) => self.copy_text_to_clipboard(&ticket),
+            None => self.set_status("No sync session active"),
+        }
+    }
 
-            // Linux: try wl-copy (Wayland) first - spawn and forget
-            if let Ok(mut child) = Command::new("wl-copy")
-                .arg(ticket)
-                .stdin(Stdio::null())
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .spawn()
-            {
-                // Don't wait, just detach
-                std::thread::spawn(move || {
-                    let _ = child.wait();
-                });
-                self.set_status(format!("Copied: {}", ticket));
-                return;
-            }
+    /// Ask the main loop for a viewer ticket (read-only access) to copy to
+    /// the system clipboard. The first one restricts the session.
+    pub fn copy_viewer_ticket_to_clipboard(&mut self) {
+        if self.sync_ticket.is_some() {
+            self.pending_viewer_ticket = true;
+        } else {
+            self.set_status("No sync session active");
+        }
+    }
 
-            // Fall back to xsel
-            let spawn_result = Command::new("xsel")
-                .args(["--clipboard", "--input"])
+    /// Copy text to system clipboard, reporting it in the status bar
+    pub fn copy_text_to_clipboard(&mut self, text: &str) {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        debug_assert!(!text.is_empty(), "precondition: text is non-empty");
+
+        // macOS: use pbcopy
+        if cfg!(target_os = "macos") {
+            let spawn_result = Command::new("pbcopy")
                 .stdin(Stdio::piped())
                 .stdout(Stdio::null())
                 .stderr(Stdio::null())
@@ -65,21 +41,59 @@ impl App {
             if let Ok(mut child) = spawn_result {
                 let stdin_option = child.stdin.take();
                 if let Some(mut stdin) = stdin_option {
-                    let ticket_clone = ticket.clone();
+                    let text_clone = text.to_string();
                     std::thread::spawn(move || {
-                        let _ = stdin.write_all(ticket_clone.as_bytes());
+                        let _ = stdin.write_all(text_clone.as_bytes());
                         drop(stdin);
                         let _ = child.wait();
                     });
-                    self.set_status(format!("Copied: {}", ticket));
+                    self.set_status(format!("Copied: {}", text));
                     return;
                 }
             }
+            self.set_status("pbcopy not available");
+            return;
+        }
 
-            self.set_status("Install wl-copy or xsel for clipboard");
-        } else {
-            self.set_status("No sync session active");
+        // Linux: try wl-copy (Wayland) first - spawn and forget
+        if let Ok(mut child) = Command::new("wl-copy")
+            .arg(text)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            // Don't wait, just detach
+            std::thread::spawn(move || {
+                let _ = child.wait();
+            });
+            self.set_status(format!("Copied: {}", text));
+            return;
+        }
+
+        // Fall back to xsel
+        let spawn_result = Command::new("xsel")
+            .args(["--clipboard", "--input"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        
+        if let Ok(mut child) = spawn_result {
+            let stdin_option = child.stdin.take();
+            if let Some(mut stdin) = stdin_option {
+                let text_clone = text.to_string();
+                std::thread::spawn(move || {
+                    let _ = stdin.write_all(text_clone.as_bytes());
+                    drop(stdin);
+                    let _ = child.wait();
+                });
+                self.set_status(format!("Copied: {}", text));
+                return;
+            }
         }
+
+        self.set_status("Install wl-copy or xsel for clipboard");
     }
 
     /// Copy selected shapes to clipboard
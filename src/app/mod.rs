@@ -4,7 +4,7 @@ pub mod qr;
 mod transform;
 mod zorder;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -16,19 +16,27 @@ use crate::canvas::{LineStyle, Position, Viewport};
 #[allow(unused_imports)]
 pub use crate::modes::{
     ConfirmDialogState, HelpScreenState, KeyboardShapeState, LabelInputState, LayerRenameState,
-    Mode, PathInputKind, PathInputState, SelectionPopupState, SessionBrowserState, TextInputState,
+    MarkdownBlocksState, Mode, PathInputKind, PathInputState, SelectionPopupState, SessionBrowserState, TextInputState,
 };
 use crate::document::{Document, GroupId, ShapeId, default_storage_path};
 use crate::layers::{Layer, LayerId};
-use crate::presence::{CursorActivity, PeerId, PeerPresence, PresenceManager, ToolKind};
+use crate::presence::{
+    CursorActivity, PeerId, PeerPresence, PeerViewport, PresenceManager, ToolKind,
+};
 use crate::recent_files::RecentFiles;
-use crate::shapes::{ResizeHandle, ShapeColor, ShapeKind, ShapeView, SnapPoint, resize_shape};
+use crate::shapes::{
+    ResizeHandle, ShapeColor, ShapeKind, ShapeView, SnapPoint, delete_vertex, find_vertex_edge,
+    freehand_to_polyline, insert_vertex, recognize_freehand, resize_shape, simplify_freehand,
+};
 
 /// Snap distance threshold (in characters)
 pub const SNAP_THRESHOLD: i32 = 3;
 const _: () = assert!(SNAP_THRESHOLD > 0, "SNAP_THRESHOLD must be positive");
 const _: () = assert!(SNAP_THRESHOLD < 100, "SNAP_THRESHOLD must be reasonable");
 
+/// How close (in characters) a click must be to an edge to insert a vertex there
+const VERTEX_EDGE_THRESHOLD: i32 = 1;
+
 /// Grid size for snap-to-grid (in characters)
 pub const GRID_SIZE: i32 = 5;
 const _: () = assert!(GRID_SIZE > 0, "GRID_SIZE must be positive");
@@ -43,6 +51,12 @@ pub const BRUSHES: &[char] = &[
     '●', '○', '■', '□', '◆', '◇', '▪', '▫',
 ];
 
+/// Default callout body size when drawn with the mouse
+pub const CALLOUT_WIDTH: i32 = 14;
+pub const CALLOUT_HEIGHT: i32 = 4;
+const _: () = assert!(CALLOUT_WIDTH >= 3, "CALLOUT_WIDTH must leave room for a label");
+const _: () = assert!(CALLOUT_HEIGHT >= 2, "CALLOUT_HEIGHT must leave room for a label");
+
 /// All available tools in order
 pub const TOOLS: &[Tool] = &[
     Tool::Select,
@@ -62,6 +76,8 @@ pub const TOOLS: &[Tool] = &[
     Tool::Cylinder,
     Tool::Cloud,
     Tool::Star,
+    Tool::Callout,
+    Tool::Polygon,
 ];
 
 /// All available colors in order
@@ -104,6 +120,8 @@ pub enum Tool {
     Cylinder,
     Cloud,
     Star,
+    Callout,
+    Polygon,
 }
 
 impl Tool {
@@ -126,6 +144,8 @@ impl Tool {
             Tool::Cylinder => "Cylinder",
             Tool::Cloud => "Cloud",
             Tool::Star => "Star",
+            Tool::Callout => "Callout",
+            Tool::Polygon => "Polygon",
         }
     }
 }
@@ -173,6 +193,37 @@ impl PendingAction {
     }
 }
 
+/// State of a link the sync thread dialed to a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerLinkState {
+    /// Linked, directly or through a relay
+    Connected(crate::sync::ConnectionType),
+    /// Link dropped; redialing
+    Reconnecting { attempt: u32 },
+}
+
+/// A participant whose viewport we keep ours locked to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FollowState {
+    pub peer_id: PeerId,
+    /// Their viewport as we last applied it; if ours no longer matches,
+    /// we panned or zoomed ourselves and stop following
+    pub applied: Option<PeerViewport>,
+}
+
+/// Invite the user asked to issue (consumed by the main loop)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InviteRequest {
+    /// Who the invite is for
+    pub label: Option<String>,
+    pub role: crate::sync::Role,
+    /// How long the invite stays valid
+    pub ttl: std::time::Duration,
+}
+
+/// Default invite lifetime, in hours
+pub const INVITE_DEFAULT_HOURS: u64 = 24;
+
 /// Severity level for status messages
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageSeverity {
@@ -191,6 +242,38 @@ impl MessageSeverity {
     }
 }
 
+/// Parse invite prompt input: an optional label, `--viewer` (or
+/// `--editor`), and `--hours N` for the lifetime.
+fn parse_invite_request(input: &str) -> anyhow::Result<InviteRequest> {
+    let mut label_words = Vec::new();
+    let mut role = crate::sync::Role::Editor;
+    let mut hours = INVITE_DEFAULT_HOURS;
+    let mut words = input.split_whitespace();
+    while let Some(word) = words.next() {
+        match word {
+            "--viewer" => role = crate::sync::Role::Viewer,
+            "--editor" => role = crate::sync::Role::Editor,
+            "--hours" => {
+                let value = words
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--hours needs a value"))?;
+                hours = value
+                    .parse()
+                    .ok()
+                    .filter(|h| *h > 0)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid hours: {}", value))?;
+            }
+            flag if flag.starts_with("--") => anyhow::bail!("Unknown invite option: {}", flag),
+            _ => label_words.push(word),
+        }
+    }
+    Ok(InviteRequest {
+        label: (!label_words.is_empty()).then(|| label_words.join(" ")),
+        role,
+        ttl: std::time::Duration::from_secs(hours.saturating_mul(3600)),
+    })
+}
+
 /// State for shape drawing (line, rectangle)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ShapeState {
@@ -249,6 +332,14 @@ pub struct FreehandState {
     pub points: Vec<Position>,
 }
 
+/// Polygon drawing state (vertices placed one click at a time)
+#[derive(Debug, Clone, Default)]
+pub struct PolygonState {
+    pub points: Vec<Position>,
+    /// Mouse position for the rubber-band edge to the next vertex
+    pub cursor: Position,
+}
+
 /// State for marquee selection drag
 #[derive(Debug, Clone, Copy)]
 pub struct MarqueeState {
@@ -276,10 +367,21 @@ pub struct App {
     pub current_color: ShapeColor,
     pub running: bool,
     pub file_path: Option<PathBuf>,
+    /// Diagram block being edited when `file_path` is a Markdown file
+    pub markdown_block: Option<crate::file_io::BlockRef>,
     pub shape_state: Option<ShapeState>,
     pub drag_state: Option<DragState>,
     pub resize_state: Option<ResizeState>,
     pub freehand_state: Option<FreehandState>,
+    pub polygon_state: Option<PolygonState>,
+    /// Whether the select tool edits vertices (Triangle, Polygon, Freehand) instead of resizing
+    pub vertex_edit: bool,
+    /// Vertex last grabbed in vertex-edit mode (target for Delete)
+    pub active_vertex: Option<(ShapeId, usize)>,
+    /// Whether finished freehand strokes are replaced by recognized shapes
+    pub auto_recognize: bool,
+    /// Whether finished freehand strokes are smoothed after simplification
+    pub smooth_strokes: bool,
     /// State for marquee selection
     pub marquee_state: Option<MarqueeState>,
     /// Status message with severity level
@@ -290,20 +392,49 @@ pub struct App {
     clipboard: Vec<ShapeKind>,
     /// Sync session ticket for sharing
     pub sync_ticket: Option<String>,
+    /// Document heads last handed to the sync thread
+    sync_heads: Vec<automerge::ChangeHash>,
     /// Pending cluster connection (set by UI, consumed by main loop)
     pub pending_cluster_ticket: Option<String>,
     /// Pending join session connection (set by UI, consumed by main loop)
     pub pending_join_ticket: Option<String>,
     /// Ticket decoded from a QR code image (set by UI, consumed by main loop)
     pub pending_qr_decoded_ticket: Option<String>,
+    /// Invites issued for the current session (refreshed by main loop)
+    pub invites: Vec<crate::sync::InviteInfo>,
+    /// Invite to issue (set by UI, consumed by main loop)
+    pub pending_invite: Option<InviteRequest>,
+    /// Invite to revoke (set by UI, consumed by main loop)
+    pub pending_invite_revoke: Option<crate::sync::InviteId>,
+    /// Viewer ticket to copy (set by UI, consumed by main loop)
+    pub pending_viewer_ticket: bool,
     /// Presence manager for remote cursors
     pub presence: Option<PresenceManager>,
     /// Our local peer ID (if syncing)
     pub local_peer_id: Option<PeerId>,
+    /// Our display name and color, broadcast with presence
+    pub profile: crate::session::Profile,
+    /// Links to peers we dialed (status bar sync indicator)
+    pub peer_links: HashMap<PeerId, PeerLinkState>,
     /// Last cursor position (for presence tracking)
     pub last_cursor_pos: Position,
     /// Whether to show the participant list panel
     pub show_participants: bool,
+    /// Participants panel area (for mouse click detection)
+    pub participant_panel_area: Option<Rect>,
+    /// Participant we're following (viewport locked to theirs)
+    pub following: Option<FollowState>,
+    /// Whether to outline other participants' viewports on the canvas
+    pub show_peer_viewports: bool,
+    /// Whether to show the chat panel (and pinned messages)
+    pub show_chat: bool,
+    /// Chat history of the current session, oldest first
+    pub chat: Vec<crate::session::ChatEntry>,
+    /// Messages we wrote, to send (consumed by main loop)
+    pub pending_chat: Vec<crate::session::ChatEntry>,
+    /// Messages to append to a session's history, by document ID
+    /// (consumed by main loop)
+    pub chat_to_save: Vec<(String, crate::session::ChatEntry)>,
     /// Whether grid snapping is enabled
     pub grid_enabled: bool,
     /// Recent files manager
@@ -350,23 +481,43 @@ impl App {
             current_color: ShapeColor::default(),
             running: true,
             file_path: None,
+            markdown_block: None,
             shape_state: None,
             drag_state: None,
             resize_state: None,
             freehand_state: None,
+            polygon_state: None,
+            vertex_edit: false,
+            active_vertex: None,
+            auto_recognize: false,
+            smooth_strokes: false,
             marquee_state: None,
             status_message: None,
             hover_snap: None,
             hover_grid_snap: None,
             clipboard: Vec::new(),
             sync_ticket: None,
+            sync_heads: Vec::new(),
             pending_cluster_ticket: None,
             pending_join_ticket: None,
             pending_qr_decoded_ticket: None,
+            invites: Vec::new(),
+            pending_invite: None,
+            pending_invite_revoke: None,
+            pending_viewer_ticket: false,
             presence: None,
             local_peer_id: None,
+            profile: crate::session::Profile::default(),
+            peer_links: HashMap::new(),
             last_cursor_pos: Position::new(0, 0),
             show_participants: false,
+            participant_panel_area: None,
+            following: None,
+            show_peer_viewports: false,
+            show_chat: false,
+            chat: Vec::new(),
+            pending_chat: Vec::new(),
+            chat_to_save: Vec::new(),
             grid_enabled: false,
             recent_files: RecentFiles::load(),
             active_layer: None,
@@ -402,6 +553,8 @@ impl App {
         self.selected.clear();
         // Reset tool state
         self.freehand_state = None;
+        self.polygon_state = None;
+        self.active_vertex = None;
         self.shape_state = None;
         self.marquee_state = None;
         self.drag_state = None;
@@ -426,6 +579,14 @@ impl App {
                 start: shape_state.start,
                 current: shape_state.current,
             }
+        } else if let Some(ref polygon_state) = self.polygon_state
+            && let Some(&last) = polygon_state.points.last()
+        {
+            CursorActivity::Drawing {
+                tool: ToolKind::Polygon,
+                start: last,
+                current: polygon_state.cursor,
+            }
         } else if let Some(ref drag_state) = self.drag_state {
             // Use actual selected shape (drag_state.shape_id is ShapeId::default() for multi-select)
             let shape_id = self
@@ -476,6 +637,8 @@ impl App {
             Tool::Cylinder => ToolKind::Cylinder,
             Tool::Cloud => ToolKind::Cloud,
             Tool::Star => ToolKind::Star,
+            Tool::Callout => ToolKind::Callout,
+            Tool::Polygon => ToolKind::Polygon,
         }
     }
 
@@ -488,13 +651,193 @@ impl App {
             .as_ref()
             .map(|d| d.started_at_ms)
             .or_else(|| self.resize_state.as_ref().map(|r| r.started_at_ms));
-        Some(PeerPresence::new(
+        Some(
+            PeerPresence::new(
+                peer_id,
+                cursor_pos,
+                self.current_activity(),
+                self.active_layer,
+                drag_start_ms,
+            )
+            .with_profile(self.profile.display_name.as_deref(), self.profile.color)
+            .with_viewport(&self.viewport),
+        )
+    }
+
+    /// Follow a participant, or stop if we already follow them
+    pub fn toggle_follow(&mut self, peer_id: PeerId) {
+        if self.following.is_some_and(|f| f.peer_id == peer_id) {
+            self.stop_following();
+            return;
+        }
+        self.following = Some(FollowState {
             peer_id,
-            cursor_pos,
-            self.current_activity(),
-            self.active_layer,
-            drag_start_ms,
-        ))
+            applied: None,
+        });
+        self.set_status(format!("Following {} (pan to stop)", self.peer_name(&peer_id)));
+        self.update_follow();
+    }
+
+    /// Follow the next participant in panel order; after the last, stop
+    pub fn follow_next_peer(&mut self) {
+        let peers: Vec<PeerId> = self
+            .presence
+            .as_ref()
+            .map(|mgr| mgr.sorted_peers().iter().map(|p| p.peer_id).collect())
+            .unwrap_or_default();
+        if peers.is_empty() {
+            self.set_warning("No one to follow");
+            return;
+        }
+        let next = match self.following {
+            Some(f) => peers
+                .iter()
+                .position(|p| *p == f.peer_id)
+                .and_then(|i| peers.get(i + 1)),
+            None => peers.first(),
+        };
+        match next {
+            Some(&peer_id) => {
+                self.following = None;
+                self.toggle_follow(peer_id);
+            }
+            None => self.stop_following(),
+        }
+    }
+
+    /// Stop following, if we were
+    pub fn stop_following(&mut self) {
+        if let Some(follow) = self.following.take() {
+            self.set_status(format!("Stopped following {}", self.peer_name(&follow.peer_id)));
+        }
+    }
+
+    /// Keep our viewport on the followed participant's (call every tick).
+    /// Stops when we've moved our own viewport or they've left.
+    pub fn update_follow(&mut self) {
+        let Some(follow) = self.following else {
+            return;
+        };
+        if follow.applied.is_some_and(|v| !v.matches(&self.viewport)) {
+            self.stop_following();
+            return;
+        }
+        let Some(peer) = self
+            .presence
+            .as_ref()
+            .and_then(|mgr| mgr.get_peer(&follow.peer_id))
+        else {
+            let name = self.peer_name(&follow.peer_id);
+            self.following = None;
+            self.set_warning(format!("{} left; stopped following", name));
+            return;
+        };
+        if let Some(view) = peer.viewport {
+            view.apply_to(&mut self.viewport);
+            self.following = Some(FollowState {
+                applied: Some(view),
+                ..follow
+            });
+        }
+    }
+
+    /// Show or hide the chat panel
+    pub fn toggle_chat(&mut self) {
+        self.show_chat = !self.show_chat;
+        self.set_status(if self.show_chat {
+            "Chat shown"
+        } else {
+            "Chat hidden"
+        });
+    }
+
+    /// Queue a chat message for the main loop to send. A leading `--pin`
+    /// pins it to the canvas at the mouse cursor.
+    pub fn execute_chat(&mut self, input: &str) {
+        let Some(local_peer_id) = self.local_peer_id else {
+            self.set_error("Chat needs sync (offline mode)");
+            return;
+        };
+        let input = input.trim();
+        let (pin, text) = match input.strip_prefix("--pin") {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => {
+                (Some(self.last_cursor_pos), rest.trim())
+            }
+            _ => (None, input),
+        };
+        if text.is_empty() {
+            self.set_error("Empty message");
+            return;
+        }
+        if text.chars().count() > crate::sync::MAX_CHAT_TEXT_LEN {
+            self.set_error(format!(
+                "Message too long (max {} characters)",
+                crate::sync::MAX_CHAT_TEXT_LEN
+            ));
+            return;
+        }
+        let presence = self.build_presence(self.last_cursor_pos);
+        let entry = crate::session::ChatEntry {
+            id: uuid::Uuid::new_v4(),
+            from: local_peer_id.0.iter().map(|b| format!("{:02x}", b)).collect(),
+            name: presence.as_ref().and_then(|p| p.name.clone()),
+            color: presence.map_or(0, |p| p.color_index),
+            text: text.to_string(),
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            pin,
+        };
+        self.chat.push(entry.clone());
+        self.pending_chat.push(entry);
+        self.show_chat = true;
+    }
+
+    /// Show a message received in the current session, noting it in the
+    /// status bar while the chat panel is hidden
+    pub fn receive_chat(&mut self, entry: crate::session::ChatEntry) {
+        if !self.show_chat {
+            self.set_status(format!("{}: {}", entry.sender_name(), entry.text));
+        }
+        self.chat.push(entry);
+        let excess = self.chat.len().saturating_sub(crate::session::MAX_CHAT_HISTORY);
+        self.chat.drain(..excess);
+    }
+
+    /// Whether a chat message is one we sent
+    pub fn is_own_chat(&self, entry: &crate::session::ChatEntry) -> bool {
+        self.local_peer_id.is_some_and(|id| {
+            entry.from == id.0.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+        })
+    }
+
+    /// Show or hide other participants' viewport outlines
+    pub fn toggle_peer_viewports(&mut self) {
+        self.show_peer_viewports = !self.show_peer_viewports;
+        self.set_status(if self.show_peer_viewports {
+            "Peer viewports shown"
+        } else {
+            "Peer viewports hidden"
+        });
+    }
+
+    /// Name to show for a peer: its broadcast name, else the one remembered
+    /// for this session, else a short form of its ID
+    pub fn peer_name(&self, peer_id: &PeerId) -> String {
+        if let Some(peer) = self
+            .presence
+            .as_ref()
+            .and_then(|mgr| mgr.active_peers().find(|p| p.peer_id == *peer_id))
+        {
+            return peer.display_name();
+        }
+        let hex: String = peer_id.0.iter().map(|b| format!("{:02x}", b)).collect();
+        self.current_session_meta
+            .as_ref()
+            .and_then(|meta| meta.collaborators.iter().find(|c| c.peer_id == hex))
+            .and_then(|c| c.display_name.clone())
+            .unwrap_or_else(|| peer_id.short_name())
     }
 
     /// Rebuild shape view from document (call after mutations)
@@ -615,7 +958,8 @@ impl App {
         debug_assert!(matches!(tool, Tool::Select | Tool::Freehand | Tool::Text | Tool::Line | 
                                       Tool::Arrow | Tool::Rectangle | Tool::DoubleBox | Tool::Diamond |
                                       Tool::Ellipse | Tool::Triangle | Tool::Parallelogram | Tool::Hexagon |
-                                      Tool::Trapezoid | Tool::RoundedRect | Tool::Cylinder | Tool::Cloud | Tool::Star),
+                                      Tool::Trapezoid | Tool::RoundedRect | Tool::Cylinder | Tool::Cloud | Tool::Star |
+                                      Tool::Callout | Tool::Polygon),
                       "Tool should be valid");
         
         // If we're in text input mode, commit the text first
@@ -627,6 +971,7 @@ impl App {
         self.drag_state = None;
         self.resize_state = None;
         self.freehand_state = None;
+        self.polygon_state = None;
         self.current_tool = tool;
         self.mode = Mode::Normal;
         self.set_status(format!("Tool: {}", tool.name()));
@@ -710,11 +1055,74 @@ impl App {
         debug_assert!(state.points.len() >= 1, "Should have at least one point");
         debug_assert!(self.brush_char.is_ascii() || !self.brush_char.is_ascii(), "Brush char valid");
         
+        let stroke = ShapeKind::Freehand {
+            points: state.points,
+            char: self.brush_char,
+            label: None,
+            color: self.current_color,
+        };
+        let recognized = if self.auto_recognize {
+            recognize_freehand(&stroke).map(|kind| self.attach_recognized_connections(kind, None))
+        } else {
+            None
+        };
+        // Only keep the samples that shape the stroke
+        let kind = recognized
+            .or_else(|| simplify_freehand(&stroke, self.smooth_strokes))
+            .unwrap_or(stroke);
+
+        self.save_undo_state();
+        if self.add_shape_to_active_layer(kind).is_ok() {
+            self.rebuild_view();
+            self.doc.mark_dirty();
+        }
+    }
+
+    /// Add a polygon vertex; clicking back on the first vertex closes the polygon
+    pub fn add_polygon_point(&mut self, pos: Position) {
+        debug_assert!(pos.x.abs() < 100000 && pos.y.abs() < 100000, "Position should be reasonable");
+        
+        let state = self.polygon_state.get_or_insert_with(PolygonState::default);
+        state.cursor = pos;
+
+        let closes = state.points.len() >= 3
+            && state.points.first().is_some_and(|first| {
+                (first.x - pos.x).abs() + (first.y - pos.y).abs() <= SNAP_THRESHOLD
+            });
+        if closes {
+            self.finish_polygon(true);
+            return;
+        }
+
+        if state.points.last() != Some(&pos) {
+            state.points.push(pos);
+        }
+    }
+
+    /// Move the rubber-band edge of the polygon in progress
+    pub fn update_polygon_cursor(&mut self, pos: Position) {
+        if let Some(ref mut state) = self.polygon_state {
+            state.cursor = pos;
+        }
+    }
+
+    /// Finish the polygon in progress (open needs 2 vertices, closed needs 3)
+    pub fn finish_polygon(&mut self, closed: bool) {
+        let Some(state) = self.polygon_state.take() else {
+            return;
+        };
+
+        let min_points = if closed { 3 } else { 2 };
+        if state.points.len() < min_points {
+            self.set_status(format!("Polygon needs at least {} vertices", min_points));
+            return;
+        }
+
         self.save_undo_state();
         if self
-            .add_shape_to_active_layer(ShapeKind::Freehand {
+            .add_shape_to_active_layer(ShapeKind::Polygon {
                 points: state.points,
-                char: self.brush_char,
+                closed,
                 label: None,
                 color: self.current_color,
             })
@@ -844,6 +1252,7 @@ impl App {
                 self.create_radial_shape(start, end)
             }
             Tool::Triangle => self.create_triangle(start, end),
+            Tool::Callout => self.create_callout(start, end, start_conn),
             _ => Err(anyhow::anyhow!("Tool not supported for shape creation")),
         }
     }
@@ -1009,12 +1418,32 @@ impl App {
         self.add_shape_to_active_layer(kind)
     }
 
+    /// Create a callout whose tail points at `tip` (connected if the drag started on a snap point)
+    fn create_callout(
+        &mut self,
+        tip: Position,
+        corner: Position,
+        tip_conn: Option<u64>,
+    ) -> anyhow::Result<ShapeId> {
+        let (start, end) = callout_body_from_drag(tip, corner);
+        let kind = ShapeKind::Callout {
+            start,
+            end,
+            tail: tip,
+            tail_connection: tip_conn,
+            label: None,
+            color: self.current_color,
+        };
+        self.add_shape_to_active_layer(kind)
+    }
+
     /// Cancel the current shape
     pub fn cancel_shape(&mut self) {
         self.shape_state = None;
         self.drag_state = None;
         self.resize_state = None;
         self.freehand_state = None;
+        self.polygon_state = None;
         self.hover_snap = None;
         self.hover_grid_snap = None;
     }
@@ -1053,7 +1482,8 @@ impl App {
             | Tool::RoundedRect
             | Tool::Cylinder
             | Tool::Cloud
-            | Tool::Star => {
+            | Tool::Star
+            | Tool::Callout => {
                 self.mode = Mode::KeyboardShapeCreate(KeyboardShapeState {
                     tool,
                     width: "10".to_string(),
@@ -1135,6 +1565,7 @@ impl App {
             Tool::Cylinder => self.create_keyboard_cylinder(center_x, center_y, w, h),
             Tool::Cloud => self.create_keyboard_cloud(center_x, center_y, w, h),
             Tool::Star => self.create_keyboard_star(center_x, center_y, w, h),
+            Tool::Callout => self.create_keyboard_callout(center_x, center_y, w, h),
             _ => unreachable!("Unsupported tool for keyboard creation"),
         }
     }
@@ -1256,6 +1687,21 @@ impl App {
         }
     }
 
+    fn create_keyboard_callout(&self, center_x: i32, center_y: i32, w: i32, h: i32) -> ShapeKind {
+        let start = Position::new(center_x - w / 2, center_y - h / 2);
+        let end = Position::new(center_x + w / 2, center_y + h / 2);
+        // Tail hangs below the lower-left of the body until the user re-aims it
+        let tail = Position::new(start.x + 2, end.y + 3);
+        ShapeKind::Callout {
+            start,
+            end,
+            tail,
+            tail_connection: None,
+            label: None,
+            color: self.current_color,
+        }
+    }
+
     /// Cancel keyboard shape creation
     pub fn cancel_keyboard_shape(&mut self) {
         if matches!(self.mode, Mode::KeyboardShapeCreate(_)) {
@@ -2217,7 +2663,15 @@ impl App {
             return false;
         }
 
-        if let Some(handle) = self.shape_view.find_resize_handle(id, pos, SNAP_THRESHOLD) {
+        let handle = if self.vertex_edit_target() == Some(id) {
+            self.shape_view.find_vertex_handle(id, pos, SNAP_THRESHOLD)
+        } else {
+            self.shape_view.find_resize_handle(id, pos, SNAP_THRESHOLD)
+        };
+        if let Some(handle) = handle {
+            if let ResizeHandle::Vertex(index) = handle {
+                self.active_vertex = Some((id, index));
+            }
             // Capture the original kind before resize starts
             let original_kind = self.shape_view.get(id).map(|s| s.kind.clone());
             if let Some(original_kind) = original_kind {
@@ -2275,15 +2729,22 @@ impl App {
 
         let old_kind = shape.kind.clone();
         let new_kind = resize_shape(&old_kind, handle, pos);
+        let new_kind = self.attach_callout_tail(shape_id, handle, new_kind);
 
         // Update ONLY the cache during resize (no document writes - they're slow!)
         self.shape_view
             .update_shape_kind(shape_id, new_kind.clone());
 
-        // Find and update connected shapes in cache
+        // Find and update connected shapes in cache. A dragged vertex is tracked
+        // step by step so attached ends stay on it instead of jumping to whichever
+        // original vertex happens to be nearest.
+        let previous_kind = match handle {
+            ResizeHandle::Vertex(_) => &old_kind,
+            _ => &original_kind,
+        };
         let connected_updates =
             self.shape_view
-                .find_connected_updates_for_resize(shape_id, &original_kind, &new_kind);
+                .find_connected_updates_for_resize(shape_id, previous_kind, &new_kind);
 
         let mut modified_ids = vec![shape_id];
         for (id, kind) in connected_updates {
@@ -2301,6 +2762,43 @@ impl App {
         }
     }
 
+    /// Connect a dragged callout tip to the snap point it lands on, if any
+    fn attach_callout_tail(&self, shape_id: ShapeId, handle: ResizeHandle, kind: ShapeKind) -> ShapeKind {
+        if handle != ResizeHandle::End {
+            return kind;
+        }
+        let ShapeKind::Callout {
+            start,
+            end,
+            tail,
+            label,
+            color,
+            ..
+        } = kind
+        else {
+            return kind;
+        };
+
+        // Never attach the tail to the callout's own body
+        let snap = self
+            .shape_view
+            .find_snap_point(tail, SNAP_THRESHOLD)
+            .filter(|snap| snap.shape_id != shape_id);
+        let (tail, tail_connection) = match snap {
+            Some(snap) => (snap.pos, Some(snap.shape_id.0.as_u128() as u64)),
+            None => (tail, None),
+        };
+
+        ShapeKind::Callout {
+            start,
+            end,
+            tail,
+            tail_connection,
+            label,
+            color,
+        }
+    }
+
     /// Finish resizing - write final positions to document
     pub fn finish_resize(&mut self) {
         debug_assert!(self.resize_state.is_some() || self.resize_state.is_none(), "Valid resize state");
@@ -2328,102 +2826,367 @@ impl App {
         self.doc.mark_dirty();
     }
 
-    /// Delete all selected shapes
-    pub fn delete_selected(&mut self) {
-        debug_assert!(self.selected.len() < 100000, "Selection count should be reasonable");
-        
-        if self.selected.is_empty() {
-            return;
+    // ========== Stroke Simplification Methods ==========
+
+    /// Toggle smoothing of finished freehand strokes
+    pub fn toggle_stroke_smoothing(&mut self) {
+        self.smooth_strokes = !self.smooth_strokes;
+        if self.smooth_strokes {
+            self.set_status("Stroke smoothing on");
+        } else {
+            self.set_status("Stroke smoothing off");
         }
+    }
 
-        // Check if any selected shapes are on locked layers
-        let locked_count = self
-            .selected
-            .iter()
-            .filter(|&&id| self.is_shape_locked(id))
-            .count();
+    /// Replace selected freehand strokes with polylines of their simplified vertices
+    pub fn convert_selected_to_polyline(&mut self) {
+        debug_assert!(self.selected.len() < 100000, "Selection count should be reasonable");
 
-        debug_assert!(locked_count <= self.selected.len(), "Locked count cannot exceed selection");
+        let mut replacements = Vec::new();
+        let mut locked = 0;
+        for &id in &self.selected {
+            let Some(kind) = self.shape_view.get(id).and_then(|s| freehand_to_polyline(&s.kind))
+            else {
+                continue;
+            };
+            if self.is_shape_locked(id) {
+                locked += 1;
+                continue;
+            }
+            replacements.push((id, kind));
+        }
 
-        if locked_count > 0 {
-            let all_locked = locked_count == self.selected.len();
-            if all_locked {
-                self.set_error("Cannot delete - all selected shapes are on locked layers");
-                return;
+        if replacements.is_empty() {
+            if locked > 0 {
+                self.set_error("Cannot convert - shapes are on a locked layer");
             } else {
-                self.set_warning(format!(
-                    "{} shape(s) on locked layers will be skipped",
-                    locked_count
-                ));
+                self.set_warning("No freehand strokes selected");
             }
+            return;
         }
 
         self.save_undo_state();
-        let ids: Vec<_> = self
-            .selected
-            .iter()
-            .copied()
-            .filter(|&id| !self.is_shape_locked(id))
-            .collect();
-        let delete_count = ids.len();
-        debug_assert!(delete_count > 0, "Should have at least one shape to delete");
-        debug_assert!(delete_count <= self.selected.len(), "Delete count cannot exceed selection");
-        
-        for id in ids {
-            let _ = self.doc.delete_shape(id);
+        let count = replacements.len();
+        for (id, kind) in replacements {
+            let _ = self.doc.update_shape(id, kind);
         }
-        self.selected.clear();
         self.rebuild_view();
         self.doc.mark_dirty();
-        self.set_status(format!(
-            "Deleted {} shape{}",
-            delete_count,
-            if delete_count == 1 { "" } else { "s" }
-        ));
+        self.set_status(format!("Converted {} stroke(s) to polylines", count));
     }
 
-    /// Nudge selected shapes by (dx, dy)
-    pub fn nudge_selection(&mut self, dx: i32, dy: i32) {
-        debug_assert!(dx.abs() <= 100, "Nudge delta should be small");
-        debug_assert!(dy.abs() <= 100, "Nudge delta should be small");
-        debug_assert!(self.selected.len() < 100000, "Selection count should be reasonable");
-        
-        if self.selected.is_empty() {
-            return;
+    // ========== Shape Recognition Methods ==========
+
+    /// Toggle automatic recognition of finished freehand strokes
+    pub fn toggle_auto_recognize(&mut self) {
+        self.auto_recognize = !self.auto_recognize;
+        if self.auto_recognize {
+            self.set_status("Auto-recognize on: freehand strokes become shapes");
+        } else {
+            self.set_status("Auto-recognize off");
         }
+    }
 
-        // Check if any selected shapes are on locked layers
-        let locked_count = self
-            .selected
-            .iter()
-            .filter(|&&id| self.is_shape_locked(id))
-            .count();
+    /// Replace selected freehand strokes with the shapes they resemble
+    pub fn recognize_selected(&mut self) {
+        debug_assert!(self.selected.len() < 100000, "Selection count should be reasonable");
 
-        let all_locked = locked_count > 0 && locked_count == self.selected.len();
-        if all_locked {
-            self.set_error("Cannot move - all selected shapes are on locked layers");
+        let mut replacements = Vec::new();
+        let mut locked = 0;
+        for &id in &self.selected {
+            let Some(shape) = self.shape_view.get(id) else {
+                continue;
+            };
+            let Some(kind) = recognize_freehand(&shape.kind) else {
+                continue;
+            };
+            if self.is_shape_locked(id) {
+                locked += 1;
+                continue;
+            }
+            replacements.push((id, self.attach_recognized_connections(kind, Some(id))));
+        }
+
+        if replacements.is_empty() {
+            if locked > 0 {
+                self.set_error("Cannot recognize - shapes are on a locked layer");
+            } else {
+                self.set_warning("No recognizable freehand strokes selected");
+            }
             return;
         }
 
         self.save_undo_state();
-        for &id in self.selected.clone().iter() {
-            if !self.is_shape_locked(id) {
-                let _ = self.doc.translate_shape(id, dx, dy);
-            }
+        let count = replacements.len();
+        for (id, kind) in replacements {
+            let _ = self.doc.update_shape(id, kind);
         }
         self.rebuild_view();
         self.doc.mark_dirty();
-    }
+        self.set_status(format!("Recognized {} shape(s)", count));
+    }
+
+    /// Connect the endpoints of a recognized line or arrow to nearby snap points
+    fn attach_recognized_connections(&self, kind: ShapeKind, exclude: Option<ShapeId>) -> ShapeKind {
+        let snap = |pos: Position| {
+            let snap = self
+                .shape_view
+                .find_snap_point(pos, SNAP_THRESHOLD)
+                .filter(|snap| Some(snap.shape_id) != exclude);
+            match snap {
+                Some(snap) => (snap.pos, Some(snap.shape_id.0.as_u128() as u64)),
+                None => (pos, None),
+            }
+        };
 
-    /// Start text input at a position
-    pub fn start_text_input(&mut self, pos: Position) {
-        self.mode = Mode::TextInput(TextInputState {
-            start_pos: pos,
-            text: String::new(),
-        });
+        match kind {
+            ShapeKind::Line {
+                start,
+                end,
+                style,
+                label,
+                color,
+                ..
+            } => {
+                let (start, start_connection) = snap(start);
+                let (end, end_connection) = snap(end);
+                ShapeKind::Line {
+                    start,
+                    end,
+                    style,
+                    start_connection,
+                    end_connection,
+                    label,
+                    color,
+                }
+            }
+            ShapeKind::Arrow {
+                start,
+                end,
+                style,
+                label,
+                color,
+                ..
+            } => {
+                let (start, start_connection) = snap(start);
+                let (end, end_connection) = snap(end);
+                ShapeKind::Arrow {
+                    start,
+                    end,
+                    style,
+                    start_connection,
+                    end_connection,
+                    label,
+                    color,
+                }
+            }
+            other => other,
+        }
     }
 
-    /// Add a character to current text input
+    // ========== Vertex Edit Methods ==========
+
+    /// Toggle vertex-edit mode for the select tool
+    pub fn toggle_vertex_edit(&mut self) {
+        self.vertex_edit = !self.vertex_edit;
+        self.active_vertex = None;
+        if self.vertex_edit {
+            self.set_status("Vertex edit: drag vertex, click edge to insert, right-click to delete");
+        } else {
+            self.set_status("Vertex edit off");
+        }
+    }
+
+    /// The single selected shape, if vertex-edit mode is on and it has vertices
+    fn vertex_edit_target(&self) -> Option<ShapeId> {
+        if !self.vertex_edit || self.selected.len() != 1 {
+            return None;
+        }
+        let &id = self.selected.iter().next()?;
+        let shape = self.shape_view.get(id)?;
+        shape.kind.supports_vertex_edit().then_some(id)
+    }
+
+    /// Insert a vertex where the cursor touches an edge of the selected shape
+    pub fn try_insert_vertex(&mut self, pos: Position) -> bool {
+        let Some(id) = self.vertex_edit_target() else {
+            return false;
+        };
+        let Some(kind) = self.shape_view.get(id).map(|s| s.kind.clone()) else {
+            return false;
+        };
+        let Some(edge) = find_vertex_edge(&kind, pos, VERTEX_EDGE_THRESHOLD) else {
+            return false;
+        };
+        if self.is_shape_locked(id) {
+            self.set_error("Cannot edit - shape is on a locked layer");
+            return false;
+        }
+        let Some(new_kind) = insert_vertex(&kind, edge, pos) else {
+            return false;
+        };
+
+        self.save_undo_state();
+        let _ = self.doc.update_shape(id, new_kind);
+        self.rebuild_view();
+        self.doc.mark_dirty();
+        self.active_vertex = Some((id, edge + 1));
+        self.set_status("Inserted vertex");
+        true
+    }
+
+    /// Delete the vertex under the cursor from the selected shape
+    pub fn try_delete_vertex(&mut self, pos: Position) -> bool {
+        let Some(id) = self.vertex_edit_target() else {
+            return false;
+        };
+        match self.shape_view.find_vertex_handle(id, pos, SNAP_THRESHOLD) {
+            Some(ResizeHandle::Vertex(index)) => self.delete_vertex_of(id, index),
+            _ => false,
+        }
+    }
+
+    /// Delete the last grabbed vertex (Delete key in vertex-edit mode)
+    pub fn delete_active_vertex(&mut self) -> bool {
+        let Some(id) = self.vertex_edit_target() else {
+            return false;
+        };
+        match self.active_vertex {
+            Some((active_id, index)) if active_id == id => self.delete_vertex_of(id, index),
+            _ => false,
+        }
+    }
+
+    fn delete_vertex_of(&mut self, id: ShapeId, index: usize) -> bool {
+        if self.is_shape_locked(id) {
+            self.set_error("Cannot edit - shape is on a locked layer");
+            return false;
+        }
+        let Some(kind) = self.shape_view.get(id).map(|s| s.kind.clone()) else {
+            return false;
+        };
+        let Some(new_kind) = delete_vertex(&kind, index) else {
+            self.set_warning(format!("{} cannot lose any more vertices", kind.type_name()));
+            return false;
+        };
+
+        self.save_undo_state();
+        let _ = self.doc.update_shape(id, new_kind);
+        // Ends attached to the removed vertex have nothing left to follow
+        if let Some(removed) = kind.vertices().and_then(|v| v.get(index).copied()) {
+            let conn_id = id.0.as_u128() as u64;
+            let detached: Vec<_> = self
+                .shape_view
+                .iter()
+                .filter_map(|shape| Some((shape.id, shape.kind.detach_at(conn_id, removed)?)))
+                .collect();
+            for (line_id, line_kind) in detached {
+                let _ = self.doc.update_shape(line_id, line_kind);
+            }
+        }
+        self.rebuild_view();
+        self.doc.mark_dirty();
+        self.active_vertex = None;
+        self.set_status("Deleted vertex");
+        true
+    }
+
+    /// Delete all selected shapes
+    pub fn delete_selected(&mut self) {
+        debug_assert!(self.selected.len() < 100000, "Selection count should be reasonable");
+        
+        if self.selected.is_empty() {
+            return;
+        }
+
+        // Check if any selected shapes are on locked layers
+        let locked_count = self
+            .selected
+            .iter()
+            .filter(|&&id| self.is_shape_locked(id))
+            .count();
+
+        debug_assert!(locked_count <= self.selected.len(), "Locked count cannot exceed selection");
+
+        if locked_count > 0 {
+            let all_locked = locked_count == self.selected.len();
+            if all_locked {
+                self.set_error("Cannot delete - all selected shapes are on locked layers");
+                return;
+            } else {
+                self.set_warning(format!(
+                    "{} shape(s) on locked layers will be skipped",
+                    locked_count
+                ));
+            }
+        }
+
+        self.save_undo_state();
+        let ids: Vec<_> = self
+            .selected
+            .iter()
+            .copied()
+            .filter(|&id| !self.is_shape_locked(id))
+            .collect();
+        let delete_count = ids.len();
+        debug_assert!(delete_count > 0, "Should have at least one shape to delete");
+        debug_assert!(delete_count <= self.selected.len(), "Delete count cannot exceed selection");
+        
+        for id in ids {
+            let _ = self.doc.delete_shape(id);
+        }
+        self.selected.clear();
+        self.rebuild_view();
+        self.doc.mark_dirty();
+        self.set_status(format!(
+            "Deleted {} shape{}",
+            delete_count,
+            if delete_count == 1 { "" } else { "s" }
+        ));
+    }
+
+    /// Nudge selected shapes by (dx, dy)
+    pub fn nudge_selection(&mut self, dx: i32, dy: i32) {
+        debug_assert!(dx.abs() <= 100, "Nudge delta should be small");
+        debug_assert!(dy.abs() <= 100, "Nudge delta should be small");
+        debug_assert!(self.selected.len() < 100000, "Selection count should be reasonable");
+        
+        if self.selected.is_empty() {
+            return;
+        }
+
+        // Check if any selected shapes are on locked layers
+        let locked_count = self
+            .selected
+            .iter()
+            .filter(|&&id| self.is_shape_locked(id))
+            .count();
+
+        let all_locked = locked_count > 0 && locked_count == self.selected.len();
+        if all_locked {
+            self.set_error("Cannot move - all selected shapes are on locked layers");
+            return;
+        }
+
+        self.save_undo_state();
+        for &id in self.selected.clone().iter() {
+            if !self.is_shape_locked(id) {
+                let _ = self.doc.translate_shape(id, dx, dy);
+            }
+        }
+        self.rebuild_view();
+        self.doc.mark_dirty();
+    }
+
+    /// Start text input at a position
+    pub fn start_text_input(&mut self, pos: Position) {
+        self.mode = Mode::TextInput(TextInputState {
+            start_pos: pos,
+            text: String::new(),
+        });
+    }
+
+    /// Add a character to current text input
     pub fn add_text_char(&mut self, ch: char) {
         if let Mode::TextInput(state) = &mut self.mode {
             state.text.push(ch);
@@ -2439,11 +3202,11 @@ impl App {
 
     /// Enter file save mode
     pub fn start_save(&mut self) {
-        let initial_path = self
-            .file_path
-            .as_ref()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "drawing.txt".to_string());
+        let initial_path = match (&self.file_path, &self.markdown_block) {
+            (Some(path), Some(block)) => format!("{}#{}", path.to_string_lossy(), block),
+            (Some(path), None) => path.to_string_lossy().to_string(),
+            (None, _) => "drawing.txt".to_string(),
+        };
         self.mode = Mode::PathInput(PathInputState {
             path: initial_path,
             kind: PathInputKind::FileSave,
@@ -2625,14 +3388,51 @@ impl App {
         self.doc.automerge()
     }
 
-    /// Clone the automerge document for syncing
-    pub fn clone_automerge(&self) -> automerge::Automerge {
+    /// Clone the whole document to open it in the sync thread. Later
+    /// flushes only send what changed after this point.
+    pub fn sync_snapshot(&mut self) -> automerge::Automerge {
+        self.sync_heads = self.doc.heads();
         self.doc.clone_automerge()
     }
 
-    /// Merge remote changes and rebuild view
-    pub fn merge_remote(&mut self, other: &mut automerge::Automerge) {
-        if let Err(e) = self.doc.merge(other) {
+    /// Encoded changes made since the last flush to the sync thread.
+    ///
+    /// If the document was replaced (a file was opened), the old heads are
+    /// unknown to it and every change is sent, as the peers need them all.
+    pub fn take_sync_changes(&mut self) -> Vec<u8> {
+        let changes = self.doc.changes_since(&self.sync_heads);
+        self.sync_heads = self.doc.heads();
+        changes
+    }
+
+    /// Whether we joined with a viewer ticket, so local edits are refused
+    pub fn read_only(&self) -> bool {
+        self.doc.is_read_only()
+    }
+
+    /// Enter or leave read-only mode (editors would drop our changes anyway)
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.doc.set_read_only(read_only);
+    }
+
+    /// Warn if an edit was refused because the document is read-only
+    pub fn warn_refused_edits(&mut self) {
+        if self.doc.take_refused_edit() {
+            self.set_warning("View only: editing is disabled");
+        }
+    }
+
+    /// Swap in another document, staying in read-only mode if we were
+    fn replace_doc(&mut self, mut doc: Document) {
+        doc.set_read_only(self.doc.is_read_only());
+        self.doc = doc;
+    }
+
+    /// Apply changes received from peers and rebuild view
+    pub fn apply_remote_changes(&mut self, changes: &[u8]) {
+        // sync_heads stays put: changes made locally in the meantime still
+        // need flushing, and re-sending the remote ones is harmless
+        if let Err(e) = self.doc.apply_changes(changes) {
             self.set_status(format!("Merge error: {}", e));
             return;
         }
@@ -2650,13 +3450,29 @@ impl App {
         });
     }
 
+    /// Toggle braille sub-cell rendering of curves and freehand strokes (document setting)
+    pub fn toggle_braille(&mut self) {
+        let enabled = !self.doc.braille();
+        if let Err(e) = self.doc.set_braille(enabled) {
+            self.set_error(format!("Braille setting error: {}", e));
+            return;
+        }
+        self.rebuild_view();
+        self.set_status(if enabled {
+            "Braille rendering: ON"
+        } else {
+            "Braille rendering: OFF"
+        });
+    }
+
     /// Create a new empty document
     pub fn new_document(&mut self) {
-        self.doc = Document::new();
+        self.replace_doc(Document::new());
         self.doc.set_storage_path(default_storage_path());
         self.shape_view = ShapeView::new();
         self.selected.clear();
         self.file_path = None;
+        self.markdown_block = None;
         // Clear global undo history (it's stored in the new doc already)
         let _ = self.doc.clear_undo_history();
         self.set_status("New document");
@@ -2925,14 +3741,27 @@ impl App {
     // File Operation Methods (used by modes/path_input.rs)
     // =========================================================================
 
-    /// Execute file save (ASCII export)
+    /// Execute file save (ASCII export, or into a diagram block of a Markdown file)
     pub fn execute_file_save(&mut self, path: &str) {
         use crate::file_io;
-        let path_buf = std::path::PathBuf::from(path);
-        match file_io::save_ascii(&self.shape_view, &path_buf) {
+        let (path_buf, selector) = file_io::split_block_selector(path);
+        if file_io::is_markdown_path(&path_buf) {
+            self.save_markdown_block(path_buf, selector);
+            return;
+        }
+        // Saving keeps every shape; the export prompt offers narrower options
+        let options = file_io::ExportOptions {
+            include_hidden: true,
+            ..file_io::ExportOptions::default()
+        };
+        let result = self.doc.read_all_layers().and_then(|layers| {
+            file_io::save_with_options(&self.shape_view, &layers, &path_buf, &options)
+        });
+        match result {
             Ok(()) => {
                 self.recent_files.add(path_buf.clone());
                 self.file_path = Some(path_buf);
+                self.markdown_block = None;
                 self.set_status("Exported!");
             }
             Err(e) => {
@@ -2941,23 +3770,44 @@ impl App {
         }
     }
 
-    /// Execute file open (ASCII import)
+    /// Execute file open (ASCII import, or a diagram block of a Markdown file)
     pub fn execute_file_open(&mut self, path: &str) {
         use crate::file_io;
-        let path_buf = std::path::PathBuf::from(path);
-        match file_io::load_ascii(&path_buf) {
-            Ok(shapes) => {
-                // Create new document and add shapes
-                self.doc = Document::new();
-                for kind in shapes {
-                    let _ = self.doc.add_shape(kind);
-                }
+        let (path_buf, selector) = file_io::split_block_selector(path);
+        if file_io::is_markdown_path(&path_buf) {
+            let block = selector.unwrap_or(file_io::BlockRef::Index(0));
+            self.open_markdown_block(path_buf, block);
+            return;
+        }
+        if file_io::is_drawio_path(&path_buf)
+            || file_io::is_ansi_path(&path_buf)
+            || file_io::is_html_path(&path_buf)
+            || file_io::is_png_path(&path_buf)
+        {
+            self.set_error("This format can only be exported");
+            return;
+        }
+        // Import into a new document, keeping connections between boxes and lines
+        let mut doc = Document::new();
+        let result = if file_io::is_mermaid_path(&path_buf) {
+            file_io::load_mermaid(&mut doc, &path_buf)
+        } else if file_io::is_dot_path(&path_buf) {
+            file_io::load_dot(&mut doc, &path_buf)
+        } else if file_io::is_excalidraw_path(&path_buf) {
+            file_io::load_excalidraw(&mut doc, &path_buf)
+        } else {
+            file_io::load_ascii_into(&mut doc, &path_buf)
+        };
+        match result {
+            Ok(_) => {
+                self.replace_doc(doc);
                 // Rebuild view
                 if let Err(e) = self.shape_view.rebuild(&self.doc) {
                     self.set_error(format!("Error rebuilding view: {}", e));
                 } else {
                     self.recent_files.add(path_buf.clone());
                     self.file_path = Some(path_buf);
+                    self.markdown_block = None;
                     self.set_status("Imported!");
                 }
             }
@@ -2967,6 +3817,72 @@ impl App {
         }
     }
 
+    /// Block picker for a Markdown path with several diagram blocks and no `#block` selector
+    pub fn markdown_block_picker(&self, path: &str) -> Option<Mode> {
+        use crate::file_io;
+        let (path_buf, selector) = file_io::split_block_selector(path);
+        if selector.is_some() || !file_io::is_markdown_path(&path_buf) {
+            return None;
+        }
+        let markdown = std::fs::read_to_string(&path_buf).ok()?;
+        let blocks = file_io::find_diagram_blocks(&markdown);
+        (blocks.len() > 1).then(|| {
+            Mode::MarkdownBlocks(MarkdownBlocksState {
+                path: path_buf,
+                blocks,
+                selected: 0,
+            })
+        })
+    }
+
+    /// Block picker for a recent file, like [`Self::markdown_block_picker`]
+    pub fn recent_file_picker(&self, index: u32) -> Option<Mode> {
+        let file = self.recent_files.get(index as usize)?;
+        self.markdown_block_picker(&file.path.to_string_lossy())
+    }
+
+    /// Import a diagram block of a Markdown file into a new document
+    pub fn open_markdown_block(&mut self, path: PathBuf, block: crate::file_io::BlockRef) {
+        use crate::file_io;
+        let mut doc = Document::new();
+        match file_io::load_markdown_block(&mut doc, &path, &block) {
+            Ok(found) => {
+                self.replace_doc(doc);
+                if let Err(e) = self.shape_view.rebuild(&self.doc) {
+                    self.set_error(format!("Error rebuilding view: {}", e));
+                } else {
+                    self.recent_files.add(path.clone());
+                    self.file_path = Some(path);
+                    self.markdown_block = Some(found.block_ref());
+                    self.set_status(format!("Imported block {}", found.title()));
+                }
+            }
+            Err(e) => {
+                self.set_error(format!("Import error: {}", e));
+            }
+        }
+    }
+
+    /// Write the drawing back into a diagram block, leaving the rest of the file untouched
+    fn save_markdown_block(&mut self, path: PathBuf, selector: Option<crate::file_io::BlockRef>) {
+        use crate::file_io;
+        let Some(block) = selector.or_else(|| self.markdown_block.clone()) else {
+            self.set_error("Choose a diagram block: FILE.md#id or FILE.md#number");
+            return;
+        };
+        match file_io::save_markdown_block(&self.shape_view, &path, &block) {
+            Ok(()) => {
+                self.recent_files.add(path.clone());
+                self.file_path = Some(path);
+                self.set_status(format!("Saved block {}", block));
+                self.markdown_block = Some(block);
+            }
+            Err(e) => {
+                self.set_error(format!("Export error: {}", e));
+            }
+        }
+    }
+
     /// Execute document save (native format)
     pub fn execute_doc_save(&mut self, path: &str) {
         let path_buf = std::path::PathBuf::from(path);
@@ -2985,7 +3901,7 @@ impl App {
         let path_buf = std::path::PathBuf::from(path);
         match Document::load(&path_buf) {
             Ok(doc) => {
-                self.doc = doc;
+                self.replace_doc(doc);
                 if let Err(e) = self.shape_view.rebuild(&self.doc) {
                     self.set_error(format!("Error rebuilding view: {}", e));
                 } else {
@@ -2998,18 +3914,101 @@ impl App {
         }
     }
 
-    /// Execute SVG export
-    pub fn execute_svg_export(&mut self, path: &str) {
-        use crate::svg_export;
-        let path_buf = std::path::PathBuf::from(path);
-        match svg_export::save_svg(&self.shape_view, &path_buf) {
+    /// Execute export: a path in any export format, then options such as
+    /// `--selection`, `--viewport`, `--layer NAME`, `--region X1,Y1,X2,Y2`,
+    /// `--include-hidden` (or `--hidden`), `--padding N`, `--no-trim`, and
+    /// for PNG `--scale N` and `--background COLOR`: the `render` and
+    /// `convert` options plus the selection and viewport
+    pub fn execute_export(&mut self, input: &str) {
+        use crate::file_io;
+        let (path_buf, options) = match self.parse_export_input(input) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.set_error(format!("Export error: {}", e));
+                return;
+            }
+        };
+        let result = self.doc.read_all_layers().and_then(|layers| {
+            file_io::save_with_options(&self.shape_view, &layers, &path_buf, &options)
+        });
+        match result {
             Ok(()) => {
                 self.set_status(format!("Exported to {}", path_buf.display()));
             }
             Err(e) => {
-                self.set_error(format!("SVG export error: {}", e));
+                self.set_error(format!("Export error: {}", e));
+            }
+        }
+    }
+
+    /// Split export prompt input into the path and the export options
+    fn parse_export_input(
+        &self,
+        input: &str,
+    ) -> anyhow::Result<(std::path::PathBuf, crate::file_io::ExportOptions)> {
+        use crate::file_io::{ExportOptions, ExportScope, Region};
+        // Options start at the first " --", so paths may contain spaces
+        let (path, flags) = match input.find(" --") {
+            Some(idx) => (&input[..idx], &input[idx..]),
+            None => (input, ""),
+        };
+        let path = path.trim();
+        if path.is_empty() {
+            anyhow::bail!("No export path given");
+        }
+
+        let mut options = ExportOptions::default();
+        let mut layer_names = Vec::new();
+        let mut tokens = flags.split_whitespace();
+        while let Some(flag) = tokens.next() {
+            let mut value = || {
+                tokens
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("{} needs a value", flag))
+            };
+            match flag {
+                "--selection" => {
+                    if self.selected.is_empty() {
+                        anyhow::bail!("Nothing selected");
+                    }
+                    options.scope = ExportScope::Shapes(self.selected.iter().copied().collect());
+                }
+                "--layer" => layer_names.push(value()?.to_string()),
+                "--region" => options.region = Some(value()?.parse()?),
+                "--viewport" => {
+                    let (width, height) = (self.viewport.width, self.viewport.height);
+                    options.region = Some(Region::new(
+                        self.viewport.screen_to_canvas(0, 0),
+                        self.viewport
+                            .screen_to_canvas(width.saturating_sub(1), height.saturating_sub(1)),
+                    ));
+                }
+                "--include-hidden" | "--hidden" => options.include_hidden = true,
+                "--padding" => {
+                    let padding = value()?;
+                    options.padding = padding
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("Invalid padding: {}", padding))?;
+                }
+                "--no-trim" => options.trim = false,
+                "--scale" => {
+                    let scale = value()?;
+                    options.png_scale = scale
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("Invalid scale: {}", scale))?;
+                }
+                "--background" => {
+                    options.png_background = crate::file_io::parse_png_background(value()?)?;
+                }
+                _ => anyhow::bail!("Unknown export option: {}", flag),
             }
         }
+        if !layer_names.is_empty() {
+            // Asking for a layer by name exports it even when hidden
+            options.scope = ExportScope::layers_named(&self.doc.read_all_layers()?, &layer_names)?;
+            options.include_hidden = true;
+        }
+        Ok((std::path::PathBuf::from(path), options))
     }
 
     pub fn execute_cluster_connect(&mut self, ticket: &str) {
@@ -3032,6 +4031,41 @@ impl App {
         self.set_status("Connecting to peer...");
     }
 
+    /// Queue an invite described as `label --viewer --hours N` (all parts
+    /// optional) for the main loop to issue.
+    pub fn execute_invite_create(&mut self, input: &str) {
+        match parse_invite_request(input) {
+            Ok(request) => {
+                self.pending_invite = Some(request);
+            }
+            Err(e) => {
+                self.set_error(format!("Invite failed: {e}"));
+            }
+        }
+    }
+
+    /// Copy the ticket of an issued invite by index
+    pub fn copy_invite_ticket(&mut self, index: u32) {
+        match self.invites.get(index as usize) {
+            Some(info) if info.revoked => self.set_status("Invite was revoked"),
+            Some(info) if info.invite.is_expired() => self.set_status("Invite has expired"),
+            Some(info) => {
+                let ticket = info.ticket.clone();
+                self.copy_text_to_clipboard(&ticket);
+            }
+            None => self.set_status("No invite selected"),
+        }
+    }
+
+    /// Queue revocation of an issued invite by index
+    pub fn revoke_invite(&mut self, index: u32) {
+        match self.invites.get(index as usize) {
+            Some(info) if info.revoked => self.set_status("Invite already revoked"),
+            Some(info) => self.pending_invite_revoke = Some(info.invite.id),
+            None => self.set_status("No invite selected"),
+        }
+    }
+
     /// Decode a QR code from an image file and display the ticket.
     pub fn execute_qr_decode(&mut self, path: &str) {
         let path = path.trim();
@@ -3070,16 +4104,19 @@ impl App {
         let index_usize = index as usize;
         if let Some(file) = self.recent_files.get(index_usize) {
             let path = file.path.clone();
-            match file_io::load_ascii(&path) {
-                Ok(shapes) => {
-                    self.doc = Document::new();
-                    for kind in shapes {
-                        let _ = self.doc.add_shape(kind);
-                    }
+            if file_io::is_markdown_path(&path) {
+                self.open_markdown_block(path, file_io::BlockRef::Index(0));
+                return;
+            }
+            let mut doc = Document::new();
+            match file_io::load_ascii_into(&mut doc, &path) {
+                Ok(_) => {
+                    self.replace_doc(doc);
                     if let Err(e) = self.shape_view.rebuild(&self.doc) {
                         self.set_error(format!("Error rebuilding view: {}", e));
                     } else {
                         self.file_path = Some(path);
+                        self.markdown_block = None;
                         self.set_status("Loaded!");
                     }
                 }
@@ -3099,6 +4136,18 @@ fn snap_to_grid(pos: Position) -> Position {
     }
 }
 
+/// Body corners for a callout drawn by dragging from the tail tip to the bubble.
+/// The bubble grows away from the tip so the tail never crosses it.
+pub fn callout_body_from_drag(tip: Position, corner: Position) -> (Position, Position) {
+    let dir_x = if corner.x >= tip.x { 1 } else { -1 };
+    let dir_y = if corner.y >= tip.y { 1 } else { -1 };
+    let far = Position::new(
+        corner.x + dir_x * (CALLOUT_WIDTH - 1),
+        corner.y + dir_y * (CALLOUT_HEIGHT - 1),
+    );
+    (corner, far)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3486,6 +4535,257 @@ mod tests {
         assert_eq!(snapped.y, -5);
     }
 
+    // ========== Callout Tests ==========
+
+    #[test]
+    fn callout_body_grows_away_from_tip() {
+        let (start, end) = callout_body_from_drag(Position::new(0, 0), Position::new(5, 3));
+        assert_eq!(start, Position::new(5, 3));
+        assert_eq!(end, Position::new(5 + CALLOUT_WIDTH - 1, 3 + CALLOUT_HEIGHT - 1));
+
+        let (start, end) = callout_body_from_drag(Position::new(0, 0), Position::new(-5, -3));
+        assert_eq!(start, Position::new(-5, -3));
+        assert_eq!(end, Position::new(-5 - (CALLOUT_WIDTH - 1), -3 - (CALLOUT_HEIGHT - 1)));
+    }
+
+    #[test]
+    fn app_callout_tool_creates_callout() {
+        let mut app = create_test_app();
+        app.set_tool(Tool::Callout);
+        app.start_shape(Position::new(40, 20));
+        app.update_shape(Position::new(52, 27));
+        app.commit_shape();
+
+        assert_eq!(app.shape_view.shape_count(), 1);
+        let shape = app.shape_view.iter().next().expect("callout was created");
+        if let ShapeKind::Callout { tail, start, tail_connection, .. } = &shape.kind {
+            assert_eq!(*tail, Position::new(40, 20));
+            assert_eq!(*start, Position::new(52, 27));
+            assert_eq!(*tail_connection, None);
+        } else {
+            panic!("Expected Callout");
+        }
+    }
+
+    // ========== Polygon / Vertex Edit Tests ==========
+
+    fn draw_test_polygon(app: &mut App) -> ShapeId {
+        app.set_tool(Tool::Polygon);
+        app.add_polygon_point(Position::new(0, 0));
+        app.add_polygon_point(Position::new(10, 0));
+        app.add_polygon_point(Position::new(10, 6));
+        // Clicking back on the first vertex closes the polygon
+        app.add_polygon_point(Position::new(1, 0));
+        assert!(app.polygon_state.is_none());
+        app.shape_view.iter().next().expect("polygon was created").id
+    }
+
+    #[test]
+    fn app_polygon_tool_closes_on_first_vertex() {
+        let mut app = create_test_app();
+        let id = draw_test_polygon(&mut app);
+        let shape = app.shape_view.get(id).unwrap();
+        if let ShapeKind::Polygon { points, closed, .. } = &shape.kind {
+            assert_eq!(points.len(), 3);
+            assert!(*closed);
+        } else {
+            panic!("Expected Polygon");
+        }
+    }
+
+    #[test]
+    fn app_polygon_tool_finishes_open_polyline() {
+        let mut app = create_test_app();
+        app.set_tool(Tool::Polygon);
+        app.add_polygon_point(Position::new(0, 0));
+        app.finish_polygon(false);
+        assert_eq!(app.shape_view.shape_count(), 0, "one vertex is not a polyline");
+
+        app.add_polygon_point(Position::new(0, 0));
+        app.add_polygon_point(Position::new(8, 4));
+        app.finish_polygon(false);
+        let shape = app.shape_view.iter().next().expect("polyline was created");
+        assert!(matches!(shape.kind, ShapeKind::Polygon { closed: false, .. }));
+    }
+
+    #[test]
+    fn app_vertex_edit_insert_drag_delete() {
+        let mut app = create_test_app();
+        let id = draw_test_polygon(&mut app);
+        app.set_tool(Tool::Select);
+        app.select_single(id);
+
+        // Outside vertex-edit mode edges are not editable
+        assert!(!app.try_insert_vertex(Position::new(5, 0)));
+        app.toggle_vertex_edit();
+
+        assert!(app.try_insert_vertex(Position::new(5, 0)));
+        assert_eq!(app.shape_view.get(id).unwrap().kind.vertices().unwrap().len(), 4);
+
+        // Grab the new vertex and drag it
+        assert!(app.try_start_resize(Position::new(5, 0)));
+        app.continue_resize(Position::new(5, -4));
+        app.finish_resize();
+        let vertices = app.doc.read_shape(id).unwrap().unwrap().vertices().unwrap();
+        assert_eq!(vertices[1], Position::new(5, -4));
+
+        // Delete removes the grabbed vertex, then refuses below three
+        assert!(app.delete_active_vertex());
+        assert_eq!(app.shape_view.get(id).unwrap().kind.vertices().unwrap().len(), 3);
+        assert!(!app.try_delete_vertex(Position::new(10, 6)));
+        assert_eq!(app.shape_view.shape_count(), 1);
+    }
+
+    #[test]
+    fn app_vertex_edit_keeps_attached_line_in_step() {
+        let mut app = create_test_app();
+        let id = draw_test_polygon(&mut app);
+        let line_id = app
+            .doc
+            .add_shape(ShapeKind::Line {
+                start: Position::new(20, 6),
+                end: Position::new(10, 6),
+                style: LineStyle::default(),
+                start_connection: None,
+                end_connection: Some(id.0.as_u128() as u64),
+                label: None,
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+        app.rebuild_view();
+        app.set_tool(Tool::Select);
+        app.select_single(id);
+        app.toggle_vertex_edit();
+        assert!(app.try_insert_vertex(Position::new(5, 0)));
+        let line = app.doc.read_shape(line_id).unwrap().unwrap();
+        assert_eq!(line.connections().1, Some(id.0.as_u128() as u64));
+
+        // Drag the attached vertex past another one; the end must stay on it
+        assert!(app.try_start_resize(Position::new(10, 6)));
+        app.continue_resize(Position::new(10, 1));
+        app.continue_resize(Position::new(10, -2));
+        app.finish_resize();
+        let line = app.doc.read_shape(line_id).unwrap().unwrap();
+        assert!(matches!(line, ShapeKind::Line { end, .. } if end == Position::new(10, -2)));
+
+        // Deleting that vertex leaves the end where it was but detached
+        assert!(app.try_delete_vertex(Position::new(10, -2)));
+        let line = app.doc.read_shape(line_id).unwrap().unwrap();
+        assert_eq!(line.connections(), (None, None));
+        assert!(matches!(line, ShapeKind::Line { end, .. } if end == Position::new(10, -2)));
+    }
+
+    // ========== Braille Rendering Tests ==========
+
+    #[test]
+    fn app_toggle_braille_updates_document_and_view() {
+        let mut app = create_test_app();
+        assert!(!app.shape_view.braille());
+
+        app.toggle_braille();
+        assert!(app.doc.braille());
+        assert!(app.shape_view.braille());
+        assert!(app.is_dirty());
+
+        app.toggle_braille();
+        assert!(!app.shape_view.braille());
+    }
+
+    // ========== Stroke Simplification Tests ==========
+
+    #[test]
+    fn app_finish_freehand_simplifies_stroke() {
+        let mut app = create_test_app();
+        app.set_tool(Tool::Freehand);
+        app.start_freehand(Position::new(0, 0));
+        app.continue_freehand(Position::new(15, 0));
+        app.continue_freehand(Position::new(15, 6));
+        assert_eq!(app.freehand_state.as_ref().unwrap().points.len(), 22);
+        app.finish_freehand();
+
+        let shape = app.shape_view.iter().next().expect("stroke was created");
+        assert_eq!(
+            shape.kind.vertices().unwrap(),
+            vec![Position::new(0, 0), Position::new(15, 0), Position::new(15, 6)]
+        );
+    }
+
+    #[test]
+    fn app_convert_selected_to_polyline() {
+        let mut app = create_test_app();
+        app.toggle_stroke_smoothing();
+        assert!(app.smooth_strokes);
+        app.set_tool(Tool::Freehand);
+        app.start_freehand(Position::new(0, 0));
+        app.continue_freehand(Position::new(10, 0));
+        app.continue_freehand(Position::new(10, 8));
+        app.finish_freehand();
+        let id = app.shape_view.iter().next().expect("stroke was created").id;
+
+        app.set_tool(Tool::Select);
+        app.select_single(id);
+        app.convert_selected_to_polyline();
+        let kind = app.doc.read_shape(id).unwrap().expect("same shape id");
+        assert!(matches!(kind, ShapeKind::Polygon { closed: false, .. }));
+    }
+
+    // ========== Shape Recognition Tests ==========
+
+    fn draw_test_stroke(app: &mut App, corners: &[Position]) {
+        app.set_tool(Tool::Freehand);
+        app.start_freehand(corners[0]);
+        for &corner in &corners[1..] {
+            app.continue_freehand(corner);
+        }
+        app.finish_freehand();
+    }
+
+    #[test]
+    fn app_auto_recognize_replaces_stroke() {
+        let mut app = create_test_app();
+        let rect = [
+            Position::new(0, 0),
+            Position::new(20, 0),
+            Position::new(20, 8),
+            Position::new(0, 8),
+            Position::new(0, 1),
+        ];
+
+        draw_test_stroke(&mut app, &rect);
+        let shape = app.shape_view.iter().next().expect("stroke was created");
+        assert!(matches!(shape.kind, ShapeKind::Freehand { .. }));
+
+        app.toggle_auto_recognize();
+        app.current_color = ShapeColor::Red;
+        let offset: Vec<Position> = rect.iter().map(|p| Position::new(p.x, p.y + 20)).collect();
+        draw_test_stroke(&mut app, &offset);
+        assert_eq!(app.shape_view.shape_count(), 2);
+        let recognized = app
+            .shape_view
+            .iter()
+            .find(|s| matches!(s.kind, ShapeKind::Rectangle { .. }))
+            .expect("second stroke was recognized");
+        assert_eq!(recognized.kind.color(), ShapeColor::Red);
+    }
+
+    #[test]
+    fn app_recognize_selected_keeps_id() {
+        let mut app = create_test_app();
+        draw_test_stroke(&mut app, &[Position::new(0, 0), Position::new(15, 3)]);
+        let id = app.shape_view.iter().next().expect("stroke was created").id;
+
+        app.set_tool(Tool::Select);
+        app.select_single(id);
+        app.recognize_selected();
+        assert_eq!(app.shape_view.shape_count(), 1);
+        let kind = app.doc.read_shape(id).unwrap().expect("same shape id");
+        assert!(matches!(kind, ShapeKind::Line { .. }));
+
+        app.undo();
+        let kind = app.doc.read_shape(id).unwrap().expect("stroke restored");
+        assert!(matches!(kind, ShapeKind::Freehand { .. }));
+    }
+
     // ========== New Document Tests ==========
 
     #[test]
@@ -3733,4 +5033,290 @@ mod tests {
             assert_eq!(center_y, 30);
         }
     }
+
+    // ========== Markdown Block Tests ==========
+
+    #[test]
+    fn app_markdown_block_picker_lists_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, "```irohscii id=arch\n+--+\n+--+\n```\n\n```\n-->\n```\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let app = create_test_app();
+
+        match app.markdown_block_picker(&path) {
+            Some(Mode::MarkdownBlocks(state)) => {
+                assert_eq!(state.blocks.len(), 2);
+                assert_eq!(state.blocks[0].id.as_deref(), Some("arch"));
+            }
+            other => panic!("Expected block picker, got {:?}", other),
+        }
+        // An explicit selector skips the picker
+        assert!(app.markdown_block_picker(&format!("{}#arch", path)).is_none());
+        assert!(app.markdown_block_picker("drawing.txt").is_none());
+    }
+
+    #[test]
+    fn app_recent_markdown_file_shows_block_picker() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = dir.path().join("notes.md");
+        std::fs::write(&notes, "```irohscii id=arch\n+--+\n+--+\n```\n\n```\n-->\n```\n").unwrap();
+        let single = dir.path().join("single.md");
+        std::fs::write(&single, "```irohscii\n+--+\n+--+\n```\n").unwrap();
+        let mut app = create_test_app();
+        // Newest first
+        app.recent_files.add(single);
+        app.recent_files.add(notes);
+
+        assert!(matches!(app.recent_file_picker(0), Some(Mode::MarkdownBlocks(_))));
+        // A single block opens directly
+        assert!(app.recent_file_picker(1).is_none());
+    }
+
+    #[test]
+    fn app_start_save_keeps_markdown_block() {
+        let mut app = create_test_app();
+        app.file_path = Some(PathBuf::from("notes.md"));
+        app.markdown_block = Some(crate::file_io::BlockRef::Id("arch".to_string()));
+
+        app.start_save();
+        match &app.mode {
+            Mode::PathInput(state) => assert_eq!(state.path, "notes.md#arch"),
+            other => panic!("Expected path input, got {:?}", other),
+        }
+    }
+
+    // ========== Export Option Tests ==========
+
+    #[test]
+    fn parse_export_input_reads_options() {
+        use crate::file_io::{ExportScope, Region};
+        let mut app = create_test_app();
+        let id = app
+            .doc
+            .add_shape(ShapeKind::Text {
+                pos: Position::new(1, 1),
+                content: "hi".to_string(),
+                color: ShapeColor::default(),
+            })
+            .unwrap();
+
+        let (path, options) = app.parse_export_input("my diagram.png").unwrap();
+        assert_eq!(path, std::path::PathBuf::from("my diagram.png"));
+        assert_eq!(options, crate::file_io::ExportOptions::default());
+
+        assert!(app.parse_export_input("out.svg --selection").is_err());
+        app.selected.insert(id);
+        let (_, options) = app
+            .parse_export_input("out.svg --selection --padding 2 --no-trim --region 0,0,9,4")
+            .unwrap();
+        assert_eq!(options.scope, ExportScope::Shapes(vec![id]));
+        assert_eq!(options.padding, 2);
+        assert!(!options.trim);
+        assert_eq!(options.region, Some(Region::new(Position::new(0, 0), Position::new(9, 4))));
+
+        let (_, options) = app
+            .parse_export_input("out.png --scale 4 --background none")
+            .unwrap();
+        assert_eq!(options.png_scale, 4);
+        assert_eq!(options.png_background, None);
+        let (_, options) = app.parse_export_input("out.png --background #fafafa").unwrap();
+        assert_eq!(options.png_background, Some([0xfa, 0xfa, 0xfa]));
+        assert!(app.parse_export_input("out.png --scale big").is_err());
+
+        let (_, options) = app.parse_export_input("out.txt --include-hidden").unwrap();
+        assert!(options.include_hidden);
+
+        let (_, options) = app.parse_export_input("out.txt --viewport").unwrap();
+        let region = options.region.unwrap();
+        assert_eq!(region.min, app.viewport.screen_to_canvas(0, 0));
+
+        assert!(app.parse_export_input("out.txt --padding").is_err());
+        assert!(app.parse_export_input("out.txt --bogus").is_err());
+        assert!(app.parse_export_input("out.txt --layer Missing").is_err());
+        assert!(app.parse_export_input(" --hidden").is_err());
+    }
+
+    // ========== Invite Tests ==========
+
+    #[test]
+    fn parse_invite_request_reads_options() {
+        use crate::sync::Role;
+        use std::time::Duration;
+
+        let request = parse_invite_request("").unwrap();
+        assert_eq!(request.label, None);
+        assert_eq!(request.role, Role::Editor);
+        assert_eq!(request.ttl, Duration::from_secs(INVITE_DEFAULT_HOURS * 3600));
+
+        let request = parse_invite_request("Ada Lovelace --viewer --hours 2").unwrap();
+        assert_eq!(request.label.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(request.role, Role::Viewer);
+        assert_eq!(request.ttl, Duration::from_secs(2 * 3600));
+
+        assert!(parse_invite_request("bob --hours").is_err());
+        assert!(parse_invite_request("bob --hours 0").is_err());
+        assert!(parse_invite_request("bob --forever").is_err());
+    }
+
+    #[test]
+    fn execute_invite_create_queues_request() {
+        let mut app = create_test_app();
+        app.execute_invite_create("carol --hours 1");
+        let request = app.pending_invite.take().unwrap();
+        assert_eq!(request.label.as_deref(), Some("carol"));
+
+        app.execute_invite_create("--bogus");
+        assert!(app.pending_invite.is_none());
+        assert!(matches!(app.status_message, Some((_, MessageSeverity::Error))));
+    }
+
+    #[test]
+    fn presence_carries_profile() {
+        let mut app = create_test_app();
+        app.init_presence(PeerId([1; 32]));
+        app.profile = crate::session::Profile {
+            display_name: Some("Ada".to_string()),
+            color: Some(2),
+        };
+        let presence = app.build_presence(Position::new(0, 0)).unwrap();
+        assert_eq!(presence.name.as_deref(), Some("Ada"));
+        assert_eq!(presence.color_index, 2);
+    }
+
+    #[test]
+    fn peer_name_prefers_presence_then_collaborators() {
+        let mut app = create_test_app();
+        app.init_presence(PeerId([1; 32]));
+        let peer = PeerId([2; 32]);
+        assert_eq!(app.peer_name(&peer), peer.short_name());
+
+        let mut meta = crate::session::SessionMeta::new("test");
+        meta.add_collaborator(crate::session::PeerId(peer.0), Some("Bob".to_string()));
+        app.current_session_meta = Some(meta);
+        assert_eq!(app.peer_name(&peer), "Bob");
+
+        let presence = PeerPresence::new(peer, Position::new(0, 0), CursorActivity::Idle, None, None)
+            .with_profile(Some("Robert"), None);
+        app.presence.as_mut().unwrap().update_peer(presence);
+        assert_eq!(app.peer_name(&peer), "Robert");
+    }
+
+    fn peer_looking_at(peer: PeerId, offset: (i32, i32)) -> PeerPresence {
+        let mut view = Viewport::new(80, 24);
+        view.pan(offset.0, offset.1);
+        PeerPresence::new(peer, Position::new(0, 0), CursorActivity::Idle, None, None)
+            .with_viewport(&view)
+    }
+
+    #[test]
+    fn follow_tracks_peer_until_we_pan() {
+        let mut app = create_test_app();
+        app.init_presence(PeerId([1; 32]));
+        let peer = PeerId([2; 32]);
+        app.presence.as_mut().unwrap().update_peer(peer_looking_at(peer, (30, 10)));
+
+        app.toggle_follow(peer);
+        assert_eq!((app.viewport.offset_x, app.viewport.offset_y), (30, 10));
+
+        // They move; we follow
+        app.presence.as_mut().unwrap().update_peer(peer_looking_at(peer, (50, 12)));
+        app.update_follow();
+        assert_eq!((app.viewport.offset_x, app.viewport.offset_y), (50, 12));
+        assert!(app.following.is_some());
+
+        // We pan ourselves; following stops and our viewport stays put
+        app.viewport.pan(1, 0);
+        app.presence.as_mut().unwrap().update_peer(peer_looking_at(peer, (70, 12)));
+        app.update_follow();
+        assert!(app.following.is_none());
+        assert_eq!(app.viewport.offset_x, 51);
+    }
+
+    #[test]
+    fn follow_stops_when_peer_leaves() {
+        let mut app = create_test_app();
+        app.init_presence(PeerId([1; 32]));
+        let peer = PeerId([2; 32]);
+        app.presence.as_mut().unwrap().update_peer(peer_looking_at(peer, (5, 5)));
+        app.toggle_follow(peer);
+        assert!(app.following.is_some());
+
+        app.presence.as_mut().unwrap().remove_peer(&peer);
+        app.update_follow();
+        assert!(app.following.is_none());
+        assert!(matches!(app.status_message, Some((_, MessageSeverity::Warning))));
+    }
+
+    #[test]
+    fn follow_next_peer_cycles_then_stops() {
+        let mut app = create_test_app();
+        app.init_presence(PeerId([1; 32]));
+        app.follow_next_peer();
+        assert!(app.following.is_none());
+
+        let (a, b) = (PeerId([2; 32]), PeerId([3; 32]));
+        for peer in [b, a] {
+            app.presence.as_mut().unwrap().update_peer(peer_looking_at(peer, (0, 0)));
+        }
+        app.follow_next_peer();
+        assert_eq!(app.following.map(|f| f.peer_id), Some(a));
+        app.follow_next_peer();
+        assert_eq!(app.following.map(|f| f.peer_id), Some(b));
+        app.follow_next_peer();
+        assert!(app.following.is_none());
+    }
+
+    #[test]
+    fn chat_needs_sync() {
+        let mut app = create_test_app();
+        app.execute_chat("hello");
+        assert!(app.chat.is_empty());
+        assert!(matches!(app.status_message, Some((_, MessageSeverity::Error))));
+    }
+
+    #[test]
+    fn chat_queues_message_and_pins_at_cursor() {
+        let mut app = create_test_app();
+        app.init_presence(PeerId([1; 32]));
+        app.last_cursor_pos = Position::new(7, 3);
+
+        app.execute_chat("  plain  ");
+        app.execute_chat("--pin look here");
+        app.execute_chat("--pinned is just text");
+        app.execute_chat("--pin");
+        let texts: Vec<_> = app.pending_chat.iter().map(|e| (e.text.as_str(), e.pin)).collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("plain", None),
+                ("look here", Some(Position::new(7, 3))),
+                ("--pinned is just text", None),
+            ]
+        );
+        assert_eq!(app.chat.len(), 3);
+        assert!(app.show_chat);
+        assert!(app.chat.iter().all(|e| app.is_own_chat(e)));
+    }
+
+    #[test]
+    fn received_chat_is_capped() {
+        let mut app = create_test_app();
+        let entry = crate::session::ChatEntry {
+            id: uuid::Uuid::new_v4(),
+            from: "02".repeat(32),
+            name: Some("Bob".to_string()),
+            color: 1,
+            text: "hi".to_string(),
+            timestamp_ms: 0,
+            pin: None,
+        };
+        app.receive_chat(entry.clone());
+        assert!(matches!(&app.status_message, Some((msg, _)) if msg == "Bob: hi"));
+        for _ in 0..crate::session::MAX_CHAT_HISTORY {
+            app.receive_chat(entry.clone());
+        }
+        assert_eq!(app.chat.len(), crate::session::MAX_CHAT_HISTORY);
+        assert!(!app.is_own_chat(&entry));
+    }
 }
@@ -87,6 +87,14 @@ pub fn dispatch_action(action: Action, ctx: &mut ModeContext<'_>) -> ModeTransit
             ctx.app.set_tool(Tool::Star);
             ModeTransition::Normal
         }
+        Action::CalloutTool => {
+            ctx.app.set_tool(Tool::Callout);
+            ModeTransition::Normal
+        }
+        Action::PolygonTool => {
+            ctx.app.set_tool(Tool::Polygon);
+            ModeTransition::Normal
+        }
 
         // Popup commands
         Action::ToolPopup => {
@@ -165,6 +173,18 @@ pub fn dispatch_action(action: Action, ctx: &mut ModeContext<'_>) -> ModeTransit
             ctx.app.copy_ticket_to_clipboard();
             ModeTransition::Normal
         }
+        Action::CopyViewerTicket => {
+            ctx.app.copy_viewer_ticket_to_clipboard();
+            ModeTransition::Normal
+        }
+        Action::ManageInvites => {
+            if ctx.app.sync_ticket.is_some() {
+                ModeTransition::to(Mode::invite_list())
+            } else {
+                ctx.app.set_status("No sync session active");
+                ModeTransition::Normal
+            }
+        }
         Action::ShowQrCode => {
             if let Some(ref ticket) = ctx.app.sync_ticket {
                 ModeTransition::to(Mode::QrCodeDisplay(QrCodeDisplayState {
@@ -207,6 +227,22 @@ pub fn dispatch_action(action: Action, ctx: &mut ModeContext<'_>) -> ModeTransit
             ctx.app.set_status(msg);
             ModeTransition::Normal
         }
+        Action::FollowPeer => {
+            ctx.app.follow_next_peer();
+            ModeTransition::Normal
+        }
+        Action::TogglePeerViewports => {
+            ctx.app.toggle_peer_viewports();
+            ModeTransition::Normal
+        }
+        Action::Chat => ModeTransition::to(Mode::PathInput(PathInputState {
+            path: String::new(),
+            kind: PathInputKind::Chat,
+        })),
+        Action::ToggleChat => {
+            ctx.app.toggle_chat();
+            ModeTransition::Normal
+        }
 
         // App operations
         Action::ShowHelp => {
@@ -232,7 +268,10 @@ pub fn dispatch_action(action: Action, ctx: &mut ModeContext<'_>) -> ModeTransit
             ModeTransition::Normal
         }
         Action::DeleteSelected => {
-            ctx.app.delete_selected();
+            // In vertex-edit mode Delete removes the last grabbed vertex first
+            if !ctx.app.delete_active_vertex() {
+                ctx.app.delete_selected();
+            }
             ModeTransition::Normal
         }
         Action::SelectAll => {
@@ -260,6 +299,30 @@ pub fn dispatch_action(action: Action, ctx: &mut ModeContext<'_>) -> ModeTransit
             ctx.app.cycle_line_style();
             ModeTransition::Normal
         }
+        Action::ToggleVertexEdit => {
+            ctx.app.toggle_vertex_edit();
+            ModeTransition::Normal
+        }
+        Action::RecognizeShapes => {
+            ctx.app.recognize_selected();
+            ModeTransition::Normal
+        }
+        Action::ToggleAutoRecognize => {
+            ctx.app.toggle_auto_recognize();
+            ModeTransition::Normal
+        }
+        Action::ToggleStrokeSmoothing => {
+            ctx.app.toggle_stroke_smoothing();
+            ModeTransition::Normal
+        }
+        Action::ConvertToPolyline => {
+            ctx.app.convert_selected_to_polyline();
+            ModeTransition::Normal
+        }
+        Action::ToggleBraille => {
+            ctx.app.toggle_braille();
+            ModeTransition::Normal
+        }
         Action::StartLabelInput => {
             let is_select = ctx.app.current_tool == Tool::Select;
             let has_selection = !ctx.app.selected.is_empty();
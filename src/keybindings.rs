@@ -39,6 +39,8 @@ pub fn parse_action_name(s: &str) -> Option<Action> {
         "cylinder_tool" => Some(Action::CylinderTool),
         "cloud_tool" => Some(Action::CloudTool),
         "star_tool" => Some(Action::StarTool),
+        "callout_tool" => Some(Action::CalloutTool),
+        "polygon_tool" => Some(Action::PolygonTool),
         
         // Popup commands
         "tool_popup" => Some(Action::ToolPopup),
@@ -71,6 +73,12 @@ pub fn parse_action_name(s: &str) -> Option<Action> {
         "group_selection" => Some(Action::GroupSelection),
         "ungroup_selection" => Some(Action::UngroupSelection),
         "cycle_line_style" => Some(Action::CycleLineStyle),
+        "toggle_vertex_edit" => Some(Action::ToggleVertexEdit),
+        "recognize_shapes" => Some(Action::RecognizeShapes),
+        "toggle_auto_recognize" => Some(Action::ToggleAutoRecognize),
+        "toggle_stroke_smoothing" => Some(Action::ToggleStrokeSmoothing),
+        "convert_to_polyline" => Some(Action::ConvertToPolyline),
+        "toggle_braille" => Some(Action::ToggleBraille),
         "start_label_input" => Some(Action::StartLabelInput),
         
         // Z-order operations
@@ -160,6 +168,7 @@ pub fn build_keymap() -> Keymap<Action, InputMode> {
     normal.insert(key(KeyCode::Esc), Action::ClearSelection);
     normal.insert(key(KeyCode::Tab), Action::SessionBrowser);
     normal.insert(kc('v'), Action::CycleLineStyle);
+    normal.insert(kc('V'), Action::ToggleVertexEdit);
     normal.insert(kc('g'), Action::ToggleGrid);
     normal.insert(key(KeyCode::Delete), Action::DeleteSelected);
     normal.insert(key(KeyCode::Backspace), Action::DeleteSelected);
@@ -82,7 +82,7 @@ impl MenuContributor<Action> for IrohsciiBuiltins {
                 priority: PRIORITY_BUILTIN,
                 source: "builtin".into(),
             },
-            // Sync/collaboration operations (matches 'T', 'Q', 'D', 'K', 'J' in leader.rs)
+            // Sync/collaboration operations (matches 'T', 'V', 'I', 'Q', 'D', 'K', 'J' in leader.rs)
             MenuContribution {
                 key: 'T',
                 label: "ticket".into(),
@@ -91,6 +91,22 @@ impl MenuContributor<Action> for IrohsciiBuiltins {
                 priority: PRIORITY_BUILTIN,
                 source: "builtin".into(),
             },
+            MenuContribution {
+                key: 'V',
+                label: "viewer ticket".into(),
+                action: LeaderAction::Action(Action::CopyViewerTicket),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            MenuContribution {
+                key: 'I',
+                label: "invites".into(),
+                action: LeaderAction::Action(Action::ManageInvites),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
             MenuContribution {
                 key: 'Q',
                 label: "qr".into(),
@@ -123,7 +139,7 @@ impl MenuContributor<Action> for IrohsciiBuiltins {
                 priority: PRIORITY_BUILTIN,
                 source: "builtin".into(),
             },
-            // View operations (matches 'g', 'l', 'p' in leader.rs)
+            // View operations (matches 'g', 'l', 'p', 'f', 'v' in leader.rs)
             MenuContribution {
                 key: 'g',
                 label: "grid".into(),
@@ -148,6 +164,79 @@ impl MenuContributor<Action> for IrohsciiBuiltins {
                 priority: PRIORITY_BUILTIN,
                 source: "builtin".into(),
             },
+            MenuContribution {
+                key: 'f',
+                label: "follow".into(),
+                action: LeaderAction::Action(Action::FollowPeer),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            MenuContribution {
+                key: 'v',
+                label: "views".into(),
+                action: LeaderAction::Action(Action::TogglePeerViewports),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            MenuContribution {
+                key: 'm',
+                label: "message".into(),
+                action: LeaderAction::Action(Action::Chat),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            MenuContribution {
+                key: 'M',
+                label: "chat".into(),
+                action: LeaderAction::Action(Action::ToggleChat),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            MenuContribution {
+                key: 'B',
+                label: "braille".into(),
+                action: LeaderAction::Action(Action::ToggleBraille),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            // Drawing operations
+            MenuContribution {
+                key: 'r',
+                label: "recognize".into(),
+                action: LeaderAction::Action(Action::RecognizeShapes),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            MenuContribution {
+                key: 'R',
+                label: "auto-recognize".into(),
+                action: LeaderAction::Action(Action::ToggleAutoRecognize),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            MenuContribution {
+                key: 'S',
+                label: "smooth strokes".into(),
+                action: LeaderAction::Action(Action::ToggleStrokeSmoothing),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
+            MenuContribution {
+                key: 'P',
+                label: "to polyline".into(),
+                action: LeaderAction::Action(Action::ConvertToPolyline),
+                placement: MenuPlacement::Root,
+                priority: PRIORITY_BUILTIN,
+                source: "builtin".into(),
+            },
             // App operations (matches '?', 'h', 'q' in leader.rs)
             MenuContribution {
                 key: '?',
@@ -7,34 +7,44 @@ pub use irohscii_core as core;
 pub use irohscii_core::{
     CachedShape, Document, DocumentId, Group, GroupId, Layer, LayerId, LineStyle, Position,
     ResizeHandle, ResizeHandleInfo, ShapeColor, ShapeId, ShapeKind, ShapeView, SnapPoint, Viewport,
-    flip_horizontal, flip_vertical, resize_shape, rotate_90_ccw, rotate_90_cw,
+    delete_vertex, find_vertex_edge, flip_horizontal, flip_vertical, freehand_to_polyline,
+    insert_vertex, move_vertex, recognize_freehand, resize_shape, rotate_90_ccw, rotate_90_cw,
+    simplify_freehand, vertex_handles,
 };
 
 // Re-export geometry functions
 pub use irohscii_geometry as geometry;
 pub use irohscii_geometry::{
-    arrow_points_styled, cloud_points, cylinder_points, diamond_points, double_rect_points,
-    ellipse_points, hexagon_points, line_points, line_points_auto_routed, line_points_styled,
-    parallelogram_points, rect_points, rounded_rect_points, star_points, trapezoid_points,
-    triangle_points,
+    arrow_points_styled, braille_points, callout_points, cloud_points, cloud_points_braille,
+    cylinder_points, diamond_points, double_rect_points, ellipse_points, ellipse_points_braille,
+    hexagon_points, line_points, line_points_auto_routed, line_points_styled, parallelogram_points,
+    polygon_points, rect_points, rounded_rect_points, simplify_path, smooth_path, star_points,
+    stroke_points, stroke_points_braille, trapezoid_points, triangle_points,
 };
 
 // Re-export sync types
 pub use irohscii_sync as sync;
 pub use irohscii_sync::{
-    CursorActivity, PEER_COLORS, PeerId, PeerPresence, PresenceManager, PresenceMessage,
-    SyncCommand, SyncConfig, SyncEvent, SyncHandle, SyncMode, ToolKind, decode_ticket,
-    encode_ticket, peer_color, start_sync_thread,
+    ChatMessage, ConnectionType, CursorActivity, DEFAULT_DOC_ID, InviteId, InviteInfo,
+    PEER_COLORS, PeerId, PeerPresence, PresenceManager, PresenceMessage, Role, SyncCommand,
+    SyncConfig, SyncEvent, SyncHandle, SyncMode, ToolKind, decode_doc_ticket, decode_ticket,
+    encode_doc_ticket, encode_ticket, peer_color, start_sync_thread,
 };
 
 // Re-export export functions
 pub use irohscii_export as export;
-pub use irohscii_export::{export_svg, load_ascii, save_ascii, save_svg};
+pub use irohscii_export::{
+    ExportOptions, export_dot, export_drawio, export_excalidraw, export_mermaid, export_svg,
+    import_ascii, import_dot, import_excalidraw, import_mermaid, load_ascii, load_ascii_into,
+    parse_ascii, render_png, render_shapes_to_ansi, render_shapes_to_html, save_ascii, save_png,
+    save_svg, save_with_options,
+};
 
 // Re-export session management
 pub use irohscii_session as session;
 pub use irohscii_session::{
-    Collaborator, SessionId, SessionManager, SessionMeta, SessionRegistry, TicketInfo, UndoManager,
+    ChatEntry, Collaborator, Profile, SessionId, SessionManager, SessionMeta, SessionRegistry,
+    TicketInfo, UndoManager,
 };
 
 // Legacy module aliases for backwards compatibility with internal code
@@ -53,20 +63,28 @@ pub mod layers {
 pub mod shapes {
     pub use irohscii_core::{
         CachedShape, ResizeHandle, ResizeHandleInfo, ShapeColor, ShapeKind, ShapeView, SnapPoint,
-        find_corresponding_snap, flip_horizontal, flip_vertical, resize_shape, rotate_90_ccw,
-        rotate_90_cw,
+        delete_vertex, find_corresponding_snap, find_vertex_edge, flip_horizontal, flip_vertical,
+        freehand_to_polyline, insert_vertex, move_vertex, recognize_freehand, resize_shape,
+        rotate_90_ccw, rotate_90_cw, simplify_freehand, vertex_handles,
     };
 }
 
 pub mod presence {
     pub use irohscii_sync::{
-        CursorActivity, PEER_COLORS, PeerId, PeerPresence, PresenceManager, PresenceMessage,
-        ToolKind, peer_color,
+        CursorActivity, PEER_COLOR_NAMES, PEER_COLORS, PeerId, PeerPresence, PeerViewport,
+        PresenceManager, PresenceMessage, ToolKind, peer_color, peer_color_index,
     };
 }
 
 pub mod file_io {
-    pub use irohscii_export::{load_ascii, save_ascii};
+    pub use irohscii_export::{
+        BlockRef, DiagramBlock, ExportOptions, ExportScope, PngOptions, Region, find_diagram_blocks,
+        is_ansi_path, is_dot_path, is_drawio_path, is_excalidraw_path, is_html_path,
+        is_markdown_path, is_mermaid_path, is_png_path, is_svg_path, load_ascii, load_ascii_into,
+        load_dot, load_excalidraw, load_markdown_block, load_mermaid, regenerate_blocks,
+        save_ansi, save_ascii, save_dot, save_drawio, save_excalidraw, save_html,
+        save_markdown_block, save_mermaid, save_png, save_with_options, split_block_selector,
+    };
 }
 
 pub mod svg_export {
@@ -32,25 +32,32 @@ mod shapes {
 }
 mod presence {
     pub use irohscii_sync::{
-        CursorActivity, PeerId, PeerPresence, PresenceManager, ToolKind, peer_color,
+        CursorActivity, PEER_COLOR_NAMES, PEER_COLORS, PeerId, PeerPresence, PeerViewport, PresenceManager,
+        ToolKind, peer_color, peer_color_index,
     };
 }
 mod file_io {
-    pub use irohscii_export::{load_ascii, save_ascii};
-}
-mod svg_export {
-    pub use irohscii_export::save_svg;
+    pub use irohscii_export::{
+        BlockRef, DiagramBlock, ExportFormat, ExportOptions, ExportScope, Region, export_with_options,
+        find_diagram_blocks, is_ansi_path, is_dot_path, is_drawio_path, is_excalidraw_path,
+        is_html_path, is_markdown_path, is_mermaid_path, is_png_path, load_ascii, load_ascii_into,
+        load_dot, load_excalidraw, load_markdown_block, load_mermaid, parse_png_background,
+        regenerate_blocks, render_shapes_to_text, save_markdown_block, save_with_options,
+        split_block_selector,
+    };
 }
 mod session {
-    pub use irohscii_session::{SessionId, SessionManager, SessionMeta};
+    pub use irohscii_session::{
+        ChatEntry, MAX_CHAT_HISTORY, PeerId, Profile, SessionId, SessionManager, SessionMeta,
+    };
 }
 
 use std::io::stdout;
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
 use crossterm::{
     event::{
         self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind, KeyModifiers,
@@ -61,7 +68,7 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 
-use app::{App, ConfirmDialogState, Mode, PendingAction, Tool};
+use app::{App, ConfirmDialogState, Mode, PeerLinkState, PendingAction, Tool};
 
 /// ASCII art drawing tool with real-time collaboration
 #[derive(Parser, Debug)]
@@ -80,6 +87,15 @@ struct Args {
     #[arg(long)]
     offline: bool,
 
+    /// Name shown to collaborators (remembered for later runs)
+    #[arg(long, value_name = "NAME")]
+    name: Option<String>,
+
+    /// Preferred cursor color: red, green, blue, magenta, light-red,
+    /// light-green, light-blue or light-magenta (remembered for later runs)
+    #[arg(long, value_name = "COLOR")]
+    color: Option<String>,
+
     /// Open a specific session by name or ID
     #[arg(long, short = 's', value_name = "SESSION")]
     session: Option<String>,
@@ -92,9 +108,120 @@ struct Args {
     #[arg(long)]
     list_sessions: bool,
 
-    /// File to open (ASCII import)
+    /// Regenerate the marked diagram blocks (```irohscii id=NAME) of a
+    /// Markdown file from the sessions of the same name, then exit
+    #[arg(long, value_name = "MARKDOWN")]
+    update_markdown: Option<PathBuf>,
+
+    /// File to open (ASCII or Mermaid import, or NOTES.md#ID for a Markdown diagram block)
     #[arg(value_name = "FILE")]
     file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Headless commands that run without the TUI
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Render a session or file to a diagram format
+    Render {
+        /// Session name or ID, or a file to import (ASCII, Mermaid, DOT,
+        /// Excalidraw, or NOTES.md#ID)
+        #[arg(value_name = "SESSION|FILE")]
+        source: String,
+
+        /// Output format: ascii, svg, png, ansi, html, mermaid, dot,
+        /// excalidraw or drawio (default: from the output's extension, else ascii)
+        #[arg(long, short = 'f')]
+        format: Option<file_io::ExportFormat>,
+
+        /// Output file; text formats print to stdout without it
+        #[arg(long, short = 'o', value_name = "OUTPUT")]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        export: ExportArgs,
+    },
+    /// Convert a file to another format, e.g. ASCII art to SVG
+    Convert {
+        /// File to read (ASCII, Mermaid, DOT, Excalidraw, or NOTES.md#ID)
+        input: PathBuf,
+
+        /// File to write, format from its extension
+        output: PathBuf,
+
+        #[command(flatten)]
+        export: ExportArgs,
+    },
+    /// Import a file into a session, creating the session if needed
+    Import {
+        /// File to read (ASCII, Mermaid, DOT, Excalidraw, or NOTES.md#ID)
+        file: PathBuf,
+
+        /// Session name or ID (default: the file name)
+        #[arg(long, short = 's', value_name = "SESSION")]
+        session: Option<String>,
+    },
+}
+
+/// Export options for the headless commands
+#[derive(clap::Args, Debug, Default)]
+struct ExportArgs {
+    /// Only shapes on this layer, even if hidden (repeatable)
+    #[arg(long, value_name = "NAME")]
+    layer: Vec<String>,
+
+    /// Only this canvas rectangle
+    #[arg(long, value_name = "X1,Y1,X2,Y2")]
+    region: Option<file_io::Region>,
+
+    /// Include shapes on hidden layers
+    #[arg(long)]
+    include_hidden: bool,
+
+    /// Blank cells around the exported area
+    #[arg(long, value_name = "CELLS", default_value_t = 0)]
+    padding: u32,
+
+    /// Keep the whole region (or reach back to the canvas origin) instead
+    /// of cropping to the drawing
+    #[arg(long)]
+    no_trim: bool,
+
+    /// PNG pixels per font pixel (default: 2)
+    #[arg(long, value_name = "N")]
+    scale: Option<u32>,
+
+    /// PNG background: a hex color such as 1e1e1e, or none for transparent
+    /// (default: black)
+    #[arg(long, value_name = "COLOR")]
+    background: Option<String>,
+}
+
+impl ExportArgs {
+    /// Export options for a document with the given layers
+    fn options(&self, layers: &[layers::Layer]) -> Result<file_io::ExportOptions> {
+        let mut options = file_io::ExportOptions {
+            region: self.region,
+            include_hidden: self.include_hidden,
+            padding: self.padding,
+            trim: !self.no_trim,
+            ..file_io::ExportOptions::default()
+        };
+        if let Some(scale) = self.scale {
+            options.png_scale = scale;
+        }
+        if let Some(background) = &self.background {
+            options.png_background = file_io::parse_png_background(background)?;
+        }
+        if !self.layer.is_empty() {
+            // Naming a layer exports it even when hidden
+            options.scope = file_io::ExportScope::layers_named(layers, &self.layer)?;
+            options.include_hidden = true;
+        }
+        Ok(options)
+    }
 }
 
 fn main() -> Result<()> {
@@ -106,9 +233,20 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    let sync_config = build_sync_config(&args);
+    if let Some(path) = &args.update_markdown {
+        handle_update_markdown_command(path, &session_manager)?;
+        return Ok(());
+    }
+
+    if let Some(command) = &args.command {
+        return handle_command(command, &mut session_manager);
+    }
+
+    let profile = update_profile(&args, &session_manager)?;
+    let sync_config = build_sync_config(&args, &session_manager);
     let mut terminal = setup_terminal()?;
     let mut app = initialize_app(&mut terminal)?;
+    app.profile = profile;
     
     determine_and_load_session(&args, &mut session_manager, &mut app)?;
     load_file_if_specified(&args, &mut app);
@@ -154,14 +292,173 @@ fn handle_list_sessions_command(session_manager: &session::SessionManager) -> Re
     Ok(())
 }
 
+/// Handle --update-markdown command
+fn handle_update_markdown_command(
+    path: &std::path::Path,
+    session_manager: &session::SessionManager,
+) -> Result<()> {
+    let markdown = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let sessions = session_manager.list_sessions()?;
+
+    let (updated, count) = file_io::regenerate_blocks(&markdown, |block| {
+        let Some(id) = block.id.as_deref() else {
+            return Ok(None);
+        };
+        let Some(meta) = sessions.iter().find(|m| m.name == id || m.id.0 == id) else {
+            eprintln!("No session named '{}' for block at line {}, skipped", id, block.line);
+            return Ok(None);
+        };
+        let doc = session_manager.load_document(&meta.id)?;
+        let mut view = shapes::ShapeView::new();
+        view.rebuild(&doc)?;
+        Ok(Some(file_io::render_shapes_to_text(&view)))
+    })?;
+
+    if updated != markdown {
+        std::fs::write(path, updated)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    println!("Updated {} diagram block(s) in {}", count, path.display());
+    Ok(())
+}
+
+/// Run a headless subcommand
+fn handle_command(command: &Command, session_manager: &mut session::SessionManager) -> Result<()> {
+    match command {
+        Command::Render {
+            source,
+            format,
+            output,
+            export,
+        } => {
+            let doc = load_source(source, session_manager)?;
+            let format = format.unwrap_or_else(|| {
+                output.as_deref().map_or(
+                    file_io::ExportFormat::Text,
+                    file_io::ExportFormat::from_path,
+                )
+            });
+            let content = export_document(&doc, format, export)?;
+            match output {
+                Some(path) => std::fs::write(path, content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None if format.is_binary() => {
+                    anyhow::bail!("Binary formats need an output file (-o)")
+                }
+                None => {
+                    use std::io::Write;
+                    let mut out = stdout().lock();
+                    out.write_all(&content)?;
+                    if !content.ends_with(b"\n") {
+                        out.write_all(b"\n")?;
+                    }
+                }
+            }
+        }
+        Command::Convert {
+            input,
+            output,
+            export,
+        } => {
+            let mut doc = document::Document::new();
+            import_file(&mut doc, input)?;
+            let content = export_document(&doc, file_io::ExportFormat::from_path(output), export)?;
+            std::fs::write(output, content)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            println!("Converted {} to {}", input.display(), output.display());
+        }
+        Command::Import { file, session } => {
+            let name = match session {
+                Some(name) => name.clone(),
+                None => file
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .context("Give a session name with --session")?,
+            };
+            // Exact match only, so a similar name doesn't get overwritten
+            let existing = session_manager
+                .list_sessions()?
+                .into_iter()
+                .find(|m| m.id.0 == name || m.name.eq_ignore_ascii_case(&name));
+            let meta = match existing {
+                Some(meta) => meta,
+                None => session_manager.create_session(&name)?,
+            };
+            let mut doc = session_manager.load_document(&meta.id)?;
+            let before = doc.read_all_shapes()?.len();
+            import_file(&mut doc, file)?;
+            let added = doc.read_all_shapes()?.len() - before;
+            session_manager.save_session(&meta.id, &mut doc, &meta)?;
+            println!("Imported {} shape(s) into session '{}'", added, meta.name);
+        }
+    }
+    Ok(())
+}
+
+/// Load a document from a file if one exists at `source`, else from the
+/// session it names
+fn load_source(
+    source: &str,
+    session_manager: &session::SessionManager,
+) -> Result<document::Document> {
+    let (path, _) = file_io::split_block_selector(source);
+    if path.exists() {
+        let mut doc = document::Document::new();
+        import_file(&mut doc, std::path::Path::new(source))?;
+        return Ok(doc);
+    }
+    let meta = session_manager
+        .find_session(source)?
+        .with_context(|| format!("No file or session named '{}'", source))?;
+    session_manager.load_document(&meta.id)
+}
+
+/// Export a document's shapes with the command's export options
+fn export_document(
+    doc: &document::Document,
+    format: file_io::ExportFormat,
+    export: &ExportArgs,
+) -> Result<Vec<u8>> {
+    let mut view = shapes::ShapeView::new();
+    view.rebuild(doc)?;
+    let layers = doc.read_all_layers()?;
+    let options = export.options(&layers)?;
+    file_io::export_with_options(&view, &layers, format, &options)
+}
+
+/// Load the user's profile, saving any name or color given on the command line
+fn update_profile(args: &Args, session_manager: &session::SessionManager) -> Result<session::Profile> {
+    let mut profile = session_manager.load_profile();
+    if args.name.is_none() && args.color.is_none() {
+        return Ok(profile);
+    }
+    if let Some(name) = &args.name {
+        let name = name.trim();
+        profile.display_name = (!name.is_empty()).then(|| name.to_string());
+    }
+    if let Some(color) = &args.color {
+        let index = presence::peer_color_index(color).with_context(|| {
+            format!("Unknown color '{}' (try one of: {})", color, presence::PEER_COLOR_NAMES.join(", "))
+        })?;
+        profile.color = Some(index);
+    }
+    session_manager.save_profile(&profile)?;
+    Ok(profile)
+}
+
 /// Build sync configuration from CLI arguments
-fn build_sync_config(args: &Args) -> SyncConfig {
+fn build_sync_config(args: &Args, session_manager: &session::SessionManager) -> SyncConfig {
     if args.offline {
         SyncConfig {
             mode: SyncMode::Disabled,
             cluster_ticket: None,
             cluster_capability: None,
             disable_discovery: false,
+            doc_id: sync::DEFAULT_DOC_ID.to_string(),
+            store_dir: None,
+            identity_path: None,
+            roles_path: None,
         }
     } else {
         SyncConfig {
@@ -171,6 +468,13 @@ fn build_sync_config(args: &Args) -> SyncConfig {
             cluster_ticket: args.cluster.clone(),
             cluster_capability: None,
             disable_discovery: false,
+            doc_id: sync::DEFAULT_DOC_ID.to_string(),
+            // Next to the sessions, so synced documents survive restarts
+            store_dir: Some(session_manager.sessions_dir().with_file_name("sync")),
+            // Same peer ID every run, so collaborators keep recognising us
+            identity_path: Some(session_manager.identity_path()),
+            // Beside it, so tickets with keys outlive a restart too
+            roles_path: Some(session_manager.identity_path().with_file_name("roles.key")),
         }
     }
 }
@@ -205,6 +509,7 @@ fn determine_and_load_session(
     if let Some(session_id) = session_to_load {
         load_session(session_id, session_manager, app);
     }
+    load_chat_history(app, session_manager);
     
     Ok(())
 }
@@ -218,6 +523,13 @@ fn determine_session_to_load(
     if let Some(name) = &args.new_session {
         return create_new_session(name, session_manager, app);
     }
+
+    if let Some(ticket) = &args.join {
+        match session_for_ticket(ticket, session_manager) {
+            Ok(session_id) => return Ok(Some(session_id)),
+            Err(e) => app.set_error(format!("Failed to open joined session: {}", e)),
+        }
+    }
     
     if let Some(query) = &args.session {
         return find_existing_session(query, session_manager, app);
@@ -244,6 +556,20 @@ fn create_new_session(
     }
 }
 
+/// Local session holding the document a join ticket names, created on
+/// first join. Tickets without a document map to [`sync::DEFAULT_DOC_ID`].
+fn session_for_ticket(
+    ticket: &str,
+    session_manager: &mut session::SessionManager,
+) -> Result<session::SessionId> {
+    let (_, doc_id) = sync::decode_doc_ticket(ticket)?;
+    let session_id = session::SessionId(doc_id);
+    if !session_manager.session_exists(&session_id) {
+        session_manager.create_session_with_id(&session_id, &session_id.0)?;
+    }
+    Ok(session_id)
+}
+
 /// Find an existing session by query
 fn find_existing_session(
     query: &str,
@@ -307,11 +633,13 @@ fn load_session(
 /// Load ASCII file if specified in args
 fn load_file_if_specified(args: &Args, app: &mut App) {
     if let Some(file_path) = &args.file {
-        match file_io::load_ascii(file_path) {
-            Ok(shapes) => {
-                for kind in shapes {
-                    let _ = app.doc.add_shape(kind);
-                }
+        let (markdown_path, selector) = file_io::split_block_selector(&file_path.to_string_lossy());
+        if file_io::is_markdown_path(&markdown_path) {
+            app.open_markdown_block(markdown_path, selector.unwrap_or(file_io::BlockRef::Index(0)));
+            return;
+        }
+        match import_file(&mut app.doc, file_path) {
+            Ok(_) => {
                 if let Err(e) = app.shape_view.rebuild(&app.doc) {
                     app.set_status(format!("Error rebuilding view: {}", e));
                 }
@@ -324,9 +652,28 @@ fn load_file_if_specified(args: &Args, app: &mut App) {
     }
 }
 
+/// Import a file into a document, picking the format from its extension
+/// (`NOTES.md#ID` names a Markdown diagram block)
+fn import_file(doc: &mut document::Document, file_path: &std::path::Path) -> Result<()> {
+    let (markdown_path, selector) = file_io::split_block_selector(&file_path.to_string_lossy());
+    if file_io::is_markdown_path(&markdown_path) {
+        let block = selector.unwrap_or(file_io::BlockRef::Index(0));
+        file_io::load_markdown_block(doc, &markdown_path, &block)?;
+    } else if file_io::is_mermaid_path(file_path) {
+        file_io::load_mermaid(doc, file_path)?;
+    } else if file_io::is_dot_path(file_path) {
+        file_io::load_dot(doc, file_path)?;
+    } else if file_io::is_excalidraw_path(file_path) {
+        file_io::load_excalidraw(doc, file_path)?;
+    } else {
+        file_io::load_ascii_into(doc, file_path)?;
+    }
+    Ok(())
+}
+
 /// Start sync thread if enabled
 fn start_sync_if_enabled(
-    sync_config: SyncConfig,
+    mut sync_config: SyncConfig,
     app: &mut App,
     session_manager: &mut session::SessionManager,
 ) -> Result<Option<SyncHandle>> {
@@ -334,13 +681,21 @@ fn start_sync_if_enabled(
         return Ok(None);
     }
 
+    let doc_id = session_doc_id(app);
+    sync_config.doc_id = doc_id.clone();
+
     match sync::start_sync_thread(sync_config) {
         Ok(handle) => {
-            if let Some(endpoint_id) = &handle.endpoint_id {
-                app.sync_ticket = Some(endpoint_id.clone());
-                save_sync_ticket_to_session(endpoint_id, app, session_manager);
-                app.set_status(format!("Session: {} (T to copy)", endpoint_id));
+            let _ = handle.send_command(sync::SyncCommand::OpenDocument {
+                doc_id: doc_id.clone(),
+                doc: Box::new(app.sync_snapshot()),
+            });
+            if let Some(ticket) = handle.doc_ticket(&doc_id).or_else(|| handle.endpoint_id.clone()) {
+                app.sync_ticket = Some(ticket.clone());
+                save_sync_ticket_to_session(&ticket, app, session_manager);
+                app.set_status(format!("Session: {} (T to copy)", ticket));
             }
+            apply_sync_role(&handle, &doc_id, app);
             Ok(Some(handle))
         }
         Err(e) => {
@@ -350,6 +705,70 @@ fn start_sync_if_enabled(
     }
 }
 
+/// Document the current session syncs as: its session ID, or the shared
+/// default document when no session is open
+fn session_doc_id(app: &App) -> String {
+    app.current_session
+        .as_ref()
+        .map(|id| id.0.clone())
+        .unwrap_or_else(|| sync::DEFAULT_DOC_ID.to_string())
+}
+
+/// Move syncing from the previous session's document to the current one
+fn switch_sync_document(
+    previous_doc_id: &str,
+    app: &mut App,
+    sync_handle: Option<&SyncHandle>,
+    session_manager: &mut session::SessionManager,
+) {
+    let Some(handle) = sync_handle else {
+        return;
+    };
+    let doc_id = session_doc_id(app);
+    if doc_id == previous_doc_id {
+        return;
+    }
+
+    let _ = handle.send_command(sync::SyncCommand::CloseDocument {
+        doc_id: previous_doc_id.to_string(),
+    });
+    let _ = handle.send_command(sync::SyncCommand::OpenDocument {
+        doc_id: doc_id.clone(),
+        doc: Box::new(app.sync_snapshot()),
+    });
+    if let Some(ticket) = handle.doc_ticket(&doc_id) {
+        save_sync_ticket_to_session(&ticket, app, session_manager);
+        app.sync_ticket = Some(ticket);
+    }
+    apply_sync_role(handle, &doc_id, app);
+}
+
+/// Enter or leave read-only mode for our role in a document
+fn apply_sync_role(handle: &SyncHandle, doc_id: &str, app: &mut App) {
+    app.set_read_only(handle.role(doc_id) == sync::Role::Viewer);
+    if app.read_only() {
+        app.set_status("Joined as viewer: the document is read-only");
+    }
+}
+
+/// Pick up the editor ticket that replaces the session's ticket once a
+/// viewer ticket or invite restricted the document
+fn refresh_sync_ticket(
+    handle: &SyncHandle,
+    doc_id: &str,
+    app: &mut App,
+    session_manager: &mut session::SessionManager,
+) {
+    let Some(ticket) = handle.doc_ticket(doc_id) else {
+        return;
+    };
+    if app.sync_ticket.as_deref() != Some(ticket.as_str()) {
+        save_sync_ticket_to_session(&ticket, app, session_manager);
+        app.sync_ticket = Some(ticket);
+        app.set_warning("Copied. The session is now restricted: older tickets stop working (T copies the new one)");
+    }
+}
+
 /// Save sync ticket to session metadata
 fn save_sync_ticket_to_session(
     endpoint_id: &str,
@@ -473,6 +892,8 @@ fn run_app(
         terminal.draw(|frame| ui::render(frame, &mut *app))?;
         prune_stale_peers_if_due(&mut last_stale_prune, app);
         process_sync_events(sync_handle, app);
+        handle_chat(app, sync_handle, session_manager);
+        app.update_follow();
 
         if event::poll(Duration::from_millis(16))? {
             match event::read()? {
@@ -500,15 +921,18 @@ fn run_app(
 fn flush_pending_sync_if_due(
     sync_pending: &mut bool,
     last_sync_sent: &mut Instant,
-    app: &App,
+    app: &mut App,
     sync_handle: Option<&SyncHandle>,
 ) {
     if *sync_pending && last_sync_sent.elapsed() >= SYNC_DEBOUNCE {
         if let Some(handle) = sync_handle {
-            let doc = app.clone_automerge();
-            let _ = handle.send_command(sync::SyncCommand::SyncDoc {
-                doc: Box::new(doc),
-            });
+            let changes = app.take_sync_changes();
+            if !changes.is_empty() {
+                let _ = handle.send_command(sync::SyncCommand::SyncDoc {
+                    doc_id: session_doc_id(app),
+                    changes,
+                });
+            }
         }
         *sync_pending = false;
         *last_sync_sent = Instant::now();
@@ -566,19 +990,65 @@ fn process_sync_events(sync_handle: Option<&SyncHandle>, app: &mut App) {
                     app.init_presence(local_peer_id);
                     app.set_status(format!("Session ready: {}", endpoint_id));
                 }
-                sync::SyncEvent::RemoteChanges { mut doc } => {
-                    app.merge_remote(&mut doc);
+                sync::SyncEvent::RemoteChanges { doc_id, changes } => {
+                    // Documents of other sessions are only kept in the store
+                    if doc_id == session_doc_id(app) {
+                        app.apply_remote_changes(&changes);
+                    }
                 }
                 sync::SyncEvent::PresenceUpdate(presence) => {
+                    if let Some(meta) = &mut app.current_session_meta {
+                        meta.add_collaborator(session::PeerId(presence.peer_id.0), presence.name.clone());
+                    }
                     if let Some(ref mut mgr) = app.presence {
                         mgr.update_peer(presence);
                     }
                 }
+                sync::SyncEvent::Chat(msg) => {
+                    let entry = session::ChatEntry {
+                        id: msg.id,
+                        from: msg.from.0.iter().map(|b| format!("{:02x}", b)).collect(),
+                        name: msg.name,
+                        color: msg.color_index,
+                        text: msg.text,
+                        timestamp_ms: msg.timestamp_ms,
+                        pin: msg.pin,
+                    };
+                    // Messages for other sessions only go to their history
+                    if msg.doc_id == session_doc_id(app) {
+                        app.receive_chat(entry.clone());
+                    }
+                    app.chat_to_save.push((msg.doc_id, entry));
+                }
                 sync::SyncEvent::PresenceRemoved { peer_id } => {
                     if let Some(ref mut mgr) = app.presence {
                         mgr.remove_peer(&peer_id);
                     }
                 }
+                sync::SyncEvent::PeerConnected { peer_id, kind } => {
+                    let was_reconnecting = matches!(
+                        app.peer_links.insert(peer_id, PeerLinkState::Connected(kind)),
+                        Some(PeerLinkState::Reconnecting { .. })
+                    );
+                    if was_reconnecting {
+                        app.set_status(format!("Reconnected to {}", app.peer_name(&peer_id)));
+                    }
+                }
+                sync::SyncEvent::PeerDisconnected { peer_id } => {
+                    // Lost links come back as Reconnecting; a give-up clears it
+                    if let Some(PeerLinkState::Reconnecting { .. }) = app.peer_links.remove(&peer_id) {
+                        app.set_warning(format!("Lost connection to {}", app.peer_name(&peer_id)));
+                    }
+                }
+                sync::SyncEvent::Reconnecting { peer_id, attempt } => {
+                    app.peer_links
+                        .insert(peer_id, PeerLinkState::Reconnecting { attempt });
+                }
+                sync::SyncEvent::ConnectionType { peer_id, kind } => {
+                    if let Some(state) = app.peer_links.get_mut(&peer_id) {
+                        *state = PeerLinkState::Connected(kind);
+                    }
+                }
                 sync::SyncEvent::Error(msg) => {
                     app.set_status(format!("Sync error: {}", msg));
                 }
@@ -600,9 +1070,10 @@ fn handle_key_event(
         KeyEventKind::Press => {
             app.clear_status();
             process_key_press(key, app, session_manager);
+            app.warn_refused_edits();
             mark_sync_and_save_pending_if_dirty(app, sync_pending, disk_save_pending, sync_handle);
-            handle_session_operations(app, session_manager);
-            handle_sync_operations(app, sync_handle);
+            handle_session_operations(app, session_manager, sync_handle);
+            handle_sync_operations(app, sync_handle, session_manager);
         }
         KeyEventKind::Release | KeyEventKind::Repeat => {}
     }
@@ -691,10 +1162,60 @@ fn mark_sync_and_save_pending_if_dirty(
 }
 
 /// Handle session switch/create/delete operations
-fn handle_session_operations(app: &mut App, session_manager: &mut session::SessionManager) {
+fn handle_session_operations(
+    app: &mut App,
+    session_manager: &mut session::SessionManager,
+    sync_handle: Option<&SyncHandle>,
+) {
+    let previous_doc_id = session_doc_id(app);
     handle_session_switch(app, session_manager);
     handle_session_creation(app, session_manager);
     handle_session_deletion(app, session_manager);
+    switch_sync_document(&previous_doc_id, app, sync_handle, session_manager);
+    if session_doc_id(app) != previous_doc_id {
+        load_chat_history(app, session_manager);
+    }
+}
+
+/// Show the current session's chat history
+fn load_chat_history(app: &mut App, session_manager: &session::SessionManager) {
+    app.chat = app
+        .current_session
+        .as_ref()
+        .map(|id| session_manager.load_chat(id))
+        .unwrap_or_default();
+}
+
+/// Send the messages we wrote, and append new messages to their session's
+/// history
+fn handle_chat(
+    app: &mut App,
+    sync_handle: Option<&SyncHandle>,
+    session_manager: &session::SessionManager,
+) {
+    let doc_id = session_doc_id(app);
+    for entry in std::mem::take(&mut app.pending_chat) {
+        if let (Some(handle), Some(from)) = (sync_handle, app.local_peer_id) {
+            let _ = handle.send_command(sync::SyncCommand::SendChat(sync::ChatMessage {
+                id: entry.id,
+                doc_id: doc_id.clone(),
+                from,
+                name: entry.name.clone(),
+                color_index: entry.color,
+                text: entry.text.clone(),
+                timestamp_ms: entry.timestamp_ms,
+                pin: entry.pin,
+            }));
+        }
+        app.chat_to_save.push((doc_id.clone(), entry));
+    }
+
+    for (doc_id, entry) in std::mem::take(&mut app.chat_to_save) {
+        let id = session::SessionId(doc_id);
+        if session_manager.session_exists(&id) {
+            let _ = session_manager.append_chat(&id, &entry);
+        }
+    }
 }
 
 /// Handle session switching
@@ -780,7 +1301,11 @@ fn save_current_session(app: &mut App, session_manager: &mut session::SessionMan
 }
 
 /// Handle cluster/peer connection operations
-fn handle_sync_operations(app: &mut App, sync_handle: Option<&SyncHandle>) {
+fn handle_sync_operations(
+    app: &mut App,
+    sync_handle: Option<&SyncHandle>,
+    session_manager: &mut session::SessionManager,
+) {
     if let Some(ticket) = app.pending_cluster_ticket.take() {
         if let Some(handle) = sync_handle {
             let _ = handle.send_command(sync::SyncCommand::ConnectCluster {
@@ -794,6 +1319,20 @@ fn handle_sync_operations(app: &mut App, sync_handle: Option<&SyncHandle>) {
 
     if let Some(ticket) = app.pending_join_ticket.take() {
         if let Some(handle) = sync_handle {
+            // Learn our role before switching, so the session opens with it
+            // (an invalid ticket is reported when opening its session)
+            let _ = handle.register_ticket(&ticket);
+            // Join into the local session for the ticket's document
+            match session_for_ticket(&ticket, session_manager) {
+                Ok(session_id) if app.current_session.as_ref() != Some(&session_id) => {
+                    let previous_doc_id = session_doc_id(app);
+                    app.session_to_switch = Some(session_id);
+                    handle_session_switch(app, session_manager);
+                    switch_sync_document(&previous_doc_id, app, sync_handle, session_manager);
+                }
+                Ok(_) => apply_sync_role(handle, &session_doc_id(app), app),
+                Err(e) => app.set_error(format!("Failed to open joined session: {}", e)),
+            }
             let _ = handle.send_command(sync::SyncCommand::ConnectPeer { ticket });
         } else {
             app.set_error("Sync is disabled (--offline)");
@@ -804,6 +1343,57 @@ fn handle_sync_operations(app: &mut App, sync_handle: Option<&SyncHandle>) {
         app.sync_ticket = Some(ticket);
         app.copy_ticket_to_clipboard();
     }
+
+    handle_invites(app, sync_handle, session_manager);
+}
+
+/// Copy viewer tickets, issue and revoke invites for the current session,
+/// and refresh the list shown in the invite menu
+fn handle_invites(
+    app: &mut App,
+    sync_handle: Option<&SyncHandle>,
+    session_manager: &mut session::SessionManager,
+) {
+    let Some(handle) = sync_handle else {
+        if app.pending_invite.take().is_some()
+            || app.pending_invite_revoke.take().is_some()
+            || std::mem::take(&mut app.pending_viewer_ticket)
+        {
+            app.set_error("Sync is disabled (--offline)");
+        }
+        return;
+    };
+    let doc_id = session_doc_id(app);
+
+    if std::mem::take(&mut app.pending_viewer_ticket) {
+        match handle.viewer_ticket(&doc_id) {
+            Ok(ticket) => {
+                app.copy_text_to_clipboard(&ticket);
+                refresh_sync_ticket(handle, &doc_id, app, session_manager);
+            }
+            Err(e) => app.set_error(format!("No viewer ticket: {}", e)),
+        }
+    }
+
+    if let Some(request) = app.pending_invite.take() {
+        match handle.create_invite(&doc_id, request.role, request.label, request.ttl) {
+            Ok(ticket) => {
+                app.copy_text_to_clipboard(&ticket);
+                refresh_sync_ticket(handle, &doc_id, app, session_manager);
+            }
+            Err(e) => app.set_error(format!("Invite failed: {}", e)),
+        }
+    }
+
+    if let Some(id) = app.pending_invite_revoke.take() {
+        match handle.revoke_invite(id) {
+            Ok(true) => app.set_status("Invite revoked"),
+            Ok(false) => app.set_error("Unknown invite"),
+            Err(e) => app.set_error(format!("Invite revoked until restart: {}", e)),
+        }
+    }
+
+    app.invites = handle.invites(&doc_id);
 }
 
 /// Handle mouse events
@@ -815,16 +1405,15 @@ fn handle_mouse_event(
     sync_handle: Option<&SyncHandle>,
 ) {
     update_cursor_position(app, &mouse);
-    
-    if handle_zoom_scroll(&mouse, app) {
-        return;
-    }
 
-    if handle_layer_panel_click(&mouse, app) {
-        return;
+    if !handle_zoom_scroll(&mouse, app)
+        && !handle_layer_panel_click(&mouse, app)
+        && !handle_participant_panel_click(&mouse, app)
+    {
+        handle_canvas_tool_event(mouse, app, sync_pending, disk_save_pending, sync_handle);
     }
 
-    handle_canvas_tool_event(mouse, app, sync_pending, disk_save_pending, sync_handle);
+    app.warn_refused_edits();
 }
 
 /// Update cursor position for presence
@@ -893,6 +1482,38 @@ fn handle_layer_panel_click(mouse: &event::MouseEvent, app: &mut App) -> bool {
     true
 }
 
+/// Handle clicks in participants panel: a peer's row follows them (or
+/// stops following), our own row stops following
+fn handle_participant_panel_click(mouse: &event::MouseEvent, app: &mut App) -> bool {
+    let Some(area) = app.participant_panel_area else {
+        return false;
+    };
+    let in_panel = mouse.column >= area.x
+        && mouse.column < area.x.saturating_add(area.width)
+        && mouse.row >= area.y
+        && mouse.row < area.y.saturating_add(area.height);
+    if !in_panel {
+        return false;
+    }
+
+    if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+        // Entry 0 is "You", then peers in panel order
+        let row_in_panel = mouse.row.saturating_sub(area.y.saturating_add(1));
+        let entry = usize::from(row_in_panel / ui::PARTICIPANT_ROWS);
+        let clicked = app
+            .presence
+            .as_ref()
+            .and_then(|mgr| Some(mgr.sorted_peers().get(entry.checked_sub(1)?)?.peer_id));
+        match clicked {
+            Some(peer_id) => app.toggle_follow(peer_id),
+            None if entry == 0 => app.stop_following(),
+            None => {}
+        }
+    }
+
+    true
+}
+
 /// Handle tool events on canvas
 fn handle_canvas_tool_event(
     mouse: event::MouseEvent,
@@ -923,6 +1544,8 @@ fn handle_canvas_tool_event(
         Tool::Cylinder => tools::handle_cylinder_event(app, mouse),
         Tool::Cloud => tools::handle_cloud_event(app, mouse),
         Tool::Star => tools::handle_star_event(app, mouse),
+        Tool::Callout => tools::handle_callout_event(app, mouse),
+        Tool::Polygon => tools::handle_polygon_event(app, mouse),
     }
 
     // Only set flags — actual I/O is debounced in the main loop
@@ -949,3 +1572,182 @@ fn broadcast_presence_if_due(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const SKETCH: &str = "┌─────┐     ┌─────┐\n│ Hub │────▶│ Leaf│\n└─────┘     └─────┘\n";
+
+    fn text_of(doc: &document::Document) -> String {
+        let mut view = shapes::ShapeView::new();
+        view.rebuild(doc).unwrap();
+        file_io::render_shapes_to_text(&view)
+    }
+
+    fn convert(input: &Path, output: &Path) {
+        let command = Command::Convert {
+            input: input.to_path_buf(),
+            output: output.to_path_buf(),
+            export: ExportArgs::default(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = session::SessionManager::with_data_dir(dir.path()).unwrap();
+        handle_command(&command, &mut manager).unwrap();
+    }
+
+    #[test]
+    fn convert_writes_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let sketch = dir.path().join("sketch.txt");
+        std::fs::write(&sketch, SKETCH).unwrap();
+
+        let expected: [(&str, &[u8]); 9] = [
+            ("txt", "┌─────┐".as_bytes()),
+            ("svg", b"<?xml"),
+            ("png", b"\x89PNG"),
+            ("ans", b"\x1b["),
+            ("html", b"<pre"),
+            ("mmd", b"flowchart"),
+            ("dot", b"digraph"),
+            ("excalidraw", b"{"),
+            ("drawio", b"<mxfile"),
+        ];
+        for (ext, magic) in expected {
+            let output = dir.path().join(format!("out.{}", ext));
+            convert(&sketch, &output);
+            let bytes = std::fs::read(&output).unwrap();
+            assert!(bytes.starts_with(magic), "{} output starts {:?}", ext, &bytes[..8]);
+        }
+    }
+
+    #[test]
+    fn convert_reads_every_import_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let sketch = dir.path().join("sketch.txt");
+        std::fs::write(&sketch, SKETCH).unwrap();
+        let notes = dir.path().join("notes.md");
+        std::fs::write(&notes, format!("# Notes\n\n```irohscii id=net\n{}```\n", SKETCH)).unwrap();
+
+        let mut inputs = vec![sketch.clone(), PathBuf::from(format!("{}#net", notes.display()))];
+        for ext in ["mmd", "dot", "excalidraw"] {
+            let path = dir.path().join(format!("sketch.{}", ext));
+            convert(&sketch, &path);
+            inputs.push(path);
+        }
+        for input in inputs {
+            let output = dir.path().join("back.txt");
+            convert(&input, &output);
+            let text = std::fs::read_to_string(&output).unwrap();
+            assert!(
+                text.contains("Hub") && text.contains("Leaf"),
+                "{} converted to {:?}",
+                input.display(),
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn render_loads_files_and_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = session::SessionManager::with_data_dir(&dir.path().join("data")).unwrap();
+        let sketch = dir.path().join("sketch.txt");
+        std::fs::write(&sketch, SKETCH).unwrap();
+
+        // A path that exists is read as a file
+        let doc = load_source(&sketch.to_string_lossy(), &manager).unwrap();
+        assert!(text_of(&doc).contains("Hub"));
+
+        // Anything else names a session
+        let meta = manager.create_session("Network").unwrap();
+        let mut session_doc = manager.load_document(&meta.id).unwrap();
+        file_io::load_ascii_into(&mut session_doc, &sketch).unwrap();
+        manager.save_session(&meta.id, &mut session_doc, &meta).unwrap();
+        assert!(text_of(&load_source("network", &manager).unwrap()).contains("Leaf"));
+        assert!(load_source("missing", &manager).is_err());
+
+        // The explicit format wins over the output's extension
+        let output = dir.path().join("network.out");
+        let render = Command::Render {
+            source: "Network".to_string(),
+            format: Some(file_io::ExportFormat::Svg),
+            output: Some(output.clone()),
+            export: ExportArgs::default(),
+        };
+        handle_command(&render, &mut manager).unwrap();
+        assert!(std::fs::read_to_string(&output).unwrap().contains("<svg"));
+
+        // Binary formats can't go to stdout
+        let png = Command::Render {
+            source: "Network".to_string(),
+            format: Some(file_io::ExportFormat::Png),
+            output: None,
+            export: ExportArgs::default(),
+        };
+        assert!(handle_command(&png, &mut manager).is_err());
+    }
+
+    #[test]
+    fn export_document_applies_export_args() {
+        let dir = tempfile::tempdir().unwrap();
+        let sketch = dir.path().join("sketch.txt");
+        std::fs::write(&sketch, SKETCH).unwrap();
+        let mut doc = document::Document::new();
+        import_file(&mut doc, &sketch).unwrap();
+
+        let region = ExportArgs {
+            region: Some("0,0,6,2".parse().unwrap()),
+            padding: 1,
+            ..ExportArgs::default()
+        };
+        let text = export_document(&doc, file_io::ExportFormat::Text, &region).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("Hub") && !text.contains("Leaf"));
+        assert!(text.starts_with('\n'));
+
+        let png = ExportArgs {
+            scale: Some(1),
+            background: Some("none".to_string()),
+            ..ExportArgs::default()
+        };
+        let bytes = export_document(&doc, file_io::ExportFormat::Png, &png).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (19 * 6, 3 * 12));
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+
+        let missing_layer = ExportArgs {
+            layer: vec!["Nope".to_string()],
+            ..ExportArgs::default()
+        };
+        assert!(export_document(&doc, file_io::ExportFormat::Text, &missing_layer).is_err());
+        let bad_background = ExportArgs {
+            background: Some("blue".to_string()),
+            ..ExportArgs::default()
+        };
+        assert!(export_document(&doc, file_io::ExportFormat::Png, &bad_background).is_err());
+    }
+
+    #[test]
+    fn import_creates_then_extends_a_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = session::SessionManager::with_data_dir(dir.path()).unwrap();
+        let sketch = dir.path().join("sketch.txt");
+        std::fs::write(&sketch, SKETCH).unwrap();
+        let import = Command::Import {
+            file: sketch.clone(),
+            session: None,
+        };
+
+        // Named after the file the first time, then added to
+        handle_command(&import, &mut manager).unwrap();
+        let meta = manager.find_session("sketch").unwrap().unwrap();
+        let count = manager.load_document(&meta.id).unwrap().read_all_shapes().unwrap().len();
+        assert!(count > 0);
+        handle_command(&import, &mut manager).unwrap();
+        assert_eq!(manager.list_sessions().unwrap().len(), 1);
+        let doc = manager.load_document(&meta.id).unwrap();
+        assert_eq!(doc.read_all_shapes().unwrap().len(), 2 * count);
+    }
+}
@@ -24,6 +24,7 @@ use crossterm::event::KeyCode;
 
 use crate::app::{App, KeyboardShapeField, PendingAction, PopupKind, Tool, BRUSHES, COLORS, TOOLS};
 use crate::dispatch::dispatch_action;
+use crate::file_io::DiagramBlock;
 
 /// Result of handling a key event in a mode.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -128,6 +129,8 @@ pub enum PathInputKind {
     ClusterConnect,
     QrDecode,
     JoinSession,
+    InviteCreate,
+    Chat,
 }
 
 impl PathInputKind {
@@ -137,10 +140,12 @@ impl PathInputKind {
             PathInputKind::FileOpen => "OPEN",
             PathInputKind::DocSave => "SAVE DOC",
             PathInputKind::DocOpen => "OPEN DOC",
-            PathInputKind::SvgExport => "SVG EXPORT",
+            PathInputKind::SvgExport => "EXPORT",
             PathInputKind::ClusterConnect => "CLUSTER",
             PathInputKind::QrDecode => "QR DECODE",
             PathInputKind::JoinSession => "JOIN",
+            PathInputKind::InviteCreate => "INVITE",
+            PathInputKind::Chat => "CHAT",
         }
     }
 
@@ -150,10 +155,12 @@ impl PathInputKind {
             PathInputKind::FileOpen => "Open file:",
             PathInputKind::DocSave => "Save document:",
             PathInputKind::DocOpen => "Open document:",
-            PathInputKind::SvgExport => "Export SVG:",
+            PathInputKind::SvgExport => "Export (path --selection/--viewport/--layer NAME/--padding N/...):",
             PathInputKind::ClusterConnect => "Cluster ticket:",
             PathInputKind::QrDecode => "QR image path:",
             PathInputKind::JoinSession => "Join ticket:",
+            PathInputKind::InviteCreate => "Invite (label --viewer --hours N):",
+            PathInputKind::Chat => "Message (--pin to pin at cursor):",
         }
     }
 }
@@ -164,6 +171,20 @@ pub struct RecentFilesState {
     pub selected: u32,
 }
 
+/// Invite list state - the invites issued for the current session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InviteListState {
+    pub selected: u32,
+}
+
+/// Markdown block picker state - choose which diagram block of a file to edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownBlocksState {
+    pub path: std::path::PathBuf,
+    pub blocks: Vec<DiagramBlock>,
+    pub selected: u32,
+}
+
 /// Selection popup state (tool, color, brush selection).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SelectionPopupState {
@@ -250,6 +271,7 @@ pub enum Mode {
     LayerRename(LayerRenameState),
     PathInput(PathInputState),
     RecentFiles(RecentFilesState),
+    MarkdownBlocks(MarkdownBlocksState),
     SelectionPopup(SelectionPopupState),
     ConfirmDialog(ConfirmDialogState),
     HelpScreen(HelpScreenState),
@@ -258,6 +280,7 @@ pub enum Mode {
     SessionCreate(SessionCreateState),
     KeyboardShapeCreate(KeyboardShapeState),
     QrCodeDisplay(QrCodeDisplayState),
+    InviteList(InviteListState),
 }
 
 // Query methods for Mode - kept for future UI use (status bar display)
@@ -272,6 +295,7 @@ impl Mode {
             Mode::LayerRename(_) => "RENAME",
             Mode::PathInput(state) => state.kind.mode_name(),
             Mode::RecentFiles(_) => "RECENT",
+            Mode::MarkdownBlocks(_) => "BLOCKS",
             Mode::SelectionPopup(state) => match state.kind {
                 PopupKind::Tool => "TOOL",
                 PopupKind::Color => "COLOR",
@@ -284,6 +308,7 @@ impl Mode {
             Mode::SessionCreate(_) => "NEW SESSION",
             Mode::KeyboardShapeCreate(_) => "CREATE",
             Mode::QrCodeDisplay(_) => "QR CODE",
+            Mode::InviteList(_) => "INVITES",
         }
     }
 
@@ -296,6 +321,7 @@ impl Mode {
             Mode::LayerRename(_) => Color::Green,
             Mode::PathInput(_) => Color::Yellow,
             Mode::RecentFiles(_) => Color::Cyan,
+            Mode::MarkdownBlocks(_) => Color::Cyan,
             Mode::SelectionPopup(_) => Color::Magenta,
             Mode::ConfirmDialog(_) => Color::Red,
             Mode::HelpScreen(_) => Color::Cyan,
@@ -304,6 +330,7 @@ impl Mode {
             Mode::SessionCreate(_) => Color::Green,
             Mode::KeyboardShapeCreate(_) => Color::Yellow,
             Mode::QrCodeDisplay(_) => Color::Magenta,
+            Mode::InviteList(_) => Color::Cyan,
         }
     }
 
@@ -458,12 +485,61 @@ impl Mode {
                             ModeTransition::Stay
                         }
                         KeyCode::Enter => {
+                            // Markdown files with several diagram blocks ask which one to open
+                            if let Some(picker) = ctx.app.recent_file_picker(state.selected) {
+                                return ModeTransition::to(picker);
+                            }
                             ctx.app.open_recent_file(state.selected);
                             ModeTransition::Normal
                         }
                         _ => ModeTransition::Stay,
                     },
 
+                    // Invite list: jk/arrows navigation + new/copy/revoke
+                    Mode::InviteList(state) => match key.code {
+                        KeyCode::Esc | KeyCode::Char('q') => ModeTransition::Normal,
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            state.selected = state.selected.saturating_sub(1);
+                            ModeTransition::Stay
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            let max = ctx.app.invites.len().saturating_sub(1);
+                            state.selected = (state.selected + 1).min(u32::try_from(max).unwrap_or(u32::MAX));
+                            ModeTransition::Stay
+                        }
+                        KeyCode::Char('n') => ModeTransition::to(Mode::invite_create()),
+                        KeyCode::Char('y') | KeyCode::Enter => {
+                            ctx.app.copy_invite_ticket(state.selected);
+                            ModeTransition::Stay
+                        }
+                        KeyCode::Char('r') | KeyCode::Char('d') | KeyCode::Delete => {
+                            ctx.app.revoke_invite(state.selected);
+                            ModeTransition::Stay
+                        }
+                        _ => ModeTransition::Stay,
+                    },
+
+                    // Markdown blocks: jk/arrows list navigation
+                    Mode::MarkdownBlocks(state) => match key.code {
+                        KeyCode::Esc => ModeTransition::Normal,
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            state.selected = state.selected.saturating_sub(1);
+                            ModeTransition::Stay
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            let max = state.blocks.len().saturating_sub(1);
+                            state.selected = (state.selected + 1).min(u32::try_from(max).unwrap_or(u32::MAX));
+                            ModeTransition::Stay
+                        }
+                        KeyCode::Enter => {
+                            if let Some(block) = state.blocks.get(state.selected as usize) {
+                                ctx.app.open_markdown_block(state.path.clone(), block.block_ref());
+                            }
+                            ModeTransition::Normal
+                        }
+                        _ => ModeTransition::Stay,
+                    },
+
                     // Session browser: navigation + filter + actions
                     Mode::SessionBrowser(state) => {
                         match key.code {
@@ -607,7 +683,7 @@ impl Mode {
         })
     }
 
-    /// Create an SVG export mode.
+    /// Create an export mode (SVG path by default).
     pub fn svg_export(path: String) -> Self {
         Mode::PathInput(PathInputState {
             path,
@@ -620,6 +696,19 @@ impl Mode {
         Mode::RecentFiles(RecentFilesState { selected: 0 })
     }
 
+    /// Create an invite list mode.
+    pub fn invite_list() -> Self {
+        Mode::InviteList(InviteListState { selected: 0 })
+    }
+
+    /// Create an invite prompt mode.
+    pub fn invite_create() -> Self {
+        Mode::PathInput(PathInputState {
+            path: String::new(),
+            kind: PathInputKind::InviteCreate,
+        })
+    }
+
     /// Create a tool selection popup.
     pub fn tool_popup(current_tool_index: u32, trigger_key: Option<crossterm::event::KeyCode>) -> Self {
         Mode::SelectionPopup(SelectionPopupState {
@@ -896,6 +985,33 @@ mod tests {
         assert!(matches!(mode.handle_key(&mut app, key(KeyCode::Enter)), ModeTransition::Normal));
     }
 
+    // --- invite list ---
+
+    #[test]
+    fn invites_esc_closes() {
+        let mut mode = Mode::invite_list();
+        let mut app = crate::app::App::new(80, 24);
+        assert!(matches!(mode.handle_key(&mut app, key(KeyCode::Esc)), ModeTransition::Normal));
+    }
+
+    #[test]
+    fn invites_n_opens_prompt() {
+        let mut mode = Mode::invite_list();
+        let mut app = crate::app::App::new(80, 24);
+        let ModeTransition::To(next) = mode.handle_key(&mut app, key(KeyCode::Char('n'))) else {
+            panic!("expected the invite prompt");
+        };
+        assert!(matches!(*next, Mode::PathInput(PathInputState { kind: PathInputKind::InviteCreate, .. })));
+    }
+
+    #[test]
+    fn invites_revoke_without_invites_stays() {
+        let mut mode = Mode::invite_list();
+        let mut app = crate::app::App::new(80, 24);
+        assert!(matches!(mode.handle_key(&mut app, key(KeyCode::Char('r'))), ModeTransition::Stay));
+        assert!(app.pending_invite_revoke.is_none());
+    }
+
     // --- session browser ---
 
     #[test]
@@ -22,6 +22,8 @@ const _: () = {
         let _ = PathInputKind::ClusterConnect;
         let _ = PathInputKind::QrDecode;
         let _ = PathInputKind::JoinSession;
+        let _ = PathInputKind::InviteCreate;
+        let _ = PathInputKind::Chat;
     }
     check_path_kind_coverage();
 };
@@ -35,10 +37,22 @@ impl ModeHandler for PathInputState {
                 ModeTransition::Normal
             }
             KeyCode::Enter => {
+                // Markdown files with several diagram blocks ask which one to open
+                if self.kind == PathInputKind::FileOpen
+                    && let Some(picker) = ctx.app.markdown_block_picker(&self.path)
+                {
+                    return ModeTransition::to(picker);
+                }
                 // Execute the path operation based on kind
                 self.execute_path_operation(ctx);
+                // New invites show up in the list they were created from
+                if self.kind == PathInputKind::InviteCreate {
+                    return ModeTransition::to(super::Mode::invite_list());
+                }
                 ModeTransition::Normal
             }
+            // Chat messages aren't paths
+            KeyCode::Tab if self.kind == PathInputKind::Chat => ModeTransition::Stay,
             KeyCode::Tab => {
                 // Path completion — operates on self.path directly since
                 // app.mode is temporarily taken during handle_key dispatch
@@ -68,6 +82,9 @@ impl ModeHandler for PathInputState {
     }
 
     fn help_text(&self) -> &'static str {
+        if self.kind == PathInputKind::Chat {
+            return "Type message, Enter to send, Esc to cancel";
+        }
         "Type path, Tab to complete, Enter to confirm, Esc to cancel"
     }
 }
@@ -123,7 +140,7 @@ impl PathInputState {
                 ctx.app.execute_doc_open(&self.path);
             }
             PathInputKind::SvgExport => {
-                ctx.app.execute_svg_export(&self.path);
+                ctx.app.execute_export(&self.path);
             }
             PathInputKind::ClusterConnect => {
                 ctx.app.execute_cluster_connect(&self.path);
@@ -134,6 +151,12 @@ impl PathInputState {
             PathInputKind::JoinSession => {
                 ctx.app.execute_join_session(&self.path);
             }
+            PathInputKind::InviteCreate => {
+                ctx.app.execute_invite_create(&self.path);
+            }
+            PathInputKind::Chat => {
+                ctx.app.execute_chat(&self.path);
+            }
         }
     }
 }
@@ -214,7 +237,7 @@ mod tests {
         );
         assert_eq!(
             test_state(PathInputKind::SvgExport).mode_name(),
-            "SVG EXPORT"
+            "EXPORT"
         );
         assert_eq!(
             test_state(PathInputKind::ClusterConnect).mode_name(),
@@ -228,5 +251,24 @@ mod tests {
             test_state(PathInputKind::JoinSession).mode_name(),
             "JOIN"
         );
+        assert_eq!(
+            test_state(PathInputKind::InviteCreate).mode_name(),
+            "INVITE"
+        );
+        assert_eq!(
+            test_state(PathInputKind::Chat).mode_name(),
+            "CHAT"
+        );
+    }
+
+    #[test]
+    fn test_tab_in_chat_keeps_message() {
+        let mut state = test_state(PathInputKind::Chat);
+        let mut app = crate::app::App::new(80, 24);
+        let mut ctx = ModeContext { app: &mut app };
+
+        let result = state.handle_key(&mut ctx, key(KeyCode::Tab));
+        assert!(matches!(result, ModeTransition::Stay));
+        assert_eq!(state.path, "/tmp/test.txt");
     }
 }
@@ -1,4 +1,5 @@
 pub mod arrow;
+pub mod callout;
 pub mod cloud;
 pub mod cylinder;
 pub mod diamond;
@@ -8,6 +9,7 @@ pub mod freehand;
 pub mod hexagon;
 pub mod line;
 pub mod parallelogram;
+pub mod polygon;
 pub mod rectangle;
 pub mod roundedrect;
 pub mod select;
@@ -17,6 +19,7 @@ pub mod trapezoid;
 pub mod triangle;
 
 pub use arrow::handle_arrow_event;
+pub use callout::handle_callout_event;
 pub use cloud::handle_cloud_event;
 pub use cylinder::handle_cylinder_event;
 pub use diamond::handle_diamond_event;
@@ -26,6 +29,7 @@ pub use freehand::handle_freehand_event;
 pub use hexagon::handle_hexagon_event;
 pub use line::handle_line_event;
 pub use parallelogram::handle_parallelogram_event;
+pub use polygon::handle_polygon_event;
 pub use rectangle::handle_rectangle_event;
 pub use roundedrect::handle_roundedrect_event;
 pub use select::handle_select_event;
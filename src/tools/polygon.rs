@@ -0,0 +1,44 @@
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+
+use crate::app::App;
+
+/// Scroll amount per tick
+const SCROLL_AMOUNT: i32 = 3;
+
+// Compile-time assertion: scroll amount must be positive
+const _: () = assert!(SCROLL_AMOUNT > 0, "SCROLL_AMOUNT must be positive");
+
+/// Handle mouse events for polygon tool (click to place vertices, click the
+/// first vertex to close, right-click to finish an open polyline)
+pub fn handle_polygon_event(app: &mut App, event: MouseEvent) {
+    debug_assert!(event.column < u16::MAX, "Event column coordinate out of valid range");
+    debug_assert!(event.row < u16::MAX, "Event row coordinate out of valid range");
+    
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            let pos = app.viewport.screen_to_canvas(event.column, event.row);
+            app.add_polygon_point(pos);
+        }
+        MouseEventKind::Down(MouseButton::Right) => {
+            // Finish as an open polyline
+            app.finish_polygon(false);
+        }
+        MouseEventKind::Moved | MouseEventKind::Drag(MouseButton::Left) => {
+            let pos = app.viewport.screen_to_canvas(event.column, event.row);
+            app.update_polygon_cursor(pos);
+        }
+        MouseEventKind::ScrollUp => {
+            app.viewport.pan(0, -SCROLL_AMOUNT);
+        }
+        MouseEventKind::ScrollDown => {
+            app.viewport.pan(0, SCROLL_AMOUNT);
+        }
+        MouseEventKind::ScrollLeft => {
+            app.viewport.pan(-SCROLL_AMOUNT, 0);
+        }
+        MouseEventKind::ScrollRight => {
+            app.viewport.pan(SCROLL_AMOUNT, 0);
+        }
+        _ => {}
+    }
+}
@@ -19,10 +19,16 @@ pub fn handle_select_event(app: &mut App, event: MouseEvent) {
             let shift = event.modifiers.contains(KeyModifiers::SHIFT);
 
             // First check if we're clicking on a resize handle (single selection only)
+            // In vertex-edit mode this grabs a vertex instead
             if app.try_start_resize(pos) {
                 return;
             }
 
+            // Vertex-edit mode: clicking an edge of the selected shape inserts a vertex
+            if app.vertex_edit && app.try_insert_vertex(pos) {
+                return;
+            }
+
             // Check if clicking on a shape
             if let Some(id) = app.shape_view.shape_at(pos) {
                 if shift {
@@ -67,6 +73,11 @@ pub fn handle_select_event(app: &mut App, event: MouseEvent) {
                 app.finish_drag();
             }
         }
+        MouseEventKind::Down(MouseButton::Right) if app.vertex_edit => {
+            // Vertex-edit mode: right-click a vertex to delete it
+            let pos = app.viewport.screen_to_canvas(event.column, event.row);
+            app.try_delete_vertex(pos);
+        }
         MouseEventKind::ScrollUp => {
             app.viewport.pan(0, -SCROLL_AMOUNT);
         }
@@ -10,21 +10,26 @@ use ratatui::{
 };
 
 use crate::app::{
-    App, BRUSHES, COLORS, GRID_SIZE, KeyboardShapeField, MessageSeverity, Mode, PendingAction,
-    PopupKind, SnapOrientation, TOOLS, Tool,
+    App, BRUSHES, COLORS, GRID_SIZE, KeyboardShapeField, MessageSeverity, Mode, PeerLinkState,
+    PendingAction, PopupKind, SnapOrientation, TOOLS, Tool,
 };
 
 // Import rat-widgets for the new UI components
 use rat_widgets;
 use crate::canvas::{
-    Position, arrow_points_styled, cloud_points, cylinder_points, diamond_points,
-    double_rect_points, ellipse_points, hexagon_points, line_points_styled, parallelogram_points,
-    rect_points, rounded_rect_points, star_points, trapezoid_points, triangle_points,
+    Position, arrow_points_styled, braille_union, callout_points, cloud_points, cloud_points_braille,
+    cylinder_points, diamond_points, double_rect_points, ellipse_points, ellipse_points_braille,
+    hexagon_points, line_points_styled, parallelogram_points, polygon_points, rect_points,
+    rounded_rect_points, star_points, stroke_points, stroke_points_braille, trapezoid_points,
+    triangle_points,
 };
 use crate::document::ShapeId;
 use crate::layers::LayerId;
-use crate::presence::{CursorActivity, PeerPresence, ToolKind, peer_color};
-use crate::shapes::ShapeKind;
+use crate::presence::{CursorActivity, PEER_COLORS, PeerPresence, ToolKind, peer_color};
+use crate::session::ChatEntry;
+use crate::shapes::{ShapeKind, vertex_handles};
+use crate::file_io::DiagramBlock;
+use crate::sync::ConnectionType;
 
 // Compile-time assertions for UI constants
 const _: () = assert!(GRID_SIZE > 0, "GRID_SIZE must be positive");
@@ -32,6 +37,9 @@ const _: () = assert!(MIN_CANVAS_WIDTH > 0, "MIN_CANVAS_WIDTH must be positive")
 const MIN_CANVAS_WIDTH: u16 = 20;
 const LAYER_PANEL_WIDTH: u16 = 18;
 const PARTICIPANT_PANEL_WIDTH: u16 = 24;
+const CHAT_PANEL_WIDTH: u16 = 32;
+/// Rows per entry in the participants panel (name, then activity and layer)
+pub const PARTICIPANT_ROWS: u16 = 2;
 
 /// Render the entire UI
 pub fn render(frame: &mut Frame, app: &mut App) {
@@ -63,9 +71,11 @@ fn render_main_area_with_panels(frame: &mut Frame, app: &mut App, area: Rect) ->
     
     let show_participants = app.show_participants && app.presence.is_some();
     let show_layers = app.show_layers;
+    let show_chat = app.show_chat;
 
-    if !show_layers && !show_participants {
+    if !show_layers && !show_participants && !show_chat {
         app.layer_panel_area = None;
+        app.participant_panel_area = None;
         return area;
     }
 
@@ -76,6 +86,9 @@ fn render_main_area_with_panels(frame: &mut Frame, app: &mut App, area: Rect) ->
     if show_participants {
         constraints.push(Constraint::Length(PARTICIPANT_PANEL_WIDTH));
     }
+    if show_chat {
+        constraints.push(Constraint::Length(CHAT_PANEL_WIDTH));
+    }
 
     let horizontal = Layout::default()
         .direction(Direction::Horizontal)
@@ -94,7 +107,16 @@ fn render_main_area_with_panels(frame: &mut Frame, app: &mut App, area: Rect) ->
     }
 
     if show_participants {
-        render_participants_panel(frame, app, horizontal[panel_idx]);
+        let participant_area = horizontal[panel_idx];
+        app.participant_panel_area = Some(participant_area);
+        render_participants_panel(frame, app, participant_area);
+        panel_idx += 1;
+    } else {
+        app.participant_panel_area = None;
+    }
+
+    if show_chat {
+        render_chat_panel(frame, app, horizontal[panel_idx]);
     }
 
     horizontal[0]
@@ -118,6 +140,12 @@ fn render_mode_overlays(frame: &mut Frame, app: &App, canvas_area: Rect) {
         Mode::RecentFiles(state) => {
             render_recent_files_menu(frame, app, state.selected as usize, canvas_area);
         }
+        Mode::MarkdownBlocks(state) => {
+            render_markdown_blocks_menu(frame, &state.blocks, state.selected as usize, canvas_area);
+        }
+        Mode::InviteList(state) => {
+            render_invite_list(frame, app, state.selected as usize, canvas_area);
+        }
         Mode::SelectionPopup(state) => {
             render_selection_popup(frame, state.kind, state.selected as usize, canvas_area);
         }
@@ -234,6 +262,26 @@ impl CanvasWidget<'_> {
         }
     }
 
+    /// Render braille cells, merging dots with braille already on screen
+    fn render_braille(&self, buf: &mut Buffer, area: Rect, points: Vec<(Position, char)>, style: Style) {
+        debug_assert!(area.width > 0 && area.height > 0);
+        for (pos, ch) in points {
+            let Some((sx, sy)) = self.app.viewport.canvas_to_screen(pos) else {
+                continue;
+            };
+            let (x, y) = (area.x + sx, area.y + sy);
+            if x < area.x + area.width && y < area.y + area.height {
+                let merged = buf[(x, y)]
+                    .symbol()
+                    .chars()
+                    .next()
+                    .and_then(|existing| braille_union(existing, ch))
+                    .unwrap_or(ch);
+                buf[(x, y)].set_char(merged).set_style(style);
+            }
+        }
+    }
+
     /// Render a text string at a canvas position
     fn render_text(&self, buf: &mut Buffer, area: Rect, pos: Position, text: &str, style: Style) {
         debug_assert!(area.width > 0 && area.height > 0);
@@ -428,14 +476,24 @@ impl Widget for CanvasWidget<'_> {
 
         // Render preview layers
         self.render_freehand_preview(buf, area, freehand_preview_style);
+        self.render_polygon_preview(buf, area, preview_style);
         self.render_snap_points(buf, area);
         self.render_shape_preview(buf, area, preview_style);
         self.render_active_snap_indicator(buf, area);
         self.render_marquee_selection(buf, area);
         self.render_selection_boxes(buf, area, selected_style);
 
-        // Render remote cursors (on top of everything)
+        if self.app.show_chat {
+            self.render_chat_pins(buf, area);
+        }
+
+        // Render remote viewports and cursors (on top of everything)
         if let Some(ref presence_mgr) = self.app.presence {
+            if self.app.show_peer_viewports {
+                for peer in presence_mgr.active_peers() {
+                    self.render_remote_viewport(buf, area, peer);
+                }
+            }
             for peer in presence_mgr.active_peers() {
                 self.render_remote_cursor(buf, area, peer);
             }
@@ -534,8 +592,13 @@ impl CanvasWidget<'_> {
                 label,
                 ..
             } => {
-                for (pos, ch) in ellipse_points(*center, *radius_x, *radius_y) {
-                    self.render_char(buf, area, pos, ch, style);
+                if self.app.shape_view.braille() {
+                    let points = ellipse_points_braille(*center, *radius_x, *radius_y);
+                    self.render_braille(buf, area, points, style);
+                } else {
+                    for (pos, ch) in ellipse_points(*center, *radius_x, *radius_y) {
+                        self.render_char(buf, area, pos, ch, style);
+                    }
                 }
                 if let Some(text) = label {
                     self.render_label_on_shape(buf, area, kind, text, style);
@@ -547,8 +610,12 @@ impl CanvasWidget<'_> {
                 label,
                 ..
             } => {
-                for &pos in points {
-                    self.render_char(buf, area, pos, *char, style);
+                if self.app.shape_view.braille() {
+                    self.render_braille(buf, area, stroke_points_braille(points, *char), style);
+                } else {
+                    for (pos, ch) in stroke_points(points, *char) {
+                        self.render_char(buf, area, pos, ch, style);
+                    }
                 }
                 if let Some(text) = label {
                     self.render_label_on_shape(buf, area, kind, text, style);
@@ -560,6 +627,19 @@ impl CanvasWidget<'_> {
                     self.render_char(buf, area, char_pos, ch, style);
                 }
             }
+            ShapeKind::Polygon {
+                points,
+                closed,
+                label,
+                ..
+            } => {
+                for (pos, ch) in polygon_points(points, *closed) {
+                    self.render_char(buf, area, pos, ch, style);
+                }
+                if let Some(text) = label {
+                    self.render_label_on_shape(buf, area, kind, text, style);
+                }
+            }
             ShapeKind::Triangle {
                 p1, p2, p3, label, ..
             } => {
@@ -627,13 +707,38 @@ impl CanvasWidget<'_> {
             ShapeKind::Cloud {
                 start, end, label, ..
             } => {
-                for (pos, ch) in cloud_points(*start, *end) {
-                    self.render_char(buf, area, pos, ch, style);
+                if self.app.shape_view.braille() {
+                    self.render_braille(buf, area, cloud_points_braille(*start, *end), style);
+                } else {
+                    for (pos, ch) in cloud_points(*start, *end) {
+                        self.render_char(buf, area, pos, ch, style);
+                    }
                 }
                 if let Some(text) = label {
                     self.render_label_on_shape(buf, area, kind, text, style);
                 }
             }
+            ShapeKind::Callout {
+                start,
+                end,
+                tail,
+                label,
+                ..
+            } => {
+                for (pos, ch) in callout_points(*start, *end, *tail) {
+                    self.render_char(buf, area, pos, ch, style);
+                }
+                if let Some(text) = label {
+                    // Center on the body, not on the tail-inclusive bounds
+                    let body = (
+                        start.x.min(end.x),
+                        start.y.min(end.y),
+                        start.x.max(end.x),
+                        start.y.max(end.y),
+                    );
+                    self.render_label(buf, area, body, text, style);
+                }
+            }
             ShapeKind::Star {
                 center,
                 outer_radius,
@@ -663,8 +768,26 @@ impl CanvasWidget<'_> {
         debug_assert!(area.width > 0 && area.height > 0);
         
         if let Some(ref freehand_state) = self.app.freehand_state {
-            for &pos in &freehand_state.points {
-                self.render_char(buf, area, pos, self.app.brush_char, style);
+            let points = &freehand_state.points;
+            if self.app.shape_view.braille() {
+                self.render_braille(buf, area, stroke_points_braille(points, self.app.brush_char), style);
+            } else {
+                for (pos, ch) in stroke_points(points, self.app.brush_char) {
+                    self.render_char(buf, area, pos, ch, style);
+                }
+            }
+        }
+    }
+
+    /// Render polygon preview (placed vertices plus rubber band to the cursor)
+    fn render_polygon_preview(&self, buf: &mut Buffer, area: Rect, style: Style) {
+        debug_assert!(area.width > 0 && area.height > 0);
+        
+        if let Some(ref polygon_state) = self.app.polygon_state {
+            let mut vertices = polygon_state.points.clone();
+            vertices.push(polygon_state.cursor);
+            for (pos, ch) in polygon_points(&vertices, false) {
+                self.render_char(buf, area, pos, ch, style);
             }
         }
     }
@@ -673,7 +796,10 @@ impl CanvasWidget<'_> {
     fn render_snap_points(&self, buf: &mut Buffer, area: Rect) {
         debug_assert!(area.width > 0 && area.height > 0);
         
-        if self.app.current_tool == Tool::Line || self.app.current_tool == Tool::Arrow {
+        if matches!(
+            self.app.current_tool,
+            Tool::Line | Tool::Arrow | Tool::Callout
+        ) {
             let snap_style = Style::default()
                 .fg(Color::Magenta)
                 .add_modifier(Modifier::DIM);
@@ -775,6 +901,12 @@ impl CanvasWidget<'_> {
                     self.render_char(buf, area, pos, ch, style);
                 }
             }
+            Tool::Callout => {
+                let (body_start, body_end) = crate::app::callout_body_from_drag(start, end);
+                for (pos, ch) in callout_points(body_start, body_end, start) {
+                    self.render_char(buf, area, pos, ch, style);
+                }
+            }
             Tool::Star => {
                 let outer_radius = (end.x - start.x).abs().max((end.y - start.y).abs()).max(2);
                 let inner_radius = outer_radius / 2;
@@ -850,14 +982,52 @@ impl CanvasWidget<'_> {
                         .fg(Color::Yellow)
                         .add_modifier(Modifier::BOLD);
 
-                    for handle_info in shape.resize_handles() {
-                        self.render_char(buf, area, handle_info.pos, '■', handle_style);
+                    if self.app.vertex_edit && shape.kind.supports_vertex_edit() {
+                        for handle_info in vertex_handles(&shape.kind) {
+                            self.render_char(buf, area, handle_info.pos, '●', handle_style);
+                        }
+                    } else {
+                        for handle_info in shape.resize_handles() {
+                            self.render_char(buf, area, handle_info.pos, '■', handle_style);
+                        }
                     }
                 }
             }
         }
     }
 
+    /// Outline the part of the canvas a remote peer is looking at
+    fn render_remote_viewport(&self, buf: &mut Buffer, area: Rect, peer: &PeerPresence) {
+        debug_assert!(area.width > 0 && area.height > 0);
+
+        // Following them: their view is ours, an outline would just frame the screen
+        if self.app.following.is_some_and(|f| f.peer_id == peer.peer_id) {
+            return;
+        }
+        let Some(view) = peer.viewport else {
+            return;
+        };
+        let (min_pos, max_pos) = view.bounds();
+        let style = Style::default().fg(peer_color(peer)).add_modifier(Modifier::DIM);
+        self.render_dashed_rect(buf, area, min_pos, max_pos, style);
+    }
+
+    /// Render pinned chat messages at the canvas positions they point at
+    fn render_chat_pins(&self, buf: &mut Buffer, area: Rect) {
+        debug_assert!(area.width > 0 && area.height > 0);
+
+        for entry in &self.app.chat {
+            let Some(pin) = entry.pin else {
+                continue;
+            };
+            let style = Style::default().fg(chat_color(entry));
+            self.render_char(buf, area, pin, '◆', style.add_modifier(Modifier::BOLD));
+            let text = format!("{}: {}", entry.sender_name(), entry.text).replace(char::is_control, " ");
+            let text_pos = Position::new(pin.x + 1, pin.y);
+            self.render_text(buf, area, text_pos, &truncate_name(&text, 24), style);
+        }
+    }
+
     /// Render a remote peer's cursor and activity
     fn render_remote_cursor(&self, buf: &mut Buffer, area: Rect, peer: &PeerPresence) {
         debug_assert!(area.width > 0 && area.height > 0);
@@ -865,8 +1035,11 @@ impl CanvasWidget<'_> {
         let color = peer_color(peer);
         let cursor_style = Style::default().fg(color).add_modifier(Modifier::BOLD);
 
-        // Render cursor marker
+        // Render cursor marker, tagged with the peer's name
         self.render_char(buf, area, peer.cursor_pos, '█', cursor_style);
+        let tag_pos = Position::new(peer.cursor_pos.x + 1, peer.cursor_pos.y);
+        let tag = truncate_name(&peer.display_name(), 12);
+        self.render_text(buf, area, tag_pos, &tag, Style::default().fg(color));
 
         // Render activity indicator based on what they're doing
         match &peer.activity {
@@ -1061,6 +1234,18 @@ impl CanvasWidget<'_> {
                     self.render_char(buf, area, pos, ch, style);
                 }
             }
+            ToolKind::Polygon => {
+                // Rubber-band edge from their last placed vertex
+                for (pos, ch) in polygon_points(&[start, current], false) {
+                    self.render_char(buf, area, pos, ch, style);
+                }
+            }
+            ToolKind::Callout => {
+                let (body_start, body_end) = crate::app::callout_body_from_drag(start, current);
+                for (pos, ch) in callout_points(body_start, body_end, start) {
+                    self.render_char(buf, area, pos, ch, style);
+                }
+            }
             ToolKind::Star => {
                 let outer_radius = (current.x - start.x)
                     .abs()
@@ -1109,19 +1294,28 @@ fn render_participants_panel(frame: &mut Frame, app: &App, area: Rect) {
         .map(|l| truncate_name(&l.name, 8))
         .unwrap_or_else(|| "?".to_string());
 
-    // Add "You" entry first (local user)
+    // Add "You" entry first (local user), in the color peers see us in
+    let local_color = app
+        .build_presence(app.last_cursor_pos)
+        .map(|p| peer_color(&p))
+        .unwrap_or(Color::White);
+    let local_name = match &app.profile.display_name {
+        Some(name) => format!("{} (you)", truncate_name(name, 12)),
+        None => "You".to_string(),
+    };
     items.push(Line::from(vec![
-        Span::styled("● ", Style::default().fg(Color::White)),
-        Span::raw("You"),
-        Span::styled(
-            format!(" [{}]", local_layer_name),
-            Style::default().fg(Color::Cyan),
-        ),
+        Span::styled("● ", Style::default().fg(local_color)),
+        Span::raw(local_name),
     ]));
+    items.push(Line::styled(
+        format!("  [{}]", local_layer_name),
+        Style::default().fg(Color::Cyan),
+    ));
 
-    // Add remote peers
-    for peer in presence_mgr.active_peers() {
+    // Add remote peers, PARTICIPANT_ROWS each (clicking one follows that peer)
+    for peer in presence_mgr.sorted_peers() {
         let color = peer_color(peer);
+        let following = app.following.is_some_and(|f| f.peer_id == peer.peer_id);
         let activity = peer.activity.label();
 
         // Get peer's active layer name
@@ -1139,27 +1333,93 @@ fn render_participants_panel(frame: &mut Frame, app: &App, area: Rect) {
             .map(|l| truncate_name(&l.name, 8))
             .unwrap_or_else(|| "?".to_string());
 
+        // ▶ marks the peer we're following
+        let marker = if following { "▶ " } else { "█ " };
         items.push(Line::from(vec![
-            Span::styled("█ ", Style::default().fg(color)),
-            Span::raw(peer.display_name()),
-            Span::styled(
-                format!(" ({})", activity),
-                Style::default().fg(Color::DarkGray),
-            ),
-            Span::styled(
-                format!(" [{}]", layer_name),
-                Style::default().fg(Color::DarkGray),
-            ),
+            Span::styled(marker, Style::default().fg(color)),
+            Span::raw(truncate_name(&peer.display_name(), 20)),
         ]));
+        items.push(Line::styled(
+            format!("  ({}) [{}]", activity, layer_name),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
 
-    let paragraph = Paragraph::new(items)
-        .block(block)
-        .wrap(ratatui::widgets::Wrap { trim: true });
+    // No wrapping: rows must line up with entries for click handling
+    let paragraph = Paragraph::new(items).block(block);
 
     frame.render_widget(paragraph, area);
 }
 
+/// Render the chat panel: the newest messages that fit, oldest at the top
+fn render_chat_panel(frame: &mut Frame, app: &App, area: Rect) {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    debug_assert!(area.width > 0 && area.height > 0);
+
+    let block = Block::default()
+        .title(" Chat (Space m) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    let inner_width = usize::from(area.width.saturating_sub(2)).max(1);
+    let inner_height = usize::from(area.height.saturating_sub(2));
+
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    // Build from the newest message back until the panel is full
+    let mut items: Vec<Line> = Vec::new();
+    for entry in app.chat.iter().rev() {
+        if items.len() >= inner_height {
+            break;
+        }
+        let name = if app.is_own_chat(entry) {
+            "You".to_string()
+        } else {
+            truncate_name(&entry.sender_name(), 16)
+        };
+        let age = format_time_ago(now_ms.saturating_sub(entry.timestamp_ms) / 1000);
+        let mut header = vec![
+            Span::styled(name, Style::default().fg(chat_color(entry)).add_modifier(Modifier::BOLD)),
+            Span::styled(format!(" · {}", age), Style::default().fg(Color::DarkGray)),
+        ];
+        if let Some(pin) = entry.pin {
+            header.push(Span::styled(
+                format!(" ◆{},{}", pin.x, pin.y),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        let mut lines = vec![Line::from(header)];
+        lines.extend(wrap_chars(&entry.text, inner_width).into_iter().map(Line::raw));
+        items.splice(0..0, lines);
+    }
+    if app.chat.is_empty() {
+        items.push(Line::styled("No messages", Style::default().fg(Color::DarkGray)));
+    }
+    // Keep the newest lines when the oldest message overflows
+    let overflow = items.len().saturating_sub(inner_height);
+    items.drain(..overflow);
+
+    frame.render_widget(Paragraph::new(items).block(block), area);
+}
+
+/// Color a chat message's sender is shown in
+fn chat_color(entry: &ChatEntry) -> Color {
+    PEER_COLORS[usize::from(entry.color) % PEER_COLORS.len()]
+}
+
+/// Split text into lines of at most `width` characters, blanking control
+/// characters a peer might have sent
+fn wrap_chars(text: &str, width: usize) -> Vec<String> {
+    debug_assert!(width > 0);
+    let chars: Vec<char> = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    chars.chunks(width).map(|chunk| chunk.iter().collect()).collect()
+}
+
 /// Truncate a name for display
 fn truncate_name(name: &str, max_len: usize) -> String {
     let name_len = name.chars().count();
@@ -1326,6 +1586,7 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     let dirty_marker = if app.is_dirty() { " *" } else { "" };
     let char_info = get_char_info(app);
     let (peer_info, peer_style) = get_peer_info(app);
+    let view_only = if app.read_only() { " [VIEW ONLY]" } else { "" };
     let (status_text, status_color) = get_status_message(app);
     let tool_info = get_tool_info(app);
     let shape_info = get_shape_info(app);
@@ -1340,6 +1601,10 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         Span::styled(count_info, Style::default().fg(Color::DarkGray)),
         Span::styled(zoom_info, Style::default().fg(Color::Magenta)),
         Span::styled(peer_info, peer_style),
+        Span::styled(
+            view_only,
+            Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD),
+        ),
         Span::styled(shape_info, Style::default().fg(Color::Cyan)),
         Span::styled(status_text, Style::default().fg(status_color)),
         Span::styled(
@@ -1366,7 +1631,10 @@ fn get_mode_info(mode: &Mode, current_tool: Tool) -> (&'static str, Color) {
             ("INS", Color::Green)
         }
         Mode::PathInput(_) => ("CMD", Color::Magenta),
-        Mode::RecentFiles(_) | Mode::SelectionPopup(_) => ("MENU", Color::Cyan),
+        Mode::RecentFiles(_)
+        | Mode::MarkdownBlocks(_)
+        | Mode::SelectionPopup(_)
+        | Mode::InviteList(_) => ("MENU", Color::Cyan),
         Mode::ConfirmDialog(_) => ("CONF", Color::Yellow),
         Mode::HelpScreen(_) => ("HELP", Color::Cyan),
         Mode::LeaderMenu(_) => ("SPACE", Color::Cyan),
@@ -1408,6 +1676,8 @@ fn get_char_info(app: &App) -> String {
         | Tool::RoundedRect
         | Tool::Cylinder
         | Tool::Cloud
+        | Tool::Callout
+        | Tool::Polygon
         | Tool::Star => {
             format!(" {}", app.current_color.name())
         }
@@ -1417,18 +1687,38 @@ fn get_char_info(app: &App) -> String {
 
 /// Get peer/sync info for status bar
 fn get_peer_info(app: &App) -> (String, Style) {
-    if let Some(ref presence) = app.presence {
-        let count = presence.peer_count();
-        if count > 0 {
-            (
-                format!(" [SYNC {}]", count),
-                Style::default().fg(Color::Green),
-            )
-        } else {
-            (String::from(" [SYNC]"), Style::default().fg(Color::Yellow))
-        }
+    let Some(ref presence) = app.presence else {
+        return (String::new(), Style::default());
+    };
+
+    let reconnecting = app
+        .peer_links
+        .values()
+        .filter_map(|state| match state {
+            PeerLinkState::Reconnecting { attempt } => Some(*attempt),
+            PeerLinkState::Connected(_) => None,
+        })
+        .max();
+    if let Some(attempt) = reconnecting {
+        return (
+            format!(" [SYNC reconnecting #{}]", attempt),
+            Style::default().fg(Color::Yellow),
+        );
+    }
+
+    let count = presence.peer_count();
+    if count > 0 {
+        let relayed = app
+            .peer_links
+            .values()
+            .any(|state| *state == PeerLinkState::Connected(ConnectionType::Relay));
+        let via = if relayed { " relay" } else { "" };
+        (
+            format!(" [SYNC {}{}]", count, via),
+            Style::default().fg(Color::Green),
+        )
     } else {
-        (String::new(), Style::default())
+        (String::from(" [SYNC]"), Style::default().fg(Color::Yellow))
     }
 }
 
@@ -1515,7 +1805,7 @@ fn get_context_help(mode: &Mode, current_tool: Tool, show_layers: bool, active_l
         }
         Mode::LayerRename(_) => "type layer name | [Enter] confirm [Esc] cancel",
         Mode::PathInput(_) => "type path | [Tab] complete [Enter] confirm [Esc] cancel",
-        Mode::RecentFiles(_) => "[j/k] navigate [Enter] open [Esc] cancel",
+        Mode::RecentFiles(_) | Mode::MarkdownBlocks(_) => "[j/k] navigate [Enter] open [Esc] cancel",
         Mode::SelectionPopup(_) => "[hjkl] navigate [Enter] select [Esc] cancel",
         Mode::ConfirmDialog(_) => "[y] Yes [n] No | [Enter] confirm [Esc] cancel",
         Mode::HelpScreen(_) => "[j/k] scroll [Space] page down [Esc/q/?] close",
@@ -1528,6 +1818,7 @@ fn get_context_help(mode: &Mode, current_tool: Tool, show_layers: bool, active_l
             "[Tab] switch field | type dimensions | [Enter] create [Esc] cancel"
         }
         Mode::QrCodeDisplay(_) => "[y] copy ticket | [w] save PNG | any key: close",
+        Mode::InviteList(_) => "[j/k] navigate [n]ew [y/Enter] copy ticket [r]evoke [Esc] close",
     }
 }
 
@@ -1545,6 +1836,7 @@ fn get_normal_mode_help(current_tool: Tool, show_layers: bool, active_layer: Opt
         }
         Tool::Freehand => "drag to draw | [Space] menu [?] help",
         Tool::Text => "click to place text | [Space] menu [?] help",
+        Tool::Polygon => "click vertices, first to close, right-click to end | [Space] menu",
         Tool::Line | Tool::Arrow => {
             "[v] line style | drag to draw | [Space] menu [?] help"
         }
@@ -1729,6 +2021,96 @@ fn render_recent_files_menu(frame: &mut Frame, app: &App, selected: usize, area:
     list.render(frame, inner, None);
 }
 
+/// Render the invites issued for the current session
+fn render_invite_list(frame: &mut Frame, app: &App, selected: usize, area: Rect) {
+    debug_assert!(area.width > 0 && area.height > 0);
+
+    let width = 60.min(area.width.saturating_sub(4));
+    let height = 12.min(area.height.saturating_sub(4));
+    let x = (area.width.saturating_sub(width)) / 2 + area.x;
+    let y = (area.height.saturating_sub(height)) / 2 + area.y;
+
+    let popup_area = Rect::new(x, y, width, height);
+
+    clear_area(frame, popup_area);
+
+    // Label, role and how long each invite has left
+    let items: Vec<String> = if app.invites.is_empty() {
+        vec!["No invites yet - press n to create one".to_string()]
+    } else {
+        app.invites
+            .iter()
+            .map(|info| {
+                let invite = &info.invite;
+                let label = invite.label.as_deref().unwrap_or("(anyone)");
+                let role = match invite.role {
+                    crate::sync::Role::Editor => "editor",
+                    crate::sync::Role::Viewer => "viewer",
+                };
+                let status = if info.revoked {
+                    "revoked".to_string()
+                } else if invite.is_expired() {
+                    "expired".to_string()
+                } else {
+                    let minutes = invite.remaining().as_secs().div_ceil(60);
+                    format!("{}h{:02}m left", minutes / 60, minutes % 60)
+                };
+                format!("{label}  {role}  {status}")
+            })
+            .collect()
+    };
+
+    let mut list = rat_widgets::ScrollableList::new(items)
+        .with_border_color(Color::Cyan);
+    list.move_to(selected);
+
+    let block = Block::default()
+        .title(" Invites ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    frame.render_widget(block, popup_area);
+
+    let inner = popup_area.inner(Margin::new(1, 1));
+    list.render(frame, inner, None);
+}
+
+/// Render the diagram block picker for a Markdown file
+fn render_markdown_blocks_menu(frame: &mut Frame, blocks: &[DiagramBlock], selected: usize, area: Rect) {
+    debug_assert!(area.width > 0 && area.height > 0);
+
+    let width = 50.min(area.width.saturating_sub(4));
+    let height = 12.min(area.height.saturating_sub(4));
+    let x = (area.width.saturating_sub(width)) / 2 + area.x;
+    let y = (area.height.saturating_sub(height)) / 2 + area.y;
+
+    let popup_area = Rect::new(x, y, width, height);
+
+    clear_area(frame, popup_area);
+
+    // Marked blocks show their id; plain text blocks their position
+    let items: Vec<String> = blocks.iter()
+        .map(|block| {
+            let first_line = block.content.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+            format!("{}  {}", block.title(), first_line.trim())
+        })
+        .collect();
+
+    let mut list = rat_widgets::ScrollableList::new(items)
+        .with_border_color(Color::Cyan);
+    list.move_to(selected);
+
+    let block = Block::default()
+        .title(" Diagram Blocks ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    frame.render_widget(block, popup_area);
+
+    let inner = popup_area.inner(Margin::new(1, 1));
+    list.render(frame, inner, None);
+}
+
 
 
 /// Render selection popup for tools, colors, or brushes
@@ -1816,11 +2198,20 @@ fn get_leader_help() -> Vec<(&'static str, &'static str)> {
         ("b", "Brush picker"),
         ("s", "Save file"),
         ("o", "Open file"),
-        ("e", "Export SVG"),
+        ("e", "Export (SVG, PNG, ...)"),
         ("n", "New document"),
         ("g", "Toggle grid"),
         ("l", "Toggle layers"),
         ("p", "Toggle peers"),
+        ("f", "Follow next peer"),
+        ("v", "Toggle peer viewports"),
+        ("m", "Send chat message"),
+        ("M", "Toggle chat"),
+        ("B", "Toggle braille curves"),
+        ("r", "Recognize freehand shapes"),
+        ("R", "Toggle auto-recognize"),
+        ("S", "Toggle stroke smoothing"),
+        ("P", "Convert stroke to polyline"),
         ("?", "Help"),
         ("q", "Quit"),
     ]
@@ -1844,6 +2235,8 @@ fn get_tools_help() -> Vec<(&'static str, &'static str)> {
         ("Space t", "RoundedRect (picker)"),
         ("Space t", "Cylinder (picker)"),
         ("Space t", "Cloud (picker)"),
+        ("Space t", "Callout (picker)"),
+        ("Space t", "Polygon (picker)"),
         ("s", "Star tool"),
     ]
 }
@@ -1876,6 +2269,7 @@ fn get_selection_help() -> Vec<(&'static str, &'static str)> {
         ("p", "Paste"),
         ("Del/Backspace", "Delete selected"),
         ("Enter", "Edit label"),
+        ("V", "Vertex edit (drag/insert/right-click delete)"),
     ]
 }
 
@@ -1928,7 +2322,7 @@ fn get_files_help() -> Vec<(&'static str, &'static str)> {
         ("Ctrl+o", "Import ASCII"),
         ("Ctrl+Shift+S", "Save document (.automerge)"),
         ("Ctrl+Shift+O", "Open document"),
-        ("E", "Export SVG"),
+        ("E", "Export (SVG, PNG, ...)"),
         ("N", "New document (confirm)"),
         ("R", "Recent files"),
     ]
@@ -1937,10 +2331,15 @@ fn get_files_help() -> Vec<(&'static str, &'static str)> {
 fn get_collaboration_help() -> Vec<(&'static str, &'static str)> {
     vec![
         ("T", "Copy sync ticket"),
+        ("V", "Copy viewer (read-only) ticket"),
+        ("I", "Invites (issue, copy, revoke)"),
         ("Q", "Show ticket as QR code (w: save PNG)"),
         ("D", "Decode QR code from image"),
         ("J", "Join peer session"),
         ("P", "Toggle participants"),
+        ("Click peer", "Follow / unfollow (pan to stop)"),
+        ("m", "Chat (--pin: pin to cursor)"),
+        ("M", "Show / hide chat"),
     ]
 }
 
@@ -2032,9 +2431,13 @@ fn render_session_browser(
 
     let filtered = app.get_filtered_sessions(filter, show_pinned_only);
     let session_count = filtered.len();
+    // Extra row for the selected session's collaborators
+    let collab_rows = filtered
+        .get(selected)
+        .map_or(0, |s| u16::from(!s.collaborators.is_empty()));
 
     let width = 60.min(area.width.saturating_sub(4));
-    let height = (session_count as u16 + 5).min(20);
+    let height = (session_count as u16 + 5 + collab_rows).min(20);
     let x = (area.width.saturating_sub(width)) / 2 + area.x;
     let y = (area.height.saturating_sub(height)) / 2 + area.y;
 
@@ -2102,6 +2505,11 @@ fn build_session_browser_lines(
     for (i, session) in filtered.iter().enumerate() {
         let line = build_session_line(*session, i == selected, app, now);
         lines.push(line);
+        if i == selected
+            && let Some(collabs) = build_collaborators_line(session)
+        {
+            lines.push(collabs);
+        }
     }
 
     if filtered.is_empty() {
@@ -2152,6 +2560,27 @@ fn build_session_line(session: &crate::session::SessionMeta, is_selected: bool,
     Line::styled(format!(" {}", line_text), style)
 }
 
+/// Build the "With: ..." line naming a session's collaborators, most recent first
+fn build_collaborators_line(session: &crate::session::SessionMeta) -> Option<Line<'static>> {
+    const MAX_SHOWN: usize = 4;
+    let collabs = session.recent_collaborators();
+    if collabs.is_empty() {
+        return None;
+    }
+    let mut names: Vec<String> = collabs
+        .iter()
+        .take(MAX_SHOWN)
+        .map(|c| truncate_name(&c.name(), 12))
+        .collect();
+    if collabs.len() > MAX_SHOWN {
+        names.push(format!("+{}", collabs.len() - MAX_SHOWN));
+    }
+    Some(Line::styled(
+        format!("     With: {}", names.join(", ")),
+        Style::default().fg(Color::DarkGray),
+    ))
+}
+
 /// Format seconds as time ago string
 fn format_time_ago(diff: u64) -> String {
     if diff < 60 {
@@ -2507,4 +2936,29 @@ mod tests {
         assert_eq!(truncate_name("Layer With Spaces", 8), "Layer ..");
         assert_eq!(truncate_name("   ", 2), "..");
     }
+
+    #[test]
+    fn wrap_chars_splits_at_width() {
+        assert_eq!(wrap_chars("abcdefg", 3), vec!["abc", "def", "g"]);
+        assert_eq!(wrap_chars("a\nb", 10), vec!["a b"]);
+        assert!(wrap_chars("", 5).is_empty());
+    }
+
+    #[test]
+    fn collaborators_line_lists_recent_names() {
+        let mut meta = crate::session::SessionMeta::new("test");
+        assert!(build_collaborators_line(&meta).is_none());
+
+        for (i, name) in ["Ada", "Bob", "Cy", "Di", "Ed"].into_iter().enumerate() {
+            meta.add_collaborator(crate::session::PeerId([i as u8; 32]), Some(name.to_string()));
+        }
+        let text: String = build_collaborators_line(&meta)
+            .unwrap()
+            .spans
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert!(text.contains("With: "));
+        assert!(text.ends_with("+1"));
+    }
 }
@@ -17,7 +17,8 @@ use irohscii::layers::LayerId;
 use irohscii::presence::{CursorActivity, PeerId, PeerPresence};
 use irohscii::shapes::{ShapeColor, ShapeKind};
 use irohscii::sync::{
-    SyncCommand, SyncConfig, SyncEvent, SyncHandle, SyncMode, start_sync_thread,
+    DEFAULT_DOC_ID, Role, SyncCommand, SyncConfig, SyncEvent, SyncHandle, SyncMode, decode_ticket,
+    encode_doc_ticket, start_sync_thread,
 };
 
 // Serialize tests — each one binds iroh endpoints on random ports, but the
@@ -64,25 +65,29 @@ fn wait_ready(h: &SyncHandle) -> (String, PeerId) {
         .expect("peer should become ready")
 }
 
-fn wait_remote(h: &SyncHandle) -> Automerge {
-    wait_for_event(h, SYNC_TIMEOUT, |e| {
+/// Apply the next RemoteChanges event to the test's replica of the document.
+fn wait_remote(h: &SyncHandle, replica: &mut Automerge) {
+    let changes = wait_for_event(h, SYNC_TIMEOUT, |e| {
         matches!(e, SyncEvent::RemoteChanges { .. })
     })
     .and_then(|e| match e {
-        SyncEvent::RemoteChanges { doc } => Some(*doc),
+        SyncEvent::RemoteChanges { changes, .. } => Some(changes),
         _ => None,
     })
-    .expect("should receive remote changes")
+    .expect("should receive remote changes");
+    replica.load_incremental(&changes).unwrap();
 }
 
-/// Drain all pending RemoteChanges events and return the last document.
-fn drain_remote(h: &SyncHandle, timeout: Duration) -> Option<Automerge> {
+/// Drain all pending RemoteChanges events into the replica. Returns whether
+/// any arrived.
+fn drain_remote(h: &SyncHandle, replica: &mut Automerge, timeout: Duration) -> bool {
     let t0 = Instant::now();
-    let mut last = None;
+    let mut received = false;
     loop {
         match h.poll_event() {
-            Some(SyncEvent::RemoteChanges { doc }) => {
-                last = Some(*doc);
+            Some(SyncEvent::RemoteChanges { changes, .. }) => {
+                replica.load_incremental(&changes).unwrap();
+                received = true;
             }
             Some(_) => {} // ignore other events
             None => {
@@ -93,7 +98,7 @@ fn drain_remote(h: &SyncHandle, timeout: Duration) -> Option<Automerge> {
             }
         }
     }
-    last
+    received
 }
 
 fn host() -> (SyncHandle, String, PeerId) {
@@ -102,6 +107,10 @@ fn host() -> (SyncHandle, String, PeerId) {
         cluster_ticket: None,
         cluster_capability: None,
         disable_discovery: true,
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        store_dir: None,
+        identity_path: None,
+        roles_path: None,
     };
     let h = start_sync_thread(cfg).expect("start host");
     let (ticket, pid) = wait_ready(&h);
@@ -109,6 +118,10 @@ fn host() -> (SyncHandle, String, PeerId) {
 }
 
 fn join(ticket: &str) -> (SyncHandle, PeerId) {
+    join_doc(ticket, DEFAULT_DOC_ID)
+}
+
+fn join_doc(ticket: &str, doc_id: &str) -> (SyncHandle, PeerId) {
     let cfg = SyncConfig {
         mode: SyncMode::Active {
             join_ticket: Some(ticket.into()),
@@ -116,6 +129,10 @@ fn join(ticket: &str) -> (SyncHandle, PeerId) {
         cluster_ticket: None,
         cluster_capability: None,
         disable_discovery: true,
+        doc_id: doc_id.to_string(),
+        store_dir: None,
+        identity_path: None,
+        roles_path: None,
     };
     let h = start_sync_thread(cfg).expect("start joiner");
     let (_, pid) = wait_ready(&h);
@@ -147,23 +164,52 @@ fn labeled_rect(x: i32, y: i32, w: i32, h: i32, label: &str) -> ShapeKind {
     }
 }
 
-/// Wait until the handle delivers a RemoteChanges doc with at least `min_shapes` shapes.
-fn wait_for_shapes(h: &SyncHandle, min_shapes: usize, timeout: Duration) -> Option<Automerge> {
+/// Apply RemoteChanges to the replica until it holds at least `min_shapes`
+/// shapes. Returns whether it got there in time.
+fn wait_for_shapes(
+    h: &SyncHandle,
+    replica: &mut Automerge,
+    min_shapes: usize,
+    timeout: Duration,
+) -> bool {
     let t0 = Instant::now();
-    let mut best: Option<Automerge> = None;
     while t0.elapsed() < timeout {
-        if let Some(ev) = h.poll_event() {
-            if let SyncEvent::RemoteChanges { doc } = ev {
-                let n = count_shapes(&doc);
-                if n >= min_shapes {
-                    return Some(*doc);
-                }
-                best = Some(*doc);
+        if let Some(SyncEvent::RemoteChanges { changes, .. }) = h.poll_event() {
+            replica.load_incremental(&changes).unwrap();
+            if count_shapes(replica) >= min_shapes {
+                return true;
             }
         }
         std::thread::sleep(Duration::from_millis(20));
     }
-    best
+    false
+}
+
+/// Whether a raw Automerge document holds a rectangle with this label.
+fn has_label(doc: &Automerge, label: &str) -> bool {
+    Document::from_automerge(doc.clone())
+        .read_all_shapes()
+        .unwrap()
+        .iter()
+        .any(|(_, kind)| {
+            matches!(kind, ShapeKind::Rectangle { label: Some(l), .. } if l == label)
+        })
+}
+
+/// Apply RemoteChanges to the replica until it holds a rectangle with this
+/// label. Returns whether it got there in time.
+fn wait_for_label(h: &SyncHandle, replica: &mut Automerge, label: &str, timeout: Duration) -> bool {
+    let t0 = Instant::now();
+    while t0.elapsed() < timeout {
+        if let Some(SyncEvent::RemoteChanges { changes, .. }) = h.poll_event() {
+            replica.load_incremental(&changes).unwrap();
+            if has_label(replica, label) {
+                return true;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    false
 }
 
 /// Read all key names from an Automerge map object at root.
@@ -208,12 +254,14 @@ fn e2e_shapes_sync_to_joiner() {
 
     // Push to sync
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
     // Joiner receives remote changes
-    let remote = wait_remote(&hb);
+    let mut remote = Automerge::new();
+    wait_remote(&hb, &mut remote);
 
     // Verify the remote document has the shapes structure
     assert!(
@@ -262,12 +310,14 @@ fn e2e_shapes_sync_to_host() {
     doc_b.add_shape(rect(30, 30, 10, 10)).unwrap();
 
     hb.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_b.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_b.changes_since(&[]),
     })
     .unwrap();
 
     // Host receives (via periodic sync pulling changes)
-    let remote = wait_remote(&ha);
+    let mut remote = Automerge::new();
+    wait_remote(&ha, &mut remote);
     assert_eq!(count_shapes(&remote), 2, "host should receive 2 shapes");
 
     let doc_a = Document::from_automerge(remote);
@@ -306,11 +356,13 @@ fn e2e_concurrent_shapes_converge() {
 
     // Push both at the same time
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
     hb.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_b.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_b.changes_since(&[]),
     })
     .unwrap();
 
@@ -318,19 +370,21 @@ fn e2e_concurrent_shapes_converge() {
     std::thread::sleep(Duration::from_secs(3));
 
     // Drain all events to get the latest state
-    let last_a = drain_remote(&ha, Duration::from_secs(2));
-    let last_b = drain_remote(&hb, Duration::from_secs(2));
+    let mut last_a = Automerge::new();
+    let mut last_b = Automerge::new();
+    let received_a = drain_remote(&ha, &mut last_a, Duration::from_secs(2));
+    let received_b = drain_remote(&hb, &mut last_b, Duration::from_secs(2));
 
     // Both should have received remote changes
     // Note: due to CRDT merge semantics, both docs should eventually converge,
     // but since each peer creates an independent document, the merged doc will
     // contain both sets of shapes.
-    if let Some(doc) = last_a {
-        let shapes = count_shapes(&doc);
+    if received_a {
+        let shapes = count_shapes(&last_a);
         assert!(shapes >= 2, "host should see at least joiner's 2 shapes, got {shapes}");
     }
-    if let Some(doc) = last_b {
-        let shapes = count_shapes(&doc);
+    if received_b {
+        let shapes = count_shapes(&last_b);
         assert!(shapes >= 2, "joiner should see at least host's 2 shapes, got {shapes}");
     }
 
@@ -356,11 +410,13 @@ fn e2e_layers_sync() {
     doc_a.add_shape(rect(20, 0, 10, 5)).unwrap();
 
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
-    let remote = wait_remote(&hb);
+    let mut remote = Automerge::new();
+    wait_remote(&hb, &mut remote);
 
     // Verify layers arrived
     let doc_b = Document::from_automerge(remote);
@@ -400,11 +456,13 @@ fn e2e_groups_sync() {
     let _group = doc_a.create_group(&[s1, s2], None).unwrap();
 
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
-    let remote = wait_remote(&hb);
+    let mut remote = Automerge::new();
+    wait_remote(&hb, &mut remote);
     let doc_b = Document::from_automerge(remote);
 
     // Verify shapes
@@ -437,21 +495,131 @@ fn e2e_three_peer_cluster() {
         .unwrap();
 
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
     // Both joiners should eventually receive the shape
     // (B connects directly to A; C also connects directly to A)
-    let remote_b = wait_remote(&hb);
+    let mut remote_b = Automerge::new();
+    wait_remote(&hb, &mut remote_b);
     assert_eq!(count_shapes(&remote_b), 1, "peer B should have 1 shape");
 
-    let remote_c = wait_remote(&hc);
+    let mut remote_c = Automerge::new();
+    wait_remote(&hc, &mut remote_c);
     assert_eq!(count_shapes(&remote_c), 1, "peer C should have 1 shape");
 
     cleanup(vec![ha, hb, hc]);
 }
 
+/// Joining reports the link, and the host leaving reports it gone.
+#[test]
+fn e2e_link_lifecycle_events() {
+    let _g = lock_tests();
+
+    let (ha, ticket, pa) = host();
+    let (hb, _pb) = join(&ticket);
+
+    let connected = wait_for_event(&hb, SYNC_TIMEOUT, |e| {
+        matches!(e, SyncEvent::PeerConnected { peer_id, .. } if *peer_id == pa)
+    });
+    assert!(connected.is_some(), "joiner should report the link to the host");
+
+    let _ = ha.send_command(SyncCommand::Shutdown);
+    let disconnected = wait_for_event(&hb, SYNC_TIMEOUT, |e| {
+        matches!(e, SyncEvent::PeerDisconnected { peer_id } if *peer_id == pa)
+    });
+    assert!(disconnected.is_some(), "joiner should report the host leaving");
+
+    // A host that left on purpose isn't redialed
+    let redial = wait_for_event(&hb, Duration::from_secs(2), |e| {
+        matches!(e, SyncEvent::Reconnecting { .. })
+    });
+    assert!(redial.is_none(), "joiner shouldn't redial a host that left");
+
+    cleanup(vec![hb]);
+}
+
+/// The host reports the links joiners open to it, and their leaving.
+#[test]
+fn e2e_host_reports_joiner_links() {
+    let _g = lock_tests();
+
+    let (ha, ticket, _pa) = host();
+    let (hb, pb) = join(&ticket);
+
+    let connected = wait_for_event(&ha, SYNC_TIMEOUT, |e| {
+        matches!(e, SyncEvent::PeerConnected { peer_id, .. } if *peer_id == pb)
+    });
+    assert!(connected.is_some(), "host should report the joiner's link");
+
+    let _ = hb.send_command(SyncCommand::Shutdown);
+    let disconnected = wait_for_event(&ha, SYNC_TIMEOUT, |e| {
+        matches!(e, SyncEvent::PeerDisconnected { peer_id } if *peer_id == pb)
+    });
+    assert!(disconnected.is_some(), "host should report the joiner leaving");
+
+    cleanup(vec![ha]);
+}
+
+/// Full mesh: both joiners only know the host's ticket, learn about each
+/// other through peer exchange, and keep syncing after the host leaves.
+#[test]
+fn e2e_mesh_survives_host_leaving() {
+    let _g = lock_tests();
+
+    let (ha, ticket, _pa) = host();
+    let (hb, pb) = join(&ticket);
+    let (hc, _pc) = join(&ticket);
+    // Long enough for a peer exchange round to link B and C directly
+    std::thread::sleep(CONNECTION_DELAY * 3);
+
+    // Host leaves
+    let _ = ha.send_command(SyncCommand::Shutdown);
+    std::thread::sleep(Duration::from_millis(500));
+
+    // B's shape reaches C without the host
+    let mut doc_b = Document::new();
+    doc_b.add_shape(labeled_rect(0, 0, 10, 5, "FromB")).unwrap();
+    hb.send_command(SyncCommand::SyncDoc {
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_b.changes_since(&[]),
+    })
+    .unwrap();
+    let mut remote_c = Automerge::new();
+    assert!(wait_for_shapes(&hc, &mut remote_c, 1, SYNC_TIMEOUT), "C should hear from B");
+    assert_eq!(count_shapes(&remote_c), 1, "C should have B's shape");
+
+    // And C's shape reaches B
+    let mut doc_c = Document::new();
+    doc_c.add_shape(labeled_rect(0, 10, 10, 5, "FromC")).unwrap();
+    hc.send_command(SyncCommand::SyncDoc {
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_c.changes_since(&[]),
+    })
+    .unwrap();
+    let mut remote_b = Automerge::new();
+    assert!(wait_for_shapes(&hb, &mut remote_b, 2, SYNC_TIMEOUT), "B should hear from C");
+    assert_eq!(count_shapes(&remote_b), 2, "B should have both shapes");
+
+    // Presence also flows directly between the remaining peers
+    hb.send_command(SyncCommand::BroadcastPresence(PeerPresence::new(
+        pb,
+        Position::new(7, 7),
+        CursorActivity::Idle,
+        None,
+        None,
+    )))
+    .unwrap();
+    let recv = wait_for_event(&hc, SYNC_TIMEOUT, |e| {
+        matches!(e, SyncEvent::PresenceUpdate(p) if p.peer_id == pb)
+    });
+    assert!(recv.is_some(), "C should receive B's presence");
+
+    cleanup(vec![hb, hc]);
+}
+
 /// Presence: host broadcasts cursor+activity, joiner receives exact data.
 #[test]
 fn e2e_presence_cursor_and_activity() {
@@ -516,11 +684,12 @@ fn e2e_peer_disconnect_new_peer_gets_state() {
     doc_a.add_shape(rect(20, 0, 10, 5)).unwrap();
 
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
-    wait_remote(&hb); // B receives
+    wait_remote(&hb, &mut Automerge::new()); // B receives
 
     // B disconnects
     let _ = hb.send_command(SyncCommand::Shutdown);
@@ -529,7 +698,8 @@ fn e2e_peer_disconnect_new_peer_gets_state() {
     // Host adds another shape
     doc_a.add_shape(rect(40, 0, 10, 5)).unwrap();
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
@@ -542,12 +712,16 @@ fn e2e_peer_disconnect_new_peer_gets_state() {
 
     // Re-send to ensure host store has the 3-shape doc
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
-    let remote_c = wait_for_shapes(&hc, 3, SYNC_TIMEOUT)
-        .expect("peer C should receive remote changes");
+    let mut remote_c = Automerge::new();
+    assert!(
+        wait_for_shapes(&hc, &mut remote_c, 3, SYNC_TIMEOUT),
+        "peer C should receive remote changes"
+    );
     assert_eq!(
         count_shapes(&remote_c),
         3,
@@ -575,28 +749,290 @@ fn e2e_undo_redo_sync() {
 
     // Sync the 2-shape state
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
-    let remote = wait_remote(&hb);
+    let mut remote = Automerge::new();
+    wait_remote(&hb, &mut remote);
     assert_eq!(count_shapes(&remote), 2, "should have 2 shapes before undo");
 
     // Host undoes last action
     doc_a.global_undo().unwrap();
     ha.send_command(SyncCommand::SyncDoc {
-        doc: Box::new(doc_a.clone_automerge()),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        changes: doc_a.changes_since(&[]),
     })
     .unwrap();
 
     // Give time for sync
     std::thread::sleep(Duration::from_secs(2));
-    let after_undo = drain_remote(&hb, Duration::from_secs(2));
-
-    if let Some(doc) = after_undo {
-        let n = count_shapes(&doc);
+    // B's replica picks up only the undo on top of what it already has
+    if drain_remote(&hb, &mut remote, Duration::from_secs(2)) {
+        let n = count_shapes(&remote);
         assert_eq!(n, 1, "after undo should have 1 shape, got {n}");
     }
 
     cleanup(vec![ha, hb]);
 }
+
+/// Two session documents share one endpoint; a doc ticket joins only its own.
+#[test]
+fn e2e_doc_ticket_joins_single_document() {
+    let _g = lock_tests();
+
+    let (ha, _ticket, _pa) = host();
+
+    let mut doc_a = Document::new();
+    doc_a.add_shape(rect(0, 0, 10, 5)).unwrap();
+    let mut doc_b = Document::new();
+    doc_b.add_shape(rect(0, 0, 10, 5)).unwrap();
+    doc_b.add_shape(rect(20, 0, 10, 5)).unwrap();
+
+    for (doc_id, doc) in [("design-a", &doc_a), ("design-b", &doc_b)] {
+        ha.send_command(SyncCommand::OpenDocument {
+            doc_id: doc_id.to_string(),
+            doc: Box::new(doc.clone_automerge()),
+        })
+        .unwrap();
+    }
+
+    let ticket_b = ha.doc_ticket("design-b").expect("host should have a doc ticket");
+    let cfg = SyncConfig {
+        mode: SyncMode::Active {
+            join_ticket: Some(ticket_b),
+        },
+        cluster_ticket: None,
+        cluster_capability: None,
+        disable_discovery: true,
+        doc_id: "design-b".to_string(),
+        store_dir: None,
+        identity_path: None,
+        roles_path: None,
+    };
+    let hb = start_sync_thread(cfg).expect("start joiner");
+    wait_ready(&hb);
+
+    let mut docs_seen = Vec::new();
+    let t0 = Instant::now();
+    let mut remote_b = Automerge::new();
+    while t0.elapsed() < SYNC_TIMEOUT && count_shapes(&remote_b) < 2 {
+        if let Some(SyncEvent::RemoteChanges { doc_id, changes }) = hb.poll_event() {
+            if doc_id == "design-b" {
+                remote_b.load_incremental(&changes).unwrap();
+            }
+            docs_seen.push(doc_id);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    assert_eq!(count_shapes(&remote_b), 2, "joiner should receive design-b's shapes");
+    assert!(
+        docs_seen.iter().all(|id| id == "design-b"),
+        "joiner should only sync design-b, saw {docs_seen:?}"
+    );
+
+    cleanup(vec![ha, hb]);
+}
+
+/// A viewer ticket's holder receives the document, but every editor drops
+/// the changes it sends, while another editor's still go through.
+#[test]
+fn e2e_viewer_changes_rejected_by_editors() {
+    let _g = lock_tests();
+
+    let (ha, _ticket, _pa) = host();
+    let mut doc_a = Document::new();
+    doc_a.add_shape(labeled_rect(0, 0, 10, 5, "host")).unwrap();
+    ha.send_command(SyncCommand::OpenDocument {
+        doc_id: "design".to_string(),
+        doc: Box::new(doc_a.clone_automerge()),
+    })
+    .unwrap();
+
+    let viewer_ticket = ha.viewer_ticket("design").expect("host should have a viewer ticket");
+    let editor_ticket = ha.doc_ticket("design").expect("host should have a doc ticket");
+
+    // The viewer gets the host's shape, then tries to add its own
+    let (hv, _pv) = join_doc(&viewer_ticket, "design");
+    assert_eq!(hv.role("design"), Role::Viewer);
+    let mut replica_v = Automerge::new();
+    assert!(
+        wait_for_label(&hv, &mut replica_v, "host", SYNC_TIMEOUT),
+        "viewer should receive the host's shape"
+    );
+    let mut doc_v = Document::from_automerge(replica_v.clone());
+    let heads = doc_v.heads();
+    doc_v.add_shape(labeled_rect(20, 0, 10, 5, "viewer")).unwrap();
+    hv.send_command(SyncCommand::SyncDoc {
+        doc_id: "design".to_string(),
+        changes: doc_v.changes_since(&heads),
+    })
+    .unwrap();
+
+    // An editor joins and adds a shape too
+    let (he, _pe) = join_doc(&editor_ticket, "design");
+    assert_eq!(he.role("design"), Role::Editor);
+    let mut replica_e = Automerge::new();
+    assert!(
+        wait_for_label(&he, &mut replica_e, "host", SYNC_TIMEOUT),
+        "editor should receive the host's shape"
+    );
+    let mut doc_e = Document::from_automerge(replica_e.clone());
+    let heads = doc_e.heads();
+    doc_e.add_shape(labeled_rect(40, 0, 10, 5, "editor")).unwrap();
+    he.send_command(SyncCommand::SyncDoc {
+        doc_id: "design".to_string(),
+        changes: doc_e.changes_since(&heads),
+    })
+    .unwrap();
+
+    // The host takes the editor's shape but never the viewer's
+    let mut replica_a = doc_a.clone_automerge();
+    assert!(
+        wait_for_label(&ha, &mut replica_a, "editor", SYNC_TIMEOUT),
+        "host should receive the editor's shape"
+    );
+    drain_remote(&ha, &mut replica_a, Duration::from_secs(3));
+    assert!(!has_label(&replica_a, "viewer"), "host should drop the viewer's shape");
+
+    // Neither does the other editor, while the viewer still follows along
+    drain_remote(&he, &mut replica_e, Duration::from_secs(1));
+    assert!(!has_label(&replica_e, "viewer"), "editor should drop the viewer's shape");
+    assert!(
+        wait_for_label(&hv, &mut replica_v, "editor", SYNC_TIMEOUT),
+        "viewer should receive the editor's shape"
+    );
+
+    cleanup(vec![ha, hv, he]);
+}
+
+/// Invite tickets let their holder edit until revoked; expired invites get
+/// nothing at all, and the host lists what it issued.
+#[test]
+fn e2e_invites_expire_and_revoke() {
+    let _g = lock_tests();
+
+    let (ha, _ticket, _pa) = host();
+    let mut doc_a = Document::new();
+    doc_a.add_shape(labeled_rect(0, 0, 10, 5, "host")).unwrap();
+    ha.send_command(SyncCommand::OpenDocument {
+        doc_id: "design".to_string(),
+        doc: Box::new(doc_a.clone_automerge()),
+    })
+    .unwrap();
+
+    let alice_ticket = ha
+        .create_invite("design", Role::Editor, Some("alice".to_string()), Duration::from_secs(3600))
+        .expect("host should issue an invite");
+    let bob_ticket = ha
+        .create_invite("design", Role::Editor, Some("bob".to_string()), Duration::ZERO)
+        .expect("host should issue an invite");
+
+    // Alice gets the host's shape, and hers reaches the host
+    let (hl, _pl) = join_doc(&alice_ticket, "design");
+    assert_eq!(hl.role("design"), Role::Editor);
+    let mut replica_l = Automerge::new();
+    assert!(
+        wait_for_label(&hl, &mut replica_l, "host", SYNC_TIMEOUT),
+        "invitee should receive the host's shape"
+    );
+    let mut doc_l = Document::from_automerge(replica_l.clone());
+    let heads = doc_l.heads();
+    doc_l.add_shape(labeled_rect(20, 0, 10, 5, "alice")).unwrap();
+    hl.send_command(SyncCommand::SyncDoc {
+        doc_id: "design".to_string(),
+        changes: doc_l.changes_since(&heads),
+    })
+    .unwrap();
+    let mut replica_a = doc_a.clone_automerge();
+    assert!(
+        wait_for_label(&ha, &mut replica_a, "alice", SYNC_TIMEOUT),
+        "host should receive the invitee's shape"
+    );
+
+    // Once revoked, nothing more of hers gets in
+    let invites = ha.invites("design");
+    let labels: Vec<_> = invites.iter().map(|info| info.invite.label.as_deref()).collect();
+    assert_eq!(labels, [Some("alice"), Some("bob")]);
+    assert!(ha.revoke_invite(invites[0].invite.id).unwrap());
+    assert!(ha.invites("design")[0].revoked);
+    assert!(!ha.invites("design")[1].revoked);
+
+    let heads = doc_l.heads();
+    doc_l.add_shape(labeled_rect(40, 0, 10, 5, "alice-late")).unwrap();
+    hl.send_command(SyncCommand::SyncDoc {
+        doc_id: "design".to_string(),
+        changes: doc_l.changes_since(&heads),
+    })
+    .unwrap();
+    drain_remote(&ha, &mut replica_a, Duration::from_secs(3));
+    assert!(!has_label(&replica_a, "alice-late"), "host should refuse a revoked invitee");
+
+    // Bob's invite expired before he used it
+    let (hb, _pb) = join_doc(&bob_ticket, "design");
+    let mut replica_b = Automerge::new();
+    drain_remote(&hb, &mut replica_b, Duration::from_secs(3));
+    assert!(!has_label(&replica_b, "host"), "an expired invite should get nothing");
+
+    cleanup(vec![ha, hl, hb]);
+}
+
+/// A revoked invitee coming back with the same peer ID through a bare
+/// document ticket, without the invite, is served nothing of the document.
+#[test]
+fn e2e_revoked_invitee_rejoins_without_the_invite() {
+    let _g = lock_tests();
+
+    let (ha, _ticket, _pa) = host();
+    let mut doc_a = Document::new();
+    doc_a.add_shape(labeled_rect(0, 0, 10, 5, "host")).unwrap();
+    ha.send_command(SyncCommand::OpenDocument {
+        doc_id: "design".to_string(),
+        doc: Box::new(doc_a.clone_automerge()),
+    })
+    .unwrap();
+
+    let alice_ticket = ha
+        .create_invite("design", Role::Viewer, Some("alice".to_string()), Duration::from_secs(3600))
+        .expect("host should issue an invite");
+    let dir = tempfile::tempdir().unwrap();
+    let join_as_alice = |ticket: &str| {
+        let cfg = SyncConfig {
+            mode: SyncMode::Active {
+                join_ticket: Some(ticket.into()),
+            },
+            cluster_ticket: None,
+            cluster_capability: None,
+            disable_discovery: true,
+            doc_id: "design".to_string(),
+            store_dir: None,
+            identity_path: Some(dir.path().join("identity.key")),
+            roles_path: None,
+        };
+        let h = start_sync_thread(cfg).expect("start joiner");
+        let (_, pid) = wait_ready(&h);
+        (h, pid)
+    };
+
+    // With the invite, Alice reads the document
+    let (hl, pl) = join_as_alice(&alice_ticket);
+    let mut replica_l = Automerge::new();
+    assert!(
+        wait_for_label(&hl, &mut replica_l, "host", SYNC_TIMEOUT),
+        "invitee should receive the host's shape"
+    );
+    assert!(ha.revoke_invite(ha.invites("design")[0].invite.id).unwrap());
+    cleanup(vec![hl]);
+
+    // Back under the same peer ID with only the host's address
+    let addr = decode_ticket(&alice_ticket).unwrap();
+    let (hl, pl_again) = join_as_alice(&encode_doc_ticket(&addr, "design"));
+    assert_eq!(pl, pl_again, "alice should keep her peer ID");
+    let mut replica_l = Automerge::new();
+    drain_remote(&hl, &mut replica_l, Duration::from_secs(3));
+    assert!(!has_label(&replica_l, "host"), "a revoked invitee should get nothing");
+
+    cleanup(vec![ha, hl]);
+}
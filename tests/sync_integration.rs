@@ -4,6 +4,7 @@
 //! - Two-peer document synchronization
 //! - Bidirectional sync
 //! - Presence sync
+//! - Chat
 //! - Graceful shutdown
 //! - Concurrent edits (CRDT convergence)
 //! - Multi-peer sync
@@ -19,7 +20,8 @@ use irohscii::layers::LayerId;
 use irohscii::presence::{CursorActivity, PeerId, PeerPresence};
 use irohscii::shapes::{ShapeColor, ShapeKind};
 use irohscii::sync::{
-    SyncCommand, SyncConfig, SyncEvent, SyncHandle, SyncMode, decode_ticket, start_sync_thread,
+    ChatMessage, DEFAULT_DOC_ID, SyncCommand, SyncConfig, SyncEvent, SyncHandle, SyncMode, decode_ticket,
+    start_sync_thread,
 };
 
 // Mutex to ensure tests run serially (avoid port conflicts)
@@ -65,13 +67,13 @@ fn wait_for_ready(handle: &SyncHandle, timeout: Duration) -> Option<(String, Pee
     )
 }
 
-/// Wait for SyncEvent::RemoteChanges and return the Automerge document
-fn wait_for_remote_changes(handle: &SyncHandle, timeout: Duration) -> Option<Automerge> {
+/// Wait for SyncEvent::RemoteChanges and return the encoded changes
+fn wait_for_remote_changes(handle: &SyncHandle, timeout: Duration) -> Option<Vec<u8>> {
     wait_for_event(handle, timeout, |e| {
         matches!(e, SyncEvent::RemoteChanges { .. })
     })
     .and_then(|e| match e {
-        SyncEvent::RemoteChanges { doc } => Some(*doc),
+        SyncEvent::RemoteChanges { changes, .. } => Some(changes),
         _ => None,
     })
 }
@@ -126,6 +128,10 @@ fn setup_host_peer() -> (SyncHandle, String, PeerId) {
         cluster_ticket: None,
         cluster_capability: None,
         disable_discovery: true,
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        store_dir: None,
+        identity_path: None,
+        roles_path: None,
     };
     let handle = start_sync_thread(config).expect("Failed to start host peer");
 
@@ -144,6 +150,10 @@ fn setup_joining_peer(ticket: &str) -> (SyncHandle, PeerId) {
         cluster_ticket: None,
         cluster_capability: None,
         disable_discovery: true,
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        store_dir: None,
+        identity_path: None,
+        roles_path: None,
     };
     let handle = start_sync_thread(config).expect("Failed to start joining peer");
 
@@ -194,19 +204,24 @@ fn test_two_peer_document_sync() {
     // Send document to sync
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to send sync command");
 
     // Wait for Peer B to receive remote changes
-    let remote_doc =
+    let remote_changes =
         wait_for_remote_changes(&handle_b, SYNC_TIMEOUT).expect("Peer B did not receive changes");
 
-    // Verify the shape arrived by checking the document has data
-    // (Can't easily reconstruct Document from raw Automerge in tests)
-    assert!(
-        !remote_doc.save().is_empty(),
-        "Remote doc should have content"
+    // Verify the shape arrived by applying the changes to an empty document
+    let mut remote_doc = Document::from_automerge(Automerge::new());
+    remote_doc
+        .apply_changes(&remote_changes)
+        .expect("Failed to apply remote changes");
+    assert_eq!(
+        remote_doc.read_all_shapes().unwrap().len(),
+        1,
+        "Remote doc should have the shape"
     );
 
     cleanup_peers(vec![handle_a, handle_b]);
@@ -228,17 +243,18 @@ fn test_bidirectional_sync() {
 
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to send from A");
 
     // Wait for Peer B to receive
-    let remote_doc_b = wait_for_remote_changes(&handle_b, SYNC_TIMEOUT)
+    let remote_changes_b = wait_for_remote_changes(&handle_b, SYNC_TIMEOUT)
         .expect("Peer B should receive shape from A");
 
     assert!(
-        !remote_doc_b.save().is_empty(),
-        "Remote doc at B should have content"
+        !remote_changes_b.is_empty(),
+        "Remote changes at B should have content"
     );
 
     // Peer B creates a new document and adds a shape
@@ -248,17 +264,18 @@ fn test_bidirectional_sync() {
 
     handle_b
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_b.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_b.changes_since(&[]),
         })
         .expect("Failed to send from B");
 
     // Wait for Peer A to receive Peer B's changes
-    let remote_doc_a = wait_for_remote_changes(&handle_a, SYNC_TIMEOUT)
+    let remote_changes_a = wait_for_remote_changes(&handle_a, SYNC_TIMEOUT)
         .expect("Peer A should receive shape from B");
 
     assert!(
-        !remote_doc_a.save().is_empty(),
-        "Remote doc at A should have content"
+        !remote_changes_a.is_empty(),
+        "Remote changes at A should have content"
     );
 
     cleanup_peers(vec![handle_a, handle_b]);
@@ -291,6 +308,46 @@ fn test_presence_sync() {
     cleanup_peers(vec![handle_a, handle_b]);
 }
 
+#[test]
+fn test_chat_sync() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let (handle_a, ticket_a, _peer_id_a) = setup_host_peer();
+    let (handle_b, peer_id_b) = setup_joining_peer(&ticket_a);
+
+    std::thread::sleep(CONNECTION_DELAY);
+
+    // Peer B sends a pinned message claiming to be someone else
+    let message = ChatMessage {
+        id: uuid::Uuid::new_v4(),
+        doc_id: DEFAULT_DOC_ID.to_string(),
+        from: PeerId([9; 32]),
+        name: Some("Bob".to_string()),
+        color_index: 1,
+        text: "look here".to_string(),
+        timestamp_ms: 1,
+        pin: Some(Position::new(4, 2)),
+    };
+    handle_b
+        .send_command(SyncCommand::SendChat(message.clone()))
+        .expect("Failed to send chat");
+
+    let received = wait_for_event(&handle_a, SYNC_TIMEOUT, |e| matches!(e, SyncEvent::Chat(_)))
+        .and_then(|e| match e {
+            SyncEvent::Chat(msg) => Some(msg),
+            _ => None,
+        })
+        .expect("Peer A should receive the chat message");
+
+    // The sender is whoever is on the other end of the connection
+    assert_eq!(received.from, peer_id_b);
+    assert_eq!(received.text, message.text);
+    assert_eq!(received.pin, message.pin);
+    assert_eq!(received.id, message.id);
+
+    cleanup_peers(vec![handle_a, handle_b]);
+}
+
 #[test]
 fn test_graceful_shutdown_presence_removed() {
     let _guard = TEST_MUTEX.lock().unwrap();
@@ -365,13 +422,15 @@ fn test_concurrent_edits_converge() {
     // Send both at nearly the same time
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to send from A");
 
     handle_b
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_b.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_b.changes_since(&[]),
         })
         .expect("Failed to send from B");
 
@@ -405,7 +464,8 @@ fn test_concurrent_shape_modification() {
     // Sync to B
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to initial sync");
 
@@ -420,7 +480,8 @@ fn test_concurrent_shape_modification() {
 
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to send modified from A");
 
@@ -452,7 +513,8 @@ fn test_three_peer_mesh_sync() {
 
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to send from A");
 
@@ -482,7 +544,8 @@ fn test_late_joiner_receives_full_state() {
 
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to send shapes from A");
 
@@ -496,7 +559,8 @@ fn test_late_joiner_receives_full_state() {
     // Trigger a sync so C receives the current state
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to re-sync for C");
 
@@ -545,7 +609,8 @@ fn test_peer_disconnect_handling() {
 
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("Failed to send from A");
 
@@ -569,7 +634,8 @@ fn test_peer_disconnect_handling() {
 
     handle_a
         .send_command(SyncCommand::SyncDoc {
-            doc: Box::new(doc_a.automerge().clone()),
+            doc_id: DEFAULT_DOC_ID.to_string(),
+            changes: doc_a.changes_since(&[]),
         })
         .expect("A should be able to sync with C");
 